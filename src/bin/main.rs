@@ -1,34 +1,27 @@
 use rusty_ache::engine::Engine;
+use rusty_ache::engine::builder::EngineBuilder;
 use rusty_ache::engine::scene::game_object::GameObject;
-use rusty_ache::engine::scene::game_object::components::script::Script;
+use rusty_ache::engine::scene::game_object::components::script::{Script, ScriptContext};
 use rusty_ache::engine::scene::game_object::position::Position;
-use rusty_ache::interface::{create_obj_with_img, init_engine, init_scene};
+use rusty_ache::interface::load_scene;
 use rusty_ache::screen::{HEIGHT, WIDTH};
 
 fn main() {
-    let tower_obj = create_obj_with_img("src/bin/resources/tower.png", 82, 37, true);
-    let junk_house_obj = create_obj_with_img("src/bin/resources/junk_house.png", 150, -150, true);
-    let pool_house_obj = create_obj_with_img("src/bin/resources/pool_house.png", 15, -25, true);
-    let tall_house_obj = create_obj_with_img("src/bin/resources/tall_house.png", 210, -80, true);
-    let skyscraper_obj = create_obj_with_img("src/bin/resources/skyscraper.png", 150, 55, true);
-    let cabin_obj = create_obj_with_img("src/bin/resources/cabin.png", 280, -60, true);
-    let main_ship_obj = create_obj_with_img("src/bin/resources/white_ship.png", 0, 0, true);
-
-    let hermit_house_obj = create_obj_with_img("src/bin/resources/junk_house.png", 400, 240, true);
-
-    let scene = init_scene(
-        &[
-            cabin_obj,
-            skyscraper_obj,
-            hermit_house_obj,
-            tower_obj,
-            tall_house_obj,
-            junk_house_obj,
-            pool_house_obj,
-        ],
-        main_ship_obj,
-    );
-    let mut engine = init_engine(scene, WIDTH, HEIGHT);
+    let _ = rusty_ache::logging::init();
+    let mut scene = load_scene("src/bin/resources/scene.toml").unwrap();
+    if cfg!(debug_assertions)
+        && let Err(e) = scene.watch()
+    {
+        log::warn!("Couldn't watch scene file: {e}");
+    }
+    let mut engine = EngineBuilder::new()
+        .resolution(WIDTH, HEIGHT)
+        .env()
+        .unwrap()
+        .args(std::env::args().skip(1))
+        .unwrap()
+        .scene(scene)
+        .build();
     engine.render().unwrap();
     engine.run().unwrap()
 }
@@ -42,7 +35,7 @@ impl Script for MyScript {
         MyScript { is_downed }
     }
 
-    fn action(&mut self, game_object: &mut GameObject) {
+    fn action(&mut self, game_object: &mut GameObject, _context: &mut ScriptContext) {
         if !self.is_downed {
             game_object.position = Position {
                 x: game_object.position.x,
@@ -65,9 +58,13 @@ impl Script for MyScript {
 
 #[cfg(test)]
 mod tests {
+    use rusty_ache::engine::bus::EventBus;
+    use rusty_ache::engine::input::InputState;
     use rusty_ache::engine::scene::game_object::{GameObject, Object, position::Position};
+    use rusty_ache::render::camera::Camera;
+    use std::time::Duration;
 
-    use crate::{MyScript, Script};
+    use crate::{MyScript, Script, ScriptContext};
 
     #[test]
     fn test_new_script() {
@@ -85,7 +82,16 @@ mod tests {
             is_relative: false,
         };
         let game_object = &mut GameObject::new(vec![], None, position);
-        script.action(game_object);
+        let input = InputState::new();
+        script.action(
+            game_object,
+            &mut ScriptContext::new(
+                Duration::from_millis(16),
+                &input,
+                Camera::default(),
+                EventBus::new(),
+            ),
+        );
         assert_eq!(game_object.position.x, 15);
         assert_eq!(game_object.position.y, 24);
         assert_eq!(game_object.position.z, 35);
@@ -101,7 +107,16 @@ mod tests {
             is_relative: false,
         };
         let game_object = &mut GameObject::new(vec![], None, position);
-        script.action(game_object);
+        let input = InputState::new();
+        script.action(
+            game_object,
+            &mut ScriptContext::new(
+                Duration::from_millis(16),
+                &input,
+                Camera::default(),
+                EventBus::new(),
+            ),
+        );
         assert_eq!(game_object.position.x, 15);
         assert_eq!(game_object.position.y, 26);
         assert_eq!(game_object.position.z, 35);
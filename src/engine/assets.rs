@@ -0,0 +1,358 @@
+//! Loading and caching of game assets.
+//!
+//! Scenes often show the same image many times, e.g. a house or a tile. An `AssetManager`
+//! decodes every image file once and hands out shared references to the cached image
+//! afterwards, so the pixel data is never copied.
+//! Images are cached by their canonical path, so different spellings of the same path
+//! share an entry. Relative paths are resolved against the asset root, if one is set.
+//!
+//! Images can also be loaded on worker threads, so a large scene does not block startup:
+//! a `Handle` resolves once its image is decoded, and a sprite created with
+//! `Sprite::loading` shows a placeholder until then.
+//!
+//! Sounds are cached the same way, so a sound played many times is decoded once. Scenes
+//! name the sounds they play with `Scene::with_sounds`, and the engine preloads them on
+//! worker threads when the scene becomes active, so the first playback does not hitch.
+
+use crate::engine::audio::Sound;
+use image::{DynamicImage, ImageReader, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, LazyLock, Mutex, RwLock};
+
+/// Side length of the placeholder shown while an image loads.
+const PLACEHOLDER_SIZE: u32 = 16;
+
+/// Half-transparent grey image shown in place of images still loading.
+static PLACEHOLDER: LazyLock<Arc<DynamicImage>> = LazyLock::new(|| {
+    Arc::new(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        PLACEHOLDER_SIZE,
+        PLACEHOLDER_SIZE,
+        Rgba([128, 128, 128, 128]),
+    )))
+});
+
+/// Returns the image shown in place of images still loading.
+pub fn placeholder_image() -> Arc<DynamicImage> {
+    PLACEHOLDER.clone()
+}
+
+/// Progress of a background load.
+enum LoadState<T> {
+    Loading,
+    Ready(T),
+    Failed(String),
+}
+
+/// [Handle](crate#handles) to an asset loading in the background.
+///
+/// The worker thread stores the loaded asset or the error, and every clone sees it.
+pub struct Handle<T> {
+    state: Arc<(Mutex<LoadState<T>>, Condvar)>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Handle<T> {
+    /// Creates a handle of an asset that is still loading.
+    pub(crate) fn loading() -> Self {
+        Handle {
+            state: Arc::new((Mutex::new(LoadState::Loading), Condvar::new())),
+        }
+    }
+
+    /// Creates a handle of an asset that is available already.
+    pub fn ready(asset: T) -> Self {
+        let handle = Handle::loading();
+        handle.finish(Ok(asset));
+        handle
+    }
+
+    /// Stores the outcome of the load and wakes up everyone waiting for it.
+    pub(crate) fn finish(&self, result: Result<T, Error>) {
+        let (state, loaded) = &*self.state;
+        *state.lock().unwrap() = match result {
+            Ok(asset) => LoadState::Ready(asset),
+            Err(e) => LoadState::Failed(e.to_string()),
+        };
+        loaded.notify_all();
+    }
+
+    /// Checks whether the asset is still loading.
+    pub fn is_loading(&self) -> bool {
+        matches!(*self.state.0.lock().unwrap(), LoadState::Loading)
+    }
+
+    /// Returns the asset, or None while it is loading or if loading failed.
+    pub fn get(&self) -> Option<T> {
+        match &*self.state.0.lock().unwrap() {
+            LoadState::Ready(asset) => Some(asset.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns why loading failed, if it did.
+    pub fn error(&self) -> Option<String> {
+        match &*self.state.0.lock().unwrap() {
+            LoadState::Failed(e) => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    /// Blocks until the asset finished loading.
+    ///
+    /// # Errors
+    /// Fails if the asset could not be loaded.
+    pub fn wait(&self) -> Result<T, Error> {
+        let (state, loaded) = &*self.state;
+        let state = loaded
+            .wait_while(state.lock().unwrap(), |state| {
+                matches!(state, LoadState::Loading)
+            })
+            .unwrap();
+        match &*state {
+            LoadState::Ready(asset) => Ok(asset.clone()),
+            LoadState::Failed(e) => Err(Error::other(e.clone())),
+            LoadState::Loading => unreachable!(),
+        }
+    }
+}
+
+/// [Handle](crate#handles) to a cache of decoded images and sounds.
+///
+/// An asset loaded through one clone, e.g. on a worker thread, is cached for all of them.
+#[derive(Clone, Default)]
+pub struct AssetManager {
+    images: Arc<RwLock<HashMap<PathBuf, Arc<DynamicImage>>>>,
+    sounds: Arc<RwLock<HashMap<PathBuf, Sound>>>,
+    /// Directory relative paths are resolved against; the working directory if None.
+    root: Option<PathBuf>,
+}
+
+impl AssetManager {
+    /// Creates an empty cache resolving paths against the working directory.
+    pub fn new() -> Self {
+        AssetManager::default()
+    }
+
+    /// Resolves relative paths against the given directory, e.g. the asset root of the config.
+    pub fn with_root(mut self, root: Option<PathBuf>) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Returns the path an asset path refers to.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.root {
+            Some(root) => root.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Returns the image stored in a file, decoding it only if it is not cached yet.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the image, e.g. a `.png`.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or decoded.
+    pub fn load_image(&self, path: &Path) -> Result<Arc<DynamicImage>, Error> {
+        let key = fs::canonicalize(self.resolve(path))?;
+        if let Some(image) = self.images.read().unwrap().get(&key) {
+            return Ok(image.clone());
+        }
+        let image = Arc::new(ImageReader::open(&key)?.decode().map_err(Error::other)?);
+        self.images.write().unwrap().insert(key, image.clone());
+        Ok(image)
+    }
+
+    /// Starts loading an image on a worker thread, see [`AssetManager::load_image`].
+    ///
+    /// The image is cached once loaded; an image cached already is ready immediately.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the image, e.g. a `.png`.
+    ///
+    /// # Returns
+    /// A handle resolving to the image, or to the reason loading failed.
+    pub fn load_image_async(&self, path: &Path) -> Handle<Arc<DynamicImage>> {
+        let handle = Handle::loading();
+        let assets = self.clone();
+        let path = path.to_path_buf();
+        let result = handle.clone();
+        rayon::spawn(move || result.finish(assets.load_image(&path)));
+        handle
+    }
+
+    /// Returns the sound stored in a file, decoding it only if it is not cached yet.
+    ///
+    /// Sounds share their samples, so the returned sound is as cheap to keep as a handle.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the sound, e.g. a `.wav`.
+    ///
+    /// # Errors
+    /// Fails for the reasons [`Sound::load`] fails.
+    pub fn load_sound(&self, path: &Path) -> Result<Sound, Error> {
+        let key = fs::canonicalize(self.resolve(path))?;
+        if let Some(sound) = self.sounds.read().unwrap().get(&key) {
+            return Ok(sound.clone());
+        }
+        let sound = Sound::load(&key)?;
+        self.sounds.write().unwrap().insert(key, sound.clone());
+        Ok(sound)
+    }
+
+    /// Starts loading a sound on a worker thread, see [`AssetManager::load_sound`].
+    ///
+    /// The sound is cached once loaded; a sound cached already is ready immediately.
+    ///
+    /// # Returns
+    /// A handle resolving to the sound, or to the reason loading failed.
+    pub fn load_sound_async(&self, path: &Path) -> Handle<Sound> {
+        let handle = Handle::loading();
+        let assets = self.clone();
+        let path = path.to_path_buf();
+        let result = handle.clone();
+        rayon::spawn(move || result.finish(assets.load_sound(&path)));
+        handle
+    }
+
+    /// Returns the number of cached sounds.
+    pub fn sound_count(&self) -> usize {
+        self.sounds.read().unwrap().len()
+    }
+
+    /// Returns the number of cached images.
+    pub fn len(&self) -> usize {
+        self.images.read().unwrap().len()
+    }
+
+    /// Checks whether no images are cached.
+    pub fn is_empty(&self) -> bool {
+        self.images.read().unwrap().is_empty()
+    }
+
+    /// Drops all cached images and sounds, e.g. after their files changed.
+    pub fn clear(&self) {
+        self.images.write().unwrap().clear();
+        self.sounds.write().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_images_are_decoded_once() {
+        let assets = AssetManager::new();
+        let first = assets
+            .load_image(Path::new("resources/perf_diag.png"))
+            .unwrap();
+        let second = assets
+            .clone()
+            .load_image(Path::new("./resources/../resources/perf_diag.png"))
+            .unwrap();
+        assert_eq!(assets.len(), 1);
+        assert!(Arc::ptr_eq(&first, &second));
+        assets.clear();
+        assert!(assets.is_empty());
+    }
+
+    #[test]
+    fn test_async_loads_resolve() {
+        let assets = AssetManager::new();
+        let image = assets.load_image_async(Path::new("resources/perf_diag.png"));
+        let missing = assets.load_image_async(Path::new("resources/missing.png"));
+        assert!(image.wait().is_ok());
+        assert!(!image.is_loading());
+        assert!(image.get().is_some());
+        assert_eq!(assets.len(), 1);
+
+        assert!(missing.wait().is_err());
+        assert!(missing.get().is_none());
+        assert!(missing.error().is_some());
+        assert!(Handle::ready(3).get() == Some(3));
+    }
+
+    #[test]
+    fn test_sounds_are_decoded_once() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_sound_cache");
+        fs::create_dir_all(&dir).unwrap();
+        let wav = [
+            b"RIFF".as_slice(),
+            &38u32.to_le_bytes(),
+            b"WAVEfmt ",
+            &16u32.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &8000u32.to_le_bytes(),
+            &8000u32.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &8u16.to_le_bytes(),
+            b"data",
+            &2u32.to_le_bytes(),
+            &[128, 255],
+        ]
+        .concat();
+        fs::write(dir.join("click.wav"), wav).unwrap();
+
+        let assets = AssetManager::new().with_root(Some(dir.clone()));
+        let first = assets.load_sound(Path::new("click.wav")).unwrap();
+        let second = assets
+            .load_sound_async(&dir.join("./click.wav"))
+            .wait()
+            .unwrap();
+        assert_eq!(first.frames(), 2);
+        assert!(std::ptr::eq(first.samples(), second.samples()));
+        assert_eq!(assets.sound_count(), 1);
+        // Images and sounds are counted apart
+        assert!(assets.is_empty());
+
+        assert!(assets.load_sound(Path::new("missing.wav")).is_err());
+        assert!(
+            assets
+                .load_sound_async(Path::new("missing.wav"))
+                .wait()
+                .is_err()
+        );
+        assets.clear();
+        assert_eq!(assets.sound_count(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_paths_are_resolved_against_root() {
+        let assets = AssetManager::new().with_root(Some(PathBuf::from("resources")));
+        assert_eq!(
+            assets.resolve(Path::new("perf_diag.png")),
+            Path::new("resources/perf_diag.png")
+        );
+        assert!(assets.load_image(Path::new("perf_diag.png")).is_ok());
+        assert_eq!(
+            AssetManager::new().resolve(Path::new("a.png")),
+            Path::new("a.png")
+        );
+    }
+
+    #[test]
+    fn test_missing_and_invalid_files_fail() {
+        let assets = AssetManager::new();
+        assert!(
+            assets
+                .load_image(Path::new("resources/missing.png"))
+                .is_err()
+        );
+        assert!(assets.load_image(Path::new("Cargo.toml")).is_err());
+        assert!(assets.is_empty());
+    }
+}
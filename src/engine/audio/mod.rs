@@ -0,0 +1,1034 @@
+//! Sound mixing into a pluggable output.
+//!
+//! The engine mixes WAV sounds and music into one stream but ships no sound device
+//! backend: the stream goes to an [`AudioOutput`] set by the game, e.g. one writing to
+//! a `cpal` or `rodio` stream, and is dropped by the default [`NullOutput`]. Games stay
+//! silent until an output is set.
+//!
+//! An `Audio` handle plays sounds, each on its own voice, so effects fired in quick
+//! succession overlap instead of cutting each other off. All playing voices are mixed into
+//! one stereo stream at [`SAMPLE_RATE`], whatever the sample rate and channel count of the
+//! sounds; mono sounds play on both channels.
+//!
+//! Sounds and music are mixed on separate [`Bus`]es, both going through the master bus.
+//! Each bus has its own volume and can be muted, as a sound options menu asks for.
+//!
+//! The engine advances the mix with the game time of every update, so sounds stop while
+//! the game is paused and headless runs mix the same samples every time. Without an output
+//! set, samples are mixed and dropped, which keeps voices finishing on time.
+//!
+//! Only WAV files holding uncompressed PCM samples are decoded, see the `wav` module.
+//! Sounds in other formats can be decoded by the game and played with [`Sound::new`].
+//!
+//! Sounds coming from somewhere in the world are played by
+//! [`AudioSource`](crate::engine::scene::game_object::components::audio_source::AudioSource)
+//! components instead; the [`AudioSystem`] sets their volume and balance from where their
+//! objects are relative to the main object.
+//!
+//! Music plays apart from the voices: a [`Music`] track is streamed from its file while it
+//! plays and loops until it is replaced. Starting another track crossfades to it, and the
+//! engine starts the music of a scene when the scene becomes active. As music is mixed with
+//! the game time too, it pauses and resumes with the engine.
+//!
+//! ```no_run
+//! use rusty_ache::engine::Engine;
+//! use rusty_ache::engine::builder::EngineBuilder;
+//! use std::time::Duration;
+//!
+//! let engine = EngineBuilder::new().build();
+//! // Stays silent unless the game sets an output, see `AudioOutput`
+//! engine.audio().play_sound("laser.wav").unwrap();
+//! engine
+//!     .audio()
+//!     .play_music("theme.wav", Duration::from_secs(2))
+//!     .unwrap();
+//! ```
+
+use crate::engine::assets::{AssetManager, Handle};
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod music;
+mod spatial;
+mod wav;
+
+pub use music::Music;
+pub use spatial::AudioSystem;
+
+/// Frames per second of the mixed stream.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Number of voices playing at once; playing another sound stops the oldest voice.
+pub const MAX_VOICES: usize = 32;
+
+/// Time the music of the previous scene takes to fade into the music of the next one,
+/// unless set with [`Audio::set_crossfade`].
+pub const MUSIC_CROSSFADE: Duration = Duration::from_secs(1);
+
+/// Group of sounds whose volume is set together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus {
+    /// Everything played, sounds and music alike.
+    Master,
+    /// Music tracks.
+    Music,
+    /// Sound effects, including those of audio sources.
+    Sfx,
+}
+
+impl Bus {
+    /// All buses, the master bus first.
+    pub const ALL: [Bus; 3] = [Bus::Master, Bus::Music, Bus::Sfx];
+
+    /// Returns the name of the bus, e.g. for an options file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Bus::Master => "master",
+            Bus::Music => "music",
+            Bus::Sfx => "sfx",
+        }
+    }
+
+    /// Returns the bus with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Bus> {
+        Bus::ALL
+            .into_iter()
+            .find(|bus| bus.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Volume setting of a bus.
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    volume: f32,
+    muted: bool,
+}
+
+impl Level {
+    /// Returns the factor the samples of the bus are scaled by.
+    fn gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.volume }
+    }
+}
+
+/// Checks whether a file is a WAV file by its extension.
+///
+/// # Errors
+/// Fails with `ErrorKind::Unsupported` for any other extension.
+fn check_wav(path: &Path) -> Result<(), Error> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("wav") => Ok(()),
+        _ => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "cannot decode {}: only WAV files are supported, other formats can be \
+                 decoded by the game and played with Sound::new",
+                path.display()
+            ),
+        )),
+    }
+}
+
+/// Decoded audio samples, shared by all voices playing them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sound {
+    /// Samples between -1 and 1, interleaved by channel.
+    samples: Arc<[f32]>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Sound {
+    /// Creates a sound from interleaved samples.
+    ///
+    /// # Parameters
+    /// - `samples`: Samples between -1 and 1, one per channel for every frame.
+    /// - `channels`: Number of channels, at least 1.
+    /// - `sample_rate`: Frames per second, at least 1.
+    pub fn new(samples: Vec<f32>, channels: u16, sample_rate: u32) -> Self {
+        Sound {
+            samples: samples.into(),
+            channels: channels.max(1),
+            sample_rate: sample_rate.max(1),
+        }
+    }
+
+    /// Decodes a sound from the contents of a WAV file.
+    ///
+    /// # Errors
+    /// Fails if the bytes are not a WAV file with a supported sample format.
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, Error> {
+        wav::decode(bytes)
+    }
+
+    /// Reads and decodes a sound file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read, is not a `.wav` file, or cannot be decoded.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        check_wav(path)?;
+        Sound::from_wav(&fs::read(path)?)
+    }
+
+    /// Returns the number of channels.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns the number of frames per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the number of frames, i.e. of samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Returns the interleaved samples.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Returns how long the sound plays.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate as f64)
+    }
+
+    /// Returns the left and right sample of a frame; silence past the end.
+    fn frame(&self, index: usize) -> (f32, f32) {
+        let channels = self.channels as usize;
+        match self.samples.get(index * channels..(index + 1) * channels) {
+            Some([mono]) => (*mono, *mono),
+            Some([left, right, ..]) => (*left, *right),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Returns the frame at a fractional position, interpolating between its neighbours.
+    fn frame_at(&self, position: f64) -> (f32, f32) {
+        let index = position as usize;
+        let fraction = (position - index as f64) as f32;
+        let (l0, r0) = self.frame(index);
+        let (l1, r1) = if index + 1 < self.frames() {
+            self.frame(index + 1)
+        } else {
+            (l0, r0)
+        };
+        (l0 + (l1 - l0) * fraction, r0 + (r1 - r0) * fraction)
+    }
+}
+
+/// Destination of the mixed stream, implemented by the game to reach a sound device.
+///
+/// Blocks are written from the engine loop as the game time advances, so an output feeding
+/// a device callback usually queues them, e.g. in a channel drained by the callback:
+///
+/// ```
+/// use rusty_ache::engine::audio::{Audio, AudioOutput};
+/// use std::sync::mpsc::{Sender, channel};
+///
+/// struct Device(Sender<Vec<f32>>);
+///
+/// impl AudioOutput for Device {
+///     fn write(&mut self, samples: &[f32]) {
+///         let _ = self.0.send(samples.to_vec());
+///     }
+/// }
+///
+/// let (sender, _for_the_device_callback) = channel();
+/// Audio::new().set_output(Box::new(Device(sender)));
+/// ```
+pub trait AudioOutput: Send {
+    /// Receives the next block of the stream.
+    ///
+    /// # Parameters
+    /// - `samples`: Stereo frames at [`SAMPLE_RATE`], left and right sample interleaved.
+    fn write(&mut self, samples: &[f32]);
+}
+
+/// Output dropping all samples, used until the game sets another one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullOutput;
+
+impl AudioOutput for NullOutput {
+    fn write(&mut self, _samples: &[f32]) {}
+}
+
+/// Identifies a sound started by [`Audio::play`], e.g. to stop it early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+/// A sound being played.
+struct Voice {
+    id: VoiceId,
+    sound: Sound,
+    /// Position in the sound, in frames of the sound.
+    position: f64,
+    volume: f32,
+    /// Balance between the channels, from -1 for left only to 1 for right only.
+    pan: f32,
+    /// Whether the sound starts over when it ends.
+    looping: bool,
+}
+
+impl Voice {
+    /// Returns the factors the left and right channel are scaled by.
+    ///
+    /// Panning turns the far channel down and keeps the near one, so a centered voice plays
+    /// at its own volume on both channels.
+    fn gains(&self) -> (f32, f32) {
+        (
+            self.volume * (1.0 - self.pan).min(1.0),
+            self.volume * (1.0 + self.pan).min(1.0),
+        )
+    }
+}
+
+/// Left and right sample of a frame.
+type Frame = (f32, f32);
+
+/// A music track being played or faded out.
+struct Track {
+    music: Music,
+    /// Position in the track, in frames of the track.
+    position: f64,
+    /// Frame the position was in last and its samples and those of the frame after it.
+    frames: Option<(u64, Frame, Frame)>,
+    /// Current volume, from 0 while silent to 1.
+    gain: f32,
+    /// Volume the track fades to, 0 to fade it out and stop it.
+    target: f32,
+    /// Change of the volume per mixed frame while fading.
+    fade_step: f32,
+}
+
+impl Track {
+    /// Returns the volume change per frame of a fade taking the given time.
+    fn fade_step(fade: Duration) -> f32 {
+        let frames = fade.as_secs_f64() * SAMPLE_RATE as f64;
+        if frames < 1.0 {
+            1.0
+        } else {
+            (1.0 / frames) as f32
+        }
+    }
+
+    /// Fades the track out for stopping it, at once if the fade takes no time.
+    fn fade_out(&mut self, fade: Duration) {
+        self.target = 0.0;
+        self.fade_step = Track::fade_step(fade);
+        if fade.is_zero() {
+            self.gain = 0.0;
+        }
+    }
+
+    /// Mixes the next frames of the track into a block of stereo frames.
+    ///
+    /// # Returns
+    /// Whether the track still plays, i.e. has neither ended nor faded out.
+    ///
+    /// # Errors
+    /// Fails if the file of the track cannot be read any more.
+    fn mix(&mut self, out: &mut [f32], volume: f32) -> Result<bool, Error> {
+        let step = self.music.sample_rate() as f64 / SAMPLE_RATE as f64;
+        for frame in out.chunks_exact_mut(2) {
+            if self.gain <= 0.0 && self.target <= 0.0 {
+                return Ok(false);
+            }
+            let index = self.position as u64;
+            if index >= self.music.frames() {
+                return Ok(false);
+            }
+            let (current, next) = match self.frames {
+                Some((at, current, next)) if at == index => (current, next),
+                _ => {
+                    let current = self.music.frame(index)?;
+                    let next = match self.music.next_frame(index) {
+                        Some(next) => self.music.frame(next)?,
+                        None => current,
+                    };
+                    self.frames = Some((index, current, next));
+                    (current, next)
+                }
+            };
+            let fraction = (self.position - index as f64) as f32;
+            let gain = self.gain * volume;
+            frame[0] += (current.0 + (next.0 - current.0) * fraction) * gain;
+            frame[1] += (current.1 + (next.1 - current.1) * fraction) * gain;
+
+            self.position += step;
+            if let Some((start, end)) = self.music.loop_points()
+                && self.position >= end as f64
+            {
+                self.position -= (end - start) as f64;
+            }
+            if self.gain < self.target {
+                self.gain = (self.gain + self.fade_step).min(self.target);
+            } else if self.gain > self.target {
+                self.gain = (self.gain - self.fade_step).max(self.target);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// State shared by all handles of an [`Audio`].
+struct Mixer {
+    voices: Vec<Voice>,
+    /// Music tracks; the last one is the current track, the others are fading out.
+    tracks: Vec<Track>,
+    /// Whether the music is held at its position.
+    music_paused: bool,
+    /// Time scene changes crossfade the music over.
+    crossfade: Duration,
+    /// Levels of the buses, in the order of [`Bus::ALL`].
+    levels: [Level; 3],
+    next_id: u64,
+    output: Box<dyn AudioOutput>,
+    /// Fraction of a frame left over from the last advance.
+    carry: f64,
+    /// Block reused for writing to the output.
+    buffer: Vec<f32>,
+}
+
+impl Mixer {
+    /// Mixes the next frames of all voices into a block of stereo frames, dropping the
+    /// voices that finished.
+    fn mix(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        let sfx = self.level(Bus::Sfx).gain();
+        for voice in &mut self.voices {
+            let step = voice.sound.sample_rate as f64 / SAMPLE_RATE as f64;
+            let frames = voice.sound.frames() as f64;
+            let gains = voice.gains();
+            let gains = (gains.0 * sfx, gains.1 * sfx);
+            for frame in out.chunks_exact_mut(2) {
+                if voice.looping && voice.position >= frames && frames > 0.0 {
+                    voice.position %= frames;
+                }
+                if voice.position >= frames {
+                    break;
+                }
+                let (left, right) = voice.sound.frame_at(voice.position);
+                frame[0] += left * gains.0;
+                frame[1] += right * gains.1;
+                voice.position += step;
+            }
+        }
+        if !self.music_paused {
+            let music = self.level(Bus::Music).gain();
+            self.tracks.retain_mut(|track| {
+                track.mix(out, music).unwrap_or_else(|e| {
+                    log::warn!(
+                        "Couldn't stream music {}: {e}",
+                        track.music.path().display()
+                    );
+                    false
+                })
+            });
+        }
+        let master = self.level(Bus::Master).gain();
+        for sample in out.iter_mut() {
+            *sample = (*sample * master).clamp(-1.0, 1.0);
+        }
+        self.voices.retain(|voice| {
+            let frames = voice.sound.frames() as f64;
+            voice.position < frames || (voice.looping && frames > 0.0)
+        });
+    }
+
+    fn level(&self, bus: Bus) -> &Level {
+        &self.levels[bus as usize]
+    }
+
+    fn level_mut(&mut self, bus: Bus) -> &mut Level {
+        &mut self.levels[bus as usize]
+    }
+}
+
+/// [Handle](crate#handles) to the sound playback of the engine.
+///
+/// Scripts, systems and scenes play sounds on the voices and music tracks of one mixer,
+/// which writes to one output.
+#[derive(Clone)]
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+    /// Resolves the paths of sound files.
+    assets: AssetManager,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Audio {
+            mixer: Arc::new(Mutex::new(Mixer {
+                voices: Vec::new(),
+                tracks: Vec::new(),
+                music_paused: false,
+                crossfade: MUSIC_CROSSFADE,
+                levels: [Level {
+                    volume: 1.0,
+                    muted: false,
+                }; 3],
+                next_id: 0,
+                output: Box::new(NullOutput),
+                carry: 0.0,
+                buffer: Vec::new(),
+            })),
+            assets: AssetManager::new(),
+        }
+    }
+}
+
+impl Audio {
+    /// Creates a playback without voices, writing to a [`NullOutput`].
+    pub fn new() -> Self {
+        Audio::default()
+    }
+
+    /// Resolves the paths of sound files like the given asset manager, e.g. against the
+    /// asset root of the config.
+    pub fn with_assets(mut self, assets: AssetManager) -> Self {
+        self.assets = assets;
+        self
+    }
+
+    /// Sends the mixed stream to another output from now on, e.g. to the sound device.
+    ///
+    /// Nothing is heard before an output is set, as the engine has no device backend.
+    pub fn set_output(&self, output: Box<dyn AudioOutput>) {
+        self.mixer.lock().unwrap().output = output;
+    }
+
+    /// Plays a sound file once, decoding it only if it is not cached yet.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the sound, e.g. `"laser.wav"`, relative to the asset root.
+    ///
+    /// # Errors
+    /// Fails for the reasons [`Sound::load`] fails.
+    pub fn play_sound(&self, path: impl AsRef<Path>) -> Result<VoiceId, Error> {
+        let sound = self.assets.load_sound(path.as_ref())?;
+        Ok(self.play(&sound))
+    }
+
+    /// Starts decoding a sound file on a worker thread and caches it, so playing it later
+    /// does not wait for the file.
+    ///
+    /// # Returns
+    /// A handle resolving to the sound, or to the reason loading failed.
+    pub fn preload(&self, path: impl AsRef<Path>) -> Handle<Sound> {
+        self.assets.load_sound_async(path.as_ref())
+    }
+
+    /// Plays a sound once at full volume.
+    pub fn play(&self, sound: &Sound) -> VoiceId {
+        self.play_with_volume(sound, 1.0)
+    }
+
+    /// Plays a sound once.
+    ///
+    /// # Parameters
+    /// - `sound`: Sound to play.
+    /// - `volume`: Factor the samples are scaled by, 1.0 for their own volume.
+    ///
+    /// # Returns
+    /// The voice playing the sound; the oldest voice is stopped if [`MAX_VOICES`] play.
+    pub fn play_with_volume(&self, sound: &Sound, volume: f32) -> VoiceId {
+        self.start(sound, volume, 0.0, false)
+    }
+
+    /// Plays a sound over and over until the voice is stopped.
+    ///
+    /// # Parameters
+    /// - `sound`: Sound to play.
+    /// - `volume`: Factor the samples are scaled by, 1.0 for their own volume.
+    pub fn play_looping(&self, sound: &Sound, volume: f32) -> VoiceId {
+        self.start(sound, volume, 0.0, true)
+    }
+
+    /// Changes the volume and the balance of a voice while it plays; voices that finished
+    /// already are ignored.
+    ///
+    /// # Parameters
+    /// - `id`: Voice to change.
+    /// - `volume`: Factor the samples are scaled by, 1.0 for their own volume.
+    /// - `pan`: Balance between the channels, from -1 for left only through 0 for both to
+    ///   1 for right only.
+    pub fn set_levels(&self, id: VoiceId, volume: f32, pan: f32) {
+        let mut mixer = self.mixer.lock().unwrap();
+        if let Some(voice) = mixer.voices.iter_mut().find(|voice| voice.id == id) {
+            voice.volume = volume.max(0.0);
+            voice.pan = pan.clamp(-1.0, 1.0);
+        }
+    }
+
+    /// Starts a voice, stopping the oldest one if [`MAX_VOICES`] play.
+    pub(crate) fn start(&self, sound: &Sound, volume: f32, pan: f32, looping: bool) -> VoiceId {
+        let mut mixer = self.mixer.lock().unwrap();
+        if mixer.voices.len() >= MAX_VOICES {
+            mixer.voices.remove(0);
+        }
+        let id = VoiceId(mixer.next_id);
+        mixer.next_id += 1;
+        mixer.voices.push(Voice {
+            id,
+            sound: sound.clone(),
+            position: 0.0,
+            volume: volume.max(0.0),
+            pan: pan.clamp(-1.0, 1.0),
+            looping,
+        });
+        id
+    }
+
+    /// Stops a voice; voices that finished already are ignored.
+    pub fn stop(&self, id: VoiceId) {
+        self.mixer
+            .lock()
+            .unwrap()
+            .voices
+            .retain(|voice| voice.id != id);
+    }
+
+    /// Stops all voices.
+    pub fn stop_all(&self) {
+        self.mixer.lock().unwrap().voices.clear();
+    }
+
+    /// Checks whether a voice is still playing.
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.mixer
+            .lock()
+            .unwrap()
+            .voices
+            .iter()
+            .any(|voice| voice.id == id)
+    }
+
+    /// Returns the number of voices playing.
+    pub fn playing(&self) -> usize {
+        self.mixer.lock().unwrap().voices.len()
+    }
+
+    /// Opens a music file and streams it in place of the music playing.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the track, e.g. `"theme.wav"`, relative to the asset root.
+    /// - `fade`: Time the music playing fades out and the new track fades in over.
+    ///
+    /// # Errors
+    /// Fails for the reasons [`Music::open`] fails; the music playing is kept then.
+    pub fn play_music(&self, path: impl AsRef<Path>, fade: Duration) -> Result<(), Error> {
+        let music = Music::open(&self.assets.resolve(path.as_ref()))?;
+        self.play_track(music, fade);
+        Ok(())
+    }
+
+    /// Streams a music track from its start in place of the music playing.
+    ///
+    /// # Parameters
+    /// - `music`: Track to play, looping as it was opened with.
+    /// - `fade`: Time the music playing fades out and the new track fades in over; zero
+    ///   switches at once.
+    pub fn play_track(&self, music: Music, fade: Duration) {
+        let mut mixer = self.mixer.lock().unwrap();
+        for track in &mut mixer.tracks {
+            track.fade_out(fade);
+        }
+        mixer.tracks.push(Track {
+            music,
+            position: 0.0,
+            frames: None,
+            gain: if fade.is_zero() { 1.0 } else { 0.0 },
+            target: 1.0,
+            fade_step: Track::fade_step(fade),
+        });
+    }
+
+    /// Fades out the music playing and stops it.
+    pub fn stop_music(&self, fade: Duration) {
+        for track in &mut self.mixer.lock().unwrap().tracks {
+            track.fade_out(fade);
+        }
+    }
+
+    /// Holds the music at its position until [`Audio::resume_music`], while sounds keep
+    /// playing. Pausing the engine pauses the music anyway.
+    pub fn pause_music(&self) {
+        self.mixer.lock().unwrap().music_paused = true;
+    }
+
+    /// Continues the music held by [`Audio::pause_music`].
+    pub fn resume_music(&self) {
+        self.mixer.lock().unwrap().music_paused = false;
+    }
+
+    /// Checks whether the music is held by [`Audio::pause_music`].
+    pub fn is_music_paused(&self) -> bool {
+        self.mixer.lock().unwrap().music_paused
+    }
+
+    /// Returns the path of the music track playing, None if there is none or it is fading
+    /// out.
+    pub fn music(&self) -> Option<PathBuf> {
+        let mixer = self.mixer.lock().unwrap();
+        let track = mixer.tracks.last().filter(|track| track.target > 0.0)?;
+        Some(track.music.path().to_path_buf())
+    }
+
+    /// Returns the position in the music track playing, which wraps around its loop.
+    pub fn music_position(&self) -> Option<Duration> {
+        let mixer = self.mixer.lock().unwrap();
+        let track = mixer.tracks.last().filter(|track| track.target > 0.0)?;
+        Some(Duration::from_secs_f64(
+            track.position / track.music.sample_rate() as f64,
+        ))
+    }
+
+    /// Returns the time scene changes crossfade the music over.
+    pub fn crossfade(&self) -> Duration {
+        self.mixer.lock().unwrap().crossfade
+    }
+
+    /// Sets the time scene changes crossfade the music over, [`MUSIC_CROSSFADE`] by default.
+    pub fn set_crossfade(&self, fade: Duration) {
+        self.mixer.lock().unwrap().crossfade = fade;
+    }
+
+    /// Sets the volume of a bus, keeping whether it is muted.
+    ///
+    /// # Parameters
+    /// - `bus`: Bus to change.
+    /// - `volume`: Factor the samples of the bus are scaled by, from 0 for silence to 1 for
+    ///   their own volume; values outside are clamped.
+    pub fn set_volume(&self, bus: Bus, volume: f32) {
+        self.mixer.lock().unwrap().level_mut(bus).volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Returns the volume of a bus, 1 unless changed.
+    pub fn volume(&self, bus: Bus) -> f32 {
+        self.mixer.lock().unwrap().level(bus).volume
+    }
+
+    /// Silences a bus or makes it heard again at its volume.
+    ///
+    /// Muted buses keep playing, so unmuting continues sounds and music where they are.
+    pub fn set_muted(&self, bus: Bus, muted: bool) {
+        self.mixer.lock().unwrap().level_mut(bus).muted = muted;
+    }
+
+    /// Checks whether a bus is muted.
+    pub fn is_muted(&self, bus: Bus) -> bool {
+        self.mixer.lock().unwrap().level(bus).muted
+    }
+
+    /// Mixes the next frames of all voices and the music, without writing them to the
+    /// output.
+    ///
+    /// # Parameters
+    /// - `out`: Block of stereo frames to fill, left and right sample interleaved.
+    pub fn mix(&self, out: &mut [f32]) {
+        self.mixer.lock().unwrap().mix(out);
+    }
+
+    /// Mixes the frames played during some game time and writes them to the output.
+    pub(crate) fn advance(&self, elapsed: Duration) {
+        let mut mixer = self.mixer.lock().unwrap();
+        let frames = elapsed.as_secs_f64() * SAMPLE_RATE as f64 + mixer.carry;
+        mixer.carry = frames.fract();
+        let mut buffer = std::mem::take(&mut mixer.buffer);
+        buffer.resize(frames as usize * 2, 0.0);
+        mixer.mix(&mut buffer);
+        mixer.output.write(&buffer);
+        mixer.buffer = buffer;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output keeping every sample written to it.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<f32>>>);
+
+    impl AudioOutput for Capture {
+        fn write(&mut self, samples: &[f32]) {
+            self.0.lock().unwrap().extend_from_slice(samples);
+        }
+    }
+
+    fn tone(value: f32, frames: usize) -> Sound {
+        Sound::new(vec![value; frames], 1, SAMPLE_RATE)
+    }
+
+    #[test]
+    fn test_overlapping_sounds_are_mixed() {
+        let audio = Audio::new();
+        let a = audio.play(&tone(0.25, 4));
+        let b = audio.play_with_volume(&tone(0.5, 2), 0.5);
+        assert_ne!(a, b);
+        assert_eq!(audio.playing(), 2);
+
+        let mut out = [0.0; 6];
+        audio.mix(&mut out);
+        assert_eq!(out, [0.5, 0.5, 0.5, 0.5, 0.25, 0.25]);
+        assert!(audio.is_playing(a));
+        assert!(!audio.is_playing(b));
+
+        audio.mix(&mut out);
+        assert_eq!(out, [0.25, 0.25, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(audio.playing(), 0);
+    }
+
+    #[test]
+    fn test_mix_is_clamped() {
+        let audio = Audio::new();
+        audio.play(&tone(0.75, 1));
+        audio.play(&tone(0.75, 1));
+        let mut out = [0.0; 2];
+        audio.mix(&mut out);
+        assert_eq!(out, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_stereo_and_resampling() {
+        let audio = Audio::new();
+        // Half the output rate: every frame plays twice as long, interpolated
+        audio.play(&Sound::new(vec![0.0, 1.0, 0.5, -1.0], 2, SAMPLE_RATE / 2));
+        let mut out = [0.0; 8];
+        audio.mix(&mut out);
+        assert_eq!(out, [0.0, 1.0, 0.25, 0.0, 0.5, -1.0, 0.5, -1.0]);
+    }
+
+    #[test]
+    fn test_stop_and_voice_limit() {
+        let audio = Audio::new();
+        let first = audio.play(&tone(0.1, 100));
+        let second = audio.play(&tone(0.1, 100));
+        audio.stop(second);
+        assert!(audio.is_playing(first));
+        assert!(!audio.is_playing(second));
+
+        for _ in 0..MAX_VOICES {
+            audio.play(&tone(0.1, 100));
+        }
+        assert_eq!(audio.playing(), MAX_VOICES);
+        assert!(!audio.is_playing(first));
+        audio.stop_all();
+        assert_eq!(audio.playing(), 0);
+    }
+
+    #[test]
+    fn test_looping_voices_and_panning() {
+        let audio = Audio::new();
+        let voice = audio.play_looping(&Sound::new(vec![0.25, 0.5], 1, SAMPLE_RATE), 1.0);
+        let mut out = [0.0; 6];
+        audio.mix(&mut out);
+        assert_eq!(out, [0.25, 0.25, 0.5, 0.5, 0.25, 0.25]);
+
+        audio.set_levels(voice, 2.0, 0.5);
+        audio.mix(&mut out);
+        assert_eq!(out, [0.5, 1.0, 0.25, 0.5, 0.5, 1.0]);
+        audio.set_levels(voice, 1.0, -3.0);
+        audio.mix(&mut out);
+        assert_eq!(out, [0.25, 0.0, 0.5, 0.0, 0.25, 0.0]);
+        assert!(audio.is_playing(voice));
+        audio.stop(voice);
+        assert_eq!(audio.playing(), 0);
+    }
+
+    #[test]
+    fn test_buses_scale_sounds_and_music() {
+        let path = music_file("bus.wav", &[8192; 100]);
+        let audio = Audio::new();
+        audio.play_music(&path, Duration::ZERO).unwrap();
+        audio.play(&tone(0.5, 100));
+        let mut out = [0.0; 2];
+        audio.mix(&mut out);
+        assert_eq!(out, [0.75, 0.75]);
+
+        audio.set_volume(Bus::Music, 0.5);
+        audio.mix(&mut out);
+        assert_eq!(out, [0.625, 0.625]);
+        audio.set_volume(Bus::Master, 0.5);
+        audio.mix(&mut out);
+        assert_eq!(out, [0.3125, 0.3125]);
+
+        audio.set_muted(Bus::Sfx, true);
+        assert!(audio.is_muted(Bus::Sfx));
+        audio.mix(&mut out);
+        assert_eq!(out, [0.0625, 0.0625]);
+        audio.set_muted(Bus::Master, true);
+        audio.mix(&mut out);
+        assert_eq!(out, [0.0, 0.0]);
+        // Muted sounds keep playing
+        assert_eq!(audio.playing(), 1);
+
+        audio.set_muted(Bus::Master, false);
+        audio.set_muted(Bus::Sfx, false);
+        audio.set_volume(Bus::Sfx, 3.0);
+        assert_eq!(audio.volume(Bus::Sfx), 1.0);
+        assert_eq!(audio.volume(Bus::Music), 0.5);
+    }
+
+    #[test]
+    fn test_bus_names() {
+        for bus in Bus::ALL {
+            assert_eq!(Bus::from_name(bus.name()), Some(bus));
+        }
+        assert_eq!(Bus::from_name("SFX"), Some(Bus::Sfx));
+        assert_eq!(Bus::from_name("voice"), None);
+        assert_eq!(Bus::Music.to_string(), "music");
+    }
+
+    #[test]
+    fn test_advance_writes_game_time_to_output() {
+        let audio = Audio::new();
+        let capture = Capture::default();
+        audio.set_output(Box::new(capture.clone()));
+        audio.play(&tone(0.5, SAMPLE_RATE as usize));
+
+        // 10 ms twice, then a third of a frame three times
+        audio.advance(Duration::from_millis(10));
+        audio.advance(Duration::from_millis(10));
+        assert_eq!(capture.0.lock().unwrap().len(), 2 * 882);
+        for _ in 0..3 {
+            audio.advance(Duration::from_secs_f64(1.0 / (3.0 * SAMPLE_RATE as f64)));
+        }
+        assert_eq!(capture.0.lock().unwrap().len(), 2 * 883);
+        assert!(capture.0.lock().unwrap().iter().all(|&s| s == 0.5));
+
+        audio.advance(Duration::from_secs(1));
+        assert_eq!(audio.playing(), 0);
+    }
+
+    /// Writes a mono track at the output rate to a file.
+    fn music_file(name: &str, samples: &[i16]) -> PathBuf {
+        let dir = std::env::temp_dir().join("rusty_ache_test_audio_music");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, wav::encode_pcm16(samples, 1, SAMPLE_RATE)).unwrap();
+        path
+    }
+
+    fn frames(time: u32) -> Duration {
+        Duration::from_secs_f64(time as f64 / SAMPLE_RATE as f64)
+    }
+
+    #[test]
+    fn test_music_loops_after_intro() {
+        let path = music_file("intro.wav", &[0, 1000, 2000, 3000]);
+        let audio = Audio::new();
+        audio.play_track(
+            Music::open(&path).unwrap().with_loop(frames(2), None),
+            Duration::ZERO,
+        );
+        let mut out = [0.0; 16];
+        audio.mix(&mut out);
+        let left: Vec<f32> = out.iter().step_by(2).map(|s| s * 32768.0).collect();
+        assert_eq!(
+            left,
+            [0.0, 1000.0, 2000.0, 3000.0, 2000.0, 3000.0, 2000.0, 3000.0]
+        );
+        assert_eq!(audio.music(), Some(path));
+        assert_eq!(audio.music_position(), Some(frames(2)));
+    }
+
+    #[test]
+    fn test_music_played_once_ends() {
+        let path = music_file("once.wav", &[8192; 3]);
+        let audio = Audio::new();
+        audio.play_track(Music::open(&path).unwrap().without_loop(), Duration::ZERO);
+        let mut out = [0.0; 8];
+        audio.mix(&mut out);
+        assert_eq!(out, [0.25, 0.25, 0.25, 0.25, 0.25, 0.25, 0.0, 0.0]);
+        audio.mix(&mut out);
+        assert_eq!(audio.music(), None);
+    }
+
+    #[test]
+    fn test_tracks_crossfade() {
+        let quiet = music_file("quiet.wav", &[8192; 100]);
+        let loud = music_file("loud.wav", &[16384; 100]);
+        let audio = Audio::new();
+        audio.play_music(&quiet, Duration::ZERO).unwrap();
+        audio.play_music(&loud, frames(4)).unwrap();
+        assert_eq!(audio.music(), Some(loud.clone()));
+
+        let mut out = [0.0; 12];
+        audio.mix(&mut out);
+        let expected = [0.25, 0.3125, 0.375, 0.4375, 0.5, 0.5];
+        for (sample, expected) in out.iter().step_by(2).zip(expected) {
+            assert!((sample - expected).abs() < 1e-3, "{sample} != {expected}");
+        }
+
+        audio.stop_music(Duration::ZERO);
+        assert_eq!(audio.music(), None);
+        audio.mix(&mut out);
+        assert_eq!(out, [0.0; 12]);
+        assert!(audio.play_music("missing.wav", Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_paused_music_holds_its_position() {
+        let path = music_file("paused.wav", &[8192; 100]);
+        let audio = Audio::new();
+        audio.play_music(&path, Duration::ZERO).unwrap();
+        audio.play(&tone(0.5, 2));
+        audio.pause_music();
+        assert!(audio.is_music_paused());
+
+        let mut out = [0.0; 4];
+        audio.mix(&mut out);
+        // Sounds keep playing
+        assert_eq!(out, [0.5; 4]);
+        assert_eq!(audio.music_position(), Some(Duration::ZERO));
+
+        audio.resume_music();
+        audio.mix(&mut out);
+        assert_eq!(out, [0.25; 4]);
+        assert_eq!(audio.music_position(), Some(frames(2)));
+    }
+
+    #[test]
+    fn test_play_sound_reads_wav_files() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_audio");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("beep.wav"),
+            wav::encode_pcm16(&[8192; 10], 1, 8000),
+        )
+        .unwrap();
+        fs::write(dir.join("beep.ogg"), b"OggS").unwrap();
+
+        let audio = Audio::new().with_assets(AssetManager::new().with_root(Some(dir.clone())));
+        let voice = audio.play_sound("beep.wav").unwrap();
+        assert!(audio.is_playing(voice));
+        assert_eq!(
+            audio.play_sound("beep.ogg").unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+        assert!(audio.play_sound("missing.wav").is_err());
+
+        let sound = Sound::load(&dir.join("beep.wav")).unwrap();
+        assert_eq!(sound.frames(), 10);
+        assert_eq!(sound.duration(), Duration::from_micros(1250));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
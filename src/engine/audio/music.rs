@@ -0,0 +1,203 @@
+//! Music tracks streamed from disk.
+//!
+//! Music runs for minutes, so decoding a whole track up front would hold tens of megabytes
+//! of samples. A `Music` keeps its file open instead and decodes one block of frames at a
+//! time, around the position being played.
+
+use super::wav::{self, Layout};
+use std::fs::File;
+use std::io::{BufReader, Error, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Number of frames decoded at once.
+const BLOCK_FRAMES: u64 = 4096;
+
+/// A music track read from a WAV file while it plays.
+///
+/// Tracks loop over their whole length unless told otherwise, as background music does.
+#[derive(Debug)]
+pub struct Music {
+    /// Path the track was opened from.
+    path: PathBuf,
+    reader: BufReader<File>,
+    layout: Layout,
+    /// First and one past the last frame of the looped part; None plays the track once.
+    loop_points: Option<(u64, u64)>,
+    /// Decoded samples of the current block, interleaved by channel.
+    block: Vec<f32>,
+    /// Frame the current block starts at.
+    block_start: u64,
+}
+
+impl Music {
+    /// Opens a WAV file for streaming, reading only the layout of its samples.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read, is not a `.wav` file, or uses a sample format
+    /// other than the supported ones.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        super::check_wav(path)?;
+        let mut reader = BufReader::new(File::open(path)?);
+        let layout = wav::read_layout(&mut reader)?;
+        Ok(Music {
+            path: path.to_path_buf(),
+            reader,
+            layout,
+            loop_points: Some((0, layout.frames)),
+            block: Vec::new(),
+            block_start: 0,
+        })
+    }
+
+    /// Loops part of the track: once the end point is reached, it continues from the start
+    /// point. The part before the start point plays only once, like an intro.
+    ///
+    /// # Parameters
+    /// - `start`: Time the looped part starts at.
+    /// - `end`: Time the looped part ends at; None loops up to the end of the track.
+    ///
+    /// Points past the end of the track are moved to it; an empty looped part plays the
+    /// track once.
+    pub fn with_loop(mut self, start: Duration, end: Option<Duration>) -> Self {
+        let frames = self.layout.frames;
+        let to_frame = |time: Duration| {
+            ((time.as_secs_f64() * self.layout.format.sample_rate as f64).round() as u64)
+                .min(frames)
+        };
+        let end = end.map_or(frames, to_frame);
+        let start = to_frame(start);
+        self.loop_points = (start < end).then_some((start, end));
+        self
+    }
+
+    /// Plays the track once instead of looping it.
+    pub fn without_loop(mut self) -> Self {
+        self.loop_points = None;
+        self
+    }
+
+    /// Returns the path the track was opened from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of frames per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.layout.format.sample_rate
+    }
+
+    /// Returns the number of frames, i.e. of samples per channel.
+    pub fn frames(&self) -> u64 {
+        self.layout.frames
+    }
+
+    /// Returns how long the track plays without looping.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.layout.frames as f64 / self.sample_rate() as f64)
+    }
+
+    /// Returns the looped part as its first and one past its last frame, if the track loops.
+    pub(crate) fn loop_points(&self) -> Option<(u64, u64)> {
+        self.loop_points
+    }
+
+    /// Returns the frame following the given one, wrapping around the looped part; None
+    /// after the last frame of a track played once.
+    pub(crate) fn next_frame(&self, index: u64) -> Option<u64> {
+        match self.loop_points {
+            Some((start, end)) if index + 1 >= end => Some(start),
+            _ => (index + 1 < self.layout.frames).then_some(index + 1),
+        }
+    }
+
+    /// Returns the left and right sample of a frame, decoding its block if needed; silence
+    /// past the end.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read any more.
+    pub(crate) fn frame(&mut self, index: u64) -> Result<(f32, f32), Error> {
+        if index >= self.layout.frames {
+            return Ok((0.0, 0.0));
+        }
+        let channels = self.layout.format.channels as usize;
+        let loaded = (self.block.len() / channels) as u64;
+        if index < self.block_start || index >= self.block_start + loaded {
+            self.read_block(index)?;
+        }
+        let at = (index - self.block_start) as usize * channels;
+        Ok(match &self.block[at..at + channels] {
+            [mono] => (*mono, *mono),
+            [left, right, ..] => (*left, *right),
+            [] => (0.0, 0.0),
+        })
+    }
+
+    /// Decodes the block of frames starting at the given frame.
+    fn read_block(&mut self, start: u64) -> Result<(), Error> {
+        let frame_size = self.layout.format.frame_size() as u64;
+        let frames = BLOCK_FRAMES.min(self.layout.frames - start);
+        let mut bytes = vec![0u8; (frames * frame_size) as usize];
+        self.reader
+            .seek(SeekFrom::Start(self.layout.data_start + start * frame_size))?;
+        self.reader.read_exact(&mut bytes)?;
+        self.block = wav::convert(&self.layout.format, &bytes);
+        self.block_start = start;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn track(name: &str, samples: &[i16]) -> PathBuf {
+        let dir = std::env::temp_dir().join("rusty_ache_test_music");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, wav::encode_pcm16(samples, 1, 10)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_streams_frames_across_blocks() {
+        let samples: Vec<i16> = (0..10_000).map(|i| (i % 100) as i16 * 100).collect();
+        let path = track("long.wav", &samples);
+        let mut music = Music::open(&path).unwrap();
+        assert_eq!(music.frames(), 10_000);
+        assert_eq!(music.duration(), Duration::from_secs(1000));
+        for index in [0, 4095, 4096, 9999, 17, 8191] {
+            let expected = samples[index as usize] as f32 / 32768.0;
+            assert_eq!(music.frame(index).unwrap(), (expected, expected));
+        }
+        assert_eq!(music.frame(10_000).unwrap(), (0.0, 0.0));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_loop_points() {
+        let path = track("loop.wav", &[0; 50]);
+        // Loops over the whole track by default
+        let music = Music::open(&path).unwrap();
+        assert_eq!(music.loop_points(), Some((0, 50)));
+        assert_eq!(music.next_frame(48), Some(49));
+        assert_eq!(music.next_frame(49), Some(0));
+
+        // 10 frames per second
+        let music = music.with_loop(Duration::from_secs(1), Some(Duration::from_secs(3)));
+        assert_eq!(music.loop_points(), Some((10, 30)));
+        assert_eq!(music.next_frame(5), Some(6));
+        assert_eq!(music.next_frame(29), Some(10));
+
+        let music = music.with_loop(Duration::from_secs(2), Some(Duration::from_secs(60)));
+        assert_eq!(music.loop_points(), Some((20, 50)));
+        let music = music.with_loop(Duration::from_secs(9), None);
+        assert_eq!(music.loop_points(), None);
+
+        let music = music.with_loop(Duration::ZERO, None).without_loop();
+        assert_eq!(music.next_frame(48), Some(49));
+        assert_eq!(music.next_frame(49), None);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+//! Positional playback of the audio sources of a scene.
+
+use super::{Audio, VoiceId};
+use crate::engine::input::InputState;
+use crate::engine::scene::game_object::components::audio_source::AudioSource;
+use crate::engine::scene::{MAIN_OBJECT_UID, Scene};
+use crate::engine::system::System;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// System playing the sounds of [`AudioSource`] components from where their objects are.
+///
+/// Every update it starts and stops the sounds of the sources as they ask and sets their
+/// volume and balance from their position relative to the main object, which is the
+/// listener. Sounds of despawned objects stop. The system is registered like any other:
+///
+/// ```no_run
+/// use rusty_ache::engine::Engine;
+/// use rusty_ache::engine::audio::AudioSystem;
+/// use rusty_ache::engine::builder::EngineBuilder;
+///
+/// let mut engine = EngineBuilder::new().build();
+/// engine.add_system(Box::new(AudioSystem::new())).unwrap();
+/// engine.run().unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct AudioSystem {
+    /// Voice of every source heard during the last update, by the UID of its object.
+    voices: HashMap<usize, VoiceId>,
+}
+
+impl AudioSystem {
+    /// Creates a system not playing any source yet.
+    pub fn new() -> Self {
+        AudioSystem::default()
+    }
+}
+
+/// Starts, stops or moves the sound of one source.
+///
+/// # Parameters
+/// - `offset`: Position of the source relative to the listener.
+///
+/// # Returns
+/// The voice of the source if it is heard.
+fn sync(audio: &Audio, source: &mut AudioSource, offset: (f32, f32)) -> Option<VoiceId> {
+    let (volume, pan) = source.levels(offset);
+    if source.take_restart() {
+        if let Some(voice) = source.voice.take() {
+            audio.stop(voice);
+        }
+        source.voice = Some(audio.start(source.sound(), volume, pan, source.looping));
+    } else if !source.is_wanted() {
+        if let Some(voice) = source.voice.take() {
+            audio.stop(voice);
+        }
+    } else if let Some(voice) = source.voice {
+        if audio.is_playing(voice) {
+            audio.set_levels(voice, volume, pan);
+        } else {
+            source.finished();
+        }
+    }
+    source.voice
+}
+
+impl System for AudioSystem {
+    /// Updates the sound of every audio source, the one of the main object included.
+    fn run(&mut self, scene: &mut Scene, _dt: Duration, _input: &InputState) {
+        let audio = scene.audio();
+        let listener = scene.main_object.position;
+        let mut heard = HashMap::new();
+        if let Some(source) = scene.main_object.audio_source_mut()
+            && let Some(voice) = sync(&audio, source, (0.0, 0.0))
+        {
+            heard.insert(MAIN_OBJECT_UID, voice);
+        }
+        for uid in scene.uids() {
+            let Some(position) = scene.world_position(uid) else {
+                continue;
+            };
+            let offset = (
+                (position.x - listener.x) as f32,
+                (position.y - listener.y) as f32,
+            );
+            if let Some(source) = scene
+                .object_mut(uid)
+                .and_then(|object| object.audio_source_mut())
+                && let Some(voice) = sync(&audio, source, offset)
+            {
+                heard.insert(uid, voice);
+            }
+        }
+        // Voices of despawned objects, and of objects replaced under their UID
+        for (uid, voice) in &self.voices {
+            if heard.get(uid) != Some(voice) {
+                audio.stop(*voice);
+            }
+        }
+        self.voices = heard;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::audio::{SAMPLE_RATE, Sound};
+    use crate::engine::scene::game_object::{GameObject, Object, Position};
+
+    fn emitter(source: AudioSource, x: i32, y: i32) -> GameObject {
+        GameObject::new(vec![Box::new(source)], None, Position::new(x, y))
+    }
+
+    fn hum() -> AudioSource {
+        AudioSource::new(Sound::new(vec![0.5; 100], 1, SAMPLE_RATE))
+            .looping()
+            .with_range(100.0)
+    }
+
+    fn tick(system: &mut AudioSystem, scene: &mut Scene) {
+        system.run(scene, Duration::from_millis(10), &InputState::new());
+    }
+
+    fn source(scene: &mut Scene, uid: usize) -> &mut AudioSource {
+        scene.object_mut(uid).unwrap().audio_source_mut().unwrap()
+    }
+
+    /// Mixes one frame of the scene's audio.
+    fn frame(scene: &Scene) -> [f32; 2] {
+        let mut out = [0.0; 2];
+        scene.audio().mix(&mut out);
+        out
+    }
+
+    #[test]
+    fn test_sources_are_heard_from_their_position() {
+        let mut scene =
+            Scene::new(vec![emitter(hum(), 50, 0)], vec![], Position::new(0, 0)).unwrap();
+        let uid = scene.uids()[0];
+        let mut system = AudioSystem::new();
+        tick(&mut system, &mut scene);
+        assert!(source(&mut scene, uid).is_playing());
+        // Half the range away, fully on the right
+        assert_eq!(frame(&scene), [0.0, 0.25]);
+
+        // The listener walks up to the source
+        scene.main_object.add_position((50, 0));
+        tick(&mut system, &mut scene);
+        assert_eq!(frame(&scene), [0.5, 0.5]);
+        scene.main_object.add_position((25, 0));
+        tick(&mut system, &mut scene);
+        let [left, right] = frame(&scene);
+        assert!((left - 0.375).abs() < 1e-6);
+        assert!(right > 0.0 && right < left);
+    }
+
+    #[test]
+    fn test_sources_stop_and_restart() {
+        let once = AudioSource::new(Sound::new(vec![0.5; 2], 1, SAMPLE_RATE));
+        let mut scene = Scene::new(
+            vec![emitter(hum().stopped(), 0, 0), emitter(once, 0, 0)],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uids = scene.uids();
+        let mut system = AudioSystem::new();
+        tick(&mut system, &mut scene);
+        assert_eq!(scene.audio().playing(), 1);
+
+        // The one-shot sound ends on its own
+        frame(&scene);
+        frame(&scene);
+        tick(&mut system, &mut scene);
+        assert!(!source(&mut scene, uids[1]).is_playing());
+
+        source(&mut scene, uids[0]).play();
+        tick(&mut system, &mut scene);
+        assert!(source(&mut scene, uids[0]).is_playing());
+        source(&mut scene, uids[0]).stop();
+        tick(&mut system, &mut scene);
+        assert_eq!(scene.audio().playing(), 0);
+    }
+
+    #[test]
+    fn test_despawned_sources_fall_silent() {
+        let mut scene = Scene::new(
+            vec![emitter(hum(), 10, 0)],
+            vec![Box::new(hum())],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let mut system = AudioSystem::new();
+        tick(&mut system, &mut scene);
+        assert_eq!(scene.audio().playing(), 2);
+        scene.despawn(scene.uids()[0]);
+        tick(&mut system, &mut scene);
+        assert_eq!(scene.audio().playing(), 1);
+        assert!(scene.main_object.audio_source().unwrap().is_playing());
+    }
+}
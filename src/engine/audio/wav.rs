@@ -0,0 +1,240 @@
+//! Decoding of WAV files holding uncompressed PCM samples.
+//!
+//! Integer samples of 8, 16, 24 and 32 bits and 32-bit float samples are supported, with
+//! any number of channels. Chunks other than the format and the data are skipped.
+//!
+//! Whole files are decoded at once for sound effects; music is streamed instead, reading
+//! the layout of the file first and decoding blocks of its data as they are played.
+
+use super::Sound;
+use std::io::{Cursor, Error, ErrorKind, Read, Seek, SeekFrom};
+
+/// Format tag of integer PCM samples.
+const FORMAT_PCM: u16 = 1;
+/// Format tag of IEEE float samples.
+const FORMAT_FLOAT: u16 = 3;
+/// Format tag telling the actual format is given in the extension of the chunk.
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+fn invalid(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid WAV file: {message}"),
+    )
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Sample layout read from the format chunk.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Format {
+    tag: u16,
+    pub(crate) channels: u16,
+    pub(crate) sample_rate: u32,
+    bits: u16,
+}
+
+impl Format {
+    /// Returns the number of bytes of a frame, one sample per channel.
+    pub(crate) fn frame_size(&self) -> usize {
+        self.channels as usize * self.bits.div_ceil(8) as usize
+    }
+}
+
+/// Where the samples of a WAV file are and how they are stored.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Layout {
+    pub(crate) format: Format,
+    /// Offset of the first sample from the start of the file.
+    pub(crate) data_start: u64,
+    /// Number of frames in the data chunk.
+    pub(crate) frames: u64,
+}
+
+/// Reads the chunk headers of a WAV file up to its data chunk.
+///
+/// # Errors
+/// Fails with `ErrorKind::InvalidData` if the file is not a WAV file or uses a sample
+/// format other than the supported ones, or if it cannot be read.
+pub(crate) fn read_layout<R: Read + Seek>(reader: &mut R) -> Result<Layout, Error> {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => invalid("missing RIFF/WAVE header"),
+        _ => e,
+    })?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(invalid("missing RIFF/WAVE header"));
+    }
+    let mut format = None;
+    let mut at = 12u64;
+    loop {
+        let mut chunk = [0u8; 8];
+        reader.seek(SeekFrom::Start(at))?;
+        if reader.read_exact(&mut chunk).is_err() {
+            return Err(invalid(if format.is_some() {
+                "missing data chunk"
+            } else {
+                "missing format chunk"
+            }));
+        }
+        let size = u32_at(&chunk, 4).unwrap_or(0) as u64;
+        let start = at + 8;
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut body = vec![0u8; size.min(64) as usize];
+                reader
+                    .read_exact(&mut body)
+                    .map_err(|_| invalid("short format chunk"))?;
+                format = Some(parse_format(&body)?);
+            }
+            b"data" => {
+                let format = format.ok_or_else(|| invalid("missing format chunk"))?;
+                // A truncated file holds fewer samples than the chunk claims
+                let end = reader.seek(SeekFrom::End(0))?;
+                let size = size.min(end.saturating_sub(start));
+                return Ok(Layout {
+                    format,
+                    data_start: start,
+                    frames: size / format.frame_size() as u64,
+                });
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        at = start + size + (size & 1);
+    }
+}
+
+/// Reads the format chunk and checks its sample format is supported.
+fn parse_format(body: &[u8]) -> Result<Format, Error> {
+    let mut tag = u16_at(body, 0).ok_or_else(|| invalid("short format chunk"))?;
+    if tag == FORMAT_EXTENSIBLE {
+        // The sub-format GUID starts with the actual format tag
+        tag = u16_at(body, 24).ok_or_else(|| invalid("short format chunk"))?;
+    }
+    let format = Format {
+        tag,
+        channels: u16_at(body, 2).ok_or_else(|| invalid("short format chunk"))?,
+        sample_rate: u32_at(body, 4).ok_or_else(|| invalid("short format chunk"))?,
+        bits: u16_at(body, 14).ok_or_else(|| invalid("short format chunk"))?,
+    };
+    if format.channels == 0 || format.sample_rate == 0 {
+        return Err(invalid("no channels or no sample rate"));
+    }
+    match (format.tag, format.bits) {
+        (FORMAT_PCM, 8 | 16 | 24 | 32) | (FORMAT_FLOAT, 32) => Ok(format),
+        (tag, bits) => Err(invalid(&format!(
+            "unsupported sample format {tag} with {bits} bits"
+        ))),
+    }
+}
+
+/// Decodes a WAV file to samples between -1 and 1.
+///
+/// # Errors
+/// Fails with `ErrorKind::InvalidData` if the bytes are not a WAV file or use a sample
+/// format other than the supported ones.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Sound, Error> {
+    let layout = read_layout(&mut Cursor::new(bytes))?;
+    let start = layout.data_start as usize;
+    let end = start + layout.frames as usize * layout.format.frame_size();
+    let samples = convert(&layout.format, &bytes[start..end]);
+    Ok(Sound::new(
+        samples,
+        layout.format.channels,
+        layout.format.sample_rate,
+    ))
+}
+
+/// Converts stored samples to samples between -1 and 1.
+pub(crate) fn convert(format: &Format, data: &[u8]) -> Vec<f32> {
+    match (format.tag, format.bits) {
+        (FORMAT_PCM, 8) => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+        (FORMAT_PCM, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (FORMAT_PCM, 24) => data
+            .chunks_exact(3)
+            .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (FORMAT_PCM, 32) => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (FORMAT_FLOAT, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]).clamp(-1.0, 1.0))
+            .collect(),
+        // Other formats are rejected when reading the layout
+        _ => Vec::new(),
+    }
+}
+
+/// Encodes 16-bit PCM samples as a WAV file, for tests.
+#[cfg(test)]
+pub(crate) fn encode_pcm16(samples: &[i16], channels: u16, sample_rate: u32) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_pcm16() {
+        let bytes = encode_pcm16(&[0, 16384, -32768, 32767], 2, 22050);
+        let sound = decode(&bytes).unwrap();
+        assert_eq!(sound.channels(), 2);
+        assert_eq!(sound.sample_rate(), 22050);
+        assert_eq!(sound.frames(), 2);
+        assert_eq!(sound.samples()[..3], [0.0, 0.5, -1.0]);
+        assert!((sound.samples()[3] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_skips_unknown_chunks() {
+        let mut bytes = encode_pcm16(&[100, 200], 1, 8000);
+        // Insert an odd-sized chunk with its padding byte before the data chunk
+        let list = [b"LIST".as_slice(), &3u32.to_le_bytes(), b"abc\0"].concat();
+        let data_at = bytes.windows(4).position(|w| w == b"data").unwrap();
+        bytes.splice(data_at..data_at, list);
+        let sound = decode(&bytes).unwrap();
+        assert_eq!(sound.frames(), 2);
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert_eq!(
+            decode(b"OggS....").unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        let mut bytes = encode_pcm16(&[0; 4], 1, 8000);
+        // 12-bit samples
+        bytes[34] = 12;
+        assert!(decode(&bytes).is_err());
+    }
+}
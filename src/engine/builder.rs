@@ -0,0 +1,353 @@
+//! Fluent construction of the game engine.
+//!
+//! `EngineBuilder` is the main entry point for starting a game: it collects the
+//! configuration and the initial scene in one chain of calls, instead of filling an
+//! `EngineConfig` and passing it to `GameEngine::new` together with a scene.
+//!
+//! Settings can come from several sources, applied in a fixed order whatever the order
+//! of the calls; later sources take precedence:
+//!
+//! 1. the defaults of `EngineConfig`, or a configuration file given with
+//!    [`EngineBuilder::config_file`],
+//! 2. environment variables such as `RUSTY_ACHE_WIDTH`, read by [`EngineBuilder::env`],
+//! 3. command-line options such as `--fullscreen`, read by [`EngineBuilder::args`],
+//! 4. settings given to the builder in code, e.g. with [`EngineBuilder::resolution`].
+//!
+//! ```no_run
+//! use rusty_ache::engine::Engine;
+//! use rusty_ache::engine::builder::EngineBuilder;
+//! use rusty_ache::interface::load_scene;
+//!
+//! let scene = load_scene("src/bin/resources/scene.toml").unwrap();
+//! let mut engine = EngineBuilder::new()
+//!     .resolution(800, 600)
+//!     .title("My game")
+//!     .scene(scene)
+//!     .vsync(true)
+//!     .build();
+//! engine.run().unwrap();
+//! ```
+
+use crate::Resolution;
+use crate::engine::config::{Config, ConfigOverride, EngineConfig};
+use crate::engine::input::InputMap;
+use crate::engine::scene::Scene;
+use crate::engine::scene::game_object::Position;
+use crate::engine::watchdog::WatchdogSettings;
+use crate::engine::{Engine, GameEngine};
+use crate::render::backend::RenderBackendKind;
+use crate::screen::{HEIGHT, PresentMode, ScalingMode, WIDTH, WindowPosition};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use winit::keyboard::KeyCode;
+
+/// A setting given to the builder in code.
+type Setting = Box<dyn FnOnce(&mut EngineConfig)>;
+
+/// Builder of a `GameEngine`.
+///
+/// Every setting not given keeps the default of [`EngineConfig`]; the resolution
+/// defaults to `WIDTH` x `HEIGHT`, and the scene to an empty one.
+pub struct EngineBuilder {
+    /// Configuration the other sources are applied to.
+    base: EngineConfig,
+    /// Overrides read from environment variables.
+    env: Vec<ConfigOverride>,
+    /// Overrides read from command-line options.
+    args: Vec<ConfigOverride>,
+    /// Settings given in code, in call order.
+    settings: Vec<Setting>,
+    scene: Option<Scene>,
+    /// Scenes registered for switching to them by name.
+    scenes: Vec<(String, Scene)>,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        EngineBuilder::new()
+    }
+}
+
+impl EngineBuilder {
+    /// Creates a builder with the default configuration and no scene.
+    pub fn new() -> Self {
+        EngineBuilder {
+            base: EngineConfig::new(Resolution::new(WIDTH, HEIGHT)),
+            env: vec![],
+            args: vec![],
+            settings: vec![],
+            scene: None,
+            scenes: vec![],
+        }
+    }
+
+    /// Starts from an existing configuration instead of the defaults.
+    pub fn config(mut self, config: EngineConfig) -> Self {
+        self.base = config;
+        self
+    }
+
+    /// Starts from a configuration file instead of the defaults, see [`EngineConfig::from_file`].
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid configuration.
+    pub fn config_file(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(self.config(EngineConfig::from_file(path)?))
+    }
+
+    /// Reads overrides from the environment variables, see [`ConfigOverride::from_env`].
+    ///
+    /// # Errors
+    /// Fails if a variable holds an invalid value.
+    pub fn env(mut self) -> Result<Self, Error> {
+        self.env = ConfigOverride::from_env()?;
+        Ok(self)
+    }
+
+    /// Reads overrides from command-line options, see [`ConfigOverride::from_args`].
+    ///
+    /// # Parameters
+    /// - `args`: Arguments of the program without its name, e.g. `std::env::args().skip(1)`.
+    ///
+    /// # Errors
+    /// Fails if an option has an invalid value.
+    pub fn args(mut self, args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+        self.args = ConfigOverride::from_args(args)?;
+        Ok(self)
+    }
+
+    /// Records a setting given in code.
+    fn setting(mut self, setting: impl FnOnce(&mut EngineConfig) + 'static) -> Self {
+        self.settings.push(Box::new(setting));
+        self
+    }
+
+    /// Sets the size of the rendered frames in pixels.
+    pub fn resolution(self, width: u32, height: u32) -> Self {
+        self.setting(move |config| config.set_resolution(width, height))
+    }
+
+    /// Sets the title of the game window.
+    pub fn title(self, title: impl Into<String>) -> Self {
+        let title = title.into();
+        self.setting(move |config| config.set_window_title(title))
+    }
+
+    /// Sets the image file used as the window icon.
+    pub fn icon(self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        self.setting(move |config| config.set_window_icon(Some(path)))
+    }
+
+    /// Sets the scene shown when the engine starts.
+    pub fn scene(mut self, scene: Scene) -> Self {
+        self.scene = Some(scene);
+        self
+    }
+
+    /// Registers a scene the engine can switch to by name, see [`Engine::switch_scene`].
+    pub fn named_scene(mut self, name: impl Into<String>, scene: Scene) -> Self {
+        self.scenes.push((name.into(), scene));
+        self
+    }
+
+    /// Chooses between presenting on display refresh and presenting immediately.
+    pub fn vsync(self, vsync: bool) -> Self {
+        self.present_mode(if vsync {
+            PresentMode::Vsync
+        } else {
+            PresentMode::NoVsync
+        })
+    }
+
+    /// Sets how frames are presented to the window.
+    pub fn present_mode(self, present_mode: PresentMode) -> Self {
+        self.setting(move |config| config.set_present_mode(present_mode))
+    }
+
+    /// Sets how frames are mapped onto the window surface.
+    pub fn scaling(self, scaling: ScalingMode) -> Self {
+        self.setting(move |config| config.set_scaling_mode(scaling))
+    }
+
+    /// Opens the window covering its whole monitor, or windowed.
+    pub fn fullscreen(self, fullscreen: bool) -> Self {
+        self.setting(move |config| config.set_fullscreen(fullscreen))
+    }
+
+    /// Sets the backend drawing the frames.
+    pub fn backend(self, backend: RenderBackendKind) -> Self {
+        self.setting(move |config| config.set_render_backend(backend))
+    }
+
+    /// Sets the frame rate the engine loop is paced to; None leaves it uncapped.
+    pub fn target_fps(self, fps: Option<u32>) -> Self {
+        self.setting(move |config| config.set_target_fps(fps))
+    }
+
+    /// Sets the updates per second of the game logic.
+    pub fn update_rate(self, rate: u32) -> Self {
+        self.setting(move |config| config.set_update_rate(rate))
+    }
+
+    /// Sets the key exiting the engine; None disables quitting by key.
+    pub fn quit_key(self, key: Option<KeyCode>) -> Self {
+        self.setting(move |config| config.set_quit_key(key))
+    }
+
+    /// Sets where the game window opens.
+    pub fn window_position(self, position: WindowPosition) -> Self {
+        self.setting(move |config| config.set_window_position(position))
+    }
+
+    /// Sets the bindings of the input actions.
+    pub fn input_map(self, map: InputMap) -> Self {
+        self.setting(move |config| config.set_input_map(map))
+    }
+
+    /// Sets the directory relative asset paths are resolved against.
+    pub fn asset_root(self, root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        self.setting(move |config| config.set_asset_root(Some(root)))
+    }
+
+    /// Writes the frame time histogram to the given file when the engine shuts down.
+    ///
+    /// A `.json` extension selects JSON, any other file is written as CSV.
+    pub fn frame_histogram(self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        self.setting(move |config| config.set_frame_histogram_path(Some(path)))
+    }
+
+    /// Sets how the watchdog reacts to a stalled engine loop; None disables it.
+    pub fn watchdog(self, settings: Option<WatchdogSettings>) -> Self {
+        self.setting(move |config| config.set_watchdog(settings))
+    }
+
+    /// Layers all sources into the final configuration.
+    fn resolve_config(
+        base: EngineConfig,
+        overrides: Vec<ConfigOverride>,
+        settings: Vec<Setting>,
+    ) -> EngineConfig {
+        let mut config = base;
+        for setting in overrides {
+            setting.apply(&mut config);
+        }
+        for setting in settings {
+            setting(&mut config);
+        }
+        config
+    }
+
+    /// Creates the engine.
+    ///
+    /// # Returns
+    /// An engine ready to run, showing the given scene or an empty one.
+    pub fn build(self) -> GameEngine {
+        let overrides = self.env.into_iter().chain(self.args).collect();
+        let config = EngineBuilder::resolve_config(self.base, overrides, self.settings);
+        let scene = self.scene.unwrap_or_else(|| {
+            Scene::new(
+                vec![],
+                vec![],
+                Position {
+                    x: 0,
+                    y: 0,
+                    z: 0,
+                    is_relative: false,
+                },
+            )
+            .expect("an empty scene always fits")
+        });
+        let mut engine = GameEngine::new(Box::new(config), scene);
+        for (name, scene) in self.scenes {
+            engine.add_scene(&name, scene);
+        }
+        engine
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_applies_settings() {
+        let builder = EngineBuilder::new()
+            .resolution(320, 240)
+            .title("Test")
+            .vsync(false)
+            .target_fps(None)
+            .quit_key(None);
+        let config = EngineBuilder::resolve_config(builder.base, vec![], builder.settings);
+        let resolution = config.get_resolution();
+        assert_eq!((resolution.width, resolution.height), (320, 240));
+        assert_eq!(config.get_window_title(), "Test");
+        assert_eq!(config.get_present_mode(), PresentMode::NoVsync);
+        assert_eq!(config.get_target_fps(), None);
+        assert_eq!(config.get_quit_key(), None);
+    }
+
+    #[test]
+    fn test_sources_are_layered_by_precedence() {
+        let builder = EngineBuilder::new()
+            .title("From code")
+            .args(["--width=640", "--title=From args", "--fullscreen"].map(String::from))
+            .unwrap()
+            .config(EngineConfig::from_toml("[window]\nwidth = 100\nheight = 50\n").unwrap());
+        let overrides = builder.env.into_iter().chain(builder.args).collect();
+        let config = EngineBuilder::resolve_config(builder.base, overrides, builder.settings);
+        let resolution = config.get_resolution();
+        assert_eq!((resolution.width, resolution.height), (640, 50));
+        assert_eq!(config.get_window_title(), "From code");
+        assert!(config.get_fullscreen());
+        assert!(
+            EngineBuilder::new()
+                .args(["--height=tall".to_string()])
+                .is_err()
+        );
+        assert!(
+            EngineBuilder::new()
+                .config_file("missing_config.toml")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_build_renders_scene() {
+        let mut engine = EngineBuilder::new()
+            .resolution(4, 3)
+            .asset_root("resources")
+            .build();
+        assert!(
+            engine
+                .assets()
+                .load_image(Path::new("perf_diag.png"))
+                .is_ok()
+        );
+        assert_eq!(
+            engine.step(std::time::Duration::ZERO).unwrap().len(),
+            4 * 3 * 4
+        );
+    }
+
+    #[test]
+    fn test_build_registers_named_scenes() {
+        let empty = || {
+            let position = Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            };
+            Scene::new(vec![], vec![], position).unwrap()
+        };
+        let mut engine = EngineBuilder::new()
+            .scene(empty())
+            .named_scene("menu", empty())
+            .build();
+        assert!(engine.switch_scene("menu").is_ok());
+        assert!(engine.switch_scene("menu").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Publish/subscribe event bus of the game.
+//!
+//! Game modules, such as scripts, systems and UI code, communicate through an `EventBus`
+//! without holding references to each other: anyone can publish a `GameEvent`, and every
+//! `Subscription` receives its own copy of the events published after it was made.
+//! The engine itself publishes key presses, spawned and despawned objects and scene changes;
+//! the collision system publishes the starts and ends of overlaps between colliders.
+
+use crate::engine::scene::game_object::components::collider::Aabb;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use winit::keyboard::KeyCode;
+
+/// Most events a subscription keeps; older ones are dropped if it is not drained.
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+/// An event sent over the event bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The colliders of two objects, given by their UIDs with `a < b`, started to overlap.
+    ///
+    /// `overlap` is the world area shared by their bounding boxes.
+    CollisionStarted { a: usize, b: usize, overlap: Aabb },
+    /// The colliders of two objects, given by their UIDs with `a < b`, stopped overlapping,
+    /// or one of the objects lost its collider or was removed.
+    CollisionEnded { a: usize, b: usize },
+    /// The collider of the object `other` started to overlap the trigger zone of the
+    /// object `trigger`, both given by their UIDs.
+    TriggerEntered { trigger: usize, other: usize },
+    /// The collider of the object `other` left the trigger zone of the object `trigger`,
+    /// or one of the objects lost its collider or was removed.
+    TriggerExited { trigger: usize, other: usize },
+    /// A key was pressed; published once per press, at the start of the update.
+    KeyPressed(KeyCode),
+    /// An object was added to the active scene under the given UID.
+    ObjectSpawned(usize),
+    /// The object with the given UID was removed from the active scene.
+    ObjectDespawned(usize),
+    /// Another scene became the active one.
+    SceneChanged,
+    /// An event defined by the game, identified by its name.
+    Custom(String),
+}
+
+/// Queue of events received by one subscription.
+type EventQueue = Mutex<VecDeque<GameEvent>>;
+
+/// [Handle](crate#handles) to the event bus.
+///
+/// Events published through any clone reach the subscriptions made through every clone.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<RwLock<Vec<Weak<EventQueue>>>>,
+}
+
+impl EventBus {
+    /// Creates a bus without subscriptions.
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    /// Sends an event to every live subscription.
+    pub fn publish(&self, event: GameEvent) {
+        let mut subscribers = self.subscribers.write().unwrap();
+        subscribers.retain(|subscriber| {
+            let Some(queue) = subscriber.upgrade() else {
+                return false;
+            };
+            let mut queue = queue.lock().unwrap();
+            if queue.len() == MAX_QUEUED_EVENTS {
+                queue.pop_front();
+            }
+            queue.push_back(event.clone());
+            true
+        });
+    }
+
+    /// Subscribes to the events published from now on.
+    ///
+    /// Dropping the subscription unsubscribes.
+    pub fn subscribe(&self) -> Subscription {
+        let queue = Arc::new(EventQueue::default());
+        self.subscribers
+            .write()
+            .unwrap()
+            .push(Arc::downgrade(&queue));
+        Subscription { queue }
+    }
+}
+
+/// Receiver of the events published on a bus.
+pub struct Subscription {
+    queue: Arc<EventQueue>,
+}
+
+impl Subscription {
+    /// Removes and returns the received events, oldest first.
+    pub fn drain(&self) -> Vec<GameEvent> {
+        self.queue.lock().unwrap().drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_subscription_receives_events() {
+        let bus = EventBus::new();
+        bus.publish(GameEvent::SceneChanged);
+        let first = bus.subscribe();
+        let second = bus.clone().subscribe();
+
+        bus.publish(GameEvent::ObjectSpawned(3));
+        bus.clone().publish(GameEvent::Custom("door_opened".into()));
+        let expected = vec![
+            GameEvent::ObjectSpawned(3),
+            GameEvent::Custom("door_opened".into()),
+        ];
+        assert_eq!(first.drain(), expected);
+        assert_eq!(second.drain(), expected);
+        assert!(first.drain().is_empty());
+    }
+
+    #[test]
+    fn test_dropped_subscription_unsubscribes() {
+        let bus = EventBus::new();
+        let subscription = bus.subscribe();
+        drop(bus.subscribe());
+        bus.publish(GameEvent::KeyPressed(KeyCode::Space));
+        assert_eq!(bus.subscribers.read().unwrap().len(), 1);
+        assert_eq!(
+            subscription.drain(),
+            vec![GameEvent::KeyPressed(KeyCode::Space)]
+        );
+    }
+
+    #[test]
+    fn test_undrained_subscription_keeps_latest_events() {
+        let bus = EventBus::new();
+        let subscription = bus.subscribe();
+        for uid in 0..MAX_QUEUED_EVENTS + 1 {
+            bus.publish(GameEvent::ObjectDespawned(uid));
+        }
+        let events = subscription.drain();
+        assert_eq!(events.len(), MAX_QUEUED_EVENTS);
+        assert_eq!(events[0], GameEvent::ObjectDespawned(1));
+    }
+}
@@ -0,0 +1,720 @@
+//! Collision detection between the colliders of a scene.
+//!
+//! The `CollisionSystem` tests the `Collider` components of all objects once per update
+//! and publishes a `GameEvent::CollisionStarted` on the scene's event bus when two of them
+//! start to overlap, and a `GameEvent::CollisionEnded` when they stop. Gameplay code
+//! subscribes to the bus instead of testing shapes itself. The main object takes part
+//! under [`MAIN_OBJECT_UID`](crate::engine::scene::MAIN_OBJECT_UID).
+//!
+//! Trigger colliders do not collide: an object overlapping a trigger zone publishes a
+//! `GameEvent::TriggerEntered` when it enters and a `GameEvent::TriggerExited` when it
+//! leaves instead. Two overlapping trigger zones report nothing.
+//!
+//! Instead of testing every pair of colliders, the system first sorts their bounding boxes
+//! into a uniform grid over world space and only tests pairs sharing a grid cell, so an
+//! update costs about n·k tests for n colliders with k neighbours each instead of n².
+//! The cell size should be about the size of a typical collider.
+//!
+//! Colliders using a pixel mask are tested pixel by pixel once their shapes overlap: the
+//! object collides only where non-transparent pixels of its sprite lie inside its shape.
+//! The mask of every sprite image is built once and shared by all sprites showing it.
+//!
+//! The system is registered like any other, usually after the systems moving objects:
+//!
+//! ```no_run
+//! use rusty_ache::engine::Engine;
+//! use rusty_ache::engine::builder::EngineBuilder;
+//! use rusty_ache::engine::collision::CollisionSystem;
+//!
+//! let mut engine = EngineBuilder::new().build();
+//! engine.add_system(Box::new(CollisionSystem::new())).unwrap();
+//! engine.run().unwrap();
+//! ```
+
+use crate::engine::bus::GameEvent;
+use crate::engine::input::InputState;
+use crate::engine::scene::game_object::GameObject;
+use crate::engine::scene::game_object::components::collider::{Aabb, WorldShape};
+use crate::engine::scene::game_object::components::sprite::Sprite;
+use crate::engine::scene::{MAIN_OBJECT_UID, Scene};
+use crate::engine::system::System;
+use image::{DynamicImage, GenericImageView};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Edge length of the grid cells in world units unless set otherwise.
+pub const DEFAULT_CELL_SIZE: u32 = 64;
+
+/// Uniform grid over world space, sorting rectangles into the cells they cover.
+pub(crate) struct SpatialGrid {
+    cell_size: i32,
+    /// Indices of the rectangles covering each cell.
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// Creates an empty grid with cells of the given edge length, at least 1.
+    pub(crate) fn new(cell_size: u32) -> Self {
+        SpatialGrid {
+            cell_size: cell_size.clamp(1, i32::MAX as u32) as i32,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Returns the range of cells covered by a rectangle, both ends included.
+    fn cell_range(&self, area: &Aabb) -> ((i32, i32), (i32, i32)) {
+        let cell = |v: i32| v.div_euclid(self.cell_size);
+        // The right and bottom edges do not belong to the rectangle
+        (
+            (cell(area.left), cell(area.bottom + 1)),
+            (
+                cell((area.right - 1).max(area.left)),
+                cell(area.top.max(area.bottom + 1)),
+            ),
+        )
+    }
+
+    /// Adds a rectangle to every cell it covers.
+    ///
+    /// # Parameters
+    /// - `index`: Index identifying the rectangle.
+    /// - `area`: World area of the rectangle.
+    pub(crate) fn insert(&mut self, index: usize, area: &Aabb) {
+        let ((x0, y0), (x1, y1)) = self.cell_range(area);
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Returns all pairs of rectangles sharing at least one cell, the smaller index first,
+    /// in ascending order.
+    pub(crate) fn candidate_pairs(&self) -> BTreeSet<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        for indices in self.cells.values() {
+            for (i, &a) in indices.iter().enumerate() {
+                for &b in &indices[i + 1..] {
+                    pairs.insert((a.min(b), a.max(b)));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Bit mask of the non-transparent pixels of an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollisionMask {
+    width: u32,
+    height: u32,
+    /// One bit per pixel, row by row.
+    bits: Vec<u64>,
+}
+
+impl CollisionMask {
+    /// Builds the mask of an image, setting the bits of all pixels that are not fully
+    /// transparent.
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let (width, height) = image.dimensions();
+        let mut bits = vec![0u64; (width as usize * height as usize).div_ceil(64)];
+        for (x, y, pixel) in image.pixels() {
+            if pixel.0[3] != 0 {
+                let index = y as usize * width as usize + x as usize;
+                bits[index / 64] |= 1 << (index % 64);
+            }
+        }
+        CollisionMask {
+            width,
+            height,
+            bits,
+        }
+    }
+
+    /// Returns the width and height of the mask in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Checks whether a pixel is set; pixels outside the image are not.
+    ///
+    /// # Parameters
+    /// - `x`: Column of the pixel, from the left.
+    /// - `y`: Row of the pixel, from the top.
+    pub fn is_set(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return false;
+        }
+        let index = y as usize * self.width as usize + x as usize;
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+
+/// A pixel mask where the sprite it was built from is shown.
+#[derive(Debug, Clone)]
+struct PlacedMask {
+    mask: Arc<CollisionMask>,
+    /// World position of the top-left corner of the sprite.
+    origin: (i32, i32),
+}
+
+impl PlacedMask {
+    /// Checks whether a non-transparent sprite pixel is shown at a world point.
+    fn covers(&self, x: i32, y: i32) -> bool {
+        // Sprite rows grow downwards from the top-left corner
+        self.mask.is_set(x - self.origin.0, self.origin.1 - y)
+    }
+}
+
+/// Colliders of a scene ready for testing.
+struct Shapes {
+    /// Shapes with their UIDs, in ascending UID order.
+    list: Vec<(usize, WorldShape)>,
+    /// UIDs of the trigger colliders.
+    triggers: BTreeSet<usize>,
+    /// Pixel masks of the colliders using one; None for objects without a sprite image,
+    /// which show no pixels and so collide nowhere.
+    masks: HashMap<usize, Option<PlacedMask>>,
+}
+
+impl Shapes {
+    /// Checks whether a world point is covered by the collider of an object.
+    fn covers(&self, uid: usize, shape: &WorldShape, x: i32, y: i32) -> bool {
+        shape.contains(x, y)
+            && match self.masks.get(&uid) {
+                None => true,
+                Some(mask) => mask.as_ref().is_some_and(|mask| mask.covers(x, y)),
+            }
+    }
+
+    /// Tests an overlap of two shapes pixel by pixel if either uses a pixel mask.
+    ///
+    /// # Parameters
+    /// - `a`, `b`: Indices of the shapes in the list.
+    /// - `area`: Shared area of their bounding boxes.
+    fn confirm(&self, a: usize, b: usize, area: &Aabb) -> bool {
+        let ((uid_a, shape_a), (uid_b, shape_b)) = (&self.list[a], &self.list[b]);
+        if !self.masks.contains_key(uid_a) && !self.masks.contains_key(uid_b) {
+            return true;
+        }
+        (area.bottom + 1..=area.top).any(|y| {
+            (area.left..area.right)
+                .any(|x| self.covers(*uid_a, shape_a, x, y) && self.covers(*uid_b, shape_b, x, y))
+        })
+    }
+}
+
+/// An overlap of two colliders: their UIDs, the smaller one first, and the shared area of
+/// their bounding boxes.
+pub type Contact = ((usize, usize), Aabb);
+
+/// System publishing the starts and ends of overlaps between colliders.
+#[derive(Debug)]
+pub struct CollisionSystem {
+    /// Pairs of UIDs overlapping after the previous update, the smaller UID first.
+    active: BTreeMap<(usize, usize), Aabb>,
+    /// Objects inside trigger zones after the previous update, as (trigger, other) UIDs.
+    inside: BTreeSet<(usize, usize)>,
+    /// Edge length of the broadphase grid cells.
+    cell_size: u32,
+    /// Pixel masks of the sprite images seen so far, by image address. The images are
+    /// kept so their addresses are not reused while cached.
+    masks: HashMap<usize, (Arc<DynamicImage>, Arc<CollisionMask>)>,
+}
+
+impl Default for CollisionSystem {
+    fn default() -> Self {
+        CollisionSystem {
+            active: BTreeMap::new(),
+            inside: BTreeSet::new(),
+            cell_size: DEFAULT_CELL_SIZE,
+            masks: HashMap::new(),
+        }
+    }
+}
+
+impl CollisionSystem {
+    /// Creates a system aware of no overlaps yet, with grid cells of [`DEFAULT_CELL_SIZE`].
+    pub fn new() -> Self {
+        CollisionSystem::default()
+    }
+
+    /// Sets the edge length of the broadphase grid cells, at least 1.
+    ///
+    /// Cells much smaller than the colliders make every collider cover many cells, cells
+    /// much larger than them put many colliders that are far apart into the same cell.
+    pub fn with_cell_size(mut self, cell_size: u32) -> Self {
+        self.cell_size = cell_size.max(1);
+        self
+    }
+
+    /// Checks whether two objects with solid colliders overlapped at the last update.
+    pub fn is_colliding(&self, a: usize, b: usize) -> bool {
+        self.active.contains_key(&(a.min(b), a.max(b)))
+    }
+
+    /// Returns the overlaps of solid colliders found at the last update, ordered by UIDs.
+    pub fn contacts(&self) -> Vec<Contact> {
+        self.active
+            .iter()
+            .map(|(&pair, &area)| (pair, area))
+            .collect()
+    }
+
+    /// Checks whether an object was inside a trigger zone at the last update.
+    ///
+    /// # Parameters
+    /// - `trigger`: UID of the object with the trigger collider.
+    /// - `other`: UID of the object with a solid collider.
+    pub fn is_inside(&self, trigger: usize, other: usize) -> bool {
+        self.inside.contains(&(trigger, other))
+    }
+
+    /// Returns the number of sprite images whose pixel masks are cached.
+    pub fn cached_masks(&self) -> usize {
+        self.masks.len()
+    }
+
+    /// Returns the pixel mask of an image, building it on first use.
+    fn mask(&mut self, image: &Arc<DynamicImage>) -> Arc<CollisionMask> {
+        let (_, mask) = self
+            .masks
+            .entry(Arc::as_ptr(image) as usize)
+            .or_insert_with(|| (image.clone(), Arc::new(CollisionMask::from_image(image))));
+        mask.clone()
+    }
+
+    /// Places the pixel mask of the first sprite of an object at its world position.
+    fn placed_mask(&mut self, object: &GameObject, position: (i32, i32)) -> Option<PlacedMask> {
+        let (image, offset) = object.components.iter().find_map(|component| {
+            let sprite = component.as_any().downcast_ref::<Sprite>()?;
+            Some((sprite.image.clone()?, sprite.offset))
+        })?;
+        Some(PlacedMask {
+            mask: self.mask(&image),
+            origin: (position.0 + offset.0, position.1 + offset.1),
+        })
+    }
+
+    /// Collects the world shapes of all objects with a collider, main object included.
+    fn shapes(&mut self, scene: &Scene) -> Shapes {
+        let mut shapes = Shapes {
+            list: Vec::new(),
+            triggers: BTreeSet::new(),
+            masks: HashMap::new(),
+        };
+        for (uid, collider, shape) in scene.colliders() {
+            if collider.trigger {
+                shapes.triggers.insert(uid);
+            }
+            if collider.pixel_mask {
+                let (object, position) = if uid == MAIN_OBJECT_UID {
+                    (Some(&scene.main_object), Some(scene.main_object.position))
+                } else {
+                    (scene.object(uid), scene.world_position(uid))
+                };
+                let placed = object.zip(position).and_then(|(object, position)| {
+                    self.placed_mask(object, (position.x, position.y))
+                });
+                shapes.masks.insert(uid, placed);
+            }
+            shapes.list.push((uid, shape));
+        }
+        // Forget images no sprite shows anymore
+        self.masks
+            .retain(|_, (image, _)| Arc::strong_count(image) > 1);
+        shapes
+    }
+
+    /// Finds all overlapping pairs of shapes.
+    ///
+    /// Only shapes whose bounding boxes share a grid cell are tested against each other.
+    ///
+    /// # Parameters
+    /// - `shapes`: Shapes with their UIDs, in ascending UID order.
+    /// - `confirm`: Final test of a pair of overlapping shapes, given by their indices and
+    ///   the shared area of their bounding boxes.
+    fn overlaps(
+        &self,
+        shapes: &[(usize, WorldShape)],
+        confirm: impl Fn(usize, usize, &Aabb) -> bool,
+    ) -> BTreeMap<(usize, usize), Aabb> {
+        let bounds: Vec<Aabb> = shapes.iter().map(|(_, shape)| shape.bounds()).collect();
+        let mut grid = SpatialGrid::new(self.cell_size);
+        for (i, area) in bounds.iter().enumerate() {
+            grid.insert(i, area);
+        }
+        let mut overlaps = BTreeMap::new();
+        for (i, j) in grid.candidate_pairs() {
+            let ((a, shape_a), (b, shape_b)) = (&shapes[i], &shapes[j]);
+            if shape_a.intersects(shape_b)
+                && let Some(area) = bounds[i].intersection(&bounds[j])
+                && confirm(i, j, &area)
+            {
+                overlaps.insert((*a, *b), area);
+            }
+        }
+        overlaps
+    }
+}
+
+impl System for CollisionSystem {
+    /// Tests all colliders and publishes the overlaps that started or ended since the
+    /// previous update; ended ones first, collisions before triggers, each ordered by UIDs.
+    fn run(&mut self, scene: &mut Scene, _dt: Duration, _input: &InputState) {
+        let shapes = self.shapes(scene);
+        let mut current = BTreeMap::new();
+        let mut inside = BTreeSet::new();
+        let overlaps = self.overlaps(&shapes.list, |a, b, area| shapes.confirm(a, b, area));
+        for ((a, b), area) in overlaps {
+            match (shapes.triggers.contains(&a), shapes.triggers.contains(&b)) {
+                (false, false) => {
+                    current.insert((a, b), area);
+                }
+                (true, false) => {
+                    inside.insert((a, b));
+                }
+                (false, true) => {
+                    inside.insert((b, a));
+                }
+                (true, true) => {}
+            }
+        }
+        let events = scene.events();
+        for &(a, b) in self.active.keys() {
+            if !current.contains_key(&(a, b)) {
+                events.publish(GameEvent::CollisionEnded { a, b });
+            }
+        }
+        for &(trigger, other) in self.inside.difference(&inside) {
+            events.publish(GameEvent::TriggerExited { trigger, other });
+        }
+        for (&(a, b), &overlap) in &current {
+            if !self.active.contains_key(&(a, b)) {
+                events.publish(GameEvent::CollisionStarted { a, b, overlap });
+            }
+        }
+        for &(trigger, other) in inside.difference(&self.inside) {
+            events.publish(GameEvent::TriggerEntered { trigger, other });
+        }
+        self.active = current;
+        self.inside = inside;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::bus::EventBus;
+    use crate::engine::scene::game_object::components::collider::Collider;
+    use crate::engine::scene::game_object::{GameObject, Object, Position};
+
+    fn boxed(x: i32, y: i32, size: u32) -> GameObject {
+        GameObject::new(
+            vec![Box::new(Collider::new((size, size), (0, 0)))],
+            None,
+            Position::new(x, y),
+        )
+    }
+
+    fn scene_with(objects: Vec<GameObject>) -> (Scene, EventBus) {
+        let mut scene = Scene::new(objects, vec![], Position::new(100, 100)).unwrap();
+        let bus = EventBus::new();
+        scene.set_event_bus(bus.clone());
+        (scene, bus)
+    }
+
+    fn step(system: &mut CollisionSystem, scene: &mut Scene) {
+        system.run(scene, Duration::from_millis(10), &InputState::new());
+    }
+
+    #[test]
+    fn test_overlaps_start_and_end_once() {
+        let (mut scene, bus) = scene_with(vec![boxed(0, 0, 10), boxed(5, -5, 10), boxed(50, 0, 4)]);
+        let events = bus.subscribe();
+        let uids = scene.uids();
+        let mut system = CollisionSystem::new();
+
+        step(&mut system, &mut scene);
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::CollisionStarted {
+                a: uids[0],
+                b: uids[1],
+                overlap: Aabb {
+                    left: 5,
+                    top: -5,
+                    right: 10,
+                    bottom: -10,
+                },
+            }]
+        );
+        assert!(system.is_colliding(uids[1], uids[0]));
+
+        // Still overlapping: nothing new to report
+        step(&mut system, &mut scene);
+        assert!(events.drain().is_empty());
+
+        scene.object_mut(uids[1]).unwrap().position.x = 30;
+        step(&mut system, &mut scene);
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::CollisionEnded {
+                a: uids[0],
+                b: uids[1],
+            }]
+        );
+        assert!(system.contacts().is_empty());
+    }
+
+    #[test]
+    fn test_main_object_and_circles_collide() {
+        let (mut scene, bus) = scene_with(vec![GameObject::new(
+            vec![Box::new(Collider::circle(5, (0, 0)))],
+            None,
+            Position::new(104, 96),
+        )]);
+        scene
+            .main_object
+            .add_component(Box::new(Collider::new((4, 4), (0, 0))))
+            .unwrap();
+        let events = bus.subscribe();
+        let uid = scene.uids()[0];
+        let mut system = CollisionSystem::new();
+
+        step(&mut system, &mut scene);
+        assert!(system.is_colliding(MAIN_OBJECT_UID, uid));
+        assert!(matches!(
+            events.drain()[..],
+            [GameEvent::CollisionStarted { a: MAIN_OBJECT_UID, b, .. }] if b == uid
+        ));
+    }
+
+    #[test]
+    fn test_removed_object_ends_collision() {
+        let (mut scene, bus) = scene_with(vec![boxed(0, 0, 10), boxed(2, -2, 10)]);
+        let uids = scene.uids();
+        let mut system = CollisionSystem::new();
+        step(&mut system, &mut scene);
+
+        scene.despawn(uids[1]);
+        let events = bus.subscribe();
+        step(&mut system, &mut scene);
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::CollisionEnded {
+                a: uids[0],
+                b: uids[1],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_triggers_report_entering_and_leaving() {
+        let zone = GameObject::new(
+            vec![Box::new(Collider::new((10, 10), (0, 0)).into_trigger())],
+            None,
+            Position::new(0, 0),
+        );
+        let other_zone = GameObject::new(
+            vec![Box::new(Collider::circle(3, (0, 0)).into_trigger())],
+            None,
+            Position::new(5, -5),
+        );
+        let (mut scene, bus) = scene_with(vec![zone, boxed(40, 0, 4), other_zone]);
+        let events = bus.subscribe();
+        let uids = scene.uids();
+        let mut system = CollisionSystem::new();
+
+        // Overlapping trigger zones report nothing
+        step(&mut system, &mut scene);
+        assert!(events.drain().is_empty());
+
+        scene.object_mut(uids[1]).unwrap().position.x = 4;
+        step(&mut system, &mut scene);
+        assert_eq!(
+            events.drain(),
+            vec![
+                GameEvent::TriggerEntered {
+                    trigger: uids[0],
+                    other: uids[1],
+                },
+                GameEvent::TriggerEntered {
+                    trigger: uids[2],
+                    other: uids[1],
+                },
+            ]
+        );
+        assert!(system.is_inside(uids[0], uids[1]));
+        assert!(!system.is_inside(uids[1], uids[0]));
+        // Triggers are not solid
+        assert!(!system.is_colliding(uids[0], uids[1]));
+        assert!(system.contacts().is_empty());
+
+        step(&mut system, &mut scene);
+        assert!(events.drain().is_empty());
+
+        scene.object_mut(uids[1]).unwrap().position.x = 9;
+        step(&mut system, &mut scene);
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::TriggerExited {
+                trigger: uids[2],
+                other: uids[1],
+            }]
+        );
+
+        scene.despawn(uids[0]);
+        events.drain();
+        step(&mut system, &mut scene);
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::TriggerExited {
+                trigger: uids[0],
+                other: uids[1],
+            }]
+        );
+    }
+
+    /// Image of the given size whose left `opaque` columns are opaque.
+    fn half_image(size: u32, opaque: u32) -> Arc<DynamicImage> {
+        let mut image = image::RgbaImage::new(size, size);
+        for (x, _, pixel) in image.enumerate_pixels_mut() {
+            if x < opaque {
+                *pixel = image::Rgba([255, 255, 255, 255]);
+            }
+        }
+        Arc::new(DynamicImage::ImageRgba8(image))
+    }
+
+    fn masked(x: i32, y: i32, image: &Arc<DynamicImage>) -> GameObject {
+        let mut sprite = Sprite::new(None, false, (0, 0));
+        sprite.image = Some(image.clone());
+        let size = image.width();
+        GameObject::new(
+            vec![
+                Box::new(sprite),
+                Box::new(Collider::new((size, size), (0, 0)).with_pixel_mask()),
+            ],
+            None,
+            Position::new(x, y),
+        )
+    }
+
+    #[test]
+    fn test_mask_from_image_alpha() {
+        let mask = CollisionMask::from_image(&half_image(10, 3));
+        assert_eq!(mask.size(), (10, 10));
+        assert!(mask.is_set(0, 0));
+        assert!(mask.is_set(2, 9));
+        assert!(!mask.is_set(3, 0));
+        assert!(!mask.is_set(-1, 0));
+        assert!(!mask.is_set(0, 10));
+    }
+
+    #[test]
+    fn test_pixel_masks_refine_overlaps() {
+        let image = half_image(10, 5);
+        let (mut scene, bus) = scene_with(vec![masked(0, 0, &image), boxed(7, 0, 4)]);
+        let events = bus.subscribe();
+        let uids = scene.uids();
+        let mut system = CollisionSystem::new();
+
+        // The box only overlaps the transparent right half of the sprite
+        step(&mut system, &mut scene);
+        assert!(events.drain().is_empty());
+        assert!(!system.is_colliding(uids[0], uids[1]));
+
+        scene.object_mut(uids[1]).unwrap().position.x = 4;
+        step(&mut system, &mut scene);
+        assert!(system.is_colliding(uids[0], uids[1]));
+        assert!(matches!(
+            events.drain()[..],
+            [GameEvent::CollisionStarted { a, b, .. }] if (a, b) == (uids[0], uids[1])
+        ));
+    }
+
+    #[test]
+    fn test_two_masks_collide_where_both_are_opaque() {
+        let image = half_image(10, 5);
+        // Opaque halves at x 0..5 and 6..11
+        let (mut scene, _bus) = scene_with(vec![masked(0, 0, &image), masked(6, -2, &image)]);
+        let uids = scene.uids();
+        let mut system = CollisionSystem::new();
+        step(&mut system, &mut scene);
+        assert!(!system.is_colliding(uids[0], uids[1]));
+
+        scene.object_mut(uids[1]).unwrap().position.x = 4;
+        step(&mut system, &mut scene);
+        assert!(system.is_colliding(uids[0], uids[1]));
+        // Both sprites show the same image, so it has a single mask
+        assert_eq!(system.cached_masks(), 1);
+
+        for uid in uids {
+            scene.despawn(uid);
+        }
+        drop(image);
+        step(&mut system, &mut scene);
+        assert_eq!(system.cached_masks(), 0);
+    }
+
+    #[test]
+    fn test_masked_collider_without_sprite_collides_nowhere() {
+        let ghost = GameObject::new(
+            vec![Box::new(Collider::new((10, 10), (0, 0)).with_pixel_mask())],
+            None,
+            Position::new(0, 0),
+        );
+        let (mut scene, _bus) = scene_with(vec![ghost, boxed(2, -2, 4)]);
+        let mut system = CollisionSystem::new();
+        step(&mut system, &mut scene);
+        assert!(system.contacts().is_empty());
+    }
+
+    #[test]
+    fn test_grid_pairs_only_share_cells() {
+        let area = |left, top, right, bottom| Aabb {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        let mut grid = SpatialGrid::new(10);
+        grid.insert(0, &area(0, 0, 10, -10));
+        // Touches the first box at x = 10, which belongs to the next cell
+        grid.insert(1, &area(10, 0, 20, -10));
+        grid.insert(2, &area(15, -5, 25, -25));
+        grid.insert(3, &area(-35, 40, -30, 35));
+        assert_eq!(grid.candidate_pairs(), BTreeSet::from([(1, 2)]));
+    }
+
+    #[test]
+    fn test_grid_finds_same_overlaps_as_testing_all_pairs() {
+        let shapes: Vec<(usize, WorldShape)> = (0..120)
+            .map(|i: i32| {
+                let position = Position::new((i * 37) % 200 - 100, (i * 53) % 160 - 80);
+                let collider = if i % 3 == 0 {
+                    Collider::circle((i % 7 + 2) as u32, (0, 0))
+                } else {
+                    Collider::new(((i % 11 + 3) as u32, (i % 5 + 3) as u32), (0, 0))
+                };
+                (i as usize + 1, collider.world_shape(&position))
+            })
+            .collect();
+        let mut expected = BTreeMap::new();
+        for (i, (a, shape_a)) in shapes.iter().enumerate() {
+            for (b, shape_b) in &shapes[i + 1..] {
+                if shape_a.intersects(shape_b) {
+                    let area = shape_a.bounds().intersection(&shape_b.bounds()).unwrap();
+                    expected.insert((*a, *b), area);
+                }
+            }
+        }
+        assert!(!expected.is_empty());
+        for cell_size in [1, 8, 64, 1000] {
+            let system = CollisionSystem::new().with_cell_size(cell_size);
+            assert_eq!(system.overlaps(&shapes, |_, _, _| true), expected);
+        }
+    }
+}
@@ -1,14 +1,66 @@
 //! Defines configuration traits and structs for the game engine.
 //!
-//! This module provides a `Config` trait to standardize engine configuration behavior,
-//! focusing on resolution settings. The `EngineConfig` struct implements this trait,
-//! encapsulating screen resolution management.
+//! This module provides a `Config` trait to standardize engine configuration behavior.
+//! It covers rendering (resolution, backend, blending, shadows and the CRT filter), the
+//! window (title, icon, placement, present and scaling modes), timing (frame and update
+//! rates), input (the input map and the quit and frame step keys) and diagnostics (asset
+//! root, frame histogram and watchdog). The `EngineConfig` struct implements this trait
+//! with a field per setting.
+//!
+//! An `EngineConfig` can also be read from a TOML file with [`EngineConfig::from_file`], so
+//! a game can be tuned without recompiling it. Every key of the file is optional:
+//!
+//! ```toml
+//! [window]
+//! width = 800
+//! height = 600
+//! title = "My game"
+//! icon = "icon.png"
+//! present_mode = "Vsync"   # or "NoVsync", "Mailbox"
+//! scaling = "IntegerScale" # or "Fit", "Stretch"
+//! fullscreen = false
+//!
+//! [timing]
+//! target_fps = 60          # 0 leaves the frame rate uncapped
+//! update_rate = 60
+//! histogram = "frames.csv" # frame time histogram written at shutdown, CSV or JSON
+//!
+//! [assets]
+//! root = "assets"
+//! ```
+//!
+//! Some settings can also be overridden per launch, through environment variables such as
+//! `RUSTY_ACHE_WIDTH=1280` or command-line options such as `--width=1280` and `--fullscreen`,
+//! see [`ConfigOverride`]. The `EngineBuilder` layers all sources; from lowest to highest
+//! precedence: the defaults, the configuration file, environment variables, command-line
+//! options and finally the settings given to the builder in code.
 
 use crate::Resolution;
+use crate::engine::input::InputMap;
+use crate::engine::timing::{DEFAULT_TARGET_FPS, DEFAULT_UPDATE_RATE};
+use crate::engine::watchdog::WatchdogSettings;
+use crate::render::backend::RenderBackendKind;
+use crate::render::post::Crt;
+use crate::render::renderer::ShadowSettings;
+use crate::screen::{HEIGHT, PresentMode, ScalingMode, WIDTH, WindowPosition};
+use serde::Deserialize;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use winit::keyboard::KeyCode;
+
+/// Window title used unless the configuration sets another one.
+pub const DEFAULT_WINDOW_TITLE: &str = "rusty_ache";
+
+/// Key exiting the engine unless the configuration sets another one.
+pub const DEFAULT_QUIT_KEY: KeyCode = KeyCode::Escape;
 
 /// Trait defining configuration interface for engine settings.
 ///
-/// Allows setting and retrieving the screen resolution.
+/// Allows setting and retrieving the screen resolution, the render backend,
+/// gamma-correct blending, the engine-wide shadow settings, the CRT filter, the window
+/// title and icon, the present mode, the scaling mode, the target frame rate, the quit key,
+/// the frame step key, where the window opens and the action bindings.
 /// The `new` method constructs a configuration instance with an initial resolution.
 pub trait Config {
     /// Sets the resolution width and height.
@@ -24,6 +76,239 @@ pub trait Config {
     /// A `Resolution` struct representing the current screen dimensions.
     fn get_resolution(&self) -> Resolution;
 
+    /// Selects the backend used to draw frames.
+    ///
+    /// # Parameters
+    /// - `backend`: Requested render backend.
+    fn set_render_backend(&mut self, backend: RenderBackendKind);
+
+    /// Gets the selected render backend.
+    ///
+    /// # Returns
+    /// The backend the engine will draw frames with.
+    fn get_render_backend(&self) -> RenderBackendKind;
+
+    /// Selects whether blending happens in linear light instead of raw sRGB bytes.
+    ///
+    /// # Parameters
+    /// - `enabled`: True for gamma-correct blending.
+    fn set_gamma_correct_blending(&mut self, enabled: bool);
+
+    /// Gets whether gamma-correct blending is enabled.
+    ///
+    /// # Returns
+    /// True if blending happens in linear light.
+    fn get_gamma_correct_blending(&self) -> bool;
+
+    /// Sets the shadow cast by sprites when neither the sprite nor its scene overrides it.
+    ///
+    /// # Parameters
+    /// - `shadow`: Shadow offset and opacity.
+    fn set_shadow_settings(&mut self, shadow: ShadowSettings);
+
+    /// Gets the engine-wide shadow settings.
+    ///
+    /// # Returns
+    /// The shadow offset and opacity used by default.
+    fn get_shadow_settings(&self) -> ShadowSettings;
+
+    /// Enables the retro CRT filter on displayed frames, or disables it with None.
+    ///
+    /// # Parameters
+    /// - `crt`: Scanline, curvature and mask settings of the filter.
+    fn set_crt_filter(&mut self, crt: Option<Crt>);
+
+    /// Gets the CRT filter settings.
+    ///
+    /// # Returns
+    /// The filter applied to displayed frames, or None if it is disabled.
+    fn get_crt_filter(&self) -> Option<Crt>;
+
+    /// Sets the title of the game window.
+    ///
+    /// # Parameters
+    /// - `title`: Text shown in the window's title bar.
+    fn set_window_title(&mut self, title: String);
+
+    /// Gets the title of the game window.
+    ///
+    /// # Returns
+    /// The text shown in the window's title bar.
+    fn get_window_title(&self) -> String;
+
+    /// Sets the image file used as the window icon, or removes the icon with None.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the icon image, in any format the `image` crate decodes.
+    fn set_window_icon(&mut self, path: Option<PathBuf>);
+
+    /// Gets the path of the window icon.
+    ///
+    /// # Returns
+    /// The path of the icon image, or None if the window uses the platform default.
+    fn get_window_icon(&self) -> Option<PathBuf>;
+
+    /// Selects how frames are presented, trading latency for tearing.
+    ///
+    /// # Parameters
+    /// - `present_mode`: Requested present mode.
+    fn set_present_mode(&mut self, present_mode: PresentMode);
+
+    /// Gets the selected present mode.
+    ///
+    /// # Returns
+    /// How frames are presented to the window.
+    fn get_present_mode(&self) -> PresentMode;
+
+    /// Selects how frames are mapped onto the window surface.
+    ///
+    /// # Parameters
+    /// - `scaling`: Integer scaling, fitting or stretching.
+    fn set_scaling_mode(&mut self, scaling: ScalingMode);
+
+    /// Gets the selected scaling mode.
+    ///
+    /// # Returns
+    /// How frames are mapped onto the window surface.
+    fn get_scaling_mode(&self) -> ScalingMode;
+
+    /// Sets the frame rate the engine loop is paced to, or leaves it uncapped with None.
+    ///
+    /// # Parameters
+    /// - `fps`: Target frames per second.
+    fn set_target_fps(&mut self, fps: Option<u32>);
+
+    /// Gets the target frame rate.
+    ///
+    /// # Returns
+    /// The frames per second the engine loop is paced to, or None if it is uncapped.
+    fn get_target_fps(&self) -> Option<u32>;
+
+    /// Sets how many times per second the game logic is updated, independently of the frame rate.
+    ///
+    /// # Parameters
+    /// - `rate`: Updates per second, at least 1.
+    fn set_update_rate(&mut self, rate: u32);
+
+    /// Gets the update rate.
+    ///
+    /// # Returns
+    /// The updates per second of the game logic.
+    fn get_update_rate(&self) -> u32;
+
+    /// Sets the key that exits the engine when pressed, or disables quitting by key with None.
+    ///
+    /// # Parameters
+    /// - `key`: Physical key closing the game window.
+    fn set_quit_key(&mut self, key: Option<KeyCode>);
+
+    /// Gets the quit key.
+    ///
+    /// # Returns
+    /// The key exiting the engine, or None if only the close button exits it.
+    fn get_quit_key(&self) -> Option<KeyCode>;
+
+    /// Sets the debug key stepping through the game frame by frame, or disables it with None.
+    ///
+    /// Pressing the key pauses the engine; every further press while paused runs exactly
+    /// one update. `Engine::resume` continues the game.
+    ///
+    /// # Parameters
+    /// - `key`: Physical key stepping the game.
+    fn set_frame_step_key(&mut self, key: Option<KeyCode>);
+
+    /// Gets the frame step key.
+    ///
+    /// # Returns
+    /// The key stepping the game by single updates, or None if frame stepping by key is off.
+    fn get_frame_step_key(&self) -> Option<KeyCode>;
+
+    /// Sets where the window opens on its monitor.
+    ///
+    /// # Parameters
+    /// - `position`: Platform default, centered, or an offset from the monitor's corner.
+    fn set_window_position(&mut self, position: WindowPosition);
+
+    /// Gets where the window opens.
+    ///
+    /// # Returns
+    /// The placement of the window on its monitor.
+    fn get_window_position(&self) -> WindowPosition;
+
+    /// Selects the monitor the window opens on, or the primary monitor with None.
+    ///
+    /// # Parameters
+    /// - `monitor`: Index of the monitor among the connected ones.
+    fn set_monitor(&mut self, monitor: Option<usize>);
+
+    /// Gets the monitor the window opens on.
+    ///
+    /// # Returns
+    /// The index of the monitor, or None for the primary monitor.
+    fn get_monitor(&self) -> Option<usize>;
+
+    /// Sets whether the window covers its whole monitor instead of opening windowed.
+    ///
+    /// # Parameters
+    /// - `fullscreen`: True for a borderless fullscreen window.
+    fn set_fullscreen(&mut self, fullscreen: bool);
+
+    /// Gets the fullscreen setting.
+    ///
+    /// # Returns
+    /// True if the window opens fullscreen.
+    fn get_fullscreen(&self) -> bool;
+
+    /// Sets the bindings of logical actions to keys and mouse buttons.
+    ///
+    /// # Parameters
+    /// - `map`: Action bindings, including the movement actions of the main object.
+    fn set_input_map(&mut self, map: InputMap);
+
+    /// Gets the action bindings.
+    ///
+    /// # Returns
+    /// A copy of the bindings of all actions.
+    fn get_input_map(&self) -> InputMap;
+
+    /// Sets the directory relative asset paths are resolved against, or the working
+    /// directory with None.
+    ///
+    /// # Parameters
+    /// - `root`: Directory holding the game's images and scene files.
+    fn set_asset_root(&mut self, root: Option<PathBuf>);
+
+    /// Gets the asset root.
+    ///
+    /// # Returns
+    /// The directory relative asset paths are resolved against, if not the working directory.
+    fn get_asset_root(&self) -> Option<PathBuf>;
+
+    /// Sets the file the frame time histogram is written to when the engine shuts down,
+    /// or disables the export with None.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the file; a `.json` extension selects JSON, otherwise CSV is written.
+    fn set_frame_histogram_path(&mut self, path: Option<PathBuf>);
+
+    /// Gets the path of the frame time histogram export.
+    ///
+    /// # Returns
+    /// The file written at shutdown, if the histogram is exported.
+    fn get_frame_histogram_path(&self) -> Option<PathBuf>;
+
+    /// Sets how the watchdog reacts to a stalled engine loop, or disables it with None.
+    ///
+    /// # Parameters
+    /// - `settings`: Timeout after which the loop counts as stalled and whether to exit then.
+    fn set_watchdog(&mut self, settings: Option<WatchdogSettings>);
+
+    /// Gets the watchdog settings.
+    ///
+    /// # Returns
+    /// The settings of the watchdog, or None if it is disabled.
+    fn get_watchdog(&self) -> Option<WatchdogSettings>;
+
     /// Creates a new configuration instance with the given resolution.
     ///
     /// # Parameters
@@ -38,11 +323,50 @@ pub trait Config {
 
 /// Concrete implementation of the engine configuration.
 ///
-/// Stores screen resolution and provides access using the `Config` trait.
+/// Stores every setting, defaults noted per field, and provides access using the `Config`
+/// trait.
 #[derive()]
 pub struct EngineConfig {
     /// The current resolution settings.
     resolution: Resolution,
+    /// The backend used to draw frames; CPU rendering by default.
+    backend: RenderBackendKind,
+    /// Whether blending happens in linear light; off by default.
+    gamma_correct: bool,
+    /// The engine-wide shadow settings.
+    shadow: ShadowSettings,
+    /// The CRT filter applied to displayed frames; disabled by default.
+    crt: Option<Crt>,
+    /// The window title; [`DEFAULT_WINDOW_TITLE`] by default.
+    window_title: String,
+    /// Path of the window icon image; none by default.
+    window_icon: Option<PathBuf>,
+    /// How frames are presented; vsync by default.
+    present_mode: PresentMode,
+    /// How frames are mapped onto the window; integer scaling by default.
+    scaling: ScalingMode,
+    /// Target frame rate; [`DEFAULT_TARGET_FPS`] by default.
+    target_fps: Option<u32>,
+    /// Updates per second of the game logic; [`DEFAULT_UPDATE_RATE`] by default.
+    update_rate: u32,
+    /// Key exiting the engine; [`DEFAULT_QUIT_KEY`] by default.
+    quit_key: Option<KeyCode>,
+    /// Key stepping the game by single updates; none by default.
+    frame_step_key: Option<KeyCode>,
+    /// Where the window opens; the platform default by default.
+    window_position: WindowPosition,
+    /// Index of the monitor the window opens on; the primary monitor by default.
+    monitor: Option<usize>,
+    /// Whether the window covers its monitor; windowed by default.
+    fullscreen: bool,
+    /// Bindings of actions; WASD movement by default.
+    input_map: InputMap,
+    /// Directory relative asset paths are resolved against; the working directory by default.
+    asset_root: Option<PathBuf>,
+    /// File the frame time histogram is written to at shutdown; not exported by default.
+    frame_histogram_path: Option<PathBuf>,
+    /// Watchdog of the engine loop; logging stalls after the default timeout by default.
+    watchdog: Option<WatchdogSettings>,
 }
 
 impl Config for EngineConfig {
@@ -60,15 +384,469 @@ impl Config for EngineConfig {
         }
     }
 
-    /// Creates a new `EngineConfig` with the specified resolution.
+    /// Selects the render backend.
+    fn set_render_backend(&mut self, backend: RenderBackendKind) {
+        self.backend = backend;
+    }
+
+    /// Returns the selected render backend.
+    fn get_render_backend(&self) -> RenderBackendKind {
+        self.backend
+    }
+
+    /// Enables or disables gamma-correct blending.
+    fn set_gamma_correct_blending(&mut self, enabled: bool) {
+        self.gamma_correct = enabled;
+    }
+
+    /// Returns whether gamma-correct blending is enabled.
+    fn get_gamma_correct_blending(&self) -> bool {
+        self.gamma_correct
+    }
+
+    /// Sets the engine-wide shadow settings.
+    fn set_shadow_settings(&mut self, shadow: ShadowSettings) {
+        self.shadow = shadow;
+    }
+
+    /// Returns the engine-wide shadow settings.
+    fn get_shadow_settings(&self) -> ShadowSettings {
+        self.shadow
+    }
+
+    /// Sets the CRT filter settings.
+    fn set_crt_filter(&mut self, crt: Option<Crt>) {
+        self.crt = crt;
+    }
+
+    /// Returns the CRT filter settings.
+    fn get_crt_filter(&self) -> Option<Crt> {
+        self.crt
+    }
+
+    /// Sets the window title.
+    fn set_window_title(&mut self, title: String) {
+        self.window_title = title;
+    }
+
+    /// Returns the window title.
+    fn get_window_title(&self) -> String {
+        self.window_title.clone()
+    }
+
+    /// Sets the path of the window icon.
+    fn set_window_icon(&mut self, path: Option<PathBuf>) {
+        self.window_icon = path;
+    }
+
+    /// Returns the path of the window icon.
+    fn get_window_icon(&self) -> Option<PathBuf> {
+        self.window_icon.clone()
+    }
+
+    /// Selects the present mode.
+    fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode;
+    }
+
+    /// Returns the selected present mode.
+    fn get_present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Selects the scaling mode.
+    fn set_scaling_mode(&mut self, scaling: ScalingMode) {
+        self.scaling = scaling;
+    }
+
+    /// Returns the selected scaling mode.
+    fn get_scaling_mode(&self) -> ScalingMode {
+        self.scaling
+    }
+
+    /// Sets the target frame rate.
+    fn set_target_fps(&mut self, fps: Option<u32>) {
+        self.target_fps = fps;
+    }
+
+    /// Returns the target frame rate.
+    fn get_target_fps(&self) -> Option<u32> {
+        self.target_fps
+    }
+
+    /// Sets the update rate; zero is raised to one update per second.
+    fn set_update_rate(&mut self, rate: u32) {
+        self.update_rate = rate.max(1);
+    }
+
+    /// Returns the update rate.
+    fn get_update_rate(&self) -> u32 {
+        self.update_rate
+    }
+
+    /// Sets the quit key.
+    fn set_quit_key(&mut self, key: Option<KeyCode>) {
+        self.quit_key = key;
+    }
+
+    /// Returns the quit key.
+    fn get_quit_key(&self) -> Option<KeyCode> {
+        self.quit_key
+    }
+
+    /// Sets the frame step key.
+    fn set_frame_step_key(&mut self, key: Option<KeyCode>) {
+        self.frame_step_key = key;
+    }
+
+    /// Returns the frame step key.
+    fn get_frame_step_key(&self) -> Option<KeyCode> {
+        self.frame_step_key
+    }
+
+    /// Sets where the window opens.
+    fn set_window_position(&mut self, position: WindowPosition) {
+        self.window_position = position;
+    }
+
+    /// Returns where the window opens.
+    fn get_window_position(&self) -> WindowPosition {
+        self.window_position
+    }
+
+    /// Selects the monitor the window opens on.
+    fn set_monitor(&mut self, monitor: Option<usize>) {
+        self.monitor = monitor;
+    }
+
+    /// Returns the monitor the window opens on.
+    fn get_monitor(&self) -> Option<usize> {
+        self.monitor
+    }
+
+    /// Sets the fullscreen setting.
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+
+    /// Returns the fullscreen setting.
+    fn get_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    /// Sets the action bindings.
+    fn set_input_map(&mut self, map: InputMap) {
+        self.input_map = map;
+    }
+
+    /// Returns the action bindings.
+    fn get_input_map(&self) -> InputMap {
+        self.input_map.clone()
+    }
+
+    /// Sets the asset root.
+    fn set_asset_root(&mut self, root: Option<PathBuf>) {
+        self.asset_root = root;
+    }
+
+    /// Returns the asset root.
+    fn get_asset_root(&self) -> Option<PathBuf> {
+        self.asset_root.clone()
+    }
+
+    /// Sets the path of the frame time histogram export.
+    fn set_frame_histogram_path(&mut self, path: Option<PathBuf>) {
+        self.frame_histogram_path = path;
+    }
+
+    /// Returns the path of the frame time histogram export.
+    fn get_frame_histogram_path(&self) -> Option<PathBuf> {
+        self.frame_histogram_path.clone()
+    }
+
+    /// Sets the watchdog settings.
+    fn set_watchdog(&mut self, settings: Option<WatchdogSettings>) {
+        self.watchdog = settings;
+    }
+
+    /// Returns the watchdog settings.
+    fn get_watchdog(&self) -> Option<WatchdogSettings> {
+        self.watchdog
+    }
+
+    /// Creates a new `EngineConfig` with the specified resolution, the default backend,
+    /// raw sRGB blending, the default shadow, no CRT filter, the default window title, no icon,
+    /// vsync, integer scaling, the default target frame rate and update rate, the default
+    /// quit key and the platform's window placement on the primary monitor and WASD movement
+    /// bindings, resolving assets against the working directory, without exporting the
+    /// frame time histogram and with a watchdog logging stalls of the engine loop.
     fn new(resolution: Resolution) -> Self {
-        EngineConfig { resolution }
+        EngineConfig {
+            resolution,
+            backend: RenderBackendKind::default(),
+            gamma_correct: false,
+            shadow: ShadowSettings::default(),
+            crt: None,
+            window_title: DEFAULT_WINDOW_TITLE.to_string(),
+            window_icon: None,
+            present_mode: PresentMode::default(),
+            scaling: ScalingMode::default(),
+            target_fps: Some(DEFAULT_TARGET_FPS),
+            update_rate: DEFAULT_UPDATE_RATE,
+            quit_key: Some(DEFAULT_QUIT_KEY),
+            frame_step_key: None,
+            window_position: WindowPosition::default(),
+            monitor: None,
+            fullscreen: false,
+            input_map: InputMap::default(),
+            asset_root: None,
+            frame_histogram_path: None,
+            watchdog: Some(WatchdogSettings::default()),
+        }
+    }
+}
+
+/// Prefix of the environment variables overriding settings, e.g. `RUSTY_ACHE_WIDTH`.
+pub const ENV_PREFIX: &str = "RUSTY_ACHE_";
+
+/// A single setting overriding the configuration for one launch.
+///
+/// Overrides are named the same in every source: `width` is set by the environment
+/// variable `RUSTY_ACHE_WIDTH` and by the command-line option `--width=<value>`.
+/// Boolean options may be given without a value, e.g. `--fullscreen`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigOverride {
+    /// `width`: width of the rendered frames in pixels.
+    Width(u32),
+    /// `height`: height of the rendered frames in pixels.
+    Height(u32),
+    /// `title`: title of the game window.
+    Title(String),
+    /// `fullscreen`: whether the window covers its monitor.
+    Fullscreen(bool),
+    /// `vsync`: whether frames are presented on display refresh.
+    Vsync(bool),
+    /// `target_fps`: frame rate the engine loop is paced to, 0 for uncapped.
+    TargetFps(Option<u32>),
+    /// `update_rate`: updates per second of the game logic.
+    UpdateRate(u32),
+    /// `asset_root`: directory relative asset paths are resolved against.
+    AssetRoot(PathBuf),
+    /// `frame_histogram`: file the frame time histogram is written to at shutdown.
+    FrameHistogram(PathBuf),
+}
+
+impl ConfigOverride {
+    /// Parses the value of a named setting.
+    ///
+    /// # Parameters
+    /// - `name`: Name of the setting in lowercase, e.g. `width`.
+    /// - `value`: Its value; `true`, `false`, `1`, `0`, `yes` and `no` for booleans.
+    ///
+    /// # Returns
+    /// The override, or None if no setting has the name.
+    ///
+    /// # Errors
+    /// Fails if the value cannot be parsed for the setting.
+    pub fn parse(name: &str, value: &str) -> Result<Option<Self>, Error> {
+        let invalid = || Error::other(format!("invalid value {value:?} for setting {name}"));
+        let number = || value.trim().parse::<u32>().map_err(|_| invalid());
+        let flag = || match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "0" | "false" | "no" | "off" => Ok(false),
+            _ => Err(invalid()),
+        };
+        Ok(Some(match name {
+            "width" => ConfigOverride::Width(number()?),
+            "height" => ConfigOverride::Height(number()?),
+            "title" => ConfigOverride::Title(value.to_string()),
+            "fullscreen" => ConfigOverride::Fullscreen(flag()?),
+            "vsync" => ConfigOverride::Vsync(flag()?),
+            "target_fps" => ConfigOverride::TargetFps(Some(number()?).filter(|fps| *fps > 0)),
+            "update_rate" => ConfigOverride::UpdateRate(number()?),
+            "asset_root" => ConfigOverride::AssetRoot(PathBuf::from(value)),
+            "frame_histogram" => ConfigOverride::FrameHistogram(PathBuf::from(value)),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Collects the overrides given by environment variables such as `RUSTY_ACHE_WIDTH`.
+    ///
+    /// Variables with the prefix that name no setting are ignored.
+    ///
+    /// # Errors
+    /// Fails if a variable of a setting holds an invalid value.
+    pub fn from_env() -> Result<Vec<Self>, Error> {
+        ConfigOverride::from_vars(std::env::vars())
+    }
+
+    /// Same as [`ConfigOverride::from_env`] for the given variables.
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<Self>, Error> {
+        let mut overrides = vec![];
+        for (key, value) in vars {
+            if let Some(name) = key.strip_prefix(ENV_PREFIX)
+                && let Some(setting) = ConfigOverride::parse(&name.to_ascii_lowercase(), &value)?
+            {
+                overrides.push(setting);
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Collects the overrides given by command-line options such as `--width=1280`.
+    ///
+    /// Arguments that are no option of a setting are ignored, so games can parse their
+    /// own options from the same arguments.
+    ///
+    /// # Parameters
+    /// - `args`: Arguments of the program, without the program name.
+    ///
+    /// # Errors
+    /// Fails if an option of a setting has an invalid value.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Vec<Self>, Error> {
+        let mut overrides = vec![];
+        for arg in args {
+            let Some(option) = arg.strip_prefix("--") else {
+                continue;
+            };
+            let (name, value) = option.split_once('=').unwrap_or((option, "true"));
+            if let Some(setting) = ConfigOverride::parse(name, value)? {
+                overrides.push(setting);
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Applies the setting to a configuration.
+    pub fn apply(self, config: &mut dyn Config) {
+        match self {
+            ConfigOverride::Width(width) => {
+                config.set_resolution(width, config.get_resolution().height)
+            }
+            ConfigOverride::Height(height) => {
+                config.set_resolution(config.get_resolution().width, height)
+            }
+            ConfigOverride::Title(title) => config.set_window_title(title),
+            ConfigOverride::Fullscreen(fullscreen) => config.set_fullscreen(fullscreen),
+            ConfigOverride::Vsync(vsync) => config.set_present_mode(if vsync {
+                PresentMode::Vsync
+            } else {
+                PresentMode::NoVsync
+            }),
+            ConfigOverride::TargetFps(fps) => config.set_target_fps(fps),
+            ConfigOverride::UpdateRate(rate) => config.set_update_rate(rate),
+            ConfigOverride::AssetRoot(root) => config.set_asset_root(Some(root)),
+            ConfigOverride::FrameHistogram(path) => config.set_frame_histogram_path(Some(path)),
+        }
+    }
+}
+
+/// Contents of a configuration file; missing keys keep the current settings.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    window: WindowSection,
+    timing: TimingSection,
+    assets: AssetsSection,
+}
+
+/// The `[window]` table of a configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WindowSection {
+    width: Option<u32>,
+    height: Option<u32>,
+    title: Option<String>,
+    icon: Option<PathBuf>,
+    present_mode: Option<PresentMode>,
+    scaling: Option<ScalingMode>,
+    fullscreen: Option<bool>,
+}
+
+/// The `[timing]` table of a configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TimingSection {
+    /// Frames per second, 0 for an uncapped frame rate.
+    target_fps: Option<u32>,
+    update_rate: Option<u32>,
+    /// File the frame time histogram is written to at shutdown.
+    histogram: Option<PathBuf>,
+}
+
+/// The `[assets]` table of a configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AssetsSection {
+    root: Option<PathBuf>,
+}
+
+impl ConfigFile {
+    /// Overrides the settings of a configuration with the ones given in the file.
+    fn apply(self, config: &mut EngineConfig) {
+        let resolution = config.get_resolution();
+        config.set_resolution(
+            self.window.width.unwrap_or(resolution.width),
+            self.window.height.unwrap_or(resolution.height),
+        );
+        if let Some(title) = self.window.title {
+            config.set_window_title(title);
+        }
+        if let Some(icon) = self.window.icon {
+            config.set_window_icon(Some(icon));
+        }
+        if let Some(present_mode) = self.window.present_mode {
+            config.set_present_mode(present_mode);
+        }
+        if let Some(scaling) = self.window.scaling {
+            config.set_scaling_mode(scaling);
+        }
+        if let Some(fullscreen) = self.window.fullscreen {
+            config.set_fullscreen(fullscreen);
+        }
+        if let Some(fps) = self.timing.target_fps {
+            config.set_target_fps((fps > 0).then_some(fps));
+        }
+        if let Some(rate) = self.timing.update_rate {
+            config.set_update_rate(rate);
+        }
+        if let Some(path) = self.timing.histogram {
+            config.set_frame_histogram_path(Some(path));
+        }
+        if let Some(root) = self.assets.root {
+            config.set_asset_root(Some(root));
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Reads a configuration from a TOML file, see the module documentation for its keys.
+    ///
+    /// Settings missing from the file keep the defaults of [`Config::new`], with a
+    /// resolution of `WIDTH` x `HEIGHT`.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid configuration.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        EngineConfig::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Same as [`EngineConfig::from_file`] for the text of a configuration file.
+    ///
+    /// # Errors
+    /// Fails if the text is not a valid configuration.
+    pub fn from_toml(text: &str) -> Result<Self, Error> {
+        let file: ConfigFile = toml::from_str(text).map_err(Error::other)?;
+        let mut config = EngineConfig::new(Resolution::new(WIDTH, HEIGHT));
+        file.apply(&mut config);
+        Ok(config)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::input::Binding;
 
     #[test]
     fn test_new_engine_config() {
@@ -100,4 +878,241 @@ mod tests {
         assert_eq!(updated.width, 2560);
         assert_eq!(updated.height, 1440);
     }
+
+    #[test]
+    fn test_render_backend_selection() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_render_backend(), RenderBackendKind::Cpu);
+
+        config.set_render_backend(RenderBackendKind::Wgpu);
+        assert_eq!(config.get_render_backend(), RenderBackendKind::Wgpu);
+    }
+
+    #[test]
+    fn test_gamma_correct_blending() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert!(!config.get_gamma_correct_blending());
+
+        config.set_gamma_correct_blending(true);
+        assert!(config.get_gamma_correct_blending());
+    }
+
+    #[test]
+    fn test_shadow_settings() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_shadow_settings(), ShadowSettings::default());
+
+        let shadow = ShadowSettings::new((4, 4), 120);
+        config.set_shadow_settings(shadow);
+        assert_eq!(config.get_shadow_settings(), shadow);
+    }
+
+    #[test]
+    fn test_crt_filter() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_crt_filter(), None);
+
+        config.set_crt_filter(Some(Crt::default()));
+        assert_eq!(config.get_crt_filter(), Some(Crt::default()));
+    }
+
+    #[test]
+    fn test_window_title_and_icon() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_window_title(), DEFAULT_WINDOW_TITLE);
+        assert_eq!(config.get_window_icon(), None);
+
+        config.set_window_title("My game".to_string());
+        config.set_window_icon(Some(PathBuf::from("icon.png")));
+        assert_eq!(config.get_window_title(), "My game");
+        assert_eq!(config.get_window_icon(), Some(PathBuf::from("icon.png")));
+    }
+
+    #[test]
+    fn test_present_mode() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_present_mode(), PresentMode::Vsync);
+
+        config.set_present_mode(PresentMode::NoVsync);
+        assert_eq!(config.get_present_mode(), PresentMode::NoVsync);
+    }
+
+    #[test]
+    fn test_target_fps() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_target_fps(), Some(DEFAULT_TARGET_FPS));
+
+        config.set_target_fps(None);
+        assert_eq!(config.get_target_fps(), None);
+    }
+
+    #[test]
+    fn test_update_rate() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_update_rate(), DEFAULT_UPDATE_RATE);
+
+        config.set_update_rate(120);
+        assert_eq!(config.get_update_rate(), 120);
+        config.set_update_rate(0);
+        assert_eq!(config.get_update_rate(), 1);
+    }
+
+    #[test]
+    fn test_quit_key() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_quit_key(), Some(KeyCode::Escape));
+
+        config.set_quit_key(Some(KeyCode::KeyQ));
+        assert_eq!(config.get_quit_key(), Some(KeyCode::KeyQ));
+        config.set_quit_key(None);
+        assert_eq!(config.get_quit_key(), None);
+    }
+
+    #[test]
+    fn test_frame_step_key() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_frame_step_key(), None);
+
+        config.set_frame_step_key(Some(KeyCode::F10));
+        assert_eq!(config.get_frame_step_key(), Some(KeyCode::F10));
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let config = EngineConfig::from_toml(
+            "[window]\nwidth = 320\nheight = 200\ntitle = \"Demo\"\npresent_mode = \"NoVsync\"\n\
+             scaling = \"Fit\"\n\n[timing]\ntarget_fps = 0\nupdate_rate = 30\n\
+             histogram = \"frames.json\"\n\n\
+             [assets]\nroot = \"assets\"\n",
+        )
+        .unwrap();
+        let resolution = config.get_resolution();
+        assert_eq!((resolution.width, resolution.height), (320, 200));
+        assert_eq!(config.get_window_title(), "Demo");
+        assert_eq!(config.get_present_mode(), PresentMode::NoVsync);
+        assert_eq!(config.get_scaling_mode(), ScalingMode::Fit);
+        assert_eq!(config.get_target_fps(), None);
+        assert_eq!(config.get_update_rate(), 30);
+        assert_eq!(config.get_asset_root(), Some(PathBuf::from("assets")));
+        assert_eq!(
+            config.get_frame_histogram_path(),
+            Some(PathBuf::from("frames.json"))
+        );
+
+        let defaults = EngineConfig::from_toml("[window]\ntitle = \"Only title\"\n").unwrap();
+        assert_eq!(defaults.get_resolution().width, WIDTH);
+        assert_eq!(defaults.get_target_fps(), Some(DEFAULT_TARGET_FPS));
+        assert_eq!(defaults.get_asset_root(), None);
+        assert_eq!(defaults.get_frame_histogram_path(), None);
+    }
+
+    #[test]
+    fn test_invalid_config_files_fail() {
+        assert!(EngineConfig::from_toml("[window]\ncolor = \"red\"\n").is_err());
+        assert!(EngineConfig::from_toml("[window]\npresent_mode = \"Sometimes\"\n").is_err());
+        assert!(EngineConfig::from_file("missing_config.toml").is_err());
+    }
+
+    #[test]
+    fn test_overrides_from_vars_and_args() {
+        let vars = [
+            ("RUSTY_ACHE_WIDTH", "1280"),
+            ("RUSTY_ACHE_FULLSCREEN", "yes"),
+            ("RUSTY_ACHE_UNKNOWN", "1"),
+            ("HOME", "/root"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+        assert_eq!(
+            ConfigOverride::from_vars(vars).unwrap(),
+            vec![
+                ConfigOverride::Width(1280),
+                ConfigOverride::Fullscreen(true)
+            ]
+        );
+
+        let args = [
+            "--fullscreen=false",
+            "--vsync",
+            "level2",
+            "--target_fps=0",
+            "--cheats",
+        ]
+        .map(String::from);
+        assert_eq!(
+            ConfigOverride::from_args(args).unwrap(),
+            vec![
+                ConfigOverride::Fullscreen(false),
+                ConfigOverride::Vsync(true),
+                ConfigOverride::TargetFps(None),
+            ]
+        );
+        assert!(ConfigOverride::from_args(["--width=wide".to_string()]).is_err());
+        assert!(
+            ConfigOverride::from_vars([("RUSTY_ACHE_VSYNC".to_string(), "maybe".to_string())])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        for setting in [
+            ConfigOverride::Height(720),
+            ConfigOverride::Vsync(false),
+            ConfigOverride::Fullscreen(true),
+            ConfigOverride::AssetRoot(PathBuf::from("assets")),
+            ConfigOverride::FrameHistogram(PathBuf::from("frames.csv")),
+        ] {
+            setting.apply(&mut config);
+        }
+        let resolution = config.get_resolution();
+        assert_eq!((resolution.width, resolution.height), (800, 720));
+        assert_eq!(config.get_present_mode(), PresentMode::NoVsync);
+        assert!(config.get_fullscreen());
+        assert_eq!(config.get_asset_root(), Some(PathBuf::from("assets")));
+        assert_eq!(
+            config.get_frame_histogram_path(),
+            Some(PathBuf::from("frames.csv"))
+        );
+    }
+
+    #[test]
+    fn test_watchdog() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_watchdog(), Some(WatchdogSettings::default()));
+
+        config.set_watchdog(None);
+        assert_eq!(config.get_watchdog(), None);
+    }
+
+    #[test]
+    fn test_window_placement() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_window_position(), WindowPosition::Default);
+        assert_eq!(config.get_monitor(), None);
+
+        config.set_window_position(WindowPosition::At(100, 50));
+        config.set_monitor(Some(1));
+        assert_eq!(config.get_window_position(), WindowPosition::At(100, 50));
+        assert_eq!(config.get_monitor(), Some(1));
+    }
+
+    #[test]
+    fn test_input_map() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_input_map(), InputMap::default());
+
+        let map = InputMap::empty().with_binding("fire", Binding::Key(KeyCode::Space));
+        config.set_input_map(map.clone());
+        assert_eq!(config.get_input_map(), map);
+    }
+
+    #[test]
+    fn test_scaling_mode() {
+        let mut config = EngineConfig::new(Resolution::new(800, 600));
+        assert_eq!(config.get_scaling_mode(), ScalingMode::IntegerScale);
+
+        config.set_scaling_mode(ScalingMode::Fit);
+        assert_eq!(config.get_scaling_mode(), ScalingMode::Fit);
+    }
 }
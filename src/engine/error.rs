@@ -0,0 +1,93 @@
+//! Errors reported by the engine.
+//!
+//! Every fallible operation of the `Engine` trait returns an `EngineError`, which tells
+//! which part of the engine failed, so games can react to a missing asset differently
+//! than to a window that cannot be opened.
+
+use crate::engine::scene::game_object::GameObjectError;
+use crate::render::error::RenderError;
+use std::path::PathBuf;
+use thiserror::Error;
+use winit::error::EventLoopError;
+
+/// Describes why an engine operation failed.
+#[derive(Debug, Error)]
+pub enum EngineError {
+    /// An asset file could not be loaded.
+    #[error("couldn't load asset {}: {source}", path.display())]
+    Asset {
+        /// Path of the asset.
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A frame could not be rendered or emitted.
+    #[error("couldn't render frame: {0}")]
+    Render(#[from] RenderError),
+    /// The window or its event loop failed.
+    #[error("window error: {0}")]
+    Window(#[from] EventLoopError),
+    /// The thread producing the frames panicked with the given message.
+    #[error("producer thread panicked: {0}")]
+    Producer(String),
+    /// The watchdog found the thread producing the frames stalled, with its diagnostics.
+    #[error("engine loop stalled: {0}")]
+    Stalled(String),
+    /// A scene could not be loaded or set up.
+    #[error("couldn't load scene: {0}")]
+    Scene(#[source] std::io::Error),
+    /// No scene is registered under the given name.
+    #[error("no scene named {0}")]
+    UnknownScene(String),
+    /// A component of a game object could not be created or accessed.
+    #[error("component error: {0}")]
+    Component(String),
+    /// Reading or writing a file failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl From<GameObjectError> for EngineError {
+    fn from(e: GameObjectError) -> Self {
+        EngineError::Component(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_display_and_source() {
+        let e = EngineError::Asset {
+            path: PathBuf::from("hero.png"),
+            source: std::io::Error::other("not found"),
+        };
+        assert_eq!(e.to_string(), "couldn't load asset hero.png: not found");
+        assert!(e.source().is_some());
+
+        let e: EngineError = RenderError::NoFrame.into();
+        assert!(matches!(e, EngineError::Render(RenderError::NoFrame)));
+        assert_eq!(
+            e.to_string(),
+            "couldn't render frame: no frame has been rendered"
+        );
+    }
+
+    #[test]
+    fn test_object_errors_are_component_errors() {
+        let e: EngineError =
+            GameObjectError::ScriptError("no script registered as foo".into()).into();
+        assert_eq!(
+            e.to_string(),
+            "component error: couldn't create script: no script registered as foo"
+        );
+    }
+
+    #[test]
+    fn test_io_errors_are_transparent() {
+        let e: EngineError = std::io::Error::other("disk full").into();
+        assert!(matches!(e, EngineError::Io(_)));
+        assert_eq!(e.to_string(), "disk full");
+    }
+}
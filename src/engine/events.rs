@@ -0,0 +1,135 @@
+//! Events reported by the window to game code.
+//!
+//! The window pushes events such as focus changes into an `EventQueue`, which game code
+//! drains whenever it likes, e.g. once per tick to pause the game while it is in the
+//! background. The current focus and minimized state can also be polled directly.
+//! Files dropped onto the window are reported too, so tools built on the engine can load them.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// An event of the game window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineEvent {
+    /// The window received keyboard focus.
+    FocusGained,
+    /// The window lost keyboard focus.
+    FocusLost,
+    /// The window was minimized.
+    Minimized,
+    /// The window was restored after being minimized.
+    Restored,
+    /// A file was dragged onto the window and dropped; one event is sent per file.
+    FileDropped(PathBuf),
+}
+
+/// [Handle](crate#handles) to the queue of window events.
+///
+/// The game window pushes events and the game drains them, each event once.
+#[derive(Clone, Default)]
+pub struct EventQueue {
+    events: Arc<RwLock<Vec<EngineEvent>>>,
+    focused: Arc<AtomicBool>,
+    minimized: Arc<AtomicBool>,
+}
+
+impl EventQueue {
+    /// Creates an empty queue; the window counts as unfocused until it reports focus.
+    pub fn new() -> Self {
+        EventQueue::default()
+    }
+
+    /// Adds an event to the end of the queue.
+    pub(crate) fn push(&self, event: EngineEvent) {
+        self.events.write().unwrap().push(event);
+    }
+
+    /// Removes and returns all queued events, oldest first.
+    pub fn drain(&self) -> Vec<EngineEvent> {
+        std::mem::take(&mut *self.events.write().unwrap())
+    }
+
+    /// Checks whether the window currently has keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused.load(Ordering::Relaxed)
+    }
+
+    /// Checks whether the window is currently minimized.
+    pub fn is_minimized(&self) -> bool {
+        self.minimized.load(Ordering::Relaxed)
+    }
+
+    /// Records the focus state, queueing an event if it changed.
+    pub(crate) fn set_focused(&self, focused: bool) {
+        if self.focused.swap(focused, Ordering::Relaxed) != focused {
+            self.push(if focused {
+                EngineEvent::FocusGained
+            } else {
+                EngineEvent::FocusLost
+            });
+        }
+    }
+
+    /// Records the minimized state, queueing an event if it changed.
+    pub(crate) fn set_minimized(&self, minimized: bool) {
+        if self.minimized.swap(minimized, Ordering::Relaxed) != minimized {
+            self.push(if minimized {
+                EngineEvent::Minimized
+            } else {
+                EngineEvent::Restored
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_changes_queue_events() {
+        let queue = EventQueue::new();
+        assert!(!queue.is_focused());
+        assert!(!queue.is_minimized());
+
+        queue.set_focused(true);
+        queue.set_focused(true);
+        queue.set_minimized(true);
+        queue.set_focused(false);
+        queue.set_minimized(false);
+        assert!(!queue.is_focused());
+        assert_eq!(
+            queue.drain(),
+            vec![
+                EngineEvent::FocusGained,
+                EngineEvent::Minimized,
+                EngineEvent::FocusLost,
+                EngineEvent::Restored,
+            ]
+        );
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn test_dropped_files_are_queued_in_order() {
+        let queue = EventQueue::new();
+        queue.push(EngineEvent::FileDropped(PathBuf::from("hero.png")));
+        queue.push(EngineEvent::FileDropped(PathBuf::from("level.txt")));
+        assert_eq!(
+            queue.drain(),
+            vec![
+                EngineEvent::FileDropped(PathBuf::from("hero.png")),
+                EngineEvent::FileDropped(PathBuf::from("level.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clones_share_queue() {
+        let queue = EventQueue::new();
+        queue.clone().set_minimized(true);
+        assert!(queue.is_minimized());
+        assert_eq!(queue.drain(), vec![EngineEvent::Minimized]);
+    }
+}
@@ -1,2 +1,1079 @@
+//! Input state shared between the window and game code.
+//!
+//! The window records every key and mouse button press and release, the mouse position
+//! over the frame and the scrolled wheel distance in an `InputState`, which game code
+//! queries on its own schedule, e.g. once per tick to check whether space is held down.
+//! Presses and releases of every tick can be checked with the `just_pressed` and
+//! `just_released` queries. Presses and releases are also queued as `InputEvent`s, so edge-triggered logic such as
+//! jumping once per press can drain them instead of comparing states between ticks.
+//! Touches and the left mouse button both act as pointers, so games handling pointers
+//! instead of the mouse work the same on touch screens.
+//! Typed characters, including text composed with an input method, are collected as a text
+//! stream for name entry fields and chat boxes.
+//!
+//! The input can be recorded tick by tick and played back later; see the `replay` module.
+//!
+//! An `InputMap` binds logical actions such as "fire" to keys and mouse buttons, so games
+//! can query actions instead of physical keys and let players rebind them, e.g. from an
+//! in-game controls menu that binds an action to the next key the player presses.
+
+use crate::engine::replay::{InputChange, InputRecording};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+use winit::event::{MouseButton, MouseScrollDelta, TouchPhase};
+use winit::keyboard::KeyCode;
+
+/// Wheel distance of one line, for devices such as touchpads reporting pixels.
+const PIXELS_PER_LINE: f64 = 20.0;
+
+/// Number of input events kept when games do not drain them; older events are dropped.
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+/// Action moving the main object up; bound to W by default.
+pub const MOVE_UP: &str = "move_up";
+/// Action moving the main object down; bound to S by default.
+pub const MOVE_DOWN: &str = "move_down";
+/// Action moving the main object left; bound to A by default.
+pub const MOVE_LEFT: &str = "move_left";
+/// Action moving the main object right; bound to D by default.
+pub const MOVE_RIGHT: &str = "move_right";
+
 /// A trait describing entity for passing info about users actions to Engine
 pub trait Input {}
+
+/// A physical input an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// A key, by its physical position.
+    Key(KeyCode),
+    /// A mouse button.
+    Button(MouseButton),
+}
+
+/// Something pointing at the frame: the mouse or a finger on a touch screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pointer {
+    /// The mouse, pressing with its left button.
+    Mouse,
+    /// A finger, identified while it touches the screen.
+    Touch(u64),
+}
+
+/// A change of the keyboard, mouse or touch state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    /// A key was pressed; held keys repeating do not send further events.
+    KeyDown(KeyCode),
+    /// A key was released.
+    KeyUp(KeyCode),
+    /// A mouse button was pressed.
+    ButtonDown {
+        button: MouseButton,
+        /// Frame position of the mouse, None if it was outside the frame.
+        position: Option<(i32, i32)>,
+    },
+    /// A mouse button was released.
+    ButtonUp {
+        button: MouseButton,
+        /// Frame position of the mouse, None if it was outside the frame.
+        position: Option<(i32, i32)>,
+    },
+    /// A finger touched the frame or the left mouse button was pressed over it.
+    PointerDown {
+        pointer: Pointer,
+        /// Frame position of the pointer.
+        position: (i32, i32),
+    },
+    /// A finger was lifted or the left mouse button released after a `PointerDown`.
+    PointerUp {
+        pointer: Pointer,
+        /// Last frame position of the pointer.
+        position: (i32, i32),
+    },
+}
+
+/// Bindings of logical actions to keys and mouse buttons.
+///
+/// An action is active while any of its bindings is held down. The default map binds the
+/// movement actions to WASD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMap {
+    /// Bindings of every action, ordered by action name.
+    bindings: BTreeMap<String, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        InputMap::empty()
+            .with_binding(MOVE_UP, Binding::Key(KeyCode::KeyW))
+            .with_binding(MOVE_DOWN, Binding::Key(KeyCode::KeyS))
+            .with_binding(MOVE_LEFT, Binding::Key(KeyCode::KeyA))
+            .with_binding(MOVE_RIGHT, Binding::Key(KeyCode::KeyD))
+    }
+}
+
+impl InputMap {
+    /// Creates a map with the default movement bindings.
+    pub fn new() -> Self {
+        InputMap::default()
+    }
+
+    /// Creates a map without any bindings.
+    pub fn empty() -> Self {
+        InputMap {
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a binding to an action, keeping its other bindings.
+    pub fn with_binding(mut self, action: impl Into<String>, binding: Binding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Adds a binding to an action, keeping its other bindings.
+    ///
+    /// # Parameters
+    /// - `action`: Name of the action, e.g. "fire".
+    /// - `binding`: Key or mouse button activating the action.
+    pub fn bind(&mut self, action: impl Into<String>, binding: Binding) {
+        let bindings = self.bindings.entry(action.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replaces all bindings of an action with a single one.
+    pub fn rebind(&mut self, action: impl Into<String>, binding: Binding) {
+        self.bindings.insert(action.into(), vec![binding]);
+    }
+
+    /// Removes all bindings of an action.
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    /// Returns the bindings of an action, empty if it is unknown.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterates over all actions and their bindings, ordered by action name.
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[Binding])> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| (action.as_str(), bindings.as_slice()))
+    }
+}
+
+/// Keys and buttons pressed or released during one tick.
+#[derive(Default)]
+struct Edges {
+    pressed: HashSet<Binding>,
+    released: HashSet<Binding>,
+}
+
+/// Keyboard, mouse and touch state recorded by the window.
+#[derive(Default)]
+struct State {
+    /// Keys currently held down, by their physical position.
+    keys: HashSet<KeyCode>,
+    /// Mouse buttons currently held down.
+    buttons: HashSet<MouseButton>,
+    /// Frame position of the mouse, None while it is outside the frame.
+    mouse_position: Option<(i32, i32)>,
+    /// Last frame position of the mouse over the frame while the left button is held.
+    mouse_pointer: Option<(i32, i32)>,
+    /// Frame positions of the fingers touching the frame, by finger.
+    touches: BTreeMap<u64, (i32, i32)>,
+    /// Wheel distance scrolled since it was last taken, in lines.
+    wheel: (f32, f32),
+    /// Bindings of the actions games query.
+    map: InputMap,
+    /// Action bound to the next pressed key or button, if a rebind is waiting.
+    pending_rebind: Option<String>,
+    /// Presses and releases not drained yet, oldest first.
+    events: VecDeque<InputEvent>,
+    /// Keys and buttons pressed or released since the current tick started; they become
+    /// the edges of the next tick.
+    pending_edges: Edges,
+    /// Keys and buttons pressed or released before the current tick started.
+    frame_edges: Edges,
+    /// Input recorded since recording started, if it is running.
+    recording: Option<InputRecording>,
+    /// Changes of the ticks left to play back, if a recording is played back.
+    playback: Option<VecDeque<Vec<InputChange>>>,
+    /// Characters typed since the text was last taken.
+    text: String,
+    /// Text being composed with an input method, not committed yet.
+    preedit: String,
+}
+
+impl State {
+    /// Queues an event, dropping the oldest one when the queue is full.
+    fn push_event(&mut self, event: InputEvent) {
+        if self.events.len() == MAX_QUEUED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Binds the waiting action, if any, to a pressed input.
+    ///
+    /// # Returns
+    /// True if the press was used for rebinding.
+    fn complete_rebind(&mut self, binding: Binding) -> bool {
+        match self.pending_rebind.take() {
+            Some(action) => {
+                self.map.rebind(action, binding);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// [Handle](crate#handles) to the current state of the keyboard, the mouse and touches.
+///
+/// The game window records events into it, and scripts and systems read the keys and
+/// buttons held, the mouse position and the touches from their clones.
+#[derive(Clone, Default)]
+pub struct InputState {
+    state: Arc<RwLock<State>>,
+}
+
+impl InputState {
+    /// Creates a state with nothing held down and the mouse outside the frame.
+    pub fn new() -> Self {
+        InputState::default()
+    }
+
+    /// Checks whether a key is currently held down.
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.state.read().unwrap().keys.contains(&key)
+    }
+
+    /// Returns all keys currently held down, in no particular order.
+    pub fn pressed_keys(&self) -> Vec<KeyCode> {
+        self.state.read().unwrap().keys.iter().copied().collect()
+    }
+
+    /// Checks whether any binding of an action is currently held down.
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        let state = self.state.read().unwrap();
+        state
+            .map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => state.keys.contains(key),
+                Binding::Button(button) => state.buttons.contains(button),
+            })
+    }
+
+    /// Checks whether a key was pressed since the previous tick.
+    pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.is_just_pressed(Binding::Key(key))
+    }
+
+    /// Returns all keys pressed since the previous tick, in no particular order.
+    pub fn just_pressed_keys(&self) -> Vec<KeyCode> {
+        let state = self.state.read().unwrap();
+        state
+            .frame_edges
+            .pressed
+            .iter()
+            .filter_map(|binding| match binding {
+                Binding::Key(key) => Some(*key),
+                Binding::Button(_) => None,
+            })
+            .collect()
+    }
+
+    /// Checks whether a key was released since the previous tick.
+    pub fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.is_just_released(Binding::Key(key))
+    }
+
+    /// Checks whether a mouse button was pressed since the previous tick.
+    pub fn is_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.is_just_pressed(Binding::Button(button))
+    }
+
+    /// Checks whether a mouse button was released since the previous tick.
+    pub fn is_button_just_released(&self, button: MouseButton) -> bool {
+        self.is_just_released(Binding::Button(button))
+    }
+
+    /// Checks whether any binding of an action was pressed since the previous tick.
+    pub fn is_action_just_pressed(&self, action: &str) -> bool {
+        let state = self.state.read().unwrap();
+        state
+            .map
+            .bindings(action)
+            .iter()
+            .any(|binding| state.frame_edges.pressed.contains(binding))
+    }
+
+    /// Checks whether any binding of an action was released since the previous tick.
+    pub fn is_action_just_released(&self, action: &str) -> bool {
+        let state = self.state.read().unwrap();
+        state
+            .map
+            .bindings(action)
+            .iter()
+            .any(|binding| state.frame_edges.released.contains(binding))
+    }
+
+    /// Checks whether a key or button was pressed since the previous tick.
+    fn is_just_pressed(&self, binding: Binding) -> bool {
+        self.state
+            .read()
+            .unwrap()
+            .frame_edges
+            .pressed
+            .contains(&binding)
+    }
+
+    /// Checks whether a key or button was released since the previous tick.
+    fn is_just_released(&self, binding: Binding) -> bool {
+        self.state
+            .read()
+            .unwrap()
+            .frame_edges
+            .released
+            .contains(&binding)
+    }
+
+    /// Starts a new tick: presses and releases recorded since the last call become the
+    /// ones the `just_pressed` and `just_released` queries report until the next call.
+    ///
+    /// While a recording is played back, the input of the recorded tick is applied first.
+    pub(crate) fn next_frame(&self) {
+        let changes = {
+            let mut state = self.state.write().unwrap();
+            if let Some(recording) = state.recording.as_mut() {
+                recording.end_frame();
+            }
+            match state.playback.as_mut().map(VecDeque::pop_front) {
+                Some(Some(changes)) => changes,
+                Some(None) => {
+                    state.playback = None;
+                    vec![InputChange::ReleaseAll]
+                }
+                None => Vec::new(),
+            }
+        };
+        for change in changes {
+            self.apply(change);
+        }
+        let mut state = self.state.write().unwrap();
+        state.frame_edges = std::mem::take(&mut state.pending_edges);
+    }
+
+    /// Returns a copy of the action bindings.
+    pub fn input_map(&self) -> InputMap {
+        self.state.read().unwrap().map.clone()
+    }
+
+    /// Replaces the action bindings.
+    pub fn set_input_map(&self, map: InputMap) {
+        self.state.write().unwrap().map = map;
+    }
+
+    /// Returns the pointers pressed on the frame and their positions: the mouse while its
+    /// left button is held, then every finger touching the frame.
+    pub fn pointers(&self) -> Vec<(Pointer, (i32, i32))> {
+        let state = self.state.read().unwrap();
+        let mouse = state
+            .mouse_pointer
+            .and(state.mouse_position)
+            .map(|position| (Pointer::Mouse, position));
+        mouse
+            .into_iter()
+            .chain(
+                state
+                    .touches
+                    .iter()
+                    .map(|(id, position)| (Pointer::Touch(*id), *position)),
+            )
+            .collect()
+    }
+
+    /// Returns the position of the main pointer: the first finger touching the frame, or
+    /// the mouse position over the frame otherwise.
+    pub fn pointer_position(&self) -> Option<(i32, i32)> {
+        let state = self.state.read().unwrap();
+        state
+            .touches
+            .values()
+            .next()
+            .copied()
+            .or(state.mouse_position)
+    }
+
+    /// Removes and returns the queued presses and releases, oldest first.
+    ///
+    /// At most the last 1024 events are kept between two drains.
+    pub fn drain_events(&self) -> Vec<InputEvent> {
+        self.state.write().unwrap().events.drain(..).collect()
+    }
+
+    /// Removes and returns the characters typed since the last call.
+    ///
+    /// Control characters are left out; editing keys such as backspace arrive as key
+    /// events instead.
+    pub fn take_text(&self) -> String {
+        std::mem::take(&mut self.state.write().unwrap().text)
+    }
+
+    /// Returns the text the player is composing with an input method, empty if none.
+    ///
+    /// Input methods only run while allowed on the game window; the text becomes part of
+    /// [`InputState::take_text`] once committed.
+    pub fn preedit(&self) -> String {
+        self.state.read().unwrap().preedit.clone()
+    }
+
+    /// Binds an action to the next key or mouse button pressed, replacing its bindings.
+    ///
+    /// That press only rebinds the action; it is not recorded as held down and does not
+    /// exit the engine even when it is the quit key.
+    pub fn rebind_next(&self, action: impl Into<String>) {
+        self.state.write().unwrap().pending_rebind = Some(action.into());
+    }
+
+    /// Checks whether an action waits to be bound to the next press.
+    pub fn is_rebinding(&self) -> bool {
+        self.state.read().unwrap().pending_rebind.is_some()
+    }
+
+    /// Stops waiting for a press to rebind an action, keeping its bindings.
+    pub fn cancel_rebind(&self) {
+        self.state.write().unwrap().pending_rebind = None;
+    }
+
+    /// Checks whether a mouse button is currently held down.
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.state.read().unwrap().buttons.contains(&button)
+    }
+
+    /// Returns the frame position of the mouse, or None while it is outside the frame.
+    pub fn mouse_position(&self) -> Option<(i32, i32)> {
+        self.state.read().unwrap().mouse_position
+    }
+
+    /// Returns the horizontal and vertical wheel distance scrolled since the last call,
+    /// in lines; positive values scroll right and up.
+    pub fn take_wheel_delta(&self) -> (f32, f32) {
+        std::mem::take(&mut self.state.write().unwrap().wheel)
+    }
+
+    /// Records a key being pressed or released; a press completes a waiting rebind instead.
+    pub(crate) fn set_key(&self, key: KeyCode, pressed: bool) {
+        self.feed(InputChange::Key { key, pressed });
+    }
+
+    /// Records a mouse button being pressed or released; a press completes a waiting rebind
+    /// instead.
+    pub(crate) fn set_button(&self, button: MouseButton, pressed: bool) {
+        self.feed(InputChange::Button { button, pressed });
+    }
+
+    /// Stores the frame position of the mouse, None meaning outside the frame.
+    pub(crate) fn set_mouse_position(&self, position: Option<(i32, i32)>) {
+        self.feed(InputChange::MousePosition { position });
+    }
+
+    /// Records a finger touching, moving over or leaving the frame.
+    ///
+    /// # Parameters
+    /// - `id`: Identifier of the finger.
+    /// - `phase`: Whether the touch started, moved, ended or was cancelled.
+    /// - `position`: Frame position of the finger, None outside the frame; touches only
+    ///   start inside the frame.
+    pub(crate) fn touch(&self, id: u64, phase: TouchPhase, position: Option<(i32, i32)>) {
+        self.feed(InputChange::Touch {
+            id,
+            phase,
+            position,
+        });
+    }
+
+    /// Appends typed or committed text, leaving out control characters.
+    pub(crate) fn add_text(&self, text: &str) {
+        self.feed(InputChange::Text {
+            text: text.to_string(),
+        });
+    }
+
+    /// Replaces the text being composed with an input method.
+    pub(crate) fn set_preedit(&self, text: String) {
+        self.feed(InputChange::Preedit { text });
+    }
+
+    /// Adds a scrolled wheel distance.
+    pub(crate) fn add_wheel_delta(&self, delta: MouseScrollDelta) {
+        let (x, y) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (x, y),
+            MouseScrollDelta::PixelDelta(position) => (
+                (position.x / PIXELS_PER_LINE) as f32,
+                (position.y / PIXELS_PER_LINE) as f32,
+            ),
+        };
+        self.feed(InputChange::Wheel { x, y });
+    }
+
+    /// Releases all keys and buttons, e.g. when the window loses focus and stops receiving
+    /// releases, queueing a release event for each of them.
+    pub(crate) fn release_all(&self) {
+        self.feed(InputChange::ReleaseAll);
+    }
+
+    /// Starts recording the input of every tick, dropping a previous recording.
+    pub fn start_recording(&self) {
+        self.state.write().unwrap().recording = Some(InputRecording::new());
+    }
+
+    /// Stops recording and returns the recorded ticks, None if no recording was running.
+    pub fn stop_recording(&self) -> Option<InputRecording> {
+        self.state.write().unwrap().recording.take()
+    }
+
+    /// Checks whether the input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.state.read().unwrap().recording.is_some()
+    }
+
+    /// Plays a recording back, one recorded tick per tick, starting with the next tick.
+    ///
+    /// All keys and buttons are released first. During playback the input of the window is
+    /// ignored; it is used again once the recording ends, again releasing everything held.
+    pub fn play(&self, recording: InputRecording) {
+        self.apply(InputChange::ReleaseAll);
+        self.state.write().unwrap().playback = Some(recording.into_frames());
+    }
+
+    /// Checks whether a recording is being played back.
+    pub fn is_playing(&self) -> bool {
+        self.state.read().unwrap().playback.is_some()
+    }
+
+    /// Stops playback early and releases all keys and buttons held by the recording.
+    pub fn stop_playback(&self) {
+        if self.state.write().unwrap().playback.take().is_some() {
+            self.apply(InputChange::ReleaseAll);
+        }
+    }
+
+    /// Applies a change coming from the window, recording it if a recording is running.
+    ///
+    /// Changes are dropped while a recording is played back.
+    fn feed(&self, change: InputChange) {
+        {
+            let mut state = self.state.write().unwrap();
+            if state.playback.is_some() {
+                return;
+            }
+            if let Some(recording) = state.recording.as_mut() {
+                recording.push_change(change.clone());
+            }
+        }
+        self.apply(change);
+    }
+
+    /// Applies a change to the state.
+    fn apply(&self, change: InputChange) {
+        match change {
+            InputChange::Key { key, pressed } => self.apply_key(key, pressed),
+            InputChange::Button { button, pressed } => self.apply_button(button, pressed),
+            InputChange::MousePosition { position } => self.apply_mouse_position(position),
+            InputChange::Wheel { x, y } => {
+                let wheel = &mut self.state.write().unwrap().wheel;
+                wheel.0 += x;
+                wheel.1 += y;
+            }
+            InputChange::Text { text } => self.apply_text(&text),
+            InputChange::Preedit { text } => self.state.write().unwrap().preedit = text,
+            InputChange::Touch {
+                id,
+                phase,
+                position,
+            } => self.apply_touch(id, phase, position),
+            InputChange::ReleaseAll => self.apply_release_all(),
+        }
+    }
+
+    /// Applies a key press or release; a press completes a waiting rebind instead.
+    fn apply_key(&self, key: KeyCode, pressed: bool) {
+        let mut state = self.state.write().unwrap();
+        if !pressed {
+            if state.keys.remove(&key) {
+                state.pending_edges.released.insert(Binding::Key(key));
+                state.push_event(InputEvent::KeyUp(key));
+            }
+        } else if !state.complete_rebind(Binding::Key(key)) && state.keys.insert(key) {
+            state.pending_edges.pressed.insert(Binding::Key(key));
+            state.push_event(InputEvent::KeyDown(key));
+        }
+    }
+
+    /// Applies a mouse button press or release; a press completes a waiting rebind instead.
+    fn apply_button(&self, button: MouseButton, pressed: bool) {
+        let mut state = self.state.write().unwrap();
+        let position = state.mouse_position;
+        if !pressed {
+            if state.buttons.remove(&button) {
+                state.pending_edges.released.insert(Binding::Button(button));
+                state.push_event(InputEvent::ButtonUp { button, position });
+            }
+            if button == MouseButton::Left
+                && let Some(last) = state.mouse_pointer.take()
+            {
+                state.push_event(InputEvent::PointerUp {
+                    pointer: Pointer::Mouse,
+                    position: last,
+                });
+            }
+        } else if !state.complete_rebind(Binding::Button(button)) && state.buttons.insert(button) {
+            state.pending_edges.pressed.insert(Binding::Button(button));
+            state.push_event(InputEvent::ButtonDown { button, position });
+            if button == MouseButton::Left
+                && let Some(position) = position
+            {
+                state.mouse_pointer = Some(position);
+                state.push_event(InputEvent::PointerDown {
+                    pointer: Pointer::Mouse,
+                    position,
+                });
+            }
+        }
+    }
+
+    /// Applies a mouse movement.
+    fn apply_mouse_position(&self, position: Option<(i32, i32)>) {
+        let mut state = self.state.write().unwrap();
+        state.mouse_position = position;
+        if let (Some(pointer), Some(position)) = (state.mouse_pointer.as_mut(), position) {
+            *pointer = position;
+        }
+    }
+
+    /// Applies a finger touching, moving over or leaving the frame; touches only start
+    /// inside the frame.
+    fn apply_touch(&self, id: u64, phase: TouchPhase, position: Option<(i32, i32)>) {
+        let mut state = self.state.write().unwrap();
+        match phase {
+            TouchPhase::Started => {
+                if let Some(position) = position
+                    && state.touches.insert(id, position).is_none()
+                {
+                    state.push_event(InputEvent::PointerDown {
+                        pointer: Pointer::Touch(id),
+                        position,
+                    });
+                }
+            }
+            TouchPhase::Moved => {
+                if let (Some(touch), Some(position)) = (state.touches.get_mut(&id), position) {
+                    *touch = position;
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if let Some(last) = state.touches.remove(&id) {
+                    state.push_event(InputEvent::PointerUp {
+                        pointer: Pointer::Touch(id),
+                        position: position.unwrap_or(last),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Appends typed or committed text, leaving out control characters.
+    fn apply_text(&self, text: &str) {
+        let mut state = self.state.write().unwrap();
+        state.text.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+    /// Releases all keys and buttons, queueing a release event for each of them.
+    fn apply_release_all(&self) {
+        let mut state = self.state.write().unwrap();
+        let position = state.mouse_position;
+        let keys: Vec<_> = state.keys.drain().collect();
+        let buttons: Vec<_> = state.buttons.drain().collect();
+        for key in keys {
+            state.pending_edges.released.insert(Binding::Key(key));
+            state.push_event(InputEvent::KeyUp(key));
+        }
+        for button in buttons {
+            state.pending_edges.released.insert(Binding::Button(button));
+            state.push_event(InputEvent::ButtonUp { button, position });
+        }
+        if let Some(last) = state.mouse_pointer.take() {
+            state.push_event(InputEvent::PointerUp {
+                pointer: Pointer::Mouse,
+                position: last,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::dpi::PhysicalPosition;
+
+    #[test]
+    fn test_press_and_release_keys() {
+        let input = InputState::new();
+        assert!(!input.is_key_pressed(KeyCode::Space));
+
+        input.set_key(KeyCode::Space, true);
+        input.set_key(KeyCode::KeyE, true);
+        input.set_key(KeyCode::Space, true);
+        assert!(input.is_key_pressed(KeyCode::Space));
+        assert_eq!(input.pressed_keys().len(), 2);
+
+        input.set_key(KeyCode::Space, false);
+        assert!(!input.is_key_pressed(KeyCode::Space));
+        assert_eq!(input.pressed_keys(), vec![KeyCode::KeyE]);
+    }
+
+    #[test]
+    fn test_clones_share_state_and_release_all() {
+        let input = InputState::new();
+        input.clone().set_key(KeyCode::KeyW, true);
+        input.clone().set_button(MouseButton::Left, true);
+        assert!(input.is_key_pressed(KeyCode::KeyW));
+        assert!(input.is_button_pressed(MouseButton::Left));
+
+        input.drain_events();
+        input.release_all();
+        assert!(input.pressed_keys().is_empty());
+        assert!(!input.is_button_pressed(MouseButton::Left));
+        assert_eq!(input.drain_events().len(), 2);
+    }
+
+    #[test]
+    fn test_default_map_binds_movement() {
+        let map = InputMap::new();
+        assert_eq!(map.bindings(MOVE_UP), &[Binding::Key(KeyCode::KeyW)]);
+        assert_eq!(map.bindings(MOVE_RIGHT), &[Binding::Key(KeyCode::KeyD)]);
+        assert!(map.bindings("fire").is_empty());
+        assert!(InputMap::empty().bindings(MOVE_UP).is_empty());
+    }
+
+    #[test]
+    fn test_actions_follow_bindings() {
+        let input = InputState::new();
+        let mut map = InputMap::empty()
+            .with_binding("fire", Binding::Key(KeyCode::Space))
+            .with_binding("fire", Binding::Button(MouseButton::Left));
+        map.bind("fire", Binding::Key(KeyCode::Space));
+        assert_eq!(map.bindings("fire").len(), 2);
+        input.set_input_map(map);
+
+        assert!(!input.is_action_pressed("fire"));
+        input.set_button(MouseButton::Left, true);
+        assert!(input.is_action_pressed("fire"));
+        input.set_button(MouseButton::Left, false);
+        input.set_key(KeyCode::Space, true);
+        assert!(input.is_action_pressed("fire"));
+        assert!(!input.is_action_pressed(MOVE_UP));
+
+        let mut map = input.input_map();
+        map.unbind("fire");
+        input.set_input_map(map);
+        assert!(!input.is_action_pressed("fire"));
+    }
+
+    #[test]
+    fn test_rebind_to_next_press() {
+        let input = InputState::new();
+        input.rebind_next(MOVE_UP);
+        assert!(input.is_rebinding());
+
+        input.set_key(KeyCode::ArrowUp, true);
+        assert!(!input.is_rebinding());
+        assert!(!input.is_key_pressed(KeyCode::ArrowUp));
+        assert_eq!(
+            input.input_map().bindings(MOVE_UP),
+            &[Binding::Key(KeyCode::ArrowUp)]
+        );
+
+        input.set_key(KeyCode::ArrowUp, false);
+        input.set_key(KeyCode::ArrowUp, true);
+        assert!(input.is_action_pressed(MOVE_UP));
+
+        input.rebind_next("fire");
+        input.set_button(MouseButton::Right, true);
+        assert_eq!(
+            input.input_map().bindings("fire"),
+            &[Binding::Button(MouseButton::Right)]
+        );
+
+        input.rebind_next(MOVE_DOWN);
+        input.cancel_rebind();
+        input.set_key(KeyCode::KeyX, true);
+        assert_eq!(
+            input.input_map().bindings(MOVE_DOWN),
+            &[Binding::Key(KeyCode::KeyS)]
+        );
+    }
+
+    #[test]
+    fn test_enumerate_bindings() {
+        let mut map = InputMap::new();
+        map.rebind(MOVE_LEFT, Binding::Key(KeyCode::ArrowLeft));
+        let actions: Vec<_> = map.actions().map(|(action, _)| action).collect();
+        assert_eq!(actions, vec![MOVE_DOWN, MOVE_LEFT, MOVE_RIGHT, MOVE_UP]);
+        assert_eq!(
+            map.actions().find(|(action, _)| *action == MOVE_LEFT),
+            Some((MOVE_LEFT, &[Binding::Key(KeyCode::ArrowLeft)][..]))
+        );
+    }
+
+    #[test]
+    fn test_presses_and_releases_are_queued() {
+        let input = InputState::new();
+        input.set_key(KeyCode::Space, true);
+        input.set_key(KeyCode::Space, true);
+        input.set_mouse_position(Some((5, 6)));
+        input.set_button(MouseButton::Right, true);
+        input.set_key(KeyCode::Space, false);
+        input.set_mouse_position(None);
+        input.set_button(MouseButton::Right, false);
+        input.set_key(KeyCode::KeyQ, false);
+        assert_eq!(
+            input.drain_events(),
+            vec![
+                InputEvent::KeyDown(KeyCode::Space),
+                InputEvent::ButtonDown {
+                    button: MouseButton::Right,
+                    position: Some((5, 6)),
+                },
+                InputEvent::KeyUp(KeyCode::Space),
+                InputEvent::ButtonUp {
+                    button: MouseButton::Right,
+                    position: None,
+                },
+            ]
+        );
+        assert!(input.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_event_queue_keeps_newest_events() {
+        let input = InputState::new();
+        for _ in 0..MAX_QUEUED_EVENTS {
+            input.set_key(KeyCode::KeyA, true);
+            input.set_key(KeyCode::KeyA, false);
+        }
+        let events = input.drain_events();
+        assert_eq!(events.len(), MAX_QUEUED_EVENTS);
+        assert_eq!(events.last(), Some(&InputEvent::KeyUp(KeyCode::KeyA)));
+    }
+
+    #[test]
+    fn test_text_stream() {
+        let input = InputState::new();
+        input.add_text("h");
+        input.add_text("i\u{8}\r");
+        input.set_preedit("にほ".to_string());
+        assert_eq!(input.preedit(), "にほ");
+
+        input.set_preedit(String::new());
+        input.add_text("日本");
+        assert_eq!(input.take_text(), "hi日本");
+        assert_eq!(input.take_text(), "");
+        assert_eq!(input.preedit(), "");
+    }
+
+    #[test]
+    fn test_touches_are_pointers() {
+        let input = InputState::new();
+        input.touch(7, TouchPhase::Started, None);
+        assert!(input.pointers().is_empty());
+
+        input.touch(1, TouchPhase::Started, Some((10, 20)));
+        input.touch(1, TouchPhase::Moved, Some((12, 22)));
+        input.touch(2, TouchPhase::Started, Some((30, 40)));
+        assert_eq!(input.pointer_position(), Some((12, 22)));
+        assert_eq!(
+            input.pointers(),
+            vec![(Pointer::Touch(1), (12, 22)), (Pointer::Touch(2), (30, 40))]
+        );
+
+        input.touch(1, TouchPhase::Ended, None);
+        input.touch(2, TouchPhase::Cancelled, Some((31, 41)));
+        assert!(input.pointers().is_empty());
+        assert_eq!(
+            input.drain_events(),
+            vec![
+                InputEvent::PointerDown {
+                    pointer: Pointer::Touch(1),
+                    position: (10, 20),
+                },
+                InputEvent::PointerDown {
+                    pointer: Pointer::Touch(2),
+                    position: (30, 40),
+                },
+                InputEvent::PointerUp {
+                    pointer: Pointer::Touch(1),
+                    position: (12, 22),
+                },
+                InputEvent::PointerUp {
+                    pointer: Pointer::Touch(2),
+                    position: (31, 41),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_left_mouse_button_is_a_pointer() {
+        let input = InputState::new();
+        input.set_mouse_position(Some((1, 2)));
+        assert_eq!(input.pointer_position(), Some((1, 2)));
+        input.set_button(MouseButton::Right, true);
+        input.set_button(MouseButton::Left, true);
+        input.set_mouse_position(Some((3, 4)));
+        assert_eq!(input.pointers(), vec![(Pointer::Mouse, (3, 4))]);
+
+        input.set_mouse_position(None);
+        assert!(input.pointers().is_empty());
+        input.set_button(MouseButton::Left, false);
+        let events = input.drain_events();
+        assert_eq!(
+            events[2],
+            InputEvent::PointerDown {
+                pointer: Pointer::Mouse,
+                position: (1, 2),
+            }
+        );
+        assert_eq!(
+            events.last(),
+            Some(&InputEvent::PointerUp {
+                pointer: Pointer::Mouse,
+                position: (3, 4),
+            })
+        );
+    }
+
+    #[test]
+    fn test_edges_last_one_tick() {
+        let input = InputState::new();
+        input.set_key(KeyCode::KeyW, true);
+        input.set_button(MouseButton::Left, true);
+        assert!(!input.is_key_just_pressed(KeyCode::KeyW));
+
+        input.next_frame();
+        assert!(input.is_key_just_pressed(KeyCode::KeyW));
+        assert_eq!(input.just_pressed_keys(), vec![KeyCode::KeyW]);
+        assert!(input.is_action_just_pressed(MOVE_UP));
+        assert!(input.is_button_just_pressed(MouseButton::Left));
+        assert!(!input.is_key_just_released(KeyCode::KeyW));
+
+        input.set_key(KeyCode::KeyW, true);
+        input.next_frame();
+        assert!(!input.is_key_just_pressed(KeyCode::KeyW));
+        assert!(input.is_key_pressed(KeyCode::KeyW));
+
+        input.set_key(KeyCode::KeyW, false);
+        input.set_button(MouseButton::Left, false);
+        input.next_frame();
+        assert!(input.is_key_just_released(KeyCode::KeyW));
+        assert!(input.is_action_just_released(MOVE_UP));
+        assert!(input.is_button_just_released(MouseButton::Left));
+
+        input.next_frame();
+        assert!(!input.is_key_just_released(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn test_tap_within_one_tick_is_both_pressed_and_released() {
+        let input = InputState::new();
+        input.set_key(KeyCode::Space, true);
+        input.set_key(KeyCode::Space, false);
+        input.next_frame();
+        assert!(input.is_key_just_pressed(KeyCode::Space));
+        assert!(input.is_key_just_released(KeyCode::Space));
+        assert!(!input.is_key_pressed(KeyCode::Space));
+    }
+
+    #[test]
+    fn test_recorded_input_is_played_back_per_tick() {
+        let input = InputState::new();
+        input.start_recording();
+        input.set_key(KeyCode::KeyW, true);
+        input.next_frame();
+        input.next_frame();
+        input.set_key(KeyCode::KeyW, false);
+        input.set_mouse_position(Some((2, 3)));
+        input.next_frame();
+        let recording = input.stop_recording().unwrap();
+        assert!(!input.is_recording());
+
+        let replay = InputState::new();
+        replay.set_key(KeyCode::KeyE, true);
+        replay.play(recording);
+        assert!(replay.is_playing());
+        assert!(!replay.is_key_pressed(KeyCode::KeyE));
+
+        replay.next_frame();
+        assert!(replay.is_key_just_pressed(KeyCode::KeyW));
+        replay.set_key(KeyCode::KeyQ, true);
+        assert!(!replay.is_key_pressed(KeyCode::KeyQ));
+        replay.next_frame();
+        assert!(replay.is_key_pressed(KeyCode::KeyW));
+        replay.next_frame();
+        assert!(replay.is_key_just_released(KeyCode::KeyW));
+        assert_eq!(replay.mouse_position(), Some((2, 3)));
+        replay.next_frame();
+        assert!(replay.is_playing());
+        replay.next_frame();
+        assert!(!replay.is_playing());
+        replay.set_key(KeyCode::KeyQ, true);
+        assert!(replay.is_key_pressed(KeyCode::KeyQ));
+    }
+
+    #[test]
+    fn test_stop_playback_releases_held_keys() {
+        let mut recording = InputRecording::new();
+        recording.push_change(InputChange::Key {
+            key: KeyCode::Space,
+            pressed: true,
+        });
+        let input = InputState::new();
+        input.play(recording);
+        input.next_frame();
+        assert!(input.is_key_pressed(KeyCode::Space));
+
+        input.stop_playback();
+        assert!(!input.is_playing());
+        assert!(!input.is_key_pressed(KeyCode::Space));
+    }
+
+    #[test]
+    fn test_mouse_position() {
+        let input = InputState::new();
+        assert_eq!(input.mouse_position(), None);
+        input.set_mouse_position(Some((3, 4)));
+        assert_eq!(input.mouse_position(), Some((3, 4)));
+        input.set_mouse_position(None);
+        assert_eq!(input.mouse_position(), None);
+    }
+
+    #[test]
+    fn test_wheel_delta_accumulates_until_taken() {
+        let input = InputState::new();
+        input.add_wheel_delta(MouseScrollDelta::LineDelta(0.0, 1.0));
+        input.add_wheel_delta(MouseScrollDelta::LineDelta(0.5, 2.0));
+        input.add_wheel_delta(MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+            0.0, -40.0,
+        )));
+        assert_eq!(input.take_wheel_delta(), (0.5, 1.0));
+        assert_eq!(input.take_wheel_delta(), (0.0, 0.0));
+    }
+}
@@ -5,42 +5,306 @@
 //! input handling, and the event loop.
 //!
 //! This module integrates configurations, scenes, rendering, and input processing
-//! to provide the core game engine loop and functionality.
+//! to provide the core game engine loop and functionality. Engines are usually created
+//! with the `EngineBuilder` of the `builder` module.
 
+pub mod assets;
+pub mod audio;
+pub mod builder;
+pub mod bus;
+pub mod collision;
 pub mod config;
+pub mod error;
+pub mod events;
 pub mod input;
+pub mod physics;
+pub mod replay;
 pub mod scene;
 pub mod scene_manager;
+pub mod system;
+pub mod timing;
+pub mod watchdog;
 
-use crate::Resolution;
+use crate::engine::assets::{AssetManager, Handle};
+use crate::engine::audio::{Audio, Bus};
+use crate::engine::bus::{EventBus, GameEvent};
 use crate::engine::config::Config;
+use crate::engine::error::EngineError;
+use crate::engine::events::EventQueue;
+use crate::engine::input::{InputState, MOVE_DOWN, MOVE_LEFT, MOVE_RIGHT, MOVE_UP};
 use crate::engine::scene::Scene;
 use crate::engine::scene::game_object::Object;
-use crate::engine::scene_manager::SceneManager;
+use crate::engine::scene::game_object::components::controller::drive_main_object;
+use crate::engine::scene::game_object::effects::apply_tint;
+use crate::engine::scene_manager::{SceneLoader, SceneManager};
+use crate::engine::system::{System, run_systems};
+use crate::engine::timing::{
+    DEFAULT_TARGET_FPS, FixedTimestep, FrameHistogram, FrameLimiter, GameClock, SharedFrameStats,
+};
+use crate::engine::watchdog::{Heartbeat, PanicGuard, Stage, Watchdog, WatchdogSettings};
+use crate::render::Pixel;
+use crate::render::backend::create_backend;
+use crate::render::camera::Camera;
+use crate::render::debug::DebugDraw;
+use crate::render::overlay::PerfOverlay;
+use crate::render::post::{PixelShader, PostEffect};
 use crate::render::renderer::{DEFAULT_BACKGROUND_COLOR, Renderer};
-use crate::screen::{App, HEIGHT, WIDTH};
+use crate::render::transition::Transition;
+use crate::render::ui::{Cursor, Ui};
+use crate::screen::frames::{FrameWriter, triple_buffer};
+use crate::screen::{
+    App, GameWindow, PresentMode, ScaleFactor, ScalingMode, WindowHandle, WindowPosition, load_icon,
+};
 //use image::ImageReader;
-use std::io::Error;
-use std::sync::atomic::Ordering;
-use std::sync::{Arc, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use winit::event_loop::{ControlFlow, EventLoop};
+use winit::keyboard::KeyCode;
 use winit::window::Window;
 
 /// Trait defining essential engine behavior.
 ///
 /// Abstracts an engine capable of managing an active scene, performing rendering,
 /// running its main loop, and supporting dynamic configuration.
+/// Fallible methods return an [`EngineError`] telling which part of the engine failed.
 pub trait Engine {
     /// Sets the currently active scene within the engine.
-    fn set_active_scene(&mut self, new_scene: Scene) -> Result<(), Error>;
+    fn set_active_scene(&mut self, new_scene: Scene) -> Result<(), EngineError>;
+
+    /// Registers a scene under a name, e.g. `"level2"`, for [`Engine::switch_scene`].
+    ///
+    /// A scene registered under the name before is replaced.
+    fn add_scene(&mut self, name: &str, scene: Scene);
+
+    /// Makes the scene registered under a name the active one.
+    ///
+    /// The objects of the old scene are despawned and the scene is dropped; the engine,
+    /// its systems and its window stay as they are. The new scene leaves the registry
+    /// while it is active, so it is registered again to come back to it later.
+    ///
+    /// Fails with [`EngineError::UnknownScene`] if no scene is registered under the name.
+    fn switch_scene(&mut self, name: &str) -> Result<(), EngineError>;
+
+    /// Builds a scene on a worker thread while the active scene keeps running, then
+    /// registers it under a name for [`Engine::switch_scene`].
+    ///
+    /// The loader gets the asset cache of the engine, so the images it loads stay cached
+    /// for the scene; the sounds the scene names with [`Scene::with_sounds`] are decoded
+    /// on the worker too. A scene registered under the name before is replaced.
+    ///
+    /// ```no_run
+    /// use rusty_ache::engine::Engine;
+    /// use rusty_ache::engine::builder::EngineBuilder;
+    /// use rusty_ache::engine::scene::Scene;
+    /// use std::path::Path;
+    ///
+    /// let mut engine = EngineBuilder::new().build();
+    /// let level2 = engine.preload_scene(
+    ///     "level2",
+    ///     Box::new(|_| Scene::load(Path::new("level2.toml"))),
+    /// );
+    /// // Later, e.g. when the player reaches the exit
+    /// if level2.wait().is_ok() {
+    ///     engine.switch_scene("level2").unwrap();
+    /// }
+    /// ```
+    ///
+    /// Returns a handle resolving once the scene is registered, or to the reason the
+    /// loader or one of the sounds failed; the scene is not registered then.
+    fn preload_scene(&mut self, name: &str, load: Box<SceneLoader>) -> Handle<()>;
+
+    /// Builds a scene on a worker thread like [`Engine::preload_scene`] and makes it the
+    /// active scene as soon as it is built, e.g. for a level following a loading screen.
+    ///
+    /// The scene is switched to as with [`Engine::switch_scene`], between two frames.
+    /// The handle resolves once the scene is active, or to the reason it is not.
+    fn switch_scene_when_loaded(&mut self, name: &str, load: Box<SceneLoader>) -> Handle<()>;
+
+    /// Puts a scene on top of the active one, e.g. a pause menu or an inventory.
+    ///
+    /// The pushed scene becomes the active one and gets the input and the updates; the
+    /// covered scene is frozen but stays visible behind it until [`Engine::pop_scene`].
+    fn push_scene(&mut self, scene: Scene) -> Result<(), EngineError>;
+
+    /// Takes the scene on top away and makes the covered scene below it active again.
+    ///
+    /// Returns the scene taken away, or None if no scene is covered by the active one.
+    fn pop_scene(&mut self) -> Option<Scene>;
+
+    /// Sets the transition shown whenever the active scene changes, e.g. a one-second
+    /// [`Transition::Fade`]; scenes change with a cut by default.
+    fn set_transition(&mut self, transition: Transition);
 
     /// Performs a rendering pass.
-    fn render(&mut self) -> Result<(), Error>;
+    fn render(&mut self) -> Result<(), EngineError>;
+
+    /// Appends a full-frame effect applied to every displayed frame.
+    fn add_post_effect(&mut self, effect: Box<dyn PostEffect>) -> Result<(), EngineError>;
+
+    /// Registers logic run once per update with access to the whole active scene.
+    ///
+    /// Systems run after the object scripts, in the order they were added, e.g. to make
+    /// all enemies chase the player.
+    fn add_system(&mut self, system: Box<dyn System>) -> Result<(), EngineError>;
+
+    /// Sets a function computing the final color of every displayed pixel.
+    fn set_pixel_shader(&mut self, shader: Box<PixelShader>) -> Result<(), EngineError>;
+
+    /// Returns a handle to the screen-space overlay used for HUD sprites and text.
+    fn ui(&self) -> Ui;
+
+    /// Returns a handle for drawing debug primitives on the next displayed frame.
+    fn debug(&self) -> DebugDraw;
+
+    /// Returns a handle to the statistics of the displayed frames, updated every second.
+    fn frame_stats(&self) -> SharedFrameStats;
+
+    /// Returns a handle to the histogram of the displayed frame times.
+    ///
+    /// The histogram collects all frames of a run; if the configuration names a file, it is
+    /// written there when the engine shuts down.
+    fn frame_histogram(&self) -> FrameHistogram;
+
+    /// Returns a handle to the performance overlay, which F3 toggles in the game window.
+    fn perf_overlay(&self) -> PerfOverlay;
+
+    /// Halts the updates of scripts and systems, e.g. while a pause menu is shown.
+    ///
+    /// The window keeps presenting the last frame, darkened as set with
+    /// [`GameClock::set_pause_dim`], and UI and debug drawings stay visible.
+    fn pause(&self);
+
+    /// Continues the updates after [`Engine::pause`]; the paused time is not caught up on.
+    fn resume(&self);
+
+    /// Checks whether the engine is paused.
+    fn is_paused(&self) -> bool;
+
+    /// Returns a handle to the game clock, e.g. to pause the game from another thread.
+    fn clock(&self) -> GameClock;
+
+    /// Returns a handle to the image cache, resolving paths against the configured asset root.
+    fn assets(&self) -> AssetManager;
+
+    /// Returns a handle to the sound playback, also reachable from the scene for scripts
+    /// and systems.
+    ///
+    /// Sounds are mixed but stay silent until the game sets an output with
+    /// [`Audio::set_output`].
+    fn audio(&self) -> Audio;
+
+    /// Sets the volume of a mixer bus, e.g. from a sound options menu.
+    ///
+    /// The volume goes from 0 for silence to 1 for the sounds' own volume.
+    fn set_volume(&self, bus: Bus, volume: f32);
+
+    /// Returns the volume of a mixer bus.
+    fn volume(&self, bus: Bus) -> f32;
+
+    /// Mutes a mixer bus or makes it heard again at its volume.
+    fn set_muted(&self, bus: Bus, muted: bool);
+
+    /// Checks whether a mixer bus is muted.
+    fn is_muted(&self, bus: Bus) -> bool;
+
+    /// Returns a handle to the queue of window events, such as focus changes and dropped files.
+    ///
+    /// Games can drain it every tick, e.g. to pause while the window is in the background.
+    fn events(&self) -> EventQueue;
+
+    /// Returns a handle to the event bus shared by scripts, systems and the engine.
+    ///
+    /// Game modules publish and subscribe to it to communicate without direct references;
+    /// the engine publishes key presses, spawned and despawned objects and scene changes.
+    fn event_bus(&self) -> EventBus;
+
+    /// Returns the camera of the most recently rendered frame.
+    ///
+    /// Games use it to translate, e.g. mouse clicks into world positions.
+    fn camera(&self) -> Camera;
+
+    /// Returns a handle to the keyboard and mouse state of the game window.
+    ///
+    /// Games can query any key or mouse button and the mouse position and wheel with it,
+    /// e.g. space to shoot, and drain their presses and releases every tick; the movement
+    /// actions move the main object. The input can also be recorded and played back, e.g. to
+    /// reproduce a bug in the headless mode.
+    fn input(&self) -> InputState;
+
+    /// Returns a handle to the game window, e.g. to move it to another monitor while it runs.
+    fn window(&self) -> GameWindow;
+
+    /// Returns a handle to the scale factor of the display showing the game window.
+    ///
+    /// Games can use it to adjust, e.g. the size of UI elements; it is 1.0 until the
+    /// window opens.
+    fn scale_factor(&self) -> ScaleFactor;
+
+    /// Draws the cursor at the mouse position over the game window instead of the OS cursor,
+    /// or restores the OS cursor with None. Takes effect when the window opens.
+    fn set_cursor(&mut self, cursor: Option<Cursor>) -> Result<(), EngineError>;
+
+    /// Writes the most recently displayed frame to an image file, e.g. a `.png`.
+    ///
+    /// While the engine runs, pressing F12 saves a screenshot to the working directory.
+    fn screenshot(&self, path: &Path) -> Result<(), EngineError>;
+
+    /// Starts recording the displayed frames, keeping the most recent `max_frames` of them.
+    fn start_recording(&mut self, max_frames: usize) -> Result<(), EngineError>;
+
+    /// Saves the recorded frames as an animated GIF, played at the target frame rate.
+    ///
+    /// Recording continues afterwards.
+    ///
+    /// # Errors
+    /// Fails if no recording is running or the file cannot be written.
+    fn save_recording_gif(&self, path: &Path) -> Result<(), EngineError>;
+
+    /// Saves the recorded frames as numbered PNG images in a directory.
+    ///
+    /// # Errors
+    /// Fails if no recording is running or the images cannot be written.
+    fn save_recording_frames(&self, dir: &Path) -> Result<(), EngineError>;
+
+    /// Adds a window opened next to the game window, e.g. for a debug view.
+    ///
+    /// The caller keeps a clone of the handle to present frames in the window.
+    fn add_window(&mut self, window: WindowHandle) -> Result<(), EngineError>;
 
     /// Starts and runs the engine main loop.
-    fn run(&mut self) -> Result<(), Error>;
+    fn run(&mut self) -> Result<(), EngineError>;
+
+    /// Advances the active scene by one update and renders it without a window.
+    ///
+    /// Like every update of the engine loop, it starts a new input frame for the
+    /// `just_pressed` and `just_released` queries, runs the object scripts and moves the
+    /// main object. While the engine is paused, the scene is only rendered.
+    /// The elapsed time is multiplied by the time scale of the game clock.
+    ///
+    /// # Parameters
+    /// - `elapsed`: Game time passed since the previous update, e.g. for timed effects.
+    ///
+    /// # Returns
+    /// The emitted frame as RGBA bytes, row by row.
+    fn step(&mut self, elapsed: Duration) -> Result<Vec<u8>, EngineError>;
+
+    /// Runs the update and render loop for a number of frames without creating a window.
+    ///
+    /// Frames are produced as fast as possible, each advancing the game by one update of the
+    /// fixed update rate, so runs are reproducible, e.g. in CI tests or for generating
+    /// screenshots.
+    ///
+    /// # Parameters
+    /// - `frames`: Number of frames to produce.
+    /// - `on_frame`: Called with the index and RGBA bytes of every frame.
+    fn run_headless(
+        &mut self,
+        frames: u32,
+        on_frame: &mut dyn FnMut(u32, &[u8]),
+    ) -> Result<(), EngineError>;
 
     /// Creates a new engine instance from configuration and initial scene.
     fn new(config: Box<dyn Config + Send>, scene: Scene) -> Self
@@ -48,6 +312,268 @@ pub trait Engine {
         Self: Sized;
 }
 
+/// Returns a file name for a screenshot taken now, in the working directory.
+fn screenshot_path() -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+    PathBuf::from(format!("screenshot_{millis}.png"))
+}
+
+/// Darkens a displayed pixel of a paused frame by the given strength.
+fn dim(pixel: Pixel, strength: u8) -> Pixel {
+    let [r, g, b, a] = apply_tint([pixel.0, pixel.1, pixel.2, pixel.3], (0, 0, 0, strength));
+    (r, g, b, a)
+}
+
+/// Advances the game logic of a scene by one update.
+///
+/// Starts a new input frame, publishing its key presses, then runs the scripts and the
+/// systems and moves the main object along the pressed movement actions: by its
+/// `CharacterController` if it has one, by one pixel otherwise. Timed effects and sounds
+/// advance by the game time of the update.
+///
+/// # Parameters
+/// - `scene`: Scene to update.
+/// - `systems`: Systems run after the scripts.
+/// - `input`: Input driving the update.
+/// - `dt`: Game time advanced by the update.
+fn update(scene: &mut Scene, systems: &mut [Box<dyn System>], input: &InputState, dt: Duration) {
+    input.next_frame();
+    scene.reload_if_changed();
+    scene.resolve_pending_images();
+    let events = scene.events();
+    for key in input.just_pressed_keys() {
+        events.publish(GameEvent::KeyPressed(key));
+    }
+    scene.run_scripts(dt, input);
+    run_systems(systems, scene, dt, input);
+    if !drive_main_object(scene, input, dt) {
+        let dx = (input.is_action_pressed(MOVE_RIGHT) as i32)
+            - (input.is_action_pressed(MOVE_LEFT) as i32);
+        let dy =
+            (input.is_action_pressed(MOVE_UP) as i32) - (input.is_action_pressed(MOVE_DOWN) as i32);
+        scene.main_object.add_position((dx, dy));
+    }
+    scene.update_effects(dt);
+    scene.audio().advance(dt);
+}
+
+/// Per-tick work of the engine loop: runs the due updates, then renders and publishes a frame.
+struct Producer {
+    renderer: Arc<RwLock<Renderer>>,
+    /// Systems registered with the engine.
+    systems: Arc<Mutex<Vec<Box<dyn System>>>>,
+    input: InputState,
+    screenshot_requested: Arc<AtomicBool>,
+    /// Triple buffer handing the finished frames to the game window.
+    frames: FrameWriter,
+    /// Moment of the previous tick.
+    last_tick: Instant,
+    /// Accumulator deciding how many updates each tick runs.
+    timestep: FixedTimestep,
+    /// Clock pausing the updates.
+    clock: GameClock,
+    /// Progress reported to the watchdog.
+    heartbeat: Heartbeat,
+}
+
+impl Producer {
+    /// Runs the engine loop until the shutdown flag is set.
+    ///
+    /// Waits for the game window to open, then runs a tick per frame of the limiter and
+    /// requests a redraw of the window for every new frame. Finished ticks are reported
+    /// to the heartbeat, which is also marked if the thread panics.
+    fn run(
+        mut self,
+        window: Arc<RwLock<Option<Arc<Window>>>>,
+        mut limiter: FrameLimiter,
+        shutdown: Arc<AtomicBool>,
+    ) {
+        let _guard = PanicGuard(self.heartbeat.clone());
+        let window = loop {
+            if shutdown.load(Ordering::Relaxed) {
+                self.heartbeat.enter(Stage::Stopped);
+                return;
+            }
+            if let Some(window) = window.read().unwrap().clone() {
+                break window;
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+
+        log::debug!("Producer has started");
+
+        self.last_tick = Instant::now();
+        self.heartbeat.beat();
+        while !shutdown.load(Ordering::Relaxed) {
+            self.heartbeat.enter(Stage::Pacing);
+            limiter.wait();
+            if self.tick() {
+                window.request_redraw();
+            }
+            self.heartbeat.beat();
+        }
+        self.heartbeat.enter(Stage::Stopped);
+        log::debug!("Producer has stopped");
+    }
+
+    /// Runs one tick of the engine loop.
+    ///
+    /// Render errors are reported and skip the frame.
+    ///
+    /// # Returns
+    /// True if a new frame was handed to the game window.
+    fn tick(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now - self.last_tick;
+        self.last_tick = now;
+        let paused = self.clock.is_paused();
+        // Time passing while paused is dropped, so resuming does not run a burst of updates
+        let updates = if paused {
+            self.clock.take_ticks()
+        } else {
+            self.timestep.advance(elapsed, self.clock.time_scale())
+        };
+        self.heartbeat.enter(Stage::Updating);
+        {
+            let mut renderer = self.renderer.write().unwrap();
+            let mut systems = self.systems.lock().unwrap();
+            for _ in 0..updates {
+                update(
+                    &mut renderer.scene_manager.active_scene,
+                    &mut systems,
+                    &self.input,
+                    self.timestep.dt(),
+                );
+            }
+            renderer.set_cursor_position(self.input.mouse_position());
+            // Transitions run in real time, so they finish while the game is paused
+            renderer.advance_transition(elapsed);
+        }
+
+        self.heartbeat.enter(Stage::Rendering);
+        if let Err(e) = self.renderer.write().unwrap().render() {
+            log::error!("Couldn't render frame: {e}");
+            return false;
+        }
+        self.heartbeat.enter(Stage::Emitting);
+        let emitted = self.renderer.write().unwrap().emit();
+        self.heartbeat.enter(Stage::Presenting);
+        match emitted {
+            Ok(colors) => {
+                if self.screenshot_requested.swap(false, Ordering::Relaxed) {
+                    let path = screenshot_path();
+                    match self.renderer.read().unwrap().save_screenshot(&path) {
+                        Ok(()) => log::info!("Saved screenshot to {}", path.display()),
+                        Err(e) => log::error!("Couldn't save screenshot: {e}"),
+                    }
+                }
+
+                let dim_strength = if paused { self.clock.pause_dim() } else { 0 };
+                self.frames.write(colors.into_iter().map(|color| {
+                    if dim_strength > 0 {
+                        dim(color, dim_strength)
+                    } else {
+                        color
+                    }
+                }));
+                true
+            }
+            Err(e) => {
+                log::error!("Couldn't emit frame: {e}");
+                false
+            }
+        }
+    }
+}
+
+/// Extracts the message of a panic, which is a `&str` or a `String` for `panic!` calls.
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Starts the music of a scene becoming active, crossfading from the music playing.
+///
+/// Nothing changes if the scene has no music or its track plays already.
+///
+/// # Errors
+/// Fails with [`EngineError::Asset`] if the track cannot be opened.
+fn play_scene_music(
+    audio: &Audio,
+    assets: &AssetManager,
+    scene: &Scene,
+) -> Result<(), EngineError> {
+    let Some(path) = scene.music() else {
+        return Ok(());
+    };
+    let resolved = assets.resolve(path);
+    if audio.music().as_deref() == Some(resolved.as_path()) {
+        return Ok(());
+    }
+    audio
+        .play_music(path, audio.crossfade())
+        .map_err(|source| EngineError::Asset {
+            path: resolved,
+            source,
+        })
+}
+
+/// Connects a scene becoming active to the event bus and the sound playback of the
+/// engine and starts preloading its sounds.
+fn prepare_scene(scene: &mut Scene, bus: &EventBus, audio: &Audio) {
+    scene.set_event_bus(bus.clone());
+    scene.set_audio(audio.clone());
+    scene.preload_sounds();
+}
+
+/// Makes the scene registered under a name the active one, see [`Engine::switch_scene`].
+fn switch_to_scene(
+    renderer: &mut Renderer,
+    name: &str,
+    bus: &EventBus,
+    audio: &Audio,
+    assets: &AssetManager,
+) -> Result<(), EngineError> {
+    let scene = renderer
+        .scene_manager
+        .scene(name)
+        .ok_or_else(|| EngineError::UnknownScene(name.to_string()))?;
+    play_scene_music(audio, assets, scene)?;
+    renderer.start_transition();
+    let mut old = renderer
+        .scene_manager
+        .switch_to(name)
+        .expect("the scene is registered");
+    old.despawn_all();
+    prepare_scene(&mut renderer.scene_manager.active_scene, bus, audio);
+    renderer.invalidate();
+    bus.publish(GameEvent::SceneChanged);
+    Ok(())
+}
+
+/// Runs a scene loader and decodes the sounds the built scene names, so nothing is left
+/// to load when it becomes active.
+///
+/// # Errors
+/// Fails if the loader fails or a sound of the scene cannot be loaded.
+fn build_scene(load: Box<SceneLoader>, assets: &AssetManager) -> Result<Scene, std::io::Error> {
+    let scene = load(assets)?;
+    for path in scene.sounds() {
+        assets.load_sound(path)?;
+    }
+    Ok(scene)
+}
+
+/// Error returned when saving a recording while none is running.
+fn no_recording() -> std::io::Error {
+    std::io::Error::other("no recording is running")
+}
+
 /// Concrete implementation of the game engine.
 ///
 /// Holds a thread-safe renderer reference, manages scenes and input handling,
@@ -55,128 +581,594 @@ pub trait Engine {
 pub struct GameEngine {
     //config: Box<dyn Config + Send>,
     render: Arc<RwLock<Renderer>>,
+    /// Systems run every update, shared with the producer loop.
+    systems: Arc<Mutex<Vec<Box<dyn System>>>>,
+    /// Title of the game window.
+    window_title: String,
+    /// Path of the window icon image, if any.
+    window_icon: Option<PathBuf>,
+    /// How frames are presented to the window.
+    present_mode: PresentMode,
+    /// How frames are mapped onto the window surface.
+    scaling: ScalingMode,
+    /// Frame rate the producer loop is paced to; None when uncapped.
+    target_fps: Option<u32>,
+    /// Updates per second of the game logic.
+    update_rate: u32,
+    /// Key exiting the engine, if any.
+    quit_key: Option<KeyCode>,
+    /// Key pausing the engine and stepping it by single updates, if any.
+    frame_step_key: Option<KeyCode>,
+    /// Where the game window opens.
+    window_position: WindowPosition,
+    /// Index of the monitor the game window opens on, if not the primary one.
+    monitor: Option<usize>,
+    /// Whether the game window opens fullscreen.
+    fullscreen: bool,
+    /// The game window, once it is open.
+    window: GameWindow,
+    /// Statistics of the frames shown by the window.
+    frame_stats: SharedFrameStats,
+    /// Watchdog settings of the engine loop; None disables the watchdog.
+    watchdog: Option<WatchdogSettings>,
+    /// Histogram of the times between the frames shown by the window.
+    frame_histogram: FrameHistogram,
+    /// File the frame time histogram is written to at shutdown, if any.
+    frame_histogram_path: Option<PathBuf>,
+    /// Additional windows opened when the engine runs.
+    extra_windows: Vec<WindowHandle>,
+    /// Scale factor of the display showing the game window.
+    scale_factor: ScaleFactor,
+    /// Events reported by the game window.
+    events: EventQueue,
+    /// Event bus of scripts, systems and the engine.
+    bus: EventBus,
+    /// Keyboard and mouse input of the game window.
+    input: InputState,
+    /// Clock pausing the game logic.
+    clock: GameClock,
+    /// Cache of the images of the game.
+    assets: AssetManager,
+    /// Sound playback of the game.
+    audio: Audio,
+}
+
+impl GameEngine {
+    /// Connects a scene becoming active to the event bus and the sound playback of the
+    /// engine and starts preloading its sounds.
+    fn prepare_scene(&self, scene: &mut Scene) {
+        prepare_scene(scene, &self.bus, &self.audio);
+    }
+
+    /// Builds a scene on a worker thread and registers it under a name once it is built,
+    /// making it the active scene right away if asked to.
+    fn spawn_scene_load(&self, name: &str, load: Box<SceneLoader>, switch: bool) -> Handle<()> {
+        let handle = Handle::loading();
+        let result = handle.clone();
+        let name = name.to_string();
+        let render = self.render.clone();
+        let (bus, audio, assets) = (self.bus.clone(), self.audio.clone(), self.assets.clone());
+        let spawned = thread::Builder::new()
+            .name(format!("scene loader {name}"))
+            .spawn(move || {
+                let loaded = build_scene(load, &assets).and_then(|scene| {
+                    // Registering and switching happen under one lock, between two frames
+                    let mut renderer = render.write().unwrap();
+                    renderer.scene_manager.add_scene(&name, scene);
+                    if switch {
+                        switch_to_scene(&mut renderer, &name, &bus, &audio, &assets)
+                            .map_err(|e| std::io::Error::other(e.to_string()))?;
+                    }
+                    Ok(())
+                });
+                if let Err(e) = &loaded {
+                    log::error!("Couldn't load scene {name}: {e}");
+                }
+                result.finish(loaded);
+            });
+        if let Err(e) = spawned {
+            handle.finish(Err(e));
+        }
+        handle
+    }
 }
 
 impl Engine for GameEngine {
     /// Sets the active scene inside the renderer's scene manager.
-    fn set_active_scene(&mut self, new_scene: Scene) -> Result<(), Error> {
-        self.render.write().unwrap().scene_manager = SceneManager::new(new_scene);
+    ///
+    /// The next rendered frame is a full redraw of the new scene, and
+    /// [`GameEvent::SceneChanged`] is published. The music of the new scene, if any,
+    /// crossfades in over [`Audio::crossfade`], and its sounds start preloading.
+    ///
+    /// Fails with [`EngineError::Asset`] if the music of the new scene cannot be opened;
+    /// the old scene stays active then.
+    fn set_active_scene(&mut self, mut new_scene: Scene) -> Result<(), EngineError> {
+        play_scene_music(&self.audio, &self.assets, &new_scene)?;
+        self.prepare_scene(&mut new_scene);
+        let mut renderer = self.render.write().unwrap();
+        renderer.start_transition();
+        let mut old = renderer.scene_manager.set_active_scene(new_scene);
+        old.despawn_all();
+        renderer.invalidate();
+        self.bus.publish(GameEvent::SceneChanged);
 
         Ok(())
     }
 
+    /// Adds the scene to the registry of the renderer's scene manager.
+    fn add_scene(&mut self, name: &str, scene: Scene) {
+        self.render
+            .write()
+            .unwrap()
+            .scene_manager
+            .add_scene(name, scene);
+    }
+
+    /// Switches the renderer's scene manager to a registered scene, like
+    /// [`GameEngine::set_active_scene`] does with a new one.
+    ///
+    /// Fails with [`EngineError::Asset`] if the music of the scene cannot be opened; the
+    /// old scene stays active and the scene registered then.
+    fn switch_scene(&mut self, name: &str) -> Result<(), EngineError> {
+        switch_to_scene(
+            &mut self.render.write().unwrap(),
+            name,
+            &self.bus,
+            &self.audio,
+            &self.assets,
+        )
+    }
+
+    /// Builds the scene on a thread of its own, which takes the renderer's lock only to
+    /// register the built scene.
+    fn preload_scene(&mut self, name: &str, load: Box<SceneLoader>) -> Handle<()> {
+        self.spawn_scene_load(name, load, false)
+    }
+
+    /// Builds the scene like [`GameEngine::preload_scene`] and switches to it under the
+    /// same lock it is registered with, so no frame is rendered in between.
+    fn switch_scene_when_loaded(&mut self, name: &str, load: Box<SceneLoader>) -> Handle<()> {
+        self.spawn_scene_load(name, load, true)
+    }
+
+    /// Pushes the scene onto the renderer's scene manager, which renders the covered scene
+    /// once more to show it behind the new one.
+    ///
+    /// The music of the pushed scene, if any, crossfades in; a scene without music keeps
+    /// the music playing. Fails with [`EngineError::Asset`] if the music cannot be opened,
+    /// or with [`EngineError::Render`] if the covered scene cannot be rendered; the scene
+    /// is not pushed then.
+    fn push_scene(&mut self, mut scene: Scene) -> Result<(), EngineError> {
+        play_scene_music(&self.audio, &self.assets, &scene)?;
+        self.prepare_scene(&mut scene);
+        let mut renderer = self.render.write().unwrap();
+        renderer.start_transition();
+        renderer.push_scene(scene)?;
+        self.bus.publish(GameEvent::SceneChanged);
+
+        Ok(())
+    }
+
+    /// Pops the scene on top of the renderer's scene manager.
+    ///
+    /// The music of the uncovered scene comes back if the pushed scene played another one.
+    fn pop_scene(&mut self) -> Option<Scene> {
+        let mut renderer = self.render.write().unwrap();
+        if renderer.scene_manager.depth() == 0 {
+            return None;
+        }
+        renderer.start_transition();
+        let popped = renderer.pop_scene()?;
+        if let Err(e) = play_scene_music(
+            &self.audio,
+            &self.assets,
+            &renderer.scene_manager.active_scene,
+        ) {
+            log::warn!("Couldn't resume the scene music: {e}");
+        }
+        self.bus.publish(GameEvent::SceneChanged);
+        Some(popped)
+    }
+
+    /// Sets the transition of the renderer, which takes effect at the next scene change.
+    fn set_transition(&mut self, transition: Transition) {
+        self.render.write().unwrap().set_transition(transition);
+    }
+
     /// Delegates rendering to the internal Renderer instance.
-    fn render(&mut self) -> Result<(), Error> {
-        self.render.write().unwrap().render();
+    ///
+    /// Render errors are returned as [`EngineError::Render`].
+    fn render(&mut self) -> Result<(), EngineError> {
+        self.render.write().unwrap().render()?;
+        Ok(())
+    }
+
+    /// Adds the effect to the renderer's post-processing chain.
+    fn add_post_effect(&mut self, effect: Box<dyn PostEffect>) -> Result<(), EngineError> {
+        self.render.write().unwrap().add_post_effect(effect);
+        Ok(())
+    }
+
+    /// Appends the system to the ones run every update.
+    fn add_system(&mut self, system: Box<dyn System>) -> Result<(), EngineError> {
+        self.systems.lock().unwrap().push(system);
+        Ok(())
+    }
+
+    /// Sets the shader applied by the renderer after its post effects.
+    fn set_pixel_shader(&mut self, shader: Box<PixelShader>) -> Result<(), EngineError> {
+        self.render.write().unwrap().set_pixel_shader(shader);
+        Ok(())
+    }
+
+    /// Hands out a handle to the renderer's overlay; it stays valid across scene changes.
+    fn ui(&self) -> Ui {
+        self.render.read().unwrap().ui()
+    }
+
+    /// Hands out a handle to the renderer's debug shape queue.
+    fn debug(&self) -> DebugDraw {
+        self.render.read().unwrap().debug()
+    }
+
+    /// Hands out a handle to the statistics published by the window.
+    fn frame_stats(&self) -> SharedFrameStats {
+        self.frame_stats.clone()
+    }
+
+    /// Hands out a handle to the histogram filled by the window.
+    fn frame_histogram(&self) -> FrameHistogram {
+        self.frame_histogram.clone()
+    }
+
+    /// Hands out a handle to the renderer's performance overlay.
+    fn perf_overlay(&self) -> PerfOverlay {
+        self.render.read().unwrap().perf_overlay()
+    }
+
+    /// Pauses the shared game clock.
+    fn pause(&self) {
+        self.clock.pause();
+    }
+
+    /// Resumes the shared game clock.
+    fn resume(&self) {
+        self.clock.resume();
+    }
+
+    /// Checks the shared game clock.
+    fn is_paused(&self) -> bool {
+        self.clock.is_paused()
+    }
+
+    /// Hands out a handle to the clock shared with the producer loop.
+    fn clock(&self) -> GameClock {
+        self.clock.clone()
+    }
+
+    /// Hands out a handle to the engine's image cache.
+    fn assets(&self) -> AssetManager {
+        self.assets.clone()
+    }
+
+    /// Hands out a handle to the engine's sound playback.
+    fn audio(&self) -> Audio {
+        self.audio.clone()
+    }
+
+    /// Sets the bus volume of the shared sound playback.
+    fn set_volume(&self, bus: Bus, volume: f32) {
+        self.audio.set_volume(bus, volume);
+    }
+
+    /// Reads the bus volume of the shared sound playback.
+    fn volume(&self, bus: Bus) -> f32 {
+        self.audio.volume(bus)
+    }
+
+    /// Mutes or unmutes the bus of the shared sound playback.
+    fn set_muted(&self, bus: Bus, muted: bool) {
+        self.audio.set_muted(bus, muted);
+    }
+
+    /// Checks the bus of the shared sound playback.
+    fn is_muted(&self, bus: Bus) -> bool {
+        self.audio.is_muted(bus)
+    }
+
+    /// Hands out a handle to the queue the window reports its events to.
+    fn events(&self) -> EventQueue {
+        self.events.clone()
+    }
+
+    /// Hands out a handle to the engine's event bus.
+    fn event_bus(&self) -> EventBus {
+        self.bus.clone()
+    }
+
+    /// Reads the camera the renderer used for the last frame.
+    fn camera(&self) -> Camera {
+        self.render.read().unwrap().camera()
+    }
+
+    /// Hands out a handle to the keyboard and mouse state recorded by the window.
+    fn input(&self) -> InputState {
+        self.input.clone()
+    }
+
+    /// Hands out a handle to the game window.
+    fn window(&self) -> GameWindow {
+        self.window.clone()
+    }
+
+    /// Hands out a handle to the scale factor published by the window.
+    fn scale_factor(&self) -> ScaleFactor {
+        self.scale_factor.clone()
+    }
+
+    /// Sets the renderer's software cursor.
+    fn set_cursor(&mut self, cursor: Option<Cursor>) -> Result<(), EngineError> {
+        self.render.write().unwrap().set_cursor(cursor);
+        Ok(())
+    }
+
+    /// Saves the renderer's last emitted frame.
+    fn screenshot(&self, path: &Path) -> Result<(), EngineError> {
+        Ok(self.render.read().unwrap().save_screenshot(path)?)
+    }
+
+    /// Starts recording in the renderer.
+    fn start_recording(&mut self, max_frames: usize) -> Result<(), EngineError> {
+        self.render.write().unwrap().start_recording(max_frames);
+        Ok(())
+    }
+
+    /// Encodes the renderer's recording; uncapped games are played back at the default rate.
+    fn save_recording_gif(&self, path: &Path) -> Result<(), EngineError> {
+        let fps = self
+            .target_fps
+            .filter(|fps| *fps > 0)
+            .unwrap_or(DEFAULT_TARGET_FPS);
+        let renderer = self.render.read().unwrap();
+        renderer
+            .recording()
+            .ok_or_else(no_recording)?
+            .save_gif(path, Duration::from_secs(1) / fps)?;
+        Ok(())
+    }
+
+    /// Writes the renderer's recording as PNG images.
+    fn save_recording_frames(&self, dir: &Path) -> Result<(), EngineError> {
+        let renderer = self.render.read().unwrap();
+        renderer
+            .recording()
+            .ok_or_else(no_recording)?
+            .save_png_sequence(dir)?;
+        Ok(())
+    }
+
+    /// Keeps the window, which is opened together with the game window in `run`.
+    fn add_window(&mut self, window: WindowHandle) -> Result<(), EngineError> {
+        self.extra_windows.push(window);
         Ok(())
     }
 
     /// Creates a new GameEngine using provided config and scene.
     ///
-    /// Initializes the Renderer with the resolution and the scene manager.
-    fn new(config: Box<dyn Config + 'static + Send>, scene: Scene) -> Self
+    /// Initializes the renderer from the render settings of the config and keeps the
+    /// window, timing and input settings for `run`. The scene is connected to the event
+    /// bus, assets and audio of the engine, and its music starts playing.
+    fn new(config: Box<dyn Config + 'static + Send>, mut scene: Scene) -> Self
     where
         Self: Sized,
     {
+        let bus = EventBus::new();
+        scene.set_event_bus(bus.clone());
+        let assets = AssetManager::new().with_root(config.get_asset_root());
+        let audio = Audio::new().with_assets(assets.clone());
+        scene.set_audio(audio.clone());
+        scene.preload_sounds();
+        if let Err(e) = play_scene_music(&audio, &assets, &scene) {
+            log::warn!("Couldn't start the scene music: {e}");
+        }
         let res = config.get_resolution();
-        GameEngine {
-            //config,
-            render: Arc::new(RwLock::from(Renderer::new(
+        let mut renderer = Renderer::new(
+            create_backend(
+                config.get_render_backend(),
                 res,
                 /*Some(ImageReader::open("src/bin/resources/tile2.png")
                 .unwrap()
                 .decode()
                 .unwrap())*/
                 None,
-                SceneManager::new(scene),
-            ))),
+            ),
+            SceneManager::new(scene),
+        );
+        renderer.set_default_shadow(config.get_shadow_settings());
+        renderer.set_gamma_correct(config.get_gamma_correct_blending());
+        let frame_stats = SharedFrameStats::new();
+        renderer.set_frame_stats(frame_stats.clone());
+        if let Some(crt) = config.get_crt_filter() {
+            renderer.add_post_effect(Box::new(crt));
+        }
+        let input = InputState::new();
+        input.set_input_map(config.get_input_map());
+        GameEngine {
+            //config,
+            render: Arc::new(RwLock::from(renderer)),
+            systems: Arc::new(Mutex::new(Vec::new())),
+            window_title: config.get_window_title(),
+            window_icon: config.get_window_icon(),
+            present_mode: config.get_present_mode(),
+            scaling: config.get_scaling_mode(),
+            target_fps: config.get_target_fps(),
+            update_rate: config.get_update_rate(),
+            quit_key: config.get_quit_key(),
+            frame_step_key: config.get_frame_step_key(),
+            window_position: config.get_window_position(),
+            monitor: config.get_monitor(),
+            fullscreen: config.get_fullscreen(),
+            window: GameWindow::new(),
+            frame_stats,
+            frame_histogram: FrameHistogram::new(),
+            frame_histogram_path: config.get_frame_histogram_path(),
+            watchdog: config.get_watchdog(),
+            extra_windows: Vec::new(),
+            scale_factor: ScaleFactor::new(),
+            events: EventQueue::new(),
+            bus,
+            input,
+            clock: GameClock::new(),
+            assets,
+            audio,
         }
     }
 
+    /// Updates the active scene, then renders and emits a frame.
+    fn step(&mut self, elapsed: Duration) -> Result<Vec<u8>, EngineError> {
+        let mut renderer = self.render.write().unwrap();
+        if !self.clock.is_paused() {
+            update(
+                &mut renderer.scene_manager.active_scene,
+                &mut self.systems.lock().unwrap(),
+                &self.input,
+                self.clock.scale(elapsed),
+            );
+        }
+        renderer.advance_transition(elapsed);
+        renderer.render()?;
+        let frame = renderer.emit()?;
+        Ok(frame
+            .iter()
+            .flat_map(|px| [px.0, px.1, px.2, px.3])
+            .collect())
+    }
+
+    /// Steps the engine by the time step of the update rate.
+    fn run_headless(
+        &mut self,
+        frames: u32,
+        on_frame: &mut dyn FnMut(u32, &[u8]),
+    ) -> Result<(), EngineError> {
+        let dt = FixedTimestep::new(self.update_rate).dt();
+        for index in 0..frames {
+            let frame = self.step(dt)?;
+            on_frame(index, &frame);
+        }
+        Ok(())
+    }
+
     /// Runs the game engine event loop.
     ///
     /// Sets up shared state for pixel data, window, and input keys.
-    /// Spawns a producer thread that updates the main object's position based on key input
-    /// and triggers rendering updates.
+    /// Spawns a producer thread that updates the scene at the fixed update rate, moving the
+    /// main object based on key input, and triggers rendering updates, paced to the target
+    /// frame rate.
     /// Runs the `winit` event loop with the associated GUI application.
-    fn run(&mut self) -> Result<(), Error> {
-        let initial_resolution = Resolution {
-            width: WIDTH,
-            height: HEIGHT,
-        };
+    ///
+    /// Unless disabled, a watchdog thread logs diagnostics when the producer stops finishing
+    /// ticks. When the window closes, the producer thread is stopped and joined before
+    /// returning, and the frame time histogram is written to the configured file, if any.
+    ///
+    /// # Errors
+    /// Fails with [`EngineError::Asset`] if the configured window icon cannot be loaded,
+    /// with [`EngineError::Window`] if the event loop cannot be created or fails, and with
+    /// [`EngineError::Producer`] if the producer thread panicked. If the watchdog is set to
+    /// exit, a stalled producer makes `run` fail with [`EngineError::Stalled`]. Fails with
+    /// [`EngineError::Io`] if the frame time histogram cannot be written.
+    fn run(&mut self) -> Result<(), EngineError> {
+        let icon = self
+            .window_icon
+            .as_deref()
+            .map(|path| {
+                load_icon(path).map_err(|source| EngineError::Asset {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            })
+            .transpose()?;
+        let initial_resolution = self.render.read().unwrap().resolution();
         let initial_pixels = vec![
             DEFAULT_BACKGROUND_COLOR;
             (initial_resolution.width * initial_resolution.height) as usize
         ];
 
-        let shared_pixel_data = Arc::new(RwLock::new(initial_pixels));
-        let shared_window = Arc::new(RwLock::new(None));
-
-        let shared_pixel_data_clone = shared_pixel_data.clone();
+        let (mut writer, reader) = triple_buffer();
+        writer.write(initial_pixels);
+        let shared_window = self.window.slot();
         let shared_window_clone = shared_window.clone();
 
-        let mut app = App::new(shared_pixel_data, shared_window);
-        //let key_pressed_clone = app.key_pressed.clone();
-        let keys_pressed_clone = app.keys_pressed.clone();
-        let renderer = self.render.clone();
+        let mut app = App::new(reader, shared_window)
+            .with_resolution(initial_resolution)
+            .with_title(self.window_title.clone())
+            .with_present_mode(self.present_mode)
+            .with_scaling_mode(self.scaling)
+            .with_quit_key(self.quit_key)
+            .with_frame_step_key(self.frame_step_key)
+            .with_clock(self.clock.clone())
+            .with_window_position(self.window_position)
+            .with_monitor(self.monitor)
+            .with_fullscreen(self.fullscreen)
+            .with_frame_stats(self.frame_stats.clone())
+            .with_frame_histogram(self.frame_histogram.clone())
+            .with_perf_overlay(self.render.read().unwrap().perf_overlay())
+            .with_scale_factor(self.scale_factor.clone())
+            .with_events(self.events.clone())
+            .with_input(self.input.clone())
+            .with_cursor_hidden(self.render.read().unwrap().has_cursor());
+        for window in &self.extra_windows {
+            app = app.with_window(window.clone());
+        }
+        if let Some(icon) = icon {
+            app = app.with_icon(icon);
+        }
+        let shutdown = app.shutdown.clone();
+        let heartbeat = Heartbeat::new();
+        let producer = Producer {
+            renderer: self.render.clone(),
+            systems: self.systems.clone(),
+            input: self.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames: writer,
+            last_tick: Instant::now(),
+            timestep: FixedTimestep::new(self.update_rate),
+            clock: self.clock.clone(),
+            heartbeat: heartbeat.clone(),
+        };
 
-        thread::spawn(move || {
-            let window_arc: Arc<Window> = loop {
-                if let Some(arc) = shared_window_clone.read().unwrap().clone() {
-                    break arc;
-                }
-                thread::sleep(Duration::from_millis(50));
-            };
-
-            //dbg!("Producer has started");
-
-            let screen_size = (WIDTH * HEIGHT) as usize;
-            loop {
-                /*let vector_move = match *key_pressed_clone.read().unwrap() {
-                    Some(KeyCode::KeyW) => (0, 1),
-                    Some(KeyCode::KeyA) => (-1, 0),
-                    Some(KeyCode::KeyS) => (0, -1),
-                    Some(KeyCode::KeyD) => (1, 0),
-                    _ => (0, 0),
-                };*/
-                let dx = (keys_pressed_clone.d.load(Ordering::Relaxed) as i32)
-                    - (keys_pressed_clone.a.load(Ordering::Relaxed) as i32);
-                let dy = (keys_pressed_clone.w.load(Ordering::Relaxed) as i32)
-                    - (keys_pressed_clone.s.load(Ordering::Relaxed) as i32);
-
-                let vector_move = (dx, dy);
-
-                renderer
-                    .write()
-                    .unwrap()
-                    .scene_manager
-                    .active_scene
-                    .main_object
-                    .add_position((vector_move.0, vector_move.1));
-
-                renderer.write().unwrap().render();
-                match renderer.write().unwrap().emit() {
-                    Some(colors) => {
-                        let mut pixels = shared_pixel_data_clone
-                            .write()
-                            .expect("Producer couldn't lock pixel data");
-
-                        for (idx, p) in pixels.iter_mut().take(screen_size).enumerate() {
-                            *p = colors[idx];
-                        }
-
-                        window_arc.request_redraw();
-                    }
-                    None => {
-                        continue;
-                    }
-                }
-            }
+        let producer_thread = {
+            let limiter = FrameLimiter::new(self.target_fps);
+            let shutdown = shutdown.clone();
+            let window = shared_window_clone.clone();
+            thread::spawn(move || producer.run(window, limiter, shutdown))
+        };
+        let watchdog_thread = self.watchdog.map(|settings| {
+            let watchdog = Watchdog::new(heartbeat, settings);
+            let shutdown = shutdown.clone();
+            thread::spawn(move || watchdog.run(shared_window_clone, shutdown))
         });
 
-        let event_loop = EventLoop::new().unwrap();
-        event_loop.set_control_flow(ControlFlow::Wait);
-        let _ = event_loop.run_app(&mut app);
+        let result = EventLoop::new().and_then(|event_loop| {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            event_loop.run_app(&mut app)
+        });
+        // The event loop may also stop without the app exiting, e.g. when it fails
+        shutdown.store(true, Ordering::Relaxed);
+        let stall = watchdog_thread.and_then(|thread| thread.join().ok().flatten());
+        // A stuck producer would never finish, so it is left behind rather than joined
+        if let Some(stall) = stall
+            && !stall.panicked
+        {
+            return Err(EngineError::Stalled(stall.to_string()));
+        }
+        producer_thread
+            .join()
+            .map_err(|payload| EngineError::Producer(panic_message(payload)))?;
+        result?;
+        if let Some(path) = &self.frame_histogram_path {
+            self.frame_histogram.save(path)?;
+            log::info!("Frame time histogram written to {}", path.display());
+        }
         Ok(())
     }
 }
@@ -208,6 +1200,7 @@ mod tests {
                 is_relative: false,
             },
         )
+        .unwrap()
     }
 
     #[test]
@@ -220,7 +1213,7 @@ mod tests {
         assert_eq!(object.position.x, 0);
         assert_eq!(object.position.y, 0);
         assert_eq!(object.position.z, 0);
-        assert_eq!(object.position.is_relative, false);
+        assert!(!object.position.is_relative);
     }
 
     #[test]
@@ -248,7 +1241,8 @@ mod tests {
                 z: 100,
                 is_relative: false,
             },
-        );
+        )
+        .unwrap();
         let result = engine.set_active_scene(scene2);
         assert!(result.is_ok());
         let render = engine.render.read().unwrap();
@@ -256,7 +1250,7 @@ mod tests {
         assert_eq!(object.position.x, 100);
         assert_eq!(object.position.y, 100);
         assert_eq!(object.position.z, 100);
-        assert_eq!(object.position.is_relative, false);
+        assert!(!object.position.is_relative);
     }
 
     #[test]
@@ -275,14 +1269,15 @@ mod tests {
                     z: i,
                     is_relative: false,
                 },
-            );
+            )
+            .unwrap();
             assert!(engine.set_active_scene(scene).is_ok());
             let render = engine.render.read().unwrap();
             let object = &render.scene_manager.active_scene.main_object;
             assert_eq!(object.position.x, i);
             assert_eq!(object.position.y, i);
             assert_eq!(object.position.z, i);
-            assert_eq!(object.position.is_relative, false);
+            assert!(!object.position.is_relative);
         }
     }
 
@@ -299,7 +1294,8 @@ mod tests {
                 z: 7,
                 is_relative: false,
             },
-        );
+        )
+        .unwrap();
         let config = create_config_with_resolution(1280, 720);
         let engine = GameEngine::new(config, scene);
         let render = engine.render.read().unwrap();
@@ -307,7 +1303,7 @@ mod tests {
         assert_eq!(object.position.x, 5);
         assert_eq!(object.position.y, 6);
         assert_eq!(object.position.z, 7);
-        assert_eq!(object.position.is_relative, false);
+        assert!(!object.position.is_relative);
     }
 
     #[test]
@@ -325,11 +1321,578 @@ mod tests {
                 z: 15,
                 is_relative: false,
             },
-        );
+        )
+        .unwrap();
         engine.set_active_scene(scene2).unwrap();
 
         for _ in 0..3 {
             assert!(engine.render().is_ok());
         }
     }
+
+    #[test]
+    fn test_screenshot_needs_emitted_frame() {
+        let engine = GameEngine::new(create_config_with_resolution(64, 48), create_empty_scene());
+        let path = std::env::temp_dir().join("rusty_ache_test_engine_screenshot.png");
+        assert!(engine.screenshot(&path).is_err());
+
+        engine.render.write().unwrap().render().unwrap();
+        engine.render.write().unwrap().emit().unwrap();
+        engine.screenshot(&path).unwrap();
+        assert_eq!(
+            image::open(&path).unwrap().to_rgba8().dimensions(),
+            (64, 48)
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_screenshot_path_is_png() {
+        let path = screenshot_path();
+        assert!(path.to_string_lossy().starts_with("screenshot_"));
+        assert_eq!(path.extension().unwrap(), "png");
+    }
+
+    #[test]
+    fn test_save_recording_gif() {
+        let mut engine =
+            GameEngine::new(create_config_with_resolution(32, 32), create_empty_scene());
+        let path = std::env::temp_dir().join("rusty_ache_test_engine_recording.gif");
+        assert!(engine.save_recording_gif(&path).is_err());
+
+        engine.start_recording(10).unwrap();
+        engine.render.write().unwrap().render().unwrap();
+        engine.render.write().unwrap().emit().unwrap();
+        engine.save_recording_gif(&path).unwrap();
+        assert!(path.exists());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_run_headless_produces_frames() {
+        let mut engine =
+            GameEngine::new(create_config_with_resolution(16, 8), create_empty_scene());
+        engine
+            .render
+            .write()
+            .unwrap()
+            .scene_manager
+            .active_scene
+            .main_object
+            .flash((255, 0, 0, 255), Duration::from_millis(20));
+
+        let mut count = 0;
+        engine
+            .run_headless(3, &mut |index, frame| {
+                assert_eq!(index, count);
+                assert_eq!(frame.len(), 16 * 8 * 4);
+                count += 1;
+            })
+            .unwrap();
+        assert_eq!(count, 3);
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.tint(), None);
+    }
+
+    #[test]
+    fn test_step_runs_one_update() {
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        engine.input.set_key(KeyCode::KeyW, true);
+        engine.step(Duration::from_millis(10)).unwrap();
+        engine.step(Duration::from_millis(10)).unwrap();
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.y, 2);
+    }
+
+    #[test]
+    fn test_paused_engine_does_not_update() {
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        engine.input.set_key(KeyCode::KeyW, true);
+        engine.pause();
+        assert!(engine.is_paused());
+        engine.step(Duration::from_millis(10)).unwrap();
+        engine.clock().resume();
+        engine.step(Duration::from_millis(10)).unwrap();
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.y, 1);
+    }
+
+    #[test]
+    fn test_scene_music_crossfades_on_scene_change() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_scene_music");
+        std::fs::create_dir_all(&dir).unwrap();
+        let track = |name: &str| {
+            let path = dir.join(name);
+            let wav = [
+                b"RIFF".as_slice(),
+                &40u32.to_le_bytes(),
+                b"WAVEfmt ",
+                &16u32.to_le_bytes(),
+                &1u16.to_le_bytes(),
+                &1u16.to_le_bytes(),
+                &8000u32.to_le_bytes(),
+                &16000u32.to_le_bytes(),
+                &2u16.to_le_bytes(),
+                &16u16.to_le_bytes(),
+                b"data",
+                &4u32.to_le_bytes(),
+                &[0, 64, 0, 64],
+            ]
+            .concat();
+            std::fs::write(&path, wav).unwrap();
+            path
+        };
+        let (town, cave) = (track("town.wav"), track("cave.wav"));
+        let mut engine = GameEngine::new(
+            create_config_with_resolution(8, 8),
+            create_empty_scene().with_music(&town),
+        );
+        assert_eq!(engine.audio().music(), Some(town.clone()));
+
+        // Scenes without music keep the music playing
+        engine.set_active_scene(create_empty_scene()).unwrap();
+        assert_eq!(engine.audio().music(), Some(town.clone()));
+        engine
+            .set_active_scene(create_empty_scene().with_music(&cave))
+            .unwrap();
+        assert_eq!(engine.audio().music(), Some(cave.clone()));
+
+        let missing = create_empty_scene().with_music(dir.join("missing.wav"));
+        assert!(matches!(
+            engine.set_active_scene(missing),
+            Err(EngineError::Asset { .. })
+        ));
+        assert_eq!(engine.audio().music(), Some(cave));
+
+        // The music stands still while the engine is paused
+        engine.step(Duration::from_millis(10)).unwrap();
+        let position = engine.audio().music_position();
+        engine.pause();
+        engine.step(Duration::from_millis(10)).unwrap();
+        assert_eq!(engine.audio().music_position(), position);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_paused_producer_runs_requested_ticks() {
+        let engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let (frames, mut reader) = triple_buffer();
+        let app = App::new(triple_buffer().1, Arc::new(RwLock::new(None)));
+        engine.input.set_key(KeyCode::KeyD, true);
+        engine.pause();
+        let mut producer = Producer {
+            renderer: engine.render.clone(),
+            systems: engine.systems.clone(),
+            input: engine.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames,
+            last_tick: Instant::now() - Duration::from_secs(1),
+            timestep: FixedTimestep::new(50),
+            clock: engine.clock(),
+            heartbeat: Heartbeat::new(),
+        };
+
+        assert!(producer.tick());
+        assert_eq!(producer.heartbeat.stage(), Stage::Presenting);
+        engine.clock().advance_tick();
+        assert!(producer.tick());
+        assert!(reader.update());
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.x, 1);
+    }
+
+    #[test]
+    fn test_time_scale_changes_update_count() {
+        let engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let (frames, _) = triple_buffer();
+        let app = App::new(triple_buffer().1, Arc::new(RwLock::new(None)));
+        engine.input.set_key(KeyCode::KeyD, true);
+        engine.clock().set_time_scale(2.0);
+        let mut producer = Producer {
+            renderer: engine.render.clone(),
+            systems: engine.systems.clone(),
+            input: engine.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames,
+            last_tick: Instant::now() - Duration::from_millis(45),
+            timestep: FixedTimestep::new(50),
+            clock: engine.clock(),
+            heartbeat: Heartbeat::new(),
+        };
+
+        // 45 ms of real time are 90 ms of game time: four updates of 20 ms
+        assert!(producer.tick());
+        engine.clock().set_time_scale(0.0);
+        producer.last_tick = Instant::now() - Duration::from_secs(1);
+        assert!(producer.tick());
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.x, 4);
+    }
+
+    #[test]
+    fn test_fast_time_scale_runs_all_due_updates() {
+        let engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let (frames, _) = triple_buffer();
+        let app = App::new(triple_buffer().1, Arc::new(RwLock::new(None)));
+        engine.input.set_key(KeyCode::KeyD, true);
+        engine.clock().set_time_scale(timing::MAX_TIME_SCALE);
+        let mut producer = Producer {
+            renderer: engine.render.clone(),
+            systems: engine.systems.clone(),
+            input: engine.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames,
+            last_tick: Instant::now() - Duration::from_micros(90_625),
+            timestep: FixedTimestep::new(10),
+            clock: engine.clock(),
+            heartbeat: Heartbeat::new(),
+        };
+
+        // 90.6 ms of real time are 1450 ms of game time: 14 updates of 100 ms, more than
+        // run for one frame at normal speed
+        assert!(producer.tick());
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.x, 14);
+    }
+
+    #[test]
+    fn test_paused_producer_dims_last_frame() {
+        let engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let (frames, mut reader) = triple_buffer();
+        let app = App::new(triple_buffer().1, Arc::new(RwLock::new(None)));
+        engine.input.set_key(KeyCode::KeyD, true);
+        engine.pause();
+        engine.clock().set_pause_dim(255);
+        let mut producer = Producer {
+            renderer: engine.render.clone(),
+            systems: engine.systems.clone(),
+            input: engine.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames,
+            last_tick: Instant::now() - Duration::from_millis(100),
+            timestep: FixedTimestep::new(50),
+            clock: engine.clock(),
+            heartbeat: Heartbeat::new(),
+        };
+
+        assert!(producer.tick());
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.x, 0);
+        assert!(reader.update());
+        let pixels = reader.frame();
+        assert!(pixels.iter().all(|px| (px.0, px.1, px.2) == (0, 0, 0)));
+        assert_eq!(dim((200, 100, 50, 255), 0), (200, 100, 50, 255));
+    }
+
+    /// System moving every object one pixel towards the main object.
+    struct Chase;
+
+    impl System for Chase {
+        fn run(&mut self, scene: &mut Scene, _dt: Duration, _input: &input::InputState) {
+            let target = scene.main_object.position;
+            for uid in scene.uids() {
+                let enemy = scene.object_mut(uid).unwrap();
+                let dx = (target.x - enemy.position.x).signum();
+                let dy = (target.y - enemy.position.y).signum();
+                enemy.add_position((dx, dy));
+            }
+        }
+    }
+
+    #[test]
+    fn test_systems_run_every_step() {
+        let enemy = scene::game_object::GameObject::new(
+            vec![],
+            None,
+            Position {
+                x: 3,
+                y: -1,
+                z: 0,
+                is_relative: false,
+            },
+        );
+        let scene = Scene::new(
+            vec![enemy],
+            vec![],
+            create_empty_scene().main_object.position,
+        )
+        .unwrap();
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), scene);
+        engine.add_system(Box::new(Chase)).unwrap();
+        for _ in 0..2 {
+            engine.step(Duration::from_millis(10)).unwrap();
+        }
+        let render = engine.render.read().unwrap();
+        let enemy = render.scene_manager.active_scene.object(1).unwrap();
+        assert_eq!((enemy.position.x, enemy.position.y), (1, 0));
+    }
+
+    #[test]
+    fn test_switch_scene_tears_down_old_scene() {
+        let object = || create_empty_scene().main_object;
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let mut level2 = create_empty_scene();
+        level2.main_object.position.x = 7;
+        engine.add_scene("level2", level2);
+        // Setting a scene keeps the registry
+        let mut level1 = create_empty_scene();
+        let uid = level1.spawn_object(object()).unwrap();
+        engine.set_active_scene(level1).unwrap();
+
+        let events = engine.event_bus().subscribe();
+        engine.switch_scene("level2").unwrap();
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::ObjectDespawned(uid), GameEvent::SceneChanged]
+        );
+        {
+            let render = engine.render.read().unwrap();
+            let manager = &render.scene_manager;
+            assert_eq!(manager.active_scene.main_object.position.x, 7);
+            assert_eq!(manager.active_name(), Some("level2"));
+            assert!(manager.scene_names().is_empty());
+        }
+        // The new scene publishes on the engine's bus
+        engine.step(Duration::from_millis(10)).unwrap();
+        let spawned = engine
+            .render
+            .write()
+            .unwrap()
+            .scene_manager
+            .active_scene
+            .spawn_object(object())
+            .unwrap();
+        assert_eq!(events.drain(), vec![GameEvent::ObjectSpawned(spawned)]);
+
+        assert!(matches!(
+            engine.switch_scene("level2"),
+            Err(EngineError::UnknownScene(name)) if name == "level2"
+        ));
+    }
+
+    #[test]
+    fn test_pushed_scene_updates_while_covered_scene_is_frozen() {
+        let enemy = || {
+            scene::game_object::GameObject::new(
+                vec![],
+                None,
+                Position {
+                    x: 3,
+                    y: 0,
+                    z: 0,
+                    is_relative: false,
+                },
+            )
+        };
+        let scene = || {
+            Scene::new(
+                vec![enemy()],
+                vec![],
+                create_empty_scene().main_object.position,
+            )
+            .unwrap()
+        };
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), scene());
+        engine.add_system(Box::new(Chase)).unwrap();
+        engine.step(Duration::from_millis(10)).unwrap();
+
+        let events = engine.event_bus().subscribe();
+        engine.push_scene(scene()).unwrap();
+        engine.step(Duration::from_millis(10)).unwrap();
+        engine.step(Duration::from_millis(10)).unwrap();
+        {
+            let render = engine.render.read().unwrap();
+            let manager = &render.scene_manager;
+            assert_eq!(manager.active_scene.object(1).unwrap().position.x, 1);
+            let covered = manager.covered_scene().unwrap();
+            assert_eq!(covered.object(1).unwrap().position.x, 2);
+        }
+
+        assert!(engine.pop_scene().is_some());
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::SceneChanged, GameEvent::SceneChanged]
+        );
+        engine.step(Duration::from_millis(10)).unwrap();
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.depth(), 0);
+        let enemy = render.scene_manager.active_scene.object(1).unwrap();
+        assert_eq!(enemy.position.x, 1);
+    }
+
+    #[test]
+    fn test_scene_changes_show_configured_transition() {
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        engine.step(Duration::from_millis(10)).unwrap();
+        // Cuts by default
+        engine.set_active_scene(create_empty_scene()).unwrap();
+        assert!(!engine.render.read().unwrap().is_transitioning());
+
+        engine.set_transition(Transition::Wipe {
+            duration: Duration::from_millis(20),
+            direction: crate::render::transition::WipeDirection::LeftToRight,
+        });
+        engine.add_scene("level2", create_empty_scene());
+        engine.switch_scene("level2").unwrap();
+        engine.pause();
+        // Transitions go on while the game is paused
+        engine.step(Duration::from_millis(10)).unwrap();
+        assert!(engine.render.read().unwrap().is_transitioning());
+        engine.step(Duration::from_millis(10)).unwrap();
+        assert!(!engine.render.read().unwrap().is_transitioning());
+    }
+
+    #[test]
+    fn test_preloaded_scenes_join_the_registry() {
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let loaded = engine.preload_scene(
+            "level2",
+            Box::new(|assets| {
+                let mut scene = create_empty_scene();
+                scene.main_object.position.x = 7;
+                assets.load_image(Path::new("resources/perf_diag.png"))?;
+                Ok(scene)
+            }),
+        );
+        loaded.wait().unwrap();
+        assert_eq!(engine.assets.len(), 1);
+        assert_eq!(
+            engine.render.read().unwrap().scene_manager.scene_names(),
+            vec!["level2"]
+        );
+        engine.switch_scene("level2").unwrap();
+
+        let events = engine.event_bus().subscribe();
+        let switched = engine.switch_scene_when_loaded(
+            "level3",
+            Box::new(|_| {
+                let mut scene = create_empty_scene();
+                scene.main_object.position.x = 9;
+                Ok(scene)
+            }),
+        );
+        switched.wait().unwrap();
+        assert_eq!(events.drain(), vec![GameEvent::SceneChanged]);
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_name(), Some("level3"));
+        assert_eq!(render.scene_manager.active_scene.main_object.position.x, 9);
+    }
+
+    #[test]
+    fn test_failed_scene_loads_are_not_registered() {
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let failed = engine.preload_scene(
+            "broken",
+            Box::new(|_| Scene::load(Path::new("resources/missing.toml"))),
+        );
+        assert!(failed.wait().is_err());
+        let missing_sound = engine.switch_scene_when_loaded(
+            "quiet",
+            Box::new(|_| Ok(create_empty_scene().with_sounds(["resources/missing.wav"]))),
+        );
+        assert!(missing_sound.wait().is_err());
+        let render = engine.render.read().unwrap();
+        assert!(render.scene_manager.scene_names().is_empty());
+        assert_eq!(render.scene_manager.active_name(), None);
+    }
+
+    #[test]
+    fn test_engine_publishes_key_presses_and_scene_changes() {
+        let mut engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let events = engine.event_bus().subscribe();
+        engine.input.set_key(KeyCode::Space, true);
+        engine.step(Duration::from_millis(10)).unwrap();
+        engine.step(Duration::from_millis(10)).unwrap();
+        engine.set_active_scene(create_empty_scene()).unwrap();
+        let uid = engine
+            .render
+            .write()
+            .unwrap()
+            .scene_manager
+            .active_scene
+            .spawn_object(create_empty_scene().main_object)
+            .unwrap();
+        assert_eq!(
+            events.drain(),
+            vec![
+                GameEvent::KeyPressed(KeyCode::Space),
+                GameEvent::SceneChanged,
+                GameEvent::ObjectSpawned(uid),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_producer_stops_on_shutdown() {
+        let engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let (frames, _) = triple_buffer();
+        let app = App::new(triple_buffer().1, Arc::new(RwLock::new(None)));
+        let heartbeat = Heartbeat::new();
+        let producer = Producer {
+            renderer: engine.render.clone(),
+            systems: engine.systems.clone(),
+            input: engine.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames,
+            last_tick: Instant::now(),
+            timestep: FixedTimestep::new(50),
+            clock: engine.clock(),
+            heartbeat: heartbeat.clone(),
+        };
+        let shutdown = app.shutdown.clone();
+        let handle = thread::spawn(move || {
+            producer.run(
+                Arc::new(RwLock::new(None)),
+                FrameLimiter::new(None),
+                shutdown,
+            )
+        });
+        app.shutdown.store(true, Ordering::Relaxed);
+        assert!(handle.join().is_ok());
+        assert_eq!(heartbeat.stage(), Stage::Stopped);
+        assert!(!heartbeat.has_panicked());
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = thread::spawn(|| panic!("producer failed"))
+            .join()
+            .unwrap_err();
+        assert_eq!(panic_message(payload), "producer failed");
+        let payload = thread::spawn(|| panic!("frame {}", 3)).join().unwrap_err();
+        assert_eq!(panic_message(payload), "frame 3");
+        assert_eq!(panic_message(Box::new(7)), "unknown panic");
+    }
+
+    #[test]
+    fn test_producer_tick_moves_and_publishes_frame() {
+        let engine = GameEngine::new(create_config_with_resolution(8, 8), create_empty_scene());
+        let (frames, mut reader) = triple_buffer();
+        let app = App::new(triple_buffer().1, Arc::new(RwLock::new(None)));
+        engine.input.set_key(KeyCode::KeyD, true);
+        let mut producer = Producer {
+            renderer: engine.render.clone(),
+            systems: engine.systems.clone(),
+            input: engine.input.clone(),
+            screenshot_requested: app.screenshot_requested.clone(),
+            frames,
+            last_tick: Instant::now() - Duration::from_millis(25),
+            timestep: FixedTimestep::new(50),
+            clock: engine.clock(),
+            heartbeat: Heartbeat::new(),
+        };
+
+        // 25 ms at 50 updates per second run a single update
+        assert!(producer.tick());
+        assert!(producer.tick());
+        let render = engine.render.read().unwrap();
+        assert_eq!(render.scene_manager.active_scene.main_object.position.x, 1);
+        assert!(reader.update());
+        assert!(
+            reader
+                .frame()
+                .iter()
+                .all(|px| *px == DEFAULT_BACKGROUND_COLOR)
+        );
+    }
 }
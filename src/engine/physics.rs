@@ -0,0 +1,345 @@
+//! Movement of objects driven by their velocity.
+//!
+//! The `PhysicsSystem` integrates the `Velocity` component of every object into its
+//! position once per update. As updates run at the fixed update rate, movement is the same
+//! whatever the frame rate. Each update the acceleration of the object and the gravity of
+//! the system are added to the velocity first, then drag slows it down and the speed is
+//! capped, and finally the object moves by the resulting velocity. Positions are whole
+//! world units, so the fractions of a unit left over are kept in the component and added to
+//! the next update; slow objects still move, just not every update. The main object moves
+//! like any other.
+//!
+//! Objects with a solid collider do not move into the solid tiles of the scene: they stop
+//! at them along the blocked axis, losing their speed along it, and slide along walls.
+//!
+//! The system is registered like any other, usually before the collision system:
+//!
+//! ```no_run
+//! use rusty_ache::engine::Engine;
+//! use rusty_ache::engine::builder::EngineBuilder;
+//! use rusty_ache::engine::physics::PhysicsSystem;
+//!
+//! let mut engine = EngineBuilder::new().build();
+//! let physics = PhysicsSystem::new()
+//!     .with_gravity(0.0, -400.0)
+//!     .with_drag(0.5)
+//!     .with_max_speed(600.0);
+//! engine.add_system(Box::new(physics)).unwrap();
+//! engine.run().unwrap();
+//! ```
+
+use crate::engine::input::InputState;
+use crate::engine::scene::game_object::components::collider::Aabb;
+use crate::engine::scene::game_object::components::velocity::Velocity;
+use crate::engine::scene::game_object::{GameObject, Object};
+use crate::engine::scene::{MAIN_OBJECT_UID, Scene};
+use crate::engine::system::System;
+use std::time::Duration;
+
+/// System moving objects by their velocity.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PhysicsSystem {
+    /// Acceleration applied to every moving object, in world units per second squared.
+    gravity: (f32, f32),
+    /// Fraction of the velocity lost per second, as a rate of exponential decay.
+    drag: f32,
+    /// Highest speed of any object in world units per second, if limited.
+    max_speed: Option<f32>,
+}
+
+impl PhysicsSystem {
+    /// Creates a system without gravity, drag or speed limit.
+    pub fn new() -> Self {
+        PhysicsSystem::default()
+    }
+
+    /// Sets the acceleration applied to every object with a velocity.
+    ///
+    /// # Parameters
+    /// - `x`: Horizontal gravity in world units per second squared.
+    /// - `y`: Vertical gravity in world units per second squared; negative pulls down.
+    pub fn with_gravity(mut self, x: f32, y: f32) -> Self {
+        self.gravity = (x, y);
+        self
+    }
+
+    /// Sets how fast objects slow down on their own.
+    ///
+    /// A drag of `d` scales the velocity by `e^(-d·t)` over `t` seconds, so the result
+    /// does not depend on the update rate. Negative values are treated as 0.
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag.max(0.0);
+        self
+    }
+
+    /// Caps the speed of every object, keeping its direction.
+    pub fn with_max_speed(mut self, max_speed: f32) -> Self {
+        self.max_speed = Some(max_speed.max(0.0));
+        self
+    }
+
+    /// Advances a velocity by one update.
+    ///
+    /// # Returns
+    /// The whole world units the object moves by.
+    fn integrate(&self, velocity: &mut Velocity, dt: f32) -> (i32, i32) {
+        velocity.x += (velocity.acceleration.0 + self.gravity.0) * dt;
+        velocity.y += (velocity.acceleration.1 + self.gravity.1) * dt;
+        if self.drag > 0.0 {
+            let factor = (-self.drag * dt).exp();
+            velocity.x *= factor;
+            velocity.y *= factor;
+        }
+        if let Some(max_speed) = self.max_speed {
+            let speed = velocity.speed();
+            if speed > max_speed {
+                let scale = max_speed / speed;
+                velocity.x *= scale;
+                velocity.y *= scale;
+            }
+        }
+        let x = velocity.x * dt + velocity.remainder.0;
+        let y = velocity.y * dt + velocity.remainder.1;
+        let step = (x.trunc(), y.trunc());
+        velocity.remainder = (x - step.0, y - step.1);
+        (step.0 as i32, step.1 as i32)
+    }
+
+    /// Moves one object by its velocity, if it has one, stopping it at solid tiles.
+    fn step(&self, scene: &mut Scene, uid: usize, dt: f32) {
+        let Some(object) = object_mut(scene, uid) else {
+            return;
+        };
+        let Some(velocity) = object.velocity_mut() else {
+            return;
+        };
+        let mut delta = self.integrate(velocity, dt);
+        if delta == (0, 0) {
+            return;
+        }
+        let mut blocked = (false, false);
+        if let Some(tiles) = scene.solid_tiles()
+            && let Some(area) = Self::solid_bounds(scene, uid)
+        {
+            (delta, blocked) = tiles.sweep(&area, delta);
+        }
+        let Some(object) = object_mut(scene, uid) else {
+            return;
+        };
+        object.add_position(delta);
+        if let Some(velocity) = object.velocity_mut() {
+            if blocked.0 {
+                velocity.x = 0.0;
+                velocity.remainder.0 = 0.0;
+            }
+            if blocked.1 {
+                velocity.y = 0.0;
+                velocity.remainder.1 = 0.0;
+            }
+        }
+    }
+
+    /// Returns the world area of the solid collider of an object, if it has one.
+    fn solid_bounds(scene: &Scene, uid: usize) -> Option<Aabb> {
+        let (object, position) = if uid == MAIN_OBJECT_UID {
+            (&scene.main_object, scene.main_object.position)
+        } else {
+            (scene.object(uid)?, scene.world_position(uid)?)
+        };
+        let collider = object.collider().filter(|collider| !collider.trigger)?;
+        Some(collider.bounds(&position))
+    }
+}
+
+/// Returns the object with the given UID for changing it, the main object included.
+fn object_mut(scene: &mut Scene, uid: usize) -> Option<&mut GameObject> {
+    if uid == MAIN_OBJECT_UID {
+        Some(&mut scene.main_object)
+    } else {
+        scene.object_mut(uid)
+    }
+}
+
+impl System for PhysicsSystem {
+    /// Moves the main object and every other object with a velocity.
+    fn run(&mut self, scene: &mut Scene, dt: Duration, _input: &InputState) {
+        let dt = dt.as_secs_f32();
+        self.step(scene, MAIN_OBJECT_UID, dt);
+        for uid in scene.uids() {
+            self.step(scene, uid, dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::game_object::Position;
+    use crate::engine::scene::game_object::components::collider::Collider;
+    use crate::engine::scene::tiles::SolidTiles;
+
+    fn moving(velocity: Velocity) -> GameObject {
+        GameObject::new(vec![Box::new(velocity)], None, Position::new(0, 0))
+    }
+
+    fn run(system: &mut PhysicsSystem, scene: &mut Scene, updates: usize) {
+        for _ in 0..updates {
+            system.run(scene, Duration::from_millis(100), &InputState::new());
+        }
+    }
+
+    fn position(scene: &Scene, uid: usize) -> (i32, i32) {
+        let position = scene.object(uid).unwrap().position;
+        (position.x, position.y)
+    }
+
+    #[test]
+    fn test_constant_velocity_moves_object() {
+        let mut scene = Scene::new(
+            vec![
+                moving(Velocity::new(20.0, -10.0)),
+                moving(Velocity::default()),
+            ],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uids = scene.uids();
+        run(&mut PhysicsSystem::new(), &mut scene, 5);
+        assert_eq!(position(&scene, uids[0]), (10, -5));
+        assert_eq!(position(&scene, uids[1]), (0, 0));
+    }
+
+    #[test]
+    fn test_fractions_carry_over_updates() {
+        let mut scene = Scene::new(
+            vec![moving(Velocity::new(3.0, -3.0))],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uid = scene.uids()[0];
+        let mut system = PhysicsSystem::new();
+        // 0.3 units per update
+        run(&mut system, &mut scene, 3);
+        assert_eq!(position(&scene, uid), (0, 0));
+        run(&mut system, &mut scene, 1);
+        assert_eq!(position(&scene, uid), (1, -1));
+        run(&mut system, &mut scene, 5);
+        assert_eq!(position(&scene, uid), (2, -2));
+    }
+
+    #[test]
+    fn test_acceleration_and_gravity_change_velocity() {
+        let mut scene = Scene::new(
+            vec![moving(Velocity::default().with_acceleration(10.0, 0.0))],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uid = scene.uids()[0];
+        run(
+            &mut PhysicsSystem::new().with_gravity(0.0, -30.0),
+            &mut scene,
+            10,
+        );
+        let velocity = *scene.object(uid).unwrap().velocity().unwrap();
+        assert!((velocity.x - 10.0).abs() < 1e-4);
+        assert!((velocity.y + 30.0).abs() < 1e-4);
+        // Semi-implicit Euler: 0.1 * (1 + 2 + ... + 10) seconds of speed
+        assert_eq!(position(&scene, uid), (5, -16));
+    }
+
+    #[test]
+    fn test_drag_slows_objects_down() {
+        let mut scene = Scene::new(
+            vec![moving(Velocity::new(100.0, 0.0))],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uid = scene.uids()[0];
+        run(&mut PhysicsSystem::new().with_drag(2.0), &mut scene, 5);
+        let velocity = scene.object(uid).unwrap().velocity().unwrap().x;
+        assert!((velocity - 100.0 * (-1.0f32).exp()).abs() < 1e-3);
+        assert!(position(&scene, uid).0 < 50);
+    }
+
+    #[test]
+    fn test_drag_does_not_depend_on_update_rate() {
+        let system = PhysicsSystem::new().with_drag(1.5);
+        let mut coarse = Velocity::new(80.0, 0.0);
+        let mut fine = coarse;
+        system.integrate(&mut coarse, 1.0);
+        for _ in 0..100 {
+            system.integrate(&mut fine, 0.01);
+        }
+        assert!((coarse.x - fine.x).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_max_speed_keeps_direction() {
+        let system = PhysicsSystem::new().with_max_speed(5.0);
+        let mut velocity = Velocity::new(30.0, -40.0);
+        system.integrate(&mut velocity, 0.1);
+        assert!((velocity.speed() - 5.0).abs() < 1e-4);
+        assert!((velocity.x - 3.0).abs() < 1e-4);
+        assert!((velocity.y + 4.0).abs() < 1e-4);
+        // Slower objects are left alone
+        let mut slow = Velocity::new(1.0, 1.0);
+        system.integrate(&mut slow, 0.1);
+        assert_eq!((slow.x, slow.y), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_solid_tiles_stop_objects() {
+        let falling = GameObject::new(
+            vec![
+                Box::new(Velocity::new(50.0, 0.0)),
+                Box::new(Collider::new((10, 10), (0, 0))),
+            ],
+            None,
+            Position::new(10, -10),
+        );
+        let ghost = GameObject::new(
+            vec![
+                Box::new(Velocity::new(0.0, -100.0)),
+                Box::new(Collider::new((10, 10), (0, 0)).into_trigger()),
+            ],
+            None,
+            Position::new(20, -10),
+        );
+        let tiles = SolidTiles::from_rows((0, 0), 10, &["#....#", "#....#", "######"]);
+        let mut scene = Scene::new(vec![falling, ghost], vec![], Position::new(0, 0))
+            .unwrap()
+            .with_solid_tiles(tiles);
+        let uids = scene.uids();
+        run(
+            &mut PhysicsSystem::new().with_gravity(0.0, -100.0),
+            &mut scene,
+            10,
+        );
+
+        // Resting on the floor against the right wall
+        assert_eq!(position(&scene, uids[0]), (40, -10));
+        let velocity = scene.object(uids[0]).unwrap().velocity().unwrap();
+        assert_eq!((velocity.x, velocity.y), (0.0, 0.0));
+        // Trigger zones pass through walls
+        assert!(position(&scene, uids[1]).1 < -30);
+    }
+
+    #[test]
+    fn test_main_object_moves() {
+        let mut scene = Scene::new(
+            vec![],
+            vec![Box::new(Velocity::new(0.0, 10.0))],
+            Position::new(4, 4),
+        )
+        .unwrap();
+        run(&mut PhysicsSystem::new(), &mut scene, 10);
+        assert_eq!(
+            (scene.main_object.position.x, scene.main_object.position.y),
+            (4, 14)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Recording and playback of the input of the game window.
+//!
+//! While recording, every change to the `InputState` is stored with the tick it happened
+//! in. A saved `InputRecording` can be played back later: each tick then receives exactly
+//! the input of the recorded tick, so a bug report or an automated gameplay test can be
+//! reproduced, e.g. through the headless mode. Recordings are stored as TOML files.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+use winit::event::{MouseButton, TouchPhase};
+use winit::keyboard::KeyCode;
+
+/// A single change of the recorded input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum InputChange {
+    /// A key was pressed or released.
+    Key { key: KeyCode, pressed: bool },
+    /// A mouse button was pressed or released.
+    Button { button: MouseButton, pressed: bool },
+    /// The mouse moved to a frame position, or left the frame without one.
+    MousePosition { position: Option<(i32, i32)> },
+    /// The wheel was scrolled by a distance in lines.
+    Wheel { x: f32, y: f32 },
+    /// Text was typed or committed by an input method.
+    Text { text: String },
+    /// The text being composed with an input method changed.
+    Preedit { text: String },
+    /// A finger touched, moved over or left the frame.
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: Option<(i32, i32)>,
+    },
+    /// All keys and buttons were released, e.g. because the window lost focus.
+    ReleaseAll,
+}
+
+/// Input changes of one tick.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct RecordedFrame {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<InputChange>,
+}
+
+/// Input of consecutive ticks, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// The changes of every tick; the last frame collects the current tick.
+    frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    /// Creates a recording with a single, empty tick.
+    pub fn new() -> Self {
+        InputRecording {
+            frames: vec![RecordedFrame::default()],
+        }
+    }
+
+    /// Returns the number of recorded ticks.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Checks whether no ticks are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Adds a change to the current tick.
+    pub(crate) fn push_change(&mut self, change: InputChange) {
+        match self.frames.last_mut() {
+            Some(frame) => frame.changes.push(change),
+            None => self.frames.push(RecordedFrame {
+                changes: vec![change],
+            }),
+        }
+    }
+
+    /// Ends the current tick and starts recording the next one.
+    pub(crate) fn end_frame(&mut self) {
+        self.frames.push(RecordedFrame::default());
+    }
+
+    /// Splits the recording into the changes of every tick, for playback.
+    pub(crate) fn into_frames(self) -> VecDeque<Vec<InputChange>> {
+        self.frames.into_iter().map(|frame| frame.changes).collect()
+    }
+
+    /// Writes the recording to a TOML file.
+    ///
+    /// # Errors
+    /// Fails if the recording cannot be encoded or the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let text = toml::to_string(self).map_err(Error::other)?;
+        fs::write(path, text)
+    }
+
+    /// Reads a recording written by [`InputRecording::save`].
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid recording.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_grouped_by_tick() {
+        let mut recording = InputRecording::new();
+        recording.push_change(InputChange::Key {
+            key: KeyCode::KeyW,
+            pressed: true,
+        });
+        recording.end_frame();
+        recording.end_frame();
+        recording.push_change(InputChange::ReleaseAll);
+        assert_eq!(recording.len(), 3);
+
+        let frames = recording.into_frames();
+        assert_eq!(frames[0].len(), 1);
+        assert!(frames[1].is_empty());
+        assert_eq!(frames[2], vec![InputChange::ReleaseAll]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut recording = InputRecording::new();
+        recording.push_change(InputChange::Key {
+            key: KeyCode::Space,
+            pressed: true,
+        });
+        recording.push_change(InputChange::MousePosition {
+            position: Some((3, 4)),
+        });
+        recording.end_frame();
+        recording.push_change(InputChange::MousePosition { position: None });
+        recording.push_change(InputChange::Button {
+            button: MouseButton::Left,
+            pressed: false,
+        });
+        recording.push_change(InputChange::Wheel { x: 0.0, y: -1.5 });
+        recording.push_change(InputChange::Text {
+            text: "hé".to_string(),
+        });
+        recording.push_change(InputChange::Touch {
+            id: 2,
+            phase: TouchPhase::Started,
+            position: None,
+        });
+        recording.end_frame();
+
+        let path = std::env::temp_dir().join("rusty_ache_test_input.toml");
+        recording.save(&path).unwrap();
+        assert_eq!(InputRecording::load(&path).unwrap(), recording);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_file() {
+        let path = std::env::temp_dir().join("rusty_ache_test_invalid_input.toml");
+        fs::write(&path, "frames = 3").unwrap();
+        assert!(InputRecording::load(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,260 @@
+//! Scene files describing the objects of a scene.
+//!
+//! A scene file is a TOML document with a `[main]` table for the main object and an
+//! `[[objects]]` table per further object. Every entry names its sprite image, position
+//! and optionally its sprite offset, shadow, layer, script, tags and collision box:
+//!
+//! ```toml
+//! [main]
+//! sprite = "white_ship.png"
+//! x = 0
+//! y = 0
+//! offset = [60, -60]
+//!
+//! [[objects]]
+//! sprite = "tower.png"
+//! x = 82
+//! y = 37
+//! tags = ["building"]
+//! collider = { size = [16, 24], offset = [0, -4] }
+//!
+//! [[objects]]
+//! sprite = "asteroid.png"
+//! x = 140
+//! y = -20
+//! collider = { radius = 8, offset = [8, -8], pixel_mask = true }
+//!
+//! [[objects]]
+//! sprite = "door.png"
+//! x = 200
+//! y = 0
+//! collider = { size = [16, 32], trigger = true }
+//! ```
+//!
+//! Sprite paths are relative to the directory of the scene file, and every image file is
+//! decoded once however many objects show it. Objects without a `z`
+//! are stacked in file order starting at 1, and the main object is placed above them.
+
+use crate::engine::assets::AssetManager;
+use crate::engine::scene::game_object::components::collider::Collider;
+use crate::engine::scene::game_object::components::sprite::Layer;
+use crate::engine::scene::game_object::{GameObject, Position};
+use crate::engine::scene::prefab::{Prefab, ScriptRegistry};
+use serde::Deserialize;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+/// Contents of a scene file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SceneFile {
+    /// The main object.
+    main: ObjectEntry,
+    /// All other objects, in file order.
+    #[serde(default)]
+    objects: Vec<ObjectEntry>,
+}
+
+/// A single object of a scene file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectEntry {
+    /// Path of the sprite image; objects without one are invisible.
+    sprite: Option<PathBuf>,
+    x: i32,
+    y: i32,
+    /// Depth of the object; None stacks it by file order.
+    z: Option<i32>,
+    #[serde(default)]
+    offset: (i32, i32),
+    #[serde(default = "default_shadow")]
+    shadow: bool,
+    #[serde(default)]
+    layer: Layer,
+    /// Name of the script, looked up in the registry the scene is loaded with.
+    script: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Collision box of the object; objects without one do not collide.
+    collider: Option<Collider>,
+}
+
+/// Sprites in scene files cast shadows unless told otherwise, like the built-in scenes.
+fn default_shadow() -> bool {
+    true
+}
+
+impl ObjectEntry {
+    /// Builds the object, loading its sprite relative to `dir`.
+    fn build(
+        &self,
+        dir: &Path,
+        z: i32,
+        scripts: &ScriptRegistry,
+        assets: &AssetManager,
+    ) -> Result<GameObject, Error> {
+        let mut prefab = Prefab::new()
+            .with_offset(self.offset)
+            .with_shadow(self.shadow)
+            .with_layer(self.layer);
+        if let Some(sprite) = &self.sprite {
+            prefab = prefab.with_shared_image(assets.load_image(&dir.join(sprite))?);
+        }
+        if let Some(script) = &self.script {
+            prefab = prefab.with_script(script.clone());
+        }
+        for tag in &self.tags {
+            prefab = prefab.with_tag(tag.clone());
+        }
+        if let Some(collider) = self.collider {
+            prefab = prefab.with_collider(collider);
+        }
+        let position = Position {
+            x: self.x,
+            y: self.y,
+            z: self.z.unwrap_or(z),
+            is_relative: false,
+        };
+        prefab.build(position, scripts).map_err(|_| {
+            Error::other(format!(
+                "no script registered as {}",
+                self.script.as_deref().unwrap_or_default()
+            ))
+        })
+    }
+}
+
+impl SceneFile {
+    /// Parses a scene file.
+    ///
+    /// # Errors
+    /// Fails if the text is not a valid scene file.
+    pub(crate) fn parse(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(Error::other)
+    }
+
+    /// Builds the objects of the scene.
+    ///
+    /// # Parameters
+    /// - `dir`: Directory sprite paths are relative to.
+    /// - `scripts`: Registry the scripts of the objects are looked up in.
+    ///
+    /// # Returns
+    /// The objects in file order and the main object.
+    ///
+    /// # Errors
+    /// Fails if a sprite cannot be loaded or a script is not registered.
+    pub(crate) fn build(
+        &self,
+        dir: &Path,
+        scripts: &ScriptRegistry,
+    ) -> Result<(Vec<GameObject>, GameObject), Error> {
+        let assets = AssetManager::new();
+        let objects = (1..)
+            .zip(&self.objects)
+            .map(|(z, entry)| entry.build(dir, z, scripts, &assets))
+            .collect::<Result<Vec<_>, _>>()?;
+        let main = self
+            .main
+            .build(dir, self.objects.len() as i32 + 1, scripts, &assets)?;
+        Ok((objects, main))
+    }
+
+    /// Reads and parses a scene file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid scene file.
+    pub(crate) fn read(path: &Path) -> Result<Self, Error> {
+        SceneFile::parse(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_defaults() {
+        let file = SceneFile::parse("[main]\nx = 1\ny = 2\n").unwrap();
+        assert!(file.objects.is_empty());
+        assert_eq!(file.main.sprite, None);
+        assert_eq!(file.main.offset, (0, 0));
+        assert!(file.main.shadow);
+        assert_eq!(file.main.layer, Layer::World);
+
+        let (objects, main) = file.build(Path::new("."), &ScriptRegistry::new()).unwrap();
+        assert!(objects.is_empty());
+        assert_eq!(
+            (main.position.x, main.position.y, main.position.z),
+            (1, 2, 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_files() {
+        assert!(SceneFile::parse("[[objects]]\nx = 1\ny = 2\n").is_err());
+        assert!(SceneFile::parse("[main]\nx = 1\ny = 2\nspeed = 3\n").is_err());
+        let unknown_script = SceneFile::parse("[main]\nx = 0\ny = 0\nscript = \"fly\"\n").unwrap();
+        assert!(
+            unknown_script
+                .build(Path::new("."), &ScriptRegistry::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_objects_are_stacked_in_file_order() {
+        let file = SceneFile::parse(
+            "[main]\nx = 0\ny = 0\n\n\
+             [[objects]]\nx = 1\ny = 1\ntags = [\"a\"]\n\n\
+             [[objects]]\nx = 2\ny = 2\nz = 7\nlayer = \"Background\"\n",
+        )
+        .unwrap();
+        let (objects, main) = file.build(Path::new("."), &ScriptRegistry::new()).unwrap();
+        assert_eq!(objects[0].position.z, 1);
+        assert!(objects[0].has_tag("a"));
+        assert_eq!(objects[1].position.z, 7);
+        assert_eq!(main.position.z, 3);
+    }
+
+    #[test]
+    fn test_objects_get_colliders() {
+        let file = SceneFile::parse(
+            "[main]\nx = 0\ny = 0\ncollider = { size = [4, 2] }\n\n\
+             [[objects]]\nx = 1\ny = 1\ncollider = { size = [16, 24], offset = [0, -4] }\n\n\
+             [[objects]]\nx = 2\ny = 2\n\n\
+             [[objects]]\nx = 3\ny = 3\ncollider = { radius = 5, offset = [2, -2] }\n",
+        )
+        .unwrap();
+        let (objects, main) = file.build(Path::new("."), &ScriptRegistry::new()).unwrap();
+        assert_eq!(main.collider(), Some(&Collider::new((4, 2), (0, 0))));
+        assert_eq!(
+            objects[0].collider(),
+            Some(&Collider::new((16, 24), (0, -4)))
+        );
+        assert_eq!(objects[1].collider(), None);
+        assert_eq!(objects[2].collider(), Some(&Collider::circle(5, (2, -2))));
+        let file = SceneFile::parse(
+            "[main]\nx = 0\ny = 0\ncollider = { size = [8, 8], trigger = true }\n\n\
+             [[objects]]\nx = 1\ny = 1\ncollider = { radius = 8, pixel_mask = true }\n",
+        )
+        .unwrap();
+        let (objects, main) = file.build(Path::new("."), &ScriptRegistry::new()).unwrap();
+        assert_eq!(
+            main.collider(),
+            Some(&Collider::new((8, 8), (0, 0)).into_trigger())
+        );
+        assert_eq!(
+            objects[0].collider(),
+            Some(&Collider::circle(8, (0, 0)).with_pixel_mask())
+        );
+        assert!(
+            SceneFile::parse("[main]\nx = 0\ny = 0\ncollider = { size = [1, 1], radius = 1 }\n")
+                .is_err()
+        );
+        assert!(
+            SceneFile::parse("[main]\nx = 0\ny = 0\ncollider = { offset = [1, 1] }\n").is_err()
+        );
+    }
+}
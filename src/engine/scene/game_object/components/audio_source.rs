@@ -0,0 +1,180 @@
+//! Sounds played from a place in the world.
+//!
+//! An `AudioSource` makes its object emit a sound that gets quieter with the distance to
+//! the listener, the main object, and comes from the side the object is on. The
+//! [`AudioSystem`](crate::engine::audio::AudioSystem) starts and stops the sounds of all
+//! sources and updates their volume and balance every update, so moving sources and a
+//! moving listener are heard where they are.
+
+use super::*;
+use crate::engine::audio::{Sound, VoiceId};
+use std::any::Any;
+
+/// Distance at which a new source becomes silent, in world units.
+pub const DEFAULT_AUDIO_RANGE: f32 = 400.0;
+
+/// Component playing a sound positioned at its object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioSource {
+    sound: Sound,
+    /// Volume right at the listener, 1.0 for the sound's own volume.
+    pub volume: f32,
+    /// Distance in world units beyond which the source is silent.
+    pub range: f32,
+    /// Whether the sound starts over when it ends.
+    pub looping: bool,
+    /// Whether the source should be heard.
+    wanted: bool,
+    /// Whether the sound should start over at the next update.
+    restart: bool,
+    /// Voice playing the sound, as of the last update.
+    pub(crate) voice: Option<VoiceId>,
+}
+
+impl AudioSource {
+    /// Creates a source playing a sound once, starting at the next update.
+    pub fn new(sound: Sound) -> Self {
+        AudioSource {
+            sound,
+            volume: 1.0,
+            range: DEFAULT_AUDIO_RANGE,
+            looping: false,
+            wanted: true,
+            restart: true,
+            voice: None,
+        }
+    }
+
+    /// Makes the source play its sound over and over, e.g. for a waterfall or an engine.
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    /// Sets the volume of the source right at the listener.
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.max(0.0);
+        self
+    }
+
+    /// Sets the distance in world units beyond which the source is silent.
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = range.max(1.0);
+        self
+    }
+
+    /// Creates a source that stays silent until [`AudioSource::play`] is called.
+    pub fn stopped(mut self) -> Self {
+        self.wanted = false;
+        self.restart = false;
+        self
+    }
+
+    /// Returns the sound of the source.
+    pub fn sound(&self) -> &Sound {
+        &self.sound
+    }
+
+    /// Plays the sound from its start at the next update, restarting it if it plays.
+    pub fn play(&mut self) {
+        self.wanted = true;
+        self.restart = true;
+    }
+
+    /// Stops the sound at the next update.
+    pub fn stop(&mut self) {
+        self.wanted = false;
+        self.restart = false;
+    }
+
+    /// Checks whether the sound played during the last update.
+    pub fn is_playing(&self) -> bool {
+        self.voice.is_some()
+    }
+
+    /// Takes the request to start the sound over, if any.
+    pub(crate) fn take_restart(&mut self) -> bool {
+        std::mem::take(&mut self.restart)
+    }
+
+    /// Checks whether the source should be heard.
+    pub(crate) fn is_wanted(&self) -> bool {
+        self.wanted
+    }
+
+    /// Forgets the voice of a sound that ended on its own.
+    pub(crate) fn finished(&mut self) {
+        self.voice = None;
+        self.wanted = false;
+    }
+
+    /// Returns the volume and the balance the source is heard with.
+    ///
+    /// The volume falls linearly from [`AudioSource::volume`] at the listener to silence
+    /// at [`AudioSource::range`]. The balance follows the horizontal offset, reaching one
+    /// side fully at half the range.
+    ///
+    /// # Parameters
+    /// - `offset`: Position of the source relative to the listener, in world units.
+    ///
+    /// # Returns
+    /// The volume and the balance, from -1 for left only to 1 for right only.
+    pub fn levels(&self, offset: (f32, f32)) -> (f32, f32) {
+        let range = self.range.max(1.0);
+        let distance = offset.0.hypot(offset.1);
+        let volume = self.volume * (1.0 - distance / range).clamp(0.0, 1.0);
+        let pan = (2.0 * offset.0 / range).clamp(-1.0, 1.0);
+        (volume, pan)
+    }
+}
+
+impl Component for AudioSource {
+    /// Returns a reference to this component as a dynamic Any for downcasting.
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Returns a mutable reference to this component as a dynamic Any for downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Returns the component type identifier as `ComponentType::AudioSource`.
+    fn get_component_type(&self) -> ComponentType {
+        ComponentType::AudioSource
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source() -> AudioSource {
+        AudioSource::new(Sound::new(vec![0.5; 4], 1, 8000)).with_range(100.0)
+    }
+
+    #[test]
+    fn test_levels_follow_distance_and_side() {
+        let source = source().with_volume(0.8);
+        assert_eq!(source.levels((0.0, 0.0)), (0.8, 0.0));
+        let (volume, pan) = source.levels((30.0, 40.0));
+        assert!((volume - 0.4).abs() < 1e-6);
+        assert!((pan - 0.6).abs() < 1e-6);
+        assert_eq!(source.levels((-60.0, 0.0)).1, -1.0);
+        assert_eq!(source.levels((0.0, -150.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_play_and_stop_requests() {
+        let mut source = source().stopped();
+        assert!(!source.is_wanted());
+        assert!(!source.take_restart());
+        source.play();
+        assert!(source.is_wanted());
+        assert!(source.take_restart());
+        assert!(!source.take_restart());
+        source.stop();
+        assert!(!source.is_wanted());
+        assert_eq!(source.get_component_type(), ComponentType::AudioSource);
+    }
+}
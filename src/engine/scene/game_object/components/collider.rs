@@ -0,0 +1,530 @@
+//! Represents the physical footprint of a game object.
+//!
+//! A `Collider` is a shape placed relative to the object position, independent of the
+//! sprite, so an object can collide with a smaller area than it shows, or with no sprite
+//! at all. Two shapes are supported: axis-aligned boxes, which like sprites hang down and
+//! to the right from their top-left corner, and circles, which fit round objects such as
+//! ships and projectiles better. The corner of a box and the center of a circle sit at the
+//! object position plus the offset.
+//!
+//! A collider can also be a trigger: a zone that is not solid, so nothing is blocked by it
+//! and nothing collides with it, but objects entering and leaving it are reported. Pickups,
+//! doorways and scripted areas are triggers.
+//!
+//! Where a box or a circle is too coarse, e.g. for irregular asteroids, a collider can
+//! also use the sprite of its object as a pixel mask: only the parts of the shape covered
+//! by non-transparent sprite pixels then collide.
+
+use super::*;
+use serde::Deserialize;
+use std::any::Any;
+
+use crate::engine::scene::game_object::Position;
+
+/// Shape of a collider, in world units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColliderShape {
+    /// Axis-aligned box of the given width and height.
+    Box { size: (u32, u32) },
+    /// Circle of the given radius.
+    Circle { radius: u32 },
+}
+
+/// Component giving an object a collision shape.
+///
+/// In scene files a collider is written as a table with either a `size` or a `radius`,
+/// an optional `offset` and optional `trigger` and `pixel_mask` flags, e.g.
+/// `{ radius = 6, offset = [8, -8] }` or `{ size = [32, 32], trigger = true }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "ColliderEntry")]
+pub struct Collider {
+    /// Shape of the collider.
+    pub shape: ColliderShape,
+    /// Offset of the box top-left corner or the circle center from the object position.
+    pub offset: (i32, i32),
+    /// Whether the collider is a non-solid trigger zone.
+    pub trigger: bool,
+    /// Whether only the non-transparent pixels of the object sprite inside the shape collide.
+    pub pixel_mask: bool,
+}
+
+/// A collider as written in a scene file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColliderEntry {
+    size: Option<(u32, u32)>,
+    radius: Option<u32>,
+    #[serde(default)]
+    offset: (i32, i32),
+    #[serde(default)]
+    trigger: bool,
+    #[serde(default)]
+    pixel_mask: bool,
+}
+
+impl TryFrom<ColliderEntry> for Collider {
+    type Error = String;
+
+    fn try_from(entry: ColliderEntry) -> Result<Self, Self::Error> {
+        let collider = match (entry.size, entry.radius) {
+            (Some(size), None) => Collider::new(size, entry.offset),
+            (None, Some(radius)) => Collider::circle(radius, entry.offset),
+            _ => return Err("a collider needs either a size or a radius".to_string()),
+        };
+        Ok(Collider {
+            trigger: entry.trigger,
+            pixel_mask: entry.pixel_mask,
+            ..collider
+        })
+    }
+}
+
+impl Collider {
+    /// Creates a collision box.
+    ///
+    /// # Parameters
+    /// - `size`: Width and height of the box.
+    /// - `offset`: Offset of the box top-left corner from the object position.
+    pub fn new(size: (u32, u32), offset: (i32, i32)) -> Self {
+        Collider {
+            shape: ColliderShape::Box { size },
+            offset,
+            trigger: false,
+            pixel_mask: false,
+        }
+    }
+
+    /// Creates a collision circle.
+    ///
+    /// # Parameters
+    /// - `radius`: Radius of the circle.
+    /// - `offset`: Offset of the circle center from the object position.
+    pub fn circle(radius: u32, offset: (i32, i32)) -> Self {
+        Collider {
+            shape: ColliderShape::Circle { radius },
+            offset,
+            trigger: false,
+            pixel_mask: false,
+        }
+    }
+
+    /// Turns the collider into a trigger zone, reporting objects entering and leaving it
+    /// instead of colliding with them.
+    pub fn into_trigger(mut self) -> Self {
+        self.trigger = true;
+        self
+    }
+
+    /// Limits the collider to the non-transparent pixels of the object sprite, for shapes a
+    /// box or a circle does not fit well.
+    ///
+    /// The shape should cover the sprite, as pixels outside of it never collide. Testing
+    /// pixels is slower than testing shapes, so it is only done for objects whose shapes
+    /// overlap.
+    pub fn with_pixel_mask(mut self) -> Self {
+        self.pixel_mask = true;
+        self
+    }
+
+    /// Returns the shape of the collider of an object at the given position, in world
+    /// coordinates.
+    pub fn world_shape(&self, position: &Position) -> WorldShape {
+        let anchor = (position.x + self.offset.0, position.y + self.offset.1);
+        match self.shape {
+            ColliderShape::Box { size } => WorldShape::Box(Aabb {
+                left: anchor.0,
+                top: anchor.1,
+                right: anchor.0 + size.0 as i32,
+                bottom: anchor.1 - size.1 as i32,
+            }),
+            ColliderShape::Circle { radius } => WorldShape::Circle(Circle {
+                center: anchor,
+                radius,
+            }),
+        }
+    }
+
+    /// Returns the world area covered by the collider of an object at the given position;
+    /// for circles, the smallest box around them.
+    pub fn bounds(&self, position: &Position) -> Aabb {
+        self.world_shape(position).bounds()
+    }
+}
+
+impl Component for Collider {
+    /// Returns a reference to this component as a dynamic Any for downcasting.
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Returns a mutable reference to this component as a dynamic Any for downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Returns the component type identifier as `ComponentType::Collider`.
+    fn get_component_type(&self) -> ComponentType {
+        ComponentType::Collider
+    }
+}
+
+/// Axis-aligned rectangle in world coordinates, with y growing upwards.
+///
+/// The left and top edges belong to the rectangle, the right and bottom ones do not,
+/// so rectangles that merely touch do not overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aabb {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Aabb {
+    /// Checks whether two rectangles share any area.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.bottom < other.top
+            && other.bottom < self.top
+    }
+
+    /// Checks whether a world point lies inside the rectangle.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.left..self.right).contains(&x) && y <= self.top && y > self.bottom
+    }
+
+    /// Returns the area shared by two rectangles, or None if they do not overlap.
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        self.intersects(other).then(|| Aabb {
+            left: self.left.max(other.left),
+            top: self.top.min(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.max(other.bottom),
+        })
+    }
+
+    /// Measures how far a ray travels before reaching the rectangle.
+    ///
+    /// # Parameters
+    /// - `origin`: World point the ray starts at.
+    /// - `direction`: Direction of the ray as a unit vector.
+    ///
+    /// # Returns
+    /// The distance along the ray to the first point of the rectangle, 0 if the ray starts
+    /// inside it; None if the ray misses it.
+    pub fn ray_distance(&self, origin: (f32, f32), direction: (f32, f32)) -> Option<f32> {
+        let mut enter = 0.0f32;
+        let mut exit = f32::INFINITY;
+        let slabs = [
+            (origin.0, direction.0, self.left as f32, self.right as f32),
+            (origin.1, direction.1, self.bottom as f32, self.top as f32),
+        ];
+        for (start, step, low, high) in slabs {
+            if step == 0.0 {
+                if start < low || start > high {
+                    return None;
+                }
+            } else {
+                let (t0, t1) = ((low - start) / step, (high - start) / step);
+                enter = enter.max(t0.min(t1));
+                exit = exit.min(t0.max(t1));
+            }
+        }
+        (enter <= exit).then_some(enter)
+    }
+}
+
+/// Circle in world coordinates; circles that merely touch do not overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Circle {
+    pub center: (i32, i32),
+    pub radius: u32,
+}
+
+impl Circle {
+    /// Checks whether two circles share any area.
+    pub fn intersects(&self, other: &Circle) -> bool {
+        let dx = (self.center.0 - other.center.0) as i64;
+        let dy = (self.center.1 - other.center.1) as i64;
+        let reach = self.radius as i64 + other.radius as i64;
+        dx * dx + dy * dy < reach * reach
+    }
+
+    /// Checks whether the circle shares any area with a rectangle.
+    ///
+    /// The point of the rectangle closest to the center decides: the circle overlaps the
+    /// rectangle if that point lies strictly inside the circle.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let closest_x = self.center.0.clamp(aabb.left, aabb.right.max(aabb.left));
+        let closest_y = self.center.1.clamp(aabb.bottom.min(aabb.top), aabb.top);
+        let dx = (self.center.0 - closest_x) as i64;
+        let dy = (self.center.1 - closest_y) as i64;
+        let radius = self.radius as i64;
+        dx * dx + dy * dy < radius * radius
+    }
+
+    /// Measures how far a ray travels before reaching the circle.
+    ///
+    /// # Parameters
+    /// - `origin`: World point the ray starts at.
+    /// - `direction`: Direction of the ray as a unit vector.
+    ///
+    /// # Returns
+    /// The distance along the ray to the first point of the circle, 0 if the ray starts
+    /// inside it; None if the ray misses it.
+    pub fn ray_distance(&self, origin: (f32, f32), direction: (f32, f32)) -> Option<f32> {
+        let to_origin = (
+            origin.0 - self.center.0 as f32,
+            origin.1 - self.center.1 as f32,
+        );
+        let radius = self.radius as f32;
+        let outside = to_origin.0 * to_origin.0 + to_origin.1 * to_origin.1 - radius * radius;
+        if outside <= 0.0 {
+            return Some(0.0);
+        }
+        let along = to_origin.0 * direction.0 + to_origin.1 * direction.1;
+        let discriminant = along * along - outside;
+        if along > 0.0 || discriminant < 0.0 {
+            // Pointing away from the circle or passing it by
+            return None;
+        }
+        Some(-along - discriminant.sqrt())
+    }
+}
+
+/// A collider shape placed in the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldShape {
+    Box(Aabb),
+    Circle(Circle),
+}
+
+impl WorldShape {
+    /// Checks whether two shapes share any area.
+    pub fn intersects(&self, other: &WorldShape) -> bool {
+        match (self, other) {
+            (WorldShape::Box(a), WorldShape::Box(b)) => a.intersects(b),
+            (WorldShape::Circle(a), WorldShape::Circle(b)) => a.intersects(b),
+            (WorldShape::Circle(circle), WorldShape::Box(aabb))
+            | (WorldShape::Box(aabb), WorldShape::Circle(circle)) => circle.intersects_aabb(aabb),
+        }
+    }
+
+    /// Measures how far a ray travels before reaching the shape, see
+    /// [`Aabb::ray_distance`] and [`Circle::ray_distance`].
+    pub fn ray_distance(&self, origin: (f32, f32), direction: (f32, f32)) -> Option<f32> {
+        match self {
+            WorldShape::Box(aabb) => aabb.ray_distance(origin, direction),
+            WorldShape::Circle(circle) => circle.ray_distance(origin, direction),
+        }
+    }
+
+    /// Checks whether a world point lies inside the shape.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        match self {
+            WorldShape::Box(aabb) => aabb.contains(x, y),
+            WorldShape::Circle(circle) => {
+                let dx = (x - circle.center.0) as i64;
+                let dy = (y - circle.center.1) as i64;
+                let radius = circle.radius as i64;
+                dx * dx + dy * dy < radius * radius
+            }
+        }
+    }
+
+    /// Returns the shape moved by the given world units.
+    pub fn translated(&self, dx: i32, dy: i32) -> WorldShape {
+        match *self {
+            WorldShape::Box(aabb) => WorldShape::Box(Aabb {
+                left: aabb.left + dx,
+                top: aabb.top + dy,
+                right: aabb.right + dx,
+                bottom: aabb.bottom + dy,
+            }),
+            WorldShape::Circle(Circle { center, radius }) => WorldShape::Circle(Circle {
+                center: (center.0 + dx, center.1 + dy),
+                radius,
+            }),
+        }
+    }
+
+    /// Returns the smallest rectangle holding the shape.
+    pub fn bounds(&self) -> Aabb {
+        match *self {
+            WorldShape::Box(aabb) => aabb,
+            WorldShape::Circle(Circle { center, radius }) => Aabb {
+                left: center.0 - radius as i32,
+                top: center.1 + radius as i32,
+                right: center.0 + radius as i32,
+                bottom: center.1 - radius as i32,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds_follow_position_and_offset() {
+        let collider = Collider::new((10, 4), (2, -1));
+        assert_eq!(
+            collider.bounds(&Position::new(5, 20)),
+            Aabb {
+                left: 7,
+                top: 19,
+                right: 17,
+                bottom: 15,
+            }
+        );
+        assert_eq!(collider.get_component_type(), ComponentType::Collider);
+        assert!(collider.as_any().is::<Collider>());
+        assert!(collider.get_sprite_unchecked().is_none());
+    }
+
+    #[test]
+    fn test_into_trigger_keeps_shape() {
+        let collider = Collider::circle(4, (1, 1));
+        assert!(!collider.trigger);
+        let trigger = collider.into_trigger();
+        assert!(trigger.trigger);
+        assert_eq!(
+            trigger.world_shape(&Position::new(0, 0)),
+            collider.world_shape(&Position::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn test_shape_contains_point() {
+        let circle = Collider::circle(3, (0, 0)).world_shape(&Position::new(0, 0));
+        assert!(circle.contains(0, 0));
+        assert!(circle.contains(2, -2));
+        assert!(!circle.contains(3, 0));
+        let boxed = Collider::new((2, 2), (0, 0)).world_shape(&Position::new(0, 0));
+        assert!(boxed.contains(1, -1));
+        assert!(!boxed.contains(2, 0));
+    }
+
+    #[test]
+    fn test_intersects_needs_shared_area() {
+        let a = Collider::new((10, 10), (0, 0)).bounds(&Position::new(0, 0));
+        assert!(a.intersects(&Collider::new((10, 10), (0, 0)).bounds(&Position::new(9, -9))));
+        assert!(a.intersects(&Collider::new((2, 2), (0, 0)).bounds(&Position::new(4, -4))));
+        // Touching edges do not overlap
+        assert!(!a.intersects(&Collider::new((10, 10), (0, 0)).bounds(&Position::new(10, 0))));
+        assert!(!a.intersects(&Collider::new((10, 10), (0, 0)).bounds(&Position::new(0, -10))));
+    }
+
+    #[test]
+    fn test_intersection_of_boxes() {
+        let a = Collider::new((10, 10), (0, 0)).bounds(&Position::new(0, 0));
+        let b = Collider::new((10, 10), (0, 0)).bounds(&Position::new(6, -4));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Aabb {
+                left: 6,
+                top: -4,
+                right: 10,
+                bottom: -10,
+            })
+        );
+        assert_eq!(a.intersection(&b), b.intersection(&a));
+        assert_eq!(
+            a.intersection(&Collider::new((1, 1), (0, 0)).bounds(&Position::new(10, 0))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let area = Collider::new((3, 2), (0, 0)).bounds(&Position::new(0, 0));
+        assert!(area.contains(0, 0));
+        assert!(area.contains(2, -1));
+        assert!(!area.contains(3, 0));
+        assert!(!area.contains(0, -2));
+        assert!(!area.contains(0, 1));
+    }
+
+    #[test]
+    fn test_circle_shape_is_centered_at_offset() {
+        let collider = Collider::circle(3, (4, -4));
+        assert_eq!(
+            collider.world_shape(&Position::new(10, 10)),
+            WorldShape::Circle(Circle {
+                center: (14, 6),
+                radius: 3,
+            })
+        );
+        assert_eq!(
+            collider.bounds(&Position::new(10, 10)),
+            Aabb {
+                left: 11,
+                top: 9,
+                right: 17,
+                bottom: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_circle_circle_intersection() {
+        let circle =
+            |x, y, radius| Collider::circle(radius, (0, 0)).world_shape(&Position::new(x, y));
+        assert!(circle(0, 0, 5).intersects(&circle(6, 0, 2)));
+        assert!(circle(0, 0, 5).intersects(&circle(0, 0, 1)));
+        // Touching circles do not overlap
+        assert!(!circle(0, 0, 5).intersects(&circle(7, 0, 2)));
+        // Diagonal distance is sqrt(50), just over 7
+        assert!(!circle(0, 0, 4).intersects(&circle(5, 5, 3)));
+        assert!(circle(0, 0, 4).intersects(&circle(5, 5, 4)));
+    }
+
+    #[test]
+    fn test_ray_distance_to_box() {
+        let area = Collider::new((10, 10), (0, 0)).bounds(&Position::new(0, 0));
+        assert_eq!(area.ray_distance((-5.0, -5.0), (1.0, 0.0)), Some(5.0));
+        assert_eq!(area.ray_distance((5.0, 10.0), (0.0, -1.0)), Some(10.0));
+        // Starting inside
+        assert_eq!(area.ray_distance((5.0, -5.0), (0.0, 1.0)), Some(0.0));
+        // Pointing away or passing by
+        assert_eq!(area.ray_distance((-5.0, -5.0), (-1.0, 0.0)), None);
+        assert_eq!(area.ray_distance((-5.0, 5.0), (1.0, 0.0)), None);
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        let distance = area
+            .ray_distance((-3.0, 3.0), (diagonal, -diagonal))
+            .unwrap();
+        assert!((distance - 18.0f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_ray_distance_to_circle() {
+        let circle = Circle {
+            center: (10, 0),
+            radius: 2,
+        };
+        assert_eq!(circle.ray_distance((0.0, 0.0), (1.0, 0.0)), Some(8.0));
+        assert_eq!(circle.ray_distance((11.0, 0.0), (1.0, 0.0)), Some(0.0));
+        assert_eq!(circle.ray_distance((0.0, 0.0), (-1.0, 0.0)), None);
+        assert_eq!(circle.ray_distance((0.0, 3.0), (1.0, 0.0)), None);
+        // Grazing the top of the circle
+        let distance = circle.ray_distance((0.0, 1.0), (1.0, 0.0)).unwrap();
+        assert!((distance - (10.0 - 3.0f32.sqrt())).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_circle_box_intersection() {
+        let boxed = Collider::new((10, 10), (0, 0)).world_shape(&Position::new(0, 0));
+        let circle =
+            |x, y, radius| Collider::circle(radius, (0, 0)).world_shape(&Position::new(x, y));
+        // Center inside the box
+        assert!(circle(5, -5, 1).intersects(&boxed));
+        // Reaching over an edge
+        assert!(boxed.intersects(&circle(12, -5, 3)));
+        assert!(!boxed.intersects(&circle(12, -5, 2)));
+        // Near a corner the distance to the corner decides, not the bounding boxes
+        assert!(!circle(13, 3, 4).intersects(&boxed));
+        assert!(circle(12, 2, 4).intersects(&boxed));
+    }
+}
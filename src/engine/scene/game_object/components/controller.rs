@@ -0,0 +1,393 @@
+//! Moves the main object by input without passing through solid things.
+//!
+//! Without a `CharacterController` the engine moves the main object one world unit per
+//! update along the pressed movement actions, through everything. Attached to the main
+//! object, the controller takes over: it moves the object at its own speed and stops it at
+//! the solid colliders of other objects and at the solid tiles of the scene. Movement is
+//! resolved along x first, then along y, so the object slides along walls instead of
+//! sticking to them. Trigger zones and objects attached to the main object never block it,
+//! and the main object needs a collider of its own to be blocked at all.
+//!
+//! By default the controller moves freely in all four directions, as in a top-down game.
+//! With gravity, as in a platformer, the up and down actions do not move the object;
+//! instead it falls until it lands on something, and pressing up while standing jumps.
+//!
+//! After every update the controller tells whether the object stands on something and on
+//! which sides it was blocked.
+
+use super::*;
+use crate::engine::input::{InputState, MOVE_DOWN, MOVE_LEFT, MOVE_RIGHT, MOVE_UP};
+use crate::engine::scene::game_object::Object;
+use crate::engine::scene::game_object::components::collider::WorldShape;
+use crate::engine::scene::{MAIN_OBJECT_UID, Scene};
+use std::any::Any;
+use std::time::Duration;
+
+/// Speed of the main object under a new controller, in world units per second.
+pub const DEFAULT_CONTROLLER_SPEED: f32 = 60.0;
+
+/// Sides on which the controlled object was stopped by something solid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Blocked {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+/// Component moving the main object by input and resolving its collisions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharacterController {
+    /// Speed of the object in world units per second; horizontal only with gravity.
+    pub speed: f32,
+    /// Downward acceleration in world units per second squared, if the object falls.
+    pub gravity: Option<f32>,
+    /// Upward speed given by a jump, in world units per second.
+    pub jump_speed: f32,
+    /// Current vertical speed while falling or jumping, positive upwards.
+    vertical_speed: f32,
+    /// Movement not applied to the integer position yet.
+    remainder: (f32, f32),
+    /// Whether something solid was right below the object after the last update.
+    grounded: bool,
+    /// Sides on which the object was blocked during the last update.
+    blocked: Blocked,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        CharacterController::new(DEFAULT_CONTROLLER_SPEED)
+    }
+}
+
+impl CharacterController {
+    /// Creates a controller moving freely in all four directions.
+    ///
+    /// # Parameters
+    /// - `speed`: Speed in world units per second; diagonal movement is as fast.
+    pub fn new(speed: f32) -> Self {
+        CharacterController {
+            speed,
+            gravity: None,
+            jump_speed: 0.0,
+            vertical_speed: 0.0,
+            remainder: (0.0, 0.0),
+            grounded: false,
+            blocked: Blocked::default(),
+        }
+    }
+
+    /// Makes the object fall and jump instead of moving up and down by input.
+    ///
+    /// # Parameters
+    /// - `gravity`: Downward acceleration in world units per second squared.
+    /// - `jump_speed`: Upward speed given by a jump, in world units per second.
+    pub fn with_gravity(mut self, gravity: f32, jump_speed: f32) -> Self {
+        self.gravity = Some(gravity);
+        self.jump_speed = jump_speed;
+        self
+    }
+
+    /// Checks whether the object stood on something solid after the last update.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Returns the sides on which the object was blocked during the last update.
+    pub fn blocked(&self) -> Blocked {
+        self.blocked
+    }
+
+    /// Returns the current vertical speed, positive upwards; always 0 without gravity.
+    pub fn vertical_speed(&self) -> f32 {
+        self.vertical_speed
+    }
+
+    /// Computes the whole world units the object wants to move by this update.
+    fn wanted_movement(&mut self, input: &InputState, dt: f32) -> (i32, i32) {
+        let axis = |positive: &str, negative: &str| {
+            input.is_action_pressed(positive) as i32 as f32
+                - input.is_action_pressed(negative) as i32 as f32
+        };
+        let horizontal = axis(MOVE_RIGHT, MOVE_LEFT);
+        let velocity = match self.gravity {
+            None => {
+                let vertical = axis(MOVE_UP, MOVE_DOWN);
+                let length = horizontal.hypot(vertical);
+                if length == 0.0 {
+                    (0.0, 0.0)
+                } else {
+                    (
+                        horizontal / length * self.speed,
+                        vertical / length * self.speed,
+                    )
+                }
+            }
+            Some(gravity) => {
+                if self.grounded && input.is_action_just_pressed(MOVE_UP) {
+                    self.vertical_speed = self.jump_speed;
+                }
+                self.vertical_speed -= gravity * dt;
+                (horizontal * self.speed, self.vertical_speed)
+            }
+        };
+        let x = velocity.0 * dt + self.remainder.0;
+        let y = velocity.1 * dt + self.remainder.1;
+        let step = (x.trunc(), y.trunc());
+        self.remainder = (x - step.0, y - step.1);
+        (step.0 as i32, step.1 as i32)
+    }
+}
+
+impl Component for CharacterController {
+    /// Returns a reference to this component as a dynamic Any for downcasting.
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Returns a mutable reference to this component as a dynamic Any for downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Returns the component type identifier as `ComponentType::Controller`.
+    fn get_component_type(&self) -> ComponentType {
+        ComponentType::Controller
+    }
+}
+
+/// Checks whether an object is the main object or attached to it, directly or not.
+fn follows_main_object(scene: &Scene, uid: usize) -> bool {
+    let mut ancestor = scene.parent(uid);
+    while let Some(current) = ancestor {
+        if current == MAIN_OBJECT_UID {
+            return true;
+        }
+        ancestor = scene.parent(current);
+    }
+    false
+}
+
+/// Moves the main object by its controller, if it has one.
+///
+/// # Returns
+/// True if the main object has a controller, false if the engine should move it itself.
+pub(crate) fn drive_main_object(scene: &mut Scene, input: &InputState, dt: Duration) -> bool {
+    let Some(controller) = scene.main_object.controller_mut() else {
+        return false;
+    };
+    let delta = controller.wanted_movement(input, dt.as_secs_f32());
+
+    let shape = scene
+        .main_object
+        .collider()
+        .filter(|collider| !collider.trigger)
+        .map(|collider| collider.world_shape(&scene.main_object.position));
+    let obstacles: Vec<WorldShape> = scene
+        .colliders()
+        .into_iter()
+        .filter(|&(uid, collider, _)| {
+            uid != MAIN_OBJECT_UID && !collider.trigger && !follows_main_object(scene, uid)
+        })
+        .map(|(_, _, shape)| shape)
+        .collect();
+    let tiles = scene.solid_tiles();
+    let hits = |dx: i32, dy: i32| {
+        shape.is_some_and(|shape| {
+            let moved = shape.translated(dx, dy);
+            obstacles.iter().any(|obstacle| moved.intersects(obstacle))
+                || tiles.is_some_and(|tiles| tiles.overlaps(&moved.bounds()))
+        })
+    };
+
+    // Objects already overlapping something solid move freely, so they can get out
+    let stuck = hits(0, 0);
+    let mut moved = (0, 0);
+    let mut stopped = (false, false);
+    while moved.0 != delta.0 {
+        let next = moved.0 + delta.0.signum();
+        if !stuck && hits(next, 0) {
+            stopped.0 = true;
+            break;
+        }
+        moved.0 = next;
+    }
+    while moved.1 != delta.1 {
+        let next = moved.1 + delta.1.signum();
+        if !stuck && hits(moved.0, next) {
+            stopped.1 = true;
+            break;
+        }
+        moved.1 = next;
+    }
+    let grounded = !stuck && hits(moved.0, moved.1 - 1);
+
+    scene.main_object.add_position(moved);
+    if let Some(controller) = scene.main_object.controller_mut() {
+        controller.blocked = Blocked {
+            left: stopped.0 && delta.0 < 0,
+            right: stopped.0 && delta.0 > 0,
+            up: stopped.1 && delta.1 > 0,
+            down: stopped.1 && delta.1 < 0,
+        };
+        if stopped.0 {
+            controller.remainder.0 = 0.0;
+        }
+        if stopped.1 {
+            controller.remainder.1 = 0.0;
+            controller.vertical_speed = 0.0;
+        }
+        controller.grounded = grounded;
+        if grounded && controller.vertical_speed < 0.0 {
+            controller.vertical_speed = 0.0;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::game_object::components::collider::Collider;
+    use crate::engine::scene::game_object::{GameObject, Position};
+    use crate::engine::scene::tiles::SolidTiles;
+    use winit::keyboard::KeyCode;
+
+    fn wall(x: i32, y: i32, size: (u32, u32)) -> GameObject {
+        GameObject::new(
+            vec![Box::new(Collider::new(size, (0, 0)))],
+            None,
+            Position::new(x, y),
+        )
+    }
+
+    fn player(controller: CharacterController) -> Vec<Box<dyn Component + Send + Sync>> {
+        vec![
+            Box::new(controller),
+            Box::new(Collider::new((10, 10), (0, 0))),
+        ]
+    }
+
+    /// Runs updates of 100 ms with the given keys held down.
+    fn drive(scene: &mut Scene, input: &InputState, keys: &[KeyCode], updates: usize) {
+        for key in keys {
+            input.set_key(*key, true);
+        }
+        for _ in 0..updates {
+            input.next_frame();
+            assert!(drive_main_object(scene, input, Duration::from_millis(100)));
+        }
+        input.release_all();
+        input.next_frame();
+    }
+
+    fn main_position(scene: &Scene) -> (i32, i32) {
+        (scene.main_object.position.x, scene.main_object.position.y)
+    }
+
+    fn controller(scene: &Scene) -> CharacterController {
+        *scene.main_object.controller().unwrap()
+    }
+
+    #[test]
+    fn test_moves_by_input_at_its_speed() {
+        let mut scene = Scene::new(
+            vec![],
+            player(CharacterController::new(50.0)),
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let input = InputState::new();
+        drive(&mut scene, &input, &[KeyCode::KeyD], 4);
+        assert_eq!(main_position(&scene), (20, 0));
+        // Diagonals are not faster
+        drive(&mut scene, &input, &[KeyCode::KeyA, KeyCode::KeyW], 10);
+        assert_eq!(main_position(&scene), (-15, 35));
+        assert!(!controller(&scene).is_grounded());
+    }
+
+    #[test]
+    fn test_slides_along_walls() {
+        let mut scene = Scene::new(
+            vec![wall(20, 50, (10, 100))],
+            player(CharacterController::new(100.0)),
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let input = InputState::new();
+        drive(&mut scene, &input, &[KeyCode::KeyD, KeyCode::KeyW], 4);
+        let (x, y) = main_position(&scene);
+        // Stopped by the wall on the right, still moving up
+        assert_eq!(x, 10);
+        assert!(y > 20);
+        assert_eq!(
+            controller(&scene).blocked(),
+            Blocked {
+                right: true,
+                ..Blocked::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_ignores_triggers_and_attached_objects() {
+        let trigger = GameObject::new(
+            vec![Box::new(Collider::new((10, 10), (0, 0)).into_trigger())],
+            None,
+            Position::new(15, 0),
+        );
+        let mut scene = Scene::new(
+            vec![trigger, wall(12, 0, (4, 10))],
+            player(CharacterController::new(100.0)),
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uids = scene.uids();
+        scene.set_parent(uids[1], Some(MAIN_OBJECT_UID)).unwrap();
+        let input = InputState::new();
+        drive(&mut scene, &input, &[KeyCode::KeyD], 3);
+        assert_eq!(main_position(&scene), (30, 0));
+        assert_eq!(controller(&scene).blocked(), Blocked::default());
+    }
+
+    #[test]
+    fn test_falls_lands_and_jumps() {
+        let tiles = SolidTiles::from_rows((-50, -20), 10, &["##########"]);
+        let mut scene = Scene::new(
+            vec![],
+            player(CharacterController::new(50.0).with_gravity(200.0, 150.0)),
+            Position::new(0, 20),
+        )
+        .unwrap()
+        .with_solid_tiles(tiles);
+        let input = InputState::new();
+        drive(&mut scene, &input, &[], 20);
+        // Standing on the floor, whose top is at y = -20
+        assert_eq!(main_position(&scene), (0, -10));
+        assert!(controller(&scene).is_grounded());
+        assert!(controller(&scene).blocked().down);
+        assert_eq!(controller(&scene).vertical_speed(), 0.0);
+
+        // Up does not fly but jumps
+        drive(&mut scene, &input, &[KeyCode::KeyW], 1);
+        assert!(main_position(&scene).1 > -10);
+        assert!(!controller(&scene).is_grounded());
+        drive(&mut scene, &input, &[], 20);
+        assert_eq!(main_position(&scene), (0, -10));
+        assert!(controller(&scene).is_grounded());
+    }
+
+    #[test]
+    fn test_without_controller_engine_moves_main_object() {
+        let mut scene = Scene::new(vec![], vec![], Position::new(0, 0)).unwrap();
+        assert!(!drive_main_object(
+            &mut scene,
+            &InputState::new(),
+            Duration::from_millis(10)
+        ));
+        assert_eq!(
+            CharacterController::default().get_component_type(),
+            ComponentType::Controller
+        );
+    }
+}
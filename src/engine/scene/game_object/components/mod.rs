@@ -4,13 +4,19 @@
 //! that can be attached to game entities. It also defines the component types
 //! and error types related to component handling.
 
+use crate::render::renderer::ShadowSettings;
 use image::DynamicImage;
+use sprite::Layer;
 use std::any::Any;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
 
+pub mod audio_source;
+pub mod collider;
+pub mod controller;
 pub mod script;
 pub mod sprite;
-mod velocity;
+pub mod velocity;
 
 /// Errors that can occur when handling components.
 pub enum ComponentError {
@@ -24,12 +30,59 @@ pub enum ComponentError {
     InvalidIndex(String),
 }
 
+impl fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentError::Exist(component) => write!(
+                f,
+                "{} component already exists",
+                component.get_component_type()
+            ),
+            ComponentError::CannotApply(msg) => write!(f, "cannot apply to component: {msg}"),
+            ComponentError::UnknownError(msg) => write!(f, "unknown component error: {msg}"),
+            ComponentError::InvalidIndex(msg) => write!(f, "invalid component index: {msg}"),
+        }
+    }
+}
+
+impl Debug for ComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentError::Exist(component) => f
+                .debug_tuple("Exist")
+                .field(&component.get_component_type())
+                .finish(),
+            ComponentError::CannotApply(msg) => f.debug_tuple("CannotApply").field(msg).finish(),
+            ComponentError::UnknownError(msg) => f.debug_tuple("UnknownError").field(msg).finish(),
+            ComponentError::InvalidIndex(msg) => f.debug_tuple("InvalidIndex").field(msg).finish(),
+        }
+    }
+}
+
+impl std::error::Error for ComponentError {}
+
 /// Enum identifying types of components supported.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum ComponentType {
     Sprite,
     Velocity,
     Action,
+    Collider,
+    Controller,
+    AudioSource,
+}
+
+impl fmt::Display for ComponentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentType::Sprite => write!(f, "sprite"),
+            ComponentType::Velocity => write!(f, "velocity"),
+            ComponentType::Action => write!(f, "action"),
+            ComponentType::Collider => write!(f, "collider"),
+            ComponentType::Controller => write!(f, "controller"),
+            ComponentType::AudioSource => write!(f, "audio source"),
+        }
+    }
 }
 
 /// Trait that defines behavior of any game component.
@@ -43,14 +96,17 @@ pub trait Component: Any {
     /// Returns the component as a dynamic Any reference, allowing downcasting.
     fn as_any(&self) -> &dyn Any;
 
+    /// Returns the component as a mutable dynamic Any reference, allowing downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
     /// Returns the component's type identifier.
     fn get_component_type(&self) -> ComponentType;
 
     /// Returns the sprite image if available.
     ///
     /// Default returns None; override in Sprite component implementations.
-    fn get_sprite_unchecked(&self) -> &Option<DynamicImage> {
-        &None
+    fn get_sprite_unchecked(&self) -> Option<&Arc<DynamicImage>> {
+        None
     }
 
     /// Indicates whether the component casts shadows.
@@ -60,10 +116,41 @@ pub trait Component: Any {
         true
     }
 
+    /// Returns the shadow settings overriding the scene ones, if any.
+    ///
+    /// Default returns None; override if component supports own shadow settings.
+    fn get_shadow_settings_unchecked(&self) -> Option<ShadowSettings> {
+        None
+    }
+
     /// Returns the sprite offset if applicable.
     ///
     /// Default returns None; override if component supports sprite offsetting.
     fn get_sprite_offset_unchecked(&self) -> Option<(i32, i32)> {
         None
     }
+
+    /// Returns the render layer of the component.
+    ///
+    /// Default returns `Layer::World`; override in Sprite component implementations.
+    fn get_layer_unchecked(&self) -> Layer {
+        Layer::World
+    }
+
+    /// Returns the normal map used to light the sprite image, if any.
+    ///
+    /// Default returns None; override in Sprite component implementations.
+    fn get_normal_map_unchecked(&self) -> &Option<Arc<DynamicImage>> {
+        &None
+    }
+
+    /// Replaces a placeholder image by the image loaded for it, once loading finished.
+    ///
+    /// Default returns false; override in components loading images in the background.
+    ///
+    /// # Returns
+    /// True if the shown image changed.
+    fn resolve_pending_unchecked(&mut self) -> bool {
+        false
+    }
 }
@@ -3,8 +3,79 @@
 //! Defines contract for game logic scripts that operate on game objects.
 //! Each script can apply an action to modify a given `GameObject`.
 //! Also requires an associated constructor method with initialization state.
+//!
+//! Every action receives a `ScriptContext` with the time step of the update, the input
+//! state and the services of the scene, such as spawning new objects and the event bus,
+//! so scripts can react to key presses and move at a steady speed.
 
+use crate::engine::bus::EventBus;
+use crate::engine::input::InputState;
 use crate::engine::scene::game_object::GameObject;
+use crate::render::camera::Camera;
+use std::time::Duration;
+
+/// Everything a script can use while its action runs.
+pub struct ScriptContext<'a> {
+    /// Game time advanced by the current update.
+    pub dt: Duration,
+    /// Keyboard and mouse state of the game window.
+    pub input: &'a InputState,
+    /// Camera of the scene at the start of the update.
+    pub camera: Camera,
+    /// Event bus of the engine, to talk to other scripts and systems.
+    pub events: EventBus,
+    /// Objects spawned by scripts during the update.
+    spawned: Vec<GameObject>,
+    /// Whether the running script asked to remove its object.
+    despawn: bool,
+}
+
+impl<'a> ScriptContext<'a> {
+    /// Creates a context for one update.
+    ///
+    /// # Parameters
+    /// - `dt`: Game time advanced by the update.
+    /// - `input`: Input state the scripts read.
+    /// - `camera`: Camera of the scene.
+    /// - `events`: Event bus the scripts publish to.
+    pub fn new(dt: Duration, input: &'a InputState, camera: Camera, events: EventBus) -> Self {
+        ScriptContext {
+            dt,
+            input,
+            camera,
+            events,
+            spawned: Vec::new(),
+            despawn: false,
+        }
+    }
+
+    /// Adds an object, e.g. a bullet, to the scene once all scripts of the update ran.
+    pub fn spawn(&mut self, game_object: GameObject) {
+        self.spawned.push(game_object);
+    }
+
+    /// Removes the object running the script from the scene once its action returns.
+    ///
+    /// Has no effect on the main object.
+    pub fn despawn(&mut self) {
+        self.despawn = true;
+    }
+
+    /// Returns the objects spawned so far.
+    pub fn spawned(&self) -> &[GameObject] {
+        &self.spawned
+    }
+
+    /// Removes and returns the objects spawned so far.
+    pub(crate) fn take_spawned(&mut self) -> Vec<GameObject> {
+        std::mem::take(&mut self.spawned)
+    }
+
+    /// Checks whether the last action asked to remove its object, and resets the request.
+    pub(crate) fn take_despawn(&mut self) -> bool {
+        std::mem::take(&mut self.despawn)
+    }
+}
 
 /// Trait representing a script that can be attached to a game object.
 ///
@@ -18,7 +89,8 @@ pub trait Script {
     /// Apply the script's action on the given mutable game object reference.
     ///
     /// Allows modifying object state, trigger events, or update components.
-    fn action(&mut self, game_object: &mut GameObject);
+    /// The context gives access to the time step, the input and the scene.
+    fn action(&mut self, game_object: &mut GameObject, context: &mut ScriptContext);
 
     /// Construct a new instance of the script.
     ///
@@ -28,3 +100,38 @@ pub trait Script {
     where
         Self: Sized;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::game_object::{Object, Position};
+
+    #[test]
+    fn test_context_collects_spawns_and_despawn_requests() {
+        let input = InputState::new();
+        let mut context = ScriptContext::new(
+            Duration::from_millis(10),
+            &input,
+            Camera::default(),
+            EventBus::new(),
+        );
+        assert!(!context.take_despawn());
+
+        context.despawn();
+        context.spawn(GameObject::new(
+            vec![],
+            None,
+            Position {
+                x: 3,
+                y: 4,
+                z: 0,
+                is_relative: false,
+            },
+        ));
+        assert_eq!(context.spawned().len(), 1);
+        assert!(context.take_despawn());
+        assert!(!context.take_despawn());
+        assert_eq!(context.take_spawned()[0].position.x, 3);
+        assert!(context.spawned().is_empty());
+    }
+}
@@ -4,18 +4,54 @@
 //! It implements the core `Component` trait, providing access to its type and data.
 //!
 //! This allows game entities to display visual representations with optional shadow
-//! and positional offset adjustments.
+//! and positional offset adjustments. Every sprite belongs to a render [`Layer`],
+//! which decides its drawing order before the z coordinate is taken into account.
+//! A sprite can also carry a normal map, used to light its pixels.
+//! Images are shared: sprites showing the same image, e.g. instances of one prefab or
+//! images from the asset cache, point to the same pixel data instead of copying it.
+//! A sprite whose image is still loading in the background shows a placeholder until
+//! the image is ready.
 
 use std::any::Any;
+use std::sync::Arc;
 
+use crate::engine::assets::{Handle, placeholder_image};
 use crate::engine::scene::game_object::components::{Component, ComponentType};
+use crate::render::renderer::ShadowSettings;
 use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// Render layer a sprite is drawn on.
+///
+/// Layers are drawn in declaration order, from `Background` to `UI`;
+/// sprites within one layer are sorted by the z coordinate of their object.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Layer {
+    /// Ground, floor tiles and other scenery below everything else.
+    Background,
+    /// Regular game objects.
+    #[default]
+    World,
+    /// Objects covering the world, such as treetops or clouds.
+    Foreground,
+    /// Interface elements drawn above the whole scene.
+    UI,
+}
 
 /// A component representing a 2D sprite with image, shadow, and offset.
 pub struct Sprite {
-    pub image: Option<DynamicImage>,
+    pub image: Option<Arc<DynamicImage>>,
     pub shadow: bool,
     pub offset: (i32, i32),
+    pub layer: Layer,
+    /// Optional normal map of the same size as the image, encoding a surface normal per pixel.
+    pub normal_map: Option<Arc<DynamicImage>>,
+    /// Shadow settings of this sprite; None uses the settings of the scene.
+    pub shadow_settings: Option<ShadowSettings>,
+    /// Image still loading in the background; `image` holds a placeholder meanwhile.
+    pending: Option<Handle<Arc<DynamicImage>>>,
 }
 
 impl Sprite {
@@ -27,14 +63,86 @@ impl Sprite {
     /// - `offset`: Positional offset for rendering.
     ///
     /// # Returns
-    /// A new `Sprite` instance configured with the provided data.
+    /// A new `Sprite` instance configured with the provided data, placed on [`Layer::World`].
     pub fn new(image: Option<DynamicImage>, shadow: bool, offset: (i32, i32)) -> Self {
         Sprite {
-            image,
+            image: image.map(Arc::new),
             shadow,
             offset,
+            layer: Layer::default(),
+            normal_map: None,
+            shadow_settings: None,
+            pending: None,
         }
     }
+
+    /// Same as [`Sprite::new`] for an image shared with other sprites, without copying it.
+    pub fn shared(image: Arc<DynamicImage>, shadow: bool, offset: (i32, i32)) -> Self {
+        let mut sprite = Sprite::new(None, shadow, offset);
+        sprite.image = Some(image);
+        sprite
+    }
+
+    /// Creates a sprite showing an image that is loaded in the background.
+    ///
+    /// Until the image is ready, the sprite shows a placeholder; if loading fails, the
+    /// placeholder stays.
+    ///
+    /// # Parameters
+    /// - `image`: Handle of the image being loaded, e.g. by `AssetManager::load_image_async`.
+    /// - `shadow`: Whether the sprite casts shadows.
+    /// - `offset`: Positional offset for rendering.
+    pub fn loading(image: Handle<Arc<DynamicImage>>, shadow: bool, offset: (i32, i32)) -> Self {
+        let mut sprite = Sprite::shared(placeholder_image(), shadow, offset);
+        sprite.pending = Some(image);
+        sprite.resolve_pending_unchecked();
+        sprite
+    }
+
+    /// Checks whether the image of the sprite is still loading.
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Moves the sprite to another render layer.
+    ///
+    /// # Parameters
+    /// - `layer`: Layer the sprite is drawn on.
+    ///
+    /// # Returns
+    /// The same sprite placed on the given layer.
+    pub fn with_layer(mut self, layer: Layer) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Overrides the shadow settings of the scene for this sprite.
+    ///
+    /// Only has an effect if the sprite casts shadows.
+    ///
+    /// # Parameters
+    /// - `shadow_settings`: Offset and opacity of the shadow.
+    ///
+    /// # Returns
+    /// The same sprite casting the given shadow.
+    pub fn with_shadow_settings(mut self, shadow_settings: ShadowSettings) -> Self {
+        self.shadow_settings = Some(shadow_settings);
+        self
+    }
+
+    /// Attaches a normal map used to light the sprite.
+    ///
+    /// The normal map must have the same dimensions as the image, otherwise it is ignored.
+    ///
+    /// # Parameters
+    /// - `normal_map`: Image encoding surface normals in its RGB channels.
+    ///
+    /// # Returns
+    /// The same sprite lit with the given normal map.
+    pub fn with_normal_map(mut self, normal_map: DynamicImage) -> Self {
+        self.normal_map = Some(Arc::new(normal_map));
+        self
+    }
 }
 
 impl Component for Sprite {
@@ -42,21 +150,60 @@ impl Component for Sprite {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn get_component_type(&self) -> ComponentType {
         ComponentType::Sprite
     }
 
-    fn get_sprite_unchecked(&self) -> &Option<DynamicImage> {
-        &self.image
+    fn get_sprite_unchecked(&self) -> Option<&Arc<DynamicImage>> {
+        self.image.as_ref()
     }
 
     fn get_shadow_unchecked(&self) -> bool {
         self.shadow
     }
 
+    fn get_shadow_settings_unchecked(&self) -> Option<ShadowSettings> {
+        self.shadow_settings
+    }
+
     fn get_sprite_offset_unchecked(&self) -> Option<(i32, i32)> {
         Some(self.offset)
     }
+
+    fn get_layer_unchecked(&self) -> Layer {
+        self.layer
+    }
+
+    fn resolve_pending_unchecked(&mut self) -> bool {
+        let Some(handle) = &self.pending else {
+            return false;
+        };
+        if handle.is_loading() {
+            return false;
+        }
+        let loaded = handle.get();
+        if loaded.is_none()
+            && let Some(e) = handle.error()
+        {
+            log::error!("Couldn't load sprite image: {e}");
+        }
+        self.pending = None;
+        match loaded {
+            Some(image) => {
+                self.image = Some(image);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn get_normal_map_unchecked(&self) -> &Option<Arc<DynamicImage>> {
+        &self.normal_map
+    }
 }
 
 #[cfg(test)]
@@ -85,6 +232,74 @@ mod tests {
             assert!(sprite.image.is_some());
         }
 
+        #[test]
+        fn test_new_sprite_on_world_layer() {
+            let sprite = Sprite::new(None, false, (0, 0));
+            assert_eq!(sprite.layer, Layer::World);
+        }
+
+        #[test]
+        fn test_with_layer() {
+            let sprite = Sprite::new(None, false, (0, 0)).with_layer(Layer::UI);
+            assert_eq!(sprite.layer, Layer::UI);
+            assert_eq!(sprite.get_layer_unchecked(), Layer::UI);
+        }
+
+        #[test]
+        fn test_with_shadow_settings() {
+            let sprite = Sprite::new(None, true, (0, 0));
+            assert!(sprite.get_shadow_settings_unchecked().is_none());
+            let settings = ShadowSettings::new((2, 2), 10);
+            let sprite = sprite.with_shadow_settings(settings);
+            assert_eq!(sprite.get_shadow_settings_unchecked(), Some(settings));
+        }
+
+        #[test]
+        fn test_with_normal_map() {
+            let sprite = Sprite::new(Some(create_test_image(4, 4)), false, (0, 0));
+            assert!(sprite.get_normal_map_unchecked().is_none());
+            let sprite = sprite.with_normal_map(create_test_image(4, 4));
+            assert!(sprite.get_normal_map_unchecked().is_some());
+        }
+
+        #[test]
+        fn test_loading_sprite_shows_placeholder_until_ready() {
+            let ready = Sprite::loading(
+                Handle::ready(Arc::new(create_test_image(3, 3))),
+                false,
+                (0, 0),
+            );
+            assert!(!ready.is_loading());
+            assert_eq!(ready.image.as_ref().map(|img| img.width()), Some(3));
+
+            let assets = crate::engine::assets::AssetManager::new();
+            let handle = assets.load_image_async(std::path::Path::new("resources/missing.png"));
+            let mut sprite = Sprite::loading(handle.clone(), false, (0, 0));
+            assert!(handle.wait().is_err());
+            assert!(!sprite.resolve_pending_unchecked());
+            assert!(!sprite.is_loading());
+            assert_eq!(sprite.image, Some(placeholder_image()));
+        }
+
+        #[test]
+        fn test_shared_sprites_do_not_copy_pixels() {
+            let image = Arc::new(create_test_image(8, 8));
+            let first = Sprite::shared(image.clone(), false, (0, 0));
+            let second = Sprite::shared(image.clone(), true, (1, 1));
+            assert_eq!(Arc::strong_count(&image), 3);
+            assert!(Arc::ptr_eq(
+                first.get_sprite_unchecked().unwrap(),
+                second.get_sprite_unchecked().unwrap()
+            ));
+        }
+
+        #[test]
+        fn test_layer_order() {
+            assert!(Layer::Background < Layer::World);
+            assert!(Layer::World < Layer::Foreground);
+            assert!(Layer::Foreground < Layer::UI);
+        }
+
         #[test]
         fn test_sprite_correct_dimensions() {
             let image = create_test_image(200, 150);
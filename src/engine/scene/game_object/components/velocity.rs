@@ -1,40 +1,70 @@
 //! Represents a velocity component attached to a game object.
 //!
-//! The `Velocity` struct holds positional delta values typically used
-//! to update an entity's movement or position each frame.
+//! The `Velocity` struct holds the speed of an object in world units per second and an
+//! optional constant acceleration. It does nothing on its own: the `PhysicsSystem`
+//! integrates it into the object position every update.
 //! It implements the `Component` trait to integrate with the component system.
 
 use super::*;
 use std::any::Any;
 
-/// Component storing velocity in x and y directions.
+/// Component storing velocity and acceleration in x and y directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Velocity {
-    _x: usize,
-    _y: usize,
-    _component_type: ComponentType,
+    /// Horizontal speed in world units per second, positive to the right.
+    pub x: f32,
+    /// Vertical speed in world units per second, positive upwards.
+    pub y: f32,
+    /// Change of the velocity per second, in addition to the gravity of the physics system.
+    pub acceleration: (f32, f32),
+    /// Movement not applied to the integer position yet.
+    pub(crate) remainder: (f32, f32),
+}
+
+impl Default for Velocity {
+    fn default() -> Self {
+        Velocity::new(0.0, 0.0)
+    }
 }
 
 impl Velocity {
-    /// Constructs a new `Velocity` component with zero initial velocity.
+    /// Constructs a new `Velocity` component without acceleration.
     ///
-    /// # Returns
-    /// A `Velocity` instance with both `_x` and `_y` set to zero.
-    pub fn _new() -> Self {
+    /// # Parameters
+    /// - `x`: Horizontal speed in world units per second.
+    /// - `y`: Vertical speed in world units per second.
+    pub fn new(x: f32, y: f32) -> Self {
         Velocity {
-            _x: 0,
-            _y: 0,
-            _component_type: ComponentType::Velocity,
+            x,
+            y,
+            acceleration: (0.0, 0.0),
+            remainder: (0.0, 0.0),
         }
     }
 
+    /// Sets a constant acceleration, e.g. the thrust of a ship.
+    ///
+    /// # Parameters
+    /// - `x`: Horizontal acceleration in world units per second squared.
+    /// - `y`: Vertical acceleration in world units per second squared.
+    pub fn with_acceleration(mut self, x: f32, y: f32) -> Self {
+        self.acceleration = (x, y);
+        self
+    }
+
     /// Updates the velocity components to new values.
     ///
     /// # Parameters
     /// - `x`: New horizontal velocity.
     /// - `y`: New vertical velocity.
-    pub fn _update(&mut self, x: usize, y: usize) {
-        self._x = x;
-        self._y = y;
+    pub fn update(&mut self, x: f32, y: f32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Returns the length of the velocity vector.
+    pub fn speed(&self) -> f32 {
+        self.x.hypot(self.y)
     }
 }
 
@@ -44,6 +74,11 @@ impl Component for Velocity {
         self
     }
 
+    /// Returns a mutable reference to this component as a dynamic Any for downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     /// Returns the component type identifier as `ComponentType::Velocity`.
     fn get_component_type(&self) -> ComponentType {
         ComponentType::Velocity
@@ -59,15 +94,23 @@ mod tests {
 
         #[test]
         fn test_new_velocity_values() {
-            let velocity = Velocity::_new();
-            assert_eq!(velocity._x, 0);
-            assert_eq!(velocity._y, 0);
+            let velocity = Velocity::new(3.0, -4.0);
+            assert_eq!(velocity.x, 3.0);
+            assert_eq!(velocity.y, -4.0);
+            assert_eq!(velocity.acceleration, (0.0, 0.0));
+            assert_eq!(velocity.speed(), 5.0);
+        }
+
+        #[test]
+        fn test_default_velocity_is_at_rest() {
+            assert_eq!(Velocity::default(), Velocity::new(0.0, 0.0));
         }
 
         #[test]
-        fn test_velocity_component_type() {
-            let velocity = Velocity::_new();
-            assert_eq!(velocity._component_type, ComponentType::Velocity);
+        fn test_with_acceleration() {
+            let velocity = Velocity::new(1.0, 0.0).with_acceleration(0.0, -9.0);
+            assert_eq!(velocity.acceleration, (0.0, -9.0));
+            assert_eq!(velocity.x, 1.0);
         }
     }
 
@@ -76,22 +119,22 @@ mod tests {
 
         #[test]
         fn test_update_changes_values() {
-            let mut velocity = Velocity::_new();
-            velocity._update(100, 200);
+            let mut velocity = Velocity::default();
+            velocity.update(100.0, 200.0);
 
-            assert_eq!(velocity._x, 100);
-            assert_eq!(velocity._y, 200);
+            assert_eq!(velocity.x, 100.0);
+            assert_eq!(velocity.y, 200.0);
         }
 
         #[test]
         fn test_get_component_type() {
-            let velocity = Velocity::_new();
+            let velocity = Velocity::default();
             assert_eq!(velocity.get_component_type(), ComponentType::Velocity);
         }
 
         #[test]
         fn test_as_any_correct_type() {
-            let velocity = Velocity::_new();
+            let velocity = Velocity::default();
             let any = velocity.as_any();
 
             assert!(any.is::<Velocity>());
@@ -100,21 +143,30 @@ mod tests {
 
         #[test]
         fn test_as_any_downcasting() {
-            let velocity = Velocity::_new();
+            let velocity = Velocity::new(1.5, 2.5);
             let any = velocity.as_any();
 
             let downcasted = any.downcast_ref::<Velocity>();
             assert!(downcasted.is_some());
 
             if let Some(v) = downcasted {
-                assert_eq!(v._x, 0);
-                assert_eq!(v._y, 0);
+                assert_eq!(v.x, 1.5);
+                assert_eq!(v.y, 2.5);
+            }
+        }
+
+        #[test]
+        fn test_as_any_mut_changes_component() {
+            let mut velocity = Velocity::default();
+            if let Some(v) = velocity.as_any_mut().downcast_mut::<Velocity>() {
+                v.update(7.0, 8.0);
             }
+            assert_eq!(velocity, Velocity::new(7.0, 8.0));
         }
 
         #[test]
         fn test_velocity_does_not_have_sprite() {
-            let velocity = Velocity::_new();
+            let velocity = Velocity::default();
             let sprite = velocity.get_sprite_unchecked();
             assert!(sprite.is_none());
         }
@@ -0,0 +1,77 @@
+//! Timed visual effects of game objects.
+//!
+//! A `Flash` tints all sprites of an object, e.g. to show it was hit. The engine advances
+//! effects every tick, and the flash removes itself once its duration has passed.
+
+use std::time::Duration;
+
+use crate::render::Pixel;
+
+/// A tint applied to the sprites of an object for a limited time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flash {
+    /// Tint color; its alpha is the strength of the tint, 255 replacing the sprite colors.
+    pub color: Pixel,
+    /// Time left until the flash ends.
+    pub remaining: Duration,
+}
+
+impl Flash {
+    /// Creates a flash lasting for the given duration.
+    pub fn new(color: Pixel, duration: Duration) -> Self {
+        Flash {
+            color,
+            remaining: duration,
+        }
+    }
+
+    /// Advances the flash by the elapsed time.
+    ///
+    /// # Returns
+    /// False once the flash has ended and should be removed.
+    pub fn advance(&mut self, elapsed: Duration) -> bool {
+        self.remaining = self.remaining.saturating_sub(elapsed);
+        !self.remaining.is_zero()
+    }
+}
+
+/// Tints a sprite color towards `tint` by the tint's alpha; the color's alpha is kept.
+pub fn apply_tint(color: [u8; 4], tint: Pixel) -> [u8; 4] {
+    let strength = tint.3 as u32;
+    let mix = |c: u8, t: u8| ((c as u32 * (255 - strength) + t as u32 * strength) / 255) as u8;
+    [
+        mix(color[0], tint.0),
+        mix(color[1], tint.1),
+        mix(color[2], tint.2),
+        color[3],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flash_ends_after_duration() {
+        let mut flash = Flash::new((255, 255, 255, 255), Duration::from_millis(100));
+        assert!(flash.advance(Duration::from_millis(60)));
+        assert_eq!(flash.remaining, Duration::from_millis(40));
+        assert!(!flash.advance(Duration::from_millis(60)));
+    }
+
+    #[test]
+    fn test_apply_tint_strength() {
+        assert_eq!(
+            apply_tint([0, 100, 200, 10], (255, 0, 0, 255)),
+            [255, 0, 0, 10]
+        );
+        assert_eq!(
+            apply_tint([0, 100, 200, 255], (255, 0, 0, 0)),
+            [0, 100, 200, 255]
+        );
+        assert_eq!(
+            apply_tint([0, 0, 0, 255], (255, 255, 255, 51)),
+            [51, 51, 51, 255]
+        );
+    }
+}
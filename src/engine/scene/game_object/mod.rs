@@ -5,17 +5,31 @@
 //! The `GameObject` struct implements this trait as a concrete entity holding
 //! components, an optional script, and its current position.
 //!
+//! Objects can carry string tags, such as "enemy", so gameplay code can find groups of
+//! objects without keeping lists of its own.
+//!
 //! Error enums encapsulate possible failure modes in component handling,
 //! unique identifier issues, position updates, and unknown errors.
 
-use crate::engine::scene::game_object::components::script::Script;
+use crate::engine::scene::game_object::components::audio_source::AudioSource;
+use crate::engine::scene::game_object::components::collider::Collider;
+use crate::engine::scene::game_object::components::controller::CharacterController;
+use crate::engine::scene::game_object::components::script::{Script, ScriptContext};
+use crate::engine::scene::game_object::components::velocity::Velocity;
 use crate::engine::scene::game_object::components::{Component, ComponentError, ComponentType};
+use crate::engine::scene::game_object::effects::Flash;
 pub(crate) use crate::engine::scene::game_object::position::Position;
+use crate::render::Pixel;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::Duration;
 
 pub mod components;
+pub mod effects;
 pub mod position;
 
 /// Errors that can arise at the GameObject level.
+#[derive(Debug)]
 pub enum GameObjectError {
     /// Represents an error originating from a component operation.
     ComponentError(ComponentError),
@@ -23,10 +37,39 @@ pub enum GameObjectError {
     UIDError(String),
     /// Position-related errors.
     PositionError(String),
+    /// A script could not be created, e.g. because no script is registered under its name.
+    ScriptError(String),
     /// Catch-all variant for unknown or unexpected errors.
     UnknownError(String),
 }
 
+impl fmt::Display for GameObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameObjectError::ComponentError(e) => write!(f, "{e}"),
+            GameObjectError::UIDError(msg) => write!(f, "invalid object UID: {msg}"),
+            GameObjectError::PositionError(msg) => write!(f, "invalid position: {msg}"),
+            GameObjectError::ScriptError(msg) => write!(f, "couldn't create script: {msg}"),
+            GameObjectError::UnknownError(msg) => write!(f, "unknown object error: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for GameObjectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GameObjectError::ComponentError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ComponentError> for GameObjectError {
+    fn from(e: ComponentError) -> Self {
+        GameObjectError::ComponentError(e)
+    }
+}
+
 /// Defines an interface for game objects.
 ///
 /// Game objects are entities with components, position, and optional behavior scripts.
@@ -52,7 +95,7 @@ pub trait Object {
 
     fn add_position(&mut self, vec: (i32, i32));
 
-    fn run_action(&self);
+    fn run_action(&mut self, context: &mut ScriptContext);
 }
 
 /// The primary game object structure holding components, optional script, and position.
@@ -61,6 +104,125 @@ pub struct GameObject {
     pub components: Vec<Box<dyn Component + Send + Sync>>,
     pub script: Option<Box<dyn Script + Send + Sync>>,
     pub position: Position,
+    /// Flash currently tinting the object's sprites, if any.
+    flash: Option<Flash>,
+    /// Tags of the object, such as "enemy".
+    tags: BTreeSet<String>,
+}
+
+impl GameObject {
+    /// Adds a tag to the object.
+    ///
+    /// # Parameters
+    /// - `tag`: Name of a group the object belongs to, such as "enemy".
+    ///
+    /// # Returns
+    /// The same object carrying the tag.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Adds a tag to the object; adding a tag it already has does nothing.
+    pub fn add_tag(&mut self, tag: impl Into<String>) {
+        self.tags.insert(tag.into());
+    }
+
+    /// Removes a tag from the object.
+    ///
+    /// # Returns
+    /// True if the object had the tag.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        self.tags.remove(tag)
+    }
+
+    /// Checks whether the object carries the tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    /// Returns the tags of the object in alphabetical order.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    /// Returns the collision box of the object, if it has a `Collider` component.
+    pub fn collider(&self) -> Option<&Collider> {
+        self.components
+            .iter()
+            .find_map(|component| component.as_any().downcast_ref::<Collider>())
+    }
+
+    /// Returns the velocity of the object, if it has a `Velocity` component.
+    pub fn velocity(&self) -> Option<&Velocity> {
+        self.components
+            .iter()
+            .find_map(|component| component.as_any().downcast_ref::<Velocity>())
+    }
+
+    /// Returns the velocity of the object for changing it, e.g. to make it jump.
+    pub fn velocity_mut(&mut self) -> Option<&mut Velocity> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<Velocity>())
+    }
+
+    /// Returns the character controller of the object, if it has one.
+    pub fn controller(&self) -> Option<&CharacterController> {
+        self.components
+            .iter()
+            .find_map(|component| component.as_any().downcast_ref::<CharacterController>())
+    }
+
+    /// Returns the character controller of the object for changing it.
+    pub fn controller_mut(&mut self) -> Option<&mut CharacterController> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<CharacterController>())
+    }
+
+    /// Returns the audio source of the object, if it has one.
+    pub fn audio_source(&self) -> Option<&AudioSource> {
+        self.components
+            .iter()
+            .find_map(|component| component.as_any().downcast_ref::<AudioSource>())
+    }
+
+    /// Returns the audio source of the object for changing it, e.g. to play its sound again.
+    pub fn audio_source_mut(&mut self) -> Option<&mut AudioSource> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<AudioSource>())
+    }
+
+    /// Tints all sprites of the object with `color` for the given duration.
+    ///
+    /// The tint is removed automatically by the engine update loop; a new flash
+    /// replaces a running one.
+    ///
+    /// # Parameters
+    /// - `color`: Tint color, its alpha being the strength of the tint.
+    /// - `duration`: How long the sprites stay tinted.
+    pub fn flash(&mut self, color: Pixel, duration: Duration) {
+        self.flash = Some(Flash::new(color, duration)).filter(|_| !duration.is_zero());
+    }
+
+    /// Returns the tint currently applied to the object's sprites, if any.
+    pub fn tint(&self) -> Option<Pixel> {
+        self.flash.map(|flash| flash.color)
+    }
+
+    /// Advances the object's timed effects, removing the ones that ended.
+    ///
+    /// # Parameters
+    /// - `elapsed`: Time since the previous update.
+    pub fn update_effects(&mut self, elapsed: Duration) {
+        if let Some(flash) = self.flash.as_mut()
+            && !flash.advance(elapsed)
+        {
+            self.flash = None;
+        }
+    }
 }
 
 impl Object for GameObject {
@@ -80,6 +242,8 @@ impl Object for GameObject {
             components,
             script,
             position,
+            flash: None,
+            tags: BTreeSet::new(),
         }
     }
 
@@ -128,15 +292,24 @@ impl Object for GameObject {
         self.position.y += vec.1;
     }
 
-    /// Runs the associated script action on the game object.
+    /// Runs the associated script action on the game object, if it has a script.
     ///
-    /// Currently a stub; should be implemented to invoke `script.action`.
-    fn run_action(&self) {}
+    /// The script is detached while it runs, so it can borrow the object mutably.
+    /// A script attached by the action replaces the running one.
+    fn run_action(&mut self, context: &mut ScriptContext) {
+        if let Some(mut script) = self.script.take() {
+            script.action(self, context);
+            self.script.get_or_insert(script);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::engine::bus::EventBus;
+    use crate::engine::input::InputState;
     use crate::engine::scene::game_object::components::sprite::Sprite;
+    use crate::render::camera::Camera;
 
     use super::*;
 
@@ -183,6 +356,16 @@ mod tests {
         assert_eq!(game_object.components.len(), initial_count + 1);
     }
 
+    #[test]
+    fn test_collider_found_among_components() {
+        let mut game_object = create_test_game_object();
+        assert!(game_object.collider().is_none());
+
+        let collider = Collider::new((8, 8), (1, -1));
+        game_object.add_component(Box::new(collider)).unwrap();
+        assert_eq!(game_object.collider(), Some(&collider));
+    }
+
     #[test]
     fn test_remove_component_valid_index() {
         let mut game_object = create_test_game_object();
@@ -210,6 +393,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_errors_display_and_source() {
+        use std::error::Error;
+
+        let sprite = Sprite::new(Some(image::DynamicImage::new_rgba8(1, 1)), true, (0, 0));
+        let e = ComponentError::Exist(Box::new(sprite));
+        assert_eq!(e.to_string(), "sprite component already exists");
+        assert_eq!(format!("{e:?}"), "Exist(Sprite)");
+
+        let e: GameObjectError = ComponentError::InvalidIndex("3 of 2".to_string()).into();
+        assert_eq!(e.to_string(), "invalid component index: 3 of 2");
+        assert!(e.source().is_some());
+        let boxed: Box<dyn Error> = Box::new(GameObjectError::UIDError("7".to_string()));
+        assert_eq!(boxed.to_string(), "invalid object UID: 7");
+    }
+
     #[test]
     fn test_get_position() {
         let position = Position {
@@ -273,4 +472,70 @@ mod tests {
 
         assert_eq!(game_object.components.len(), 0);
     }
+
+    /// Script moving its object to the right on every action.
+    struct MoveRight;
+
+    impl Script for MoveRight {
+        fn action(&mut self, game_object: &mut GameObject, _context: &mut ScriptContext) {
+            game_object.add_position((1, 0));
+        }
+
+        fn new(_is_downed: bool) -> Self {
+            MoveRight
+        }
+    }
+
+    #[test]
+    fn test_run_action_runs_script() {
+        let input = InputState::new();
+        let mut context = ScriptContext::new(
+            Duration::from_millis(10),
+            &input,
+            Camera::default(),
+            EventBus::new(),
+        );
+        let mut game_object = create_test_game_object();
+        game_object.run_action(&mut context);
+        assert_eq!(game_object.position.x, 0);
+
+        game_object.script = Some(Box::new(MoveRight::new(false)));
+        game_object.run_action(&mut context);
+        game_object.run_action(&mut context);
+        assert_eq!(game_object.position.x, 2);
+        assert!(game_object.script.is_some());
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut game_object = create_test_game_object()
+            .with_tag("enemy")
+            .with_tag("flying");
+        assert!(game_object.has_tag("enemy"));
+        assert!(!game_object.has_tag("player"));
+
+        game_object.add_tag("boss");
+        assert!(game_object.remove_tag("flying"));
+        assert!(!game_object.remove_tag("flying"));
+        assert_eq!(
+            game_object.tags().collect::<Vec<_>>(),
+            vec!["boss", "enemy"]
+        );
+    }
+
+    #[test]
+    fn test_flash_tints_until_duration_elapsed() {
+        let mut obj = create_test_game_object();
+        assert_eq!(obj.tint(), None);
+
+        obj.flash((255, 0, 0, 200), Duration::from_millis(100));
+        assert_eq!(obj.tint(), Some((255, 0, 0, 200)));
+        obj.update_effects(Duration::from_millis(99));
+        assert_eq!(obj.tint(), Some((255, 0, 0, 200)));
+        obj.update_effects(Duration::from_millis(1));
+        assert_eq!(obj.tint(), None);
+
+        obj.flash((255, 0, 0, 200), Duration::ZERO);
+        assert_eq!(obj.tint(), None);
+    }
 }
@@ -9,6 +9,8 @@
 ///
 /// - `x`, `y`, `z`: Coordinates in the game world's 3D space.
 /// - `is_relative`: Flag indicating if the position is relative (true) or absolute (false).
+///   A relative position is measured from the world position of the object's parent, so the
+///   object moves with its parent; objects without a parent are placed as if absolute.
 #[derive(Debug, Clone, Copy)]
 pub struct Position {
     pub x: i32,
@@ -17,6 +19,24 @@ pub struct Position {
     pub is_relative: bool,
 }
 
+impl Position {
+    /// Creates an absolute position at the given coordinates, on layer `z = 0`.
+    pub fn new(x: i32, y: i32) -> Self {
+        Position {
+            x,
+            y,
+            z: 0,
+            is_relative: false,
+        }
+    }
+
+    /// Moves the position to another layer.
+    pub fn with_z(mut self, z: i32) -> Self {
+        self.z = z;
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,7 +52,14 @@ mod tests {
         assert_eq!(pos.x, 10);
         assert_eq!(pos.y, 20);
         assert_eq!(pos.z, 30);
-        assert_eq!(pos.is_relative, false);
+        assert!(!pos.is_relative);
+    }
+
+    #[test]
+    fn test_new_is_absolute_on_layer_zero() {
+        let pos = Position::new(3, -4);
+        assert_eq!((pos.x, pos.y, pos.z, pos.is_relative), (3, -4, 0, false));
+        assert_eq!(pos.with_z(7).z, 7);
     }
 
     #[test]
@@ -51,6 +78,6 @@ mod tests {
         assert_eq!(pos.x, 5);
         assert_eq!(pos.y, -5);
         assert_eq!(pos.z, 10);
-        assert_eq!(pos.is_relative, false);
+        assert!(!pos.is_relative);
     }
 }
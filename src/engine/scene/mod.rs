@@ -5,17 +5,88 @@
 //! of renderable entities sorted by z-position for rendering purposes.
 //!
 //! This module abstracts the coordination of game objects and prepares sprite data
-//! for the rendering pipeline.
+//! for the rendering pipeline. The same drawing order is used to pick the object
+//! shown at a screen position, e.g. the one under the mouse. Every update, the scene
+//! runs the scripts of its objects, adding the objects they spawn and removing the ones
+//! they despawn. Objects added and removed are announced on the engine's event bus.
+//!
+//! Objects can be looked up by their UID or by their tags, and built from prefabs.
+//! Scenes can also be loaded from scene files, see the `file` module, and be rebuilt
+//! whenever their file changes, so levels can be edited while the game runs.
+//!
+//! Objects can be attached to a parent object, e.g. a turret to a ship. A child with a
+//! relative position is placed relative to its parent and moves along with it.
+//!
+//! Level geometry can be given as a grid of solid tiles, see the `tiles` module, instead of
+//! one collider object per wall tile.
+//!
+//! Rays can be cast through the colliders of the scene, e.g. to check the line of sight of
+//! an enemy or to find what a hitscan weapon hits.
 
+use crate::engine::assets::Handle;
+use crate::engine::audio::{Audio, Sound};
+use crate::engine::bus::{EventBus, GameEvent};
+use crate::engine::input::InputState;
+use crate::engine::scene::file::SceneFile;
 use crate::engine::scene::game_object::Object;
+use crate::engine::scene::game_object::components::collider::{Collider, WorldShape};
+use crate::engine::scene::game_object::components::script::{Script, ScriptContext};
+use crate::engine::scene::game_object::components::sprite::Layer;
 use crate::engine::scene::game_object::components::{Component, ComponentType};
-use crate::engine::scene::game_object::{GameObject, Position};
+use crate::engine::scene::game_object::{GameObject, GameObjectError, Position};
 use crate::engine::scene::object_manager::GameObjectManager;
-use image::DynamicImage;
+use crate::engine::scene::prefab::{Prefab, ScriptConstructor, ScriptRegistry};
+use crate::engine::scene::tiles::SolidTiles;
+use crate::engine::scene::watch::FileWatcher;
+use crate::render::camera::Camera;
+use crate::render::error::RenderError;
+use crate::render::grading::ColorLut;
+use crate::render::renderer::ShadowSettings;
+use image::{DynamicImage, GenericImageView};
+use std::collections::HashSet;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 pub mod game_object;
+pub mod prefab;
+pub mod tiles;
 
+mod file;
 mod object_manager;
+mod watch;
+
+/// UID reported for the main object; the object manager hands out UIDs starting from 1.
+pub const MAIN_OBJECT_UID: usize = 0;
+
+/// Maximum number of objects a scene holds besides its main object.
+pub const MAX_OBJECTS: usize = 256;
+
+/// Type alias for a sprite prepared for rendering:
+/// owner UID, owner object, sprite image, offset from the object's own position (including
+/// the world position of the parent for relative positions), shadow (None if the sprite
+/// casts none), render layer and normal map.
+pub type RenderableSprite<'a> = (
+    usize,
+    &'a GameObject,
+    &'a Arc<DynamicImage>,
+    (i32, i32),
+    Option<ShadowSettings>,
+    Layer,
+    Option<&'a Arc<DynamicImage>>,
+);
+
+/// The first collider met by a ray, see [`Scene::raycast`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// UID of the object hit, [`MAIN_OBJECT_UID`] for the main object.
+    pub uid: usize,
+    /// World point where the ray reaches the collider.
+    pub point: (f32, f32),
+    /// Distance from the ray origin to the hit point.
+    pub distance: f32,
+}
 
 /// Represents the game scene containing game objects and main entity.
 pub struct Scene {
@@ -23,6 +94,32 @@ pub struct Scene {
     manager: GameObjectManager,
     /// The main game object within this scene.
     pub main_object: GameObject,
+    /// Shadow settings of this scene; None uses the engine-wide settings.
+    shadow: Option<ShadowSettings>,
+    /// Color grading applied to frames showing this scene.
+    color_grading: Option<ColorLut>,
+    /// Solid level geometry objects cannot move into.
+    solid_tiles: Option<SolidTiles>,
+    /// Bus announcing spawned and despawned objects; the engine's bus once the scene is active.
+    events: EventBus,
+    /// Sound playback; the engine's once the scene is active.
+    audio: Audio,
+    /// Music track played while the scene is active.
+    music: Option<PathBuf>,
+    /// Sound files preloaded when the scene becomes active.
+    sounds: Vec<PathBuf>,
+    /// UIDs despawned since the last rendered frame.
+    despawned: HashSet<usize>,
+    /// Whether the renderer must redraw everything: one of those UIDs was handed to a new
+    /// object, so the objects cannot be told apart by UID, or a loaded image replaced a
+    /// placeholder without anything moving.
+    needs_redraw: bool,
+    /// Scripts prefabs can refer to by name.
+    scripts: ScriptRegistry,
+    /// Scene file the scene was loaded from.
+    source: Option<PathBuf>,
+    /// Watcher of the scene file, while hot reload is enabled.
+    watcher: Option<FileWatcher>,
 }
 
 impl Scene {
@@ -35,29 +132,651 @@ impl Scene {
     ///
     /// # Returns
     /// A new scene instance managing game objects and main entity.
+    ///
+    /// # Errors
+    /// Fails if there are more objects than a scene can hold.
     pub fn new(
         objects: Vec<GameObject>,
         main_components: Vec<Box<dyn Component + Send + Sync>>,
         main_position: Position,
-    ) -> Self {
-        let mut obj_manager = GameObjectManager::new(256);
+    ) -> Result<Self, GameObjectError> {
+        let mut scene = Scene {
+            manager: GameObjectManager::new(MAX_OBJECTS),
+            main_object: GameObject::new(main_components, None, main_position),
+            shadow: None,
+            color_grading: None,
+            events: EventBus::new(),
+            audio: Audio::new(),
+            music: None,
+            sounds: Vec::new(),
+            despawned: HashSet::new(),
+            needs_redraw: false,
+            scripts: ScriptRegistry::new(),
+            source: None,
+            watcher: None,
+            solid_tiles: None,
+        };
         for obj in objects {
-            obj_manager.add_game_object(obj.components, obj.position)
+            scene.spawn_object(obj)?;
         }
-        Scene {
-            manager: obj_manager,
-            main_object: GameObject::new(main_components, None, main_position),
+        Ok(scene)
+    }
+
+    /// Loads a scene from a scene file, see the `file` module for its format.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the scene file; sprite paths in it are relative to its directory.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or parsed, a sprite cannot be loaded, or an
+    /// object has a script, since no scripts are registered.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Scene::load_with_scripts(path, ScriptRegistry::new())
+    }
+
+    /// Same as [`Scene::load`], looking the scripts of the objects up in a registry.
+    ///
+    /// The scene keeps the registry, so prefabs instantiated in it can use the scripts too.
+    pub fn load_with_scripts(path: &Path, scripts: ScriptRegistry) -> Result<Self, Error> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let (objects, main) = SceneFile::read(path)?.build(dir, &scripts)?;
+        let mut scene =
+            Scene::new(objects, vec![], main.position).map_err(|e| Error::other(e.to_string()))?;
+        scene.main_object = main;
+        scene.scripts = scripts;
+        scene.source = Some(path.to_path_buf());
+        Ok(scene)
+    }
+
+    /// Rebuilds the scene from the file it was loaded from.
+    ///
+    /// All objects are replaced by the ones in the file; the main object is rebuilt too
+    /// but keeps its current position, so the camera does not jump. If the file cannot be
+    /// loaded, the scene is left unchanged.
+    ///
+    /// # Errors
+    /// Fails if the scene was not loaded from a file, or for the reasons [`Scene::load`] fails.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let Some(path) = &self.source else {
+            return Err(Error::other("scene was not loaded from a file"));
+        };
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let (objects, mut main) = SceneFile::read(path)?.build(dir, &self.scripts)?;
+        if objects.len() > MAX_OBJECTS {
+            return Err(Error::other(format!(
+                "scene file has more than {MAX_OBJECTS} objects"
+            )));
+        }
+        for uid in self.uids() {
+            self.despawn(uid);
+        }
+        for obj in objects {
+            self.spawn_object(obj)
+                .map_err(|e| Error::other(e.to_string()))?;
+        }
+        main.position = self.main_object.position;
+        self.main_object = main;
+        Ok(())
+    }
+
+    /// Starts watching the scene file, so the engine reloads the scene whenever the file is
+    /// saved, see [`Scene::reload`]. Meant for iterating on levels during development.
+    ///
+    /// # Errors
+    /// Fails if the scene was not loaded from a file or the file cannot be watched.
+    pub fn watch(&mut self) -> Result<(), Error> {
+        let Some(path) = &self.source else {
+            return Err(Error::other("scene was not loaded from a file"));
+        };
+        self.watcher = Some(FileWatcher::new(path)?);
+        Ok(())
+    }
+
+    /// Reloads the scene if its watched file changed since the last call.
+    pub(crate) fn reload_if_changed(&mut self) {
+        if self
+            .watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.take_changed())
+            && let Err(e) = self.reload()
+        {
+            log::error!("Couldn't reload scene: {e}");
+        }
+    }
+
+    /// Creates an object from components and adds it to the scene under a new UID.
+    ///
+    /// Can be called at any time, e.g. from a system; the object is drawn from the next
+    /// rendered frame on. Publishes [`GameEvent::ObjectSpawned`].
+    ///
+    /// # Parameters
+    /// - `components`: Components of the new object.
+    /// - `position`: Position of the new object.
+    ///
+    /// # Returns
+    /// The UID of the added object.
+    ///
+    /// # Errors
+    /// Fails with [`GameObjectError::UIDError`] if the scene already holds the maximum
+    /// number of objects.
+    pub fn spawn(
+        &mut self,
+        components: Vec<Box<dyn Component + Send + Sync>>,
+        position: Position,
+    ) -> Result<usize, GameObjectError> {
+        let uid = self.manager.add_game_object(components, position)?;
+        Ok(self.spawned(uid))
+    }
+
+    /// Same as [`Scene::spawn`] for an object already built, keeping its script and tags.
+    pub fn spawn_object(&mut self, obj: GameObject) -> Result<usize, GameObjectError> {
+        let uid = self.manager.insert_game_object(obj)?;
+        Ok(self.spawned(uid))
+    }
+
+    /// Makes a script available to prefabs instantiated in this scene.
+    ///
+    /// # Parameters
+    /// - `name`: Name prefabs refer to the script by.
+    /// - `constructor`: Creates the script of every new instance.
+    pub fn register_script(&mut self, name: impl Into<String>, constructor: ScriptConstructor) {
+        self.scripts.register(name, constructor);
+    }
+
+    /// Builds an object from a prefab and adds it to the scene, like [`Scene::spawn`].
+    ///
+    /// # Parameters
+    /// - `prefab`: Template of the object.
+    /// - `position`: Position of the new object.
+    ///
+    /// # Returns
+    /// The UID of the added object.
+    ///
+    /// # Errors
+    /// Fails if the prefab's script is not registered with [`Scene::register_script`], or
+    /// the scene already holds the maximum number of objects.
+    pub fn instantiate(
+        &mut self,
+        prefab: &Prefab,
+        position: Position,
+    ) -> Result<usize, GameObjectError> {
+        let obj = prefab.build(position, &self.scripts)?;
+        self.spawn_object(obj)
+    }
+
+    /// Records that an object was added under the UID and announces it.
+    fn spawned(&mut self, uid: usize) -> usize {
+        self.needs_redraw |= self.despawned.contains(&uid);
+        self.events.publish(GameEvent::ObjectSpawned(uid));
+        uid
+    }
+
+    /// Checks whether the scene changed in a way only a full redraw shows, and resets the
+    /// records, see `needs_redraw`.
+    ///
+    /// The renderer calls it once per frame.
+    pub(crate) fn take_needs_redraw(&mut self) -> bool {
+        self.despawned.clear();
+        std::mem::take(&mut self.needs_redraw)
+    }
+
+    /// Removes an object from the scene; its UID may be reused by objects added later.
+    ///
+    /// Can be called at any time, e.g. from a system; the object is gone from the next
+    /// rendered frame on. Children of the object are detached and stay where they are.
+    /// Publishes [`GameEvent::ObjectDespawned`] if the object existed.
+    ///
+    /// # Returns
+    /// The removed object, or None if the scene has no object with this UID.
+    pub fn despawn(&mut self, uid: usize) -> Option<GameObject> {
+        for child in self.manager.children(uid) {
+            let position = self.world_position(child)?;
+            self.manager.game_objects.get_mut(&child)?.position = position;
+        }
+        let obj = self.manager.remove_game_object(uid)?;
+        self.despawned.insert(uid);
+        self.events.publish(GameEvent::ObjectDespawned(uid));
+        Some(obj)
+    }
+
+    /// Removes all objects but the main object, e.g. when the scene is left for good.
+    ///
+    /// [`GameEvent::ObjectDespawned`] is published for every object, so systems and
+    /// subscribers keeping state per object let go of it.
+    pub fn despawn_all(&mut self) {
+        for uid in self.uids() {
+            self.despawn(uid);
+        }
+    }
+
+    /// Attaches an object to a parent, or detaches it with None.
+    ///
+    /// The parent may be the main object, given by [`MAIN_OBJECT_UID`]. If the child's
+    /// position is relative, it is measured from the parent from now on.
+    ///
+    /// # Errors
+    /// Fails with [`GameObjectError::UIDError`] if one of the objects does not exist or the
+    /// parent is the child itself or one of its descendants.
+    pub fn set_parent(
+        &mut self,
+        child: usize,
+        parent: Option<usize>,
+    ) -> Result<(), GameObjectError> {
+        if !self.manager.game_objects.contains_key(&child) {
+            return Err(GameObjectError::UIDError(format!(
+                "no object with UID {child} to attach"
+            )));
+        }
+        if let Some(parent) = parent {
+            if parent != MAIN_OBJECT_UID && !self.manager.game_objects.contains_key(&parent) {
+                return Err(GameObjectError::UIDError(format!(
+                    "no parent object with UID {parent}"
+                )));
+            }
+            let mut ancestor = Some(parent);
+            while let Some(uid) = ancestor {
+                if uid == child {
+                    return Err(GameObjectError::UIDError(format!(
+                        "object {parent} cannot parent its ancestor {child}"
+                    )));
+                }
+                ancestor = self.manager.parent(uid);
+            }
+        }
+        self.manager.set_parent(child, parent);
+        Ok(())
+    }
+
+    /// Returns the UID of the object's parent, if it has one.
+    pub fn parent(&self, uid: usize) -> Option<usize> {
+        self.manager.parent(uid)
+    }
+
+    /// Computes where an object is in the world, following its relative position up its parents.
+    ///
+    /// # Returns
+    /// The absolute position of the object, keeping its own z; None if there is no object
+    /// with this UID.
+    pub fn world_position(&self, uid: usize) -> Option<Position> {
+        let obj = self.object(uid)?;
+        let origin = self.parent_origin(uid);
+        Some(Position {
+            x: obj.position.x + origin.0,
+            y: obj.position.y + origin.1,
+            z: obj.position.z,
+            is_relative: false,
+        })
+    }
+
+    /// Returns the world position its own position is measured from: the world position of
+    /// the parent for relative positions, the world origin otherwise.
+    fn parent_origin(&self, uid: usize) -> (i32, i32) {
+        let mut origin = (0, 0);
+        let mut current = uid;
+        while let Some(obj) = self.object(current)
+            && obj.position.is_relative
+            && let Some(parent) = self.manager.parent(current)
+        {
+            let parent_position = if parent == MAIN_OBJECT_UID {
+                self.main_object.position
+            } else {
+                match self.object(parent) {
+                    Some(parent_obj) => parent_obj.position,
+                    None => break,
+                }
+            };
+            origin.0 += parent_position.x;
+            origin.1 += parent_position.y;
+            current = parent;
+        }
+        origin
+    }
+
+    /// Returns a handle to the event bus of the scene, e.g. for systems.
+    pub fn events(&self) -> EventBus {
+        self.events.clone()
+    }
+
+    /// Publishes the events of this scene on the given bus from now on.
+    pub(crate) fn set_event_bus(&mut self, events: EventBus) {
+        self.events = events;
+    }
+
+    /// Returns a handle to the sound playback of the scene, e.g. for scripts playing effects.
+    pub fn audio(&self) -> Audio {
+        self.audio.clone()
+    }
+
+    /// Plays the sounds of this scene on the given playback from now on.
+    pub(crate) fn set_audio(&mut self, audio: Audio) {
+        self.audio = audio;
+    }
+
+    /// Returns the UIDs of all objects except the main object, in ascending order.
+    pub fn uids(&self) -> Vec<usize> {
+        let mut uids: Vec<usize> = self.manager.game_objects.keys().copied().collect();
+        uids.sort_unstable();
+        uids
+    }
+
+    /// Returns the UIDs of all objects carrying the tag, in ascending order.
+    ///
+    /// # Parameters
+    /// - `tag`: Tag to look for, such as "enemy".
+    pub fn find_by_tag(&self, tag: &str) -> Vec<usize> {
+        self.manager.find_by_tag(tag)
+    }
+
+    /// Returns the object with the given UID, if any.
+    pub fn object(&self, uid: usize) -> Option<&GameObject> {
+        self.manager.get(uid)
+    }
+
+    /// Returns the object with the given UID for changing it, if any.
+    ///
+    /// Gameplay code uses it to move an object or change its components after it was created.
+    pub fn object_mut(&mut self, uid: usize) -> Option<&mut GameObject> {
+        self.manager.get_mut(uid)
+    }
+
+    /// Returns the colliders of all objects having one, main object included, in ascending
+    /// UID order, with their shapes placed in the world.
+    pub(crate) fn colliders(&self) -> Vec<(usize, &Collider, WorldShape)> {
+        let main = self.main_object.collider().map(|collider| {
+            (
+                MAIN_OBJECT_UID,
+                collider,
+                collider.world_shape(&self.main_object.position),
+            )
+        });
+        let objects = self.uids().into_iter().filter_map(|uid| {
+            let collider = self.object(uid)?.collider()?;
+            Some((
+                uid,
+                collider,
+                collider.world_shape(&self.world_position(uid)?),
+            ))
+        });
+        main.into_iter().chain(objects).collect()
+    }
+
+    /// Casts a ray through the solid colliders of the scene; trigger zones are passed
+    /// through.
+    ///
+    /// A ray starting inside a collider hits it right at its origin, so a ray of length 0
+    /// finds the collider under a point, such as the mouse position in world coordinates.
+    /// To ignore the collider of the object casting the ray, use
+    /// [`raycast_filtered`](Self::raycast_filtered).
+    ///
+    /// # Parameters
+    /// - `origin`: World point the ray starts at.
+    /// - `direction`: Direction of the ray; it does not need to be normalized.
+    /// - `max_dist`: Length of the ray in world units.
+    ///
+    /// # Returns
+    /// The closest hit, of several at the same distance the one with the smallest UID;
+    /// None if the ray hits nothing or the direction is zero.
+    pub fn raycast(
+        &self,
+        origin: (f32, f32),
+        direction: (f32, f32),
+        max_dist: f32,
+    ) -> Option<RaycastHit> {
+        self.raycast_filtered(origin, direction, max_dist, |_, collider| !collider.trigger)
+    }
+
+    /// Casts a ray through the colliders accepted by a filter, see [`raycast`](Self::raycast).
+    ///
+    /// # Parameters
+    /// - `filter`: Called with the UID and collider of every object with a collider;
+    ///   returns whether the ray can hit it.
+    pub fn raycast_filtered(
+        &self,
+        origin: (f32, f32),
+        direction: (f32, f32),
+        max_dist: f32,
+        filter: impl Fn(usize, &Collider) -> bool,
+    ) -> Option<RaycastHit> {
+        let length = direction.0.hypot(direction.1);
+        if length == 0.0 || !length.is_finite() {
+            return None;
+        }
+        let direction = (direction.0 / length, direction.1 / length);
+        let mut closest: Option<(usize, f32)> = None;
+        for (uid, collider, shape) in self.colliders() {
+            if !filter(uid, collider) {
+                continue;
+            }
+            if let Some(distance) = shape.ray_distance(origin, direction)
+                && distance <= max_dist
+                && closest.is_none_or(|(_, best)| distance < best)
+            {
+                closest = Some((uid, distance));
+            }
+        }
+        closest.map(|(uid, distance)| RaycastHit {
+            uid,
+            point: (
+                origin.0 + direction.0 * distance,
+                origin.1 + direction.1 * distance,
+            ),
+            distance,
+        })
+    }
+
+    /// Returns the camera following the main object, as used by the renderer.
+    fn camera(&self) -> Camera {
+        Camera::new((self.main_object.position.x, self.main_object.position.y))
+    }
+
+    /// Attaches a script to the main object, run every update like the scripts of other objects.
+    ///
+    /// # Parameters
+    /// - `script`: Script controlling the main object.
+    ///
+    /// # Returns
+    /// The same scene with the main object running the script.
+    pub fn with_main_script(mut self, script: Box<dyn Script + Send + Sync>) -> Self {
+        self.main_object.script = Some(script);
+        self
+    }
+
+    /// Sets the shadow cast by sprites of this scene that have no shadow settings of their own.
+    ///
+    /// # Parameters
+    /// - `shadow`: Shadow settings overriding the engine-wide ones.
+    ///
+    /// # Returns
+    /// The same scene using the given shadow settings.
+    pub fn with_shadow(mut self, shadow: ShadowSettings) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    /// Sets the color lookup table applied to every frame while this scene is active.
+    ///
+    /// # Parameters
+    /// - `lut`: Lookup table giving the scene its overall mood.
+    ///
+    /// # Returns
+    /// The same scene using the given color grading.
+    pub fn with_color_grading(mut self, lut: ColorLut) -> Self {
+        self.color_grading = Some(lut);
+        self
+    }
+
+    /// Sets the grid of solid tiles the physics system keeps moving objects out of.
+    ///
+    /// # Returns
+    /// The same scene with the given level geometry.
+    pub fn with_solid_tiles(mut self, tiles: SolidTiles) -> Self {
+        self.solid_tiles = Some(tiles);
+        self
+    }
+
+    /// Replaces the grid of solid tiles, e.g. when a wall is destroyed; None removes it.
+    pub fn set_solid_tiles(&mut self, tiles: Option<SolidTiles>) {
+        self.solid_tiles = tiles;
+    }
+
+    /// Returns the grid of solid tiles of this scene, if any.
+    pub fn solid_tiles(&self) -> Option<&SolidTiles> {
+        self.solid_tiles.as_ref()
+    }
+
+    /// Sets the music streamed while this scene is active.
+    ///
+    /// When the scene becomes active, the engine crossfades from the music playing to this
+    /// track, unless it plays already. Scenes without music keep the music playing.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the track, e.g. `"theme.wav"`, relative to the asset root.
+    ///
+    /// # Returns
+    /// The same scene with the given music.
+    pub fn with_music(mut self, path: impl Into<PathBuf>) -> Self {
+        self.music = Some(path.into());
+        self
+    }
+
+    /// Returns the path of the music of this scene, if any.
+    pub fn music(&self) -> Option<&Path> {
+        self.music.as_deref()
+    }
+
+    /// Names the sound files this scene plays, so they are decoded ahead of their first
+    /// playback.
+    ///
+    /// The engine preloads them in the background when the scene becomes active; a sound
+    /// played before it finished loading is decoded right away instead.
+    ///
+    /// # Parameters
+    /// - `paths`: Paths of the sounds, e.g. `"laser.wav"`, relative to the asset root.
+    ///
+    /// # Returns
+    /// The same scene with the given sounds added to its preloaded ones.
+    pub fn with_sounds<P: Into<PathBuf>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.sounds.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns the paths of the sounds preloaded for this scene.
+    pub fn sounds(&self) -> &[PathBuf] {
+        &self.sounds
+    }
+
+    /// Starts decoding the sounds of this scene on worker threads, see
+    /// [`Scene::with_sounds`].
+    ///
+    /// # Returns
+    /// A handle per sound, in the order they were named, e.g. for a loading screen to wait
+    /// on.
+    pub fn preload_sounds(&self) -> Vec<Handle<Sound>> {
+        self.sounds
+            .iter()
+            .map(|path| self.audio.preload(path))
+            .collect()
+    }
+
+    /// Returns the color lookup table of this scene, if any.
+    pub fn color_grading(&self) -> Option<&ColorLut> {
+        self.color_grading.as_ref()
+    }
+
+    /// Runs the script of every object once, then the script of the main object.
+    ///
+    /// Objects run in the order of their UIDs, so updates are reproducible. Objects
+    /// despawned by their script are removed right after it ran, objects spawned by
+    /// scripts are added once all scripts ran, and first run in the next update.
+    ///
+    /// # Parameters
+    /// - `dt`: Game time advanced by the update.
+    /// - `input`: Input state the scripts read.
+    ///
+    /// Objects spawned beyond the maximum number of objects of the scene are dropped and
+    /// logged as errors.
+    pub fn run_scripts(&mut self, dt: Duration, input: &InputState) {
+        let mut context = ScriptContext::new(dt, input, self.camera(), self.events());
+        for uid in self.uids() {
+            if let Some(obj) = self.manager.game_objects.get_mut(&uid) {
+                obj.run_action(&mut context);
+                if context.take_despawn() {
+                    self.despawn(uid);
+                }
+            }
+        }
+        self.main_object.run_action(&mut context);
+        context.take_despawn();
+        for obj in context.take_spawned() {
+            if let Err(e) = self.spawn_object(obj) {
+                log::error!("Couldn't spawn object: {e}");
+            }
+        }
+    }
+
+    /// Advances the timed effects, such as flashes, of all objects in the scene.
+    ///
+    /// # Parameters
+    /// - `elapsed`: Time since the previous update.
+    pub fn update_effects(&mut self, elapsed: Duration) {
+        for obj in self.manager.game_objects.values_mut() {
+            obj.update_effects(elapsed);
+        }
+        self.main_object.update_effects(elapsed);
+    }
+
+    /// Shows the images that finished loading in the background in place of their placeholders.
+    pub(crate) fn resolve_pending_images(&mut self) {
+        let objects = self
+            .manager
+            .game_objects
+            .values_mut()
+            .chain(std::iter::once(&mut self.main_object));
+        for obj in objects {
+            for component in obj.components.iter_mut() {
+                self.needs_redraw |= component.resolve_pending_unchecked();
+            }
         }
     }
 
     /// Initializes and collects all renderable sprite objects in the scene.
     ///
-    /// Returns a vector of tuples containing references to game objects and their
-    /// sprite images, positional offsets, and shadow flags. The returned vector
-    /// is sorted by the `z` value of the game object's position to maintain correct rendering order.
-    pub fn init(&self) -> Vec<(&GameObject, &DynamicImage, (i32, i32), bool)> {
-        let mut renderable_objects: Vec<(&GameObject, &DynamicImage, (i32, i32), bool)> = vec![];
-        for obj in self.manager.game_objects.values() {
+    /// Returns a vector of tuples containing the owning object's UID, references to game objects and their
+    /// sprite images, positional offsets, shadow flags, render layers and normal maps. The returned vector
+    /// is sorted by render layer and then by the `z` value of the game object's position
+    /// to maintain correct rendering order.
+    /// The main object takes part in the same ordering and is reported with [`MAIN_OBJECT_UID`].
+    ///
+    /// Shadows use [`ShadowSettings::default`] unless the scene or sprite overrides them.
+    ///
+    /// # Errors
+    /// Fails if a sprite component of a scene object has no image or no usable offset.
+    /// A main object sprite without an image is skipped, since the main object
+    /// often only carries the camera.
+    pub fn init(&self) -> Result<Vec<RenderableSprite<'_>>, RenderError> {
+        self.init_with_shadow(ShadowSettings::default())
+    }
+
+    /// Same as [`Scene::init`], with the given shadow used when neither the sprite
+    /// nor the scene has its own shadow settings.
+    ///
+    /// # Parameters
+    /// - `default_shadow`: Engine-wide shadow settings.
+    pub fn init_with_shadow(
+        &self,
+        default_shadow: ShadowSettings,
+    ) -> Result<Vec<RenderableSprite<'_>>, RenderError> {
+        let scene_shadow = self.shadow.unwrap_or(default_shadow);
+        let shadow_of = |component: &dyn Component| {
+            component.get_shadow_unchecked().then(|| {
+                component
+                    .get_shadow_settings_unchecked()
+                    .unwrap_or(scene_shadow)
+            })
+        };
+        let mut renderable_objects: Vec<RenderableSprite> = vec![];
+        for (uid, obj) in self.manager.game_objects.iter() {
+            let origin = self.parent_origin(*uid);
             for component in obj.components.iter() {
                 if component.get_component_type() == ComponentType::Sprite {
                     /*match &component.get_shadow_unchecked() {
@@ -73,37 +792,102 @@ impl Scene {
                             ));
                         }
                     };*/
+                    let image = component
+                        .get_sprite_unchecked()
+                        .ok_or(RenderError::MissingSprite { uid: *uid })?;
+                    let offset = component
+                        .get_sprite_offset_unchecked()
+                        .and_then(|offset| {
+                            Some((
+                                offset.0.checked_add(origin.0)?,
+                                offset.1.checked_add(origin.1)?,
+                            ))
+                        })
+                        .filter(|offset| {
+                            obj.position.x.checked_add(offset.0).is_some()
+                                && obj.position.y.checked_add(offset.1).is_some()
+                        })
+                        .ok_or(RenderError::BadOffset { uid: *uid })?;
                     renderable_objects.push((
+                        *uid,
                         obj,
-                        component.get_sprite_unchecked().as_ref().unwrap(),
-                        component.get_sprite_offset_unchecked().unwrap(),
-                        component.get_shadow_unchecked(),
+                        image,
+                        offset,
+                        shadow_of(component.as_ref()),
+                        component.get_layer_unchecked(),
+                        component.get_normal_map_unchecked().as_ref(),
                     ));
                 }
             }
         }
-        renderable_objects.sort_by(|a, b| a.0.position.z.cmp(&b.0.position.z));
-
         for component in self.main_object.components.iter() {
-            if component.get_component_type() == ComponentType::Sprite {
-                if let Some(sprite_img) = component.get_sprite_unchecked().as_ref() {
-                    renderable_objects.push((
-                        &self.main_object,
-                        sprite_img,
-                        component.get_sprite_offset_unchecked().unwrap_or((0, 0)),
-                        component.get_shadow_unchecked(),
-                    ));
-                }
+            if component.get_component_type() == ComponentType::Sprite
+                && let Some(sprite_img) = component.get_sprite_unchecked()
+            {
+                renderable_objects.push((
+                    MAIN_OBJECT_UID,
+                    &self.main_object,
+                    sprite_img,
+                    component.get_sprite_offset_unchecked().unwrap_or((0, 0)),
+                    shadow_of(component.as_ref()),
+                    component.get_layer_unchecked(),
+                    component.get_normal_map_unchecked().as_ref(),
+                ));
             }
         }
 
-        renderable_objects
+        // Layers are drawn in order, within a layer the main object is drawn
+        // above other objects with the same z, ties broken by UID
+        renderable_objects.sort_by_key(|a| (a.5, a.1.position.z, a.0 == MAIN_OBJECT_UID, a.0));
+
+        Ok(renderable_objects)
+    }
+
+    /// Finds the object drawn at a screen position, such as the mouse position.
+    ///
+    /// The position is converted to world coordinates with the camera following the main
+    /// object, as the renderer does. Only non-transparent sprite pixels count, and of
+    /// several objects covering the position the one drawn last, i.e. on top, is picked.
+    ///
+    /// # Parameters
+    /// - `screen_x`: Column of the frame pixel.
+    /// - `screen_y`: Row of the frame pixel.
+    ///
+    /// # Returns
+    /// The UID and the object shown at that position, [`MAIN_OBJECT_UID`] standing for the
+    /// main object; None if only the background is shown there or the scene cannot be rendered.
+    pub fn pick(&self, screen_x: i32, screen_y: i32) -> Option<(usize, &GameObject)> {
+        let (wx, wy) = self.camera().screen_to_world(screen_x, screen_y);
+        let sprites = self.init().ok()?;
+        sprites
+            .into_iter()
+            .rev()
+            .find(|&(_, obj, image, offset, ..)| {
+                // Sprite rows grow downwards from the top-left corner, like the frame rows
+                let sprite_x = wx - (obj.position.x + offset.0);
+                let sprite_y = (obj.position.y + offset.1) - wy;
+                sprite_x >= 0
+                    && sprite_y >= 0
+                    && (sprite_x as u32) < image.width()
+                    && (sprite_y as u32) < image.height()
+                    && image.get_pixel(sprite_x as u32, sprite_y as u32).0[3] != 0
+            })
+            .map(|(uid, obj, ..)| (uid, obj))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::scene::game_object::components::sprite::Sprite;
+
+    fn sprite_components() -> Vec<Box<dyn Component + Send + Sync>> {
+        vec![Box::new(Sprite::new(
+            Some(DynamicImage::new_rgba8(4, 4)),
+            false,
+            (0, 0),
+        ))]
+    }
 
     #[test]
     fn test_new_scene_with_empty_objects_and_main() {
@@ -116,7 +900,8 @@ mod tests {
                 z: 3,
                 is_relative: false,
             },
-        );
+        )
+        .unwrap();
         assert_eq!(scene.manager.game_objects.len(), 0);
         assert_eq!(scene.main_object.components.len(), 0);
         assert_eq!(scene.main_object.position.x, 1);
@@ -156,7 +941,8 @@ mod tests {
                 z: 0,
                 is_relative: false,
             },
-        );
+        )
+        .unwrap();
         assert_eq!(scene.manager.game_objects.len(), 2);
     }
 
@@ -171,7 +957,8 @@ mod tests {
                 z: 2,
                 is_relative: false,
             },
-        );
+        )
+        .unwrap();
         assert_eq!(scene.main_object.components.len(), 0);
     }
 
@@ -196,8 +983,673 @@ mod tests {
                 z: 0,
                 is_relative: false,
             },
-        );
-        let result = scene.init();
+        )
+        .unwrap();
+        let result = scene.init().unwrap();
         assert_eq!(result.len(), 0);
     }
+
+    #[test]
+    fn test_init_sorts_main_object_by_z() {
+        let below = GameObject::new(sprite_components(), None, Position::new(0, 0).with_z(-1));
+        let above = GameObject::new(sprite_components(), None, Position::new(0, 0).with_z(5));
+        let scene = Scene::new(
+            vec![above, below],
+            sprite_components(),
+            Position::new(0, 0).with_z(2),
+        )
+        .unwrap();
+
+        let result = scene.init().unwrap();
+        let order: Vec<i32> = result.iter().map(|r| r.1.position.z).collect();
+        assert_eq!(order, vec![-1, 2, 5]);
+        assert_eq!(result[1].0, MAIN_OBJECT_UID);
+    }
+
+    #[test]
+    fn test_init_draws_main_object_last_on_equal_z() {
+        let obj = GameObject::new(sprite_components(), None, Position::new(0, 0).with_z(3));
+        let scene = Scene::new(
+            vec![obj],
+            sprite_components(),
+            Position::new(0, 0).with_z(3),
+        )
+        .unwrap();
+
+        let result = scene.init().unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1].0, MAIN_OBJECT_UID);
+    }
+
+    #[test]
+    fn test_init_sorts_by_layer_before_z() {
+        let ui = GameObject::new(
+            vec![Box::new(
+                Sprite::new(Some(DynamicImage::new_rgba8(4, 4)), false, (0, 0))
+                    .with_layer(Layer::UI),
+            )],
+            None,
+            Position::new(0, 0).with_z(-10),
+        );
+        let ground = GameObject::new(
+            vec![Box::new(
+                Sprite::new(Some(DynamicImage::new_rgba8(4, 4)), false, (0, 0))
+                    .with_layer(Layer::Background),
+            )],
+            None,
+            Position::new(0, 0).with_z(10),
+        );
+        let scene = Scene::new(vec![ui, ground], sprite_components(), Position::new(0, 0)).unwrap();
+
+        let layers: Vec<Layer> = scene.init().unwrap().iter().map(|r| r.5).collect();
+        assert_eq!(layers, vec![Layer::Background, Layer::World, Layer::UI]);
+    }
+
+    #[test]
+    fn test_init_resolves_shadow_settings() {
+        let custom = ShadowSettings::new((3, 4), 200);
+        let scene_shadow = ShadowSettings::new((-5, 5), 40);
+        let own = GameObject::new(
+            vec![Box::new(
+                Sprite::new(Some(DynamicImage::new_rgba8(4, 4)), true, (0, 0))
+                    .with_shadow_settings(custom),
+            )],
+            None,
+            Position::new(0, 0),
+        );
+        let inherited = GameObject::new(
+            vec![Box::new(Sprite::new(
+                Some(DynamicImage::new_rgba8(4, 4)),
+                true,
+                (0, 0),
+            ))],
+            None,
+            Position::new(0, 0).with_z(1),
+        );
+        let scene = Scene::new(
+            vec![own, inherited],
+            sprite_components(),
+            Position::new(0, 0).with_z(2),
+        )
+        .unwrap();
+
+        let shadows: Vec<_> = scene.init().unwrap().iter().map(|r| r.4).collect();
+        assert_eq!(
+            shadows,
+            vec![Some(custom), Some(ShadowSettings::default()), None]
+        );
+
+        let scene = scene.with_shadow(scene_shadow);
+        let shadows: Vec<_> = scene
+            .init_with_shadow(ShadowSettings::new((1, 1), 1))
+            .unwrap()
+            .iter()
+            .map(|r| r.4)
+            .collect();
+        assert_eq!(shadows, vec![Some(custom), Some(scene_shadow), None]);
+    }
+
+    #[test]
+    fn test_init_reports_missing_image() {
+        let obj = GameObject::new(
+            vec![Box::new(Sprite::new(None, false, (0, 0)))],
+            None,
+            Position::new(0, 0),
+        );
+        let scene = Scene::new(vec![obj], vec![], Position::new(0, 0)).unwrap();
+        assert_eq!(
+            scene.init().err(),
+            Some(RenderError::MissingSprite { uid: 1 })
+        );
+    }
+
+    #[test]
+    fn test_init_reports_overflowing_offset() {
+        let obj = GameObject::new(
+            vec![Box::new(Sprite::new(
+                Some(DynamicImage::new_rgba8(4, 4)),
+                false,
+                (i32::MAX, 0),
+            ))],
+            None,
+            Position {
+                x: 1,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        );
+        let scene = Scene::new(vec![obj], vec![], Position::new(0, 0)).unwrap();
+        assert_eq!(scene.init().err(), Some(RenderError::BadOffset { uid: 1 }));
+    }
+
+    fn opaque_sprite(width: u32, height: u32) -> Vec<Box<dyn Component + Send + Sync>> {
+        let mut image = image::RgbaImage::new(width, height);
+        for pixel in image.pixels_mut() {
+            pixel.0 = [255, 255, 255, 255];
+        }
+        // Transparent top-left pixel
+        image.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+        vec![Box::new(Sprite::new(
+            Some(DynamicImage::ImageRgba8(image)),
+            false,
+            (0, 0),
+        ))]
+    }
+
+    fn object_at(
+        x: i32,
+        y: i32,
+        z: i32,
+        components: Vec<Box<dyn Component + Send + Sync>>,
+    ) -> GameObject {
+        GameObject::new(components, None, Position::new(x, y).with_z(z))
+    }
+
+    #[test]
+    fn test_pick_returns_topmost_object() {
+        let low = object_at(10, 50, 0, opaque_sprite(8, 8));
+        let high = object_at(14, 46, 1, opaque_sprite(8, 8));
+        let scene = Scene::new(vec![high, low], vec![], Position::new(0, 0)).unwrap();
+        let scene = Scene {
+            main_object: object_at(0, 60, 0, vec![]),
+            ..scene
+        };
+
+        // World (12, 48) is shown at screen (12, 12), only covered by the lower object
+        let (_, obj) = scene.pick(12, 12).unwrap();
+        assert_eq!(obj.position.z, 0);
+        // World (15, 45) is covered by both objects
+        let (_, obj) = scene.pick(15, 15).unwrap();
+        assert_eq!(obj.position.z, 1);
+        assert!(scene.pick(0, 0).is_none());
+        assert!(scene.pick(-1, 15).is_none());
+    }
+
+    #[test]
+    fn test_pick_ignores_transparent_pixels() {
+        let below = object_at(-1, 1, 0, opaque_sprite(4, 4));
+        let above = object_at(0, 0, 1, opaque_sprite(4, 4));
+        let scene = Scene::new(
+            vec![below, above],
+            opaque_sprite(4, 4),
+            Position::new(0, 0).with_z(2),
+        )
+        .unwrap();
+
+        // The transparent corners let the objects below show through
+        assert_eq!(scene.pick(0, 0).unwrap().1.position.z, 0);
+        assert_eq!(scene.pick(1, 1).unwrap().0, MAIN_OBJECT_UID);
+        assert!(scene.pick(-1, -1).is_none());
+        assert!(scene.pick(4, 4).is_none());
+    }
+
+    /// Script appending the x coordinate of its object to a shared log and moving it.
+    struct LogX(std::sync::Arc<std::sync::Mutex<Vec<i32>>>);
+
+    impl Script for LogX {
+        fn action(&mut self, game_object: &mut GameObject, context: &mut ScriptContext) {
+            self.0.lock().unwrap().push(game_object.position.x);
+            game_object.add_position((0, context.dt.as_millis() as i32));
+        }
+
+        fn new(_is_downed: bool) -> Self {
+            LogX(Default::default())
+        }
+    }
+
+    #[test]
+    fn test_run_scripts_runs_every_script_once() {
+        let log = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let objects = (1..=3)
+            .map(|x| {
+                let mut obj = object_at(x, 0, 0, vec![]);
+                obj.script = Some(Box::new(LogX(log.clone())));
+                obj
+            })
+            .collect();
+        let mut scene = Scene::new(objects, vec![], Position::new(0, 0))
+            .unwrap()
+            .with_main_script(Box::new(LogX(log.clone())));
+
+        scene.run_scripts(Duration::from_millis(1), &InputState::new());
+        assert_eq!(*log.lock().unwrap(), vec![1, 2, 3, 0]);
+        assert!(
+            scene
+                .manager
+                .game_objects
+                .values()
+                .all(|obj| obj.position.y == 1)
+        );
+        assert_eq!(scene.main_object.position.y, 1);
+    }
+
+    /// Script firing a bullet while space is pressed and despawning bullets at a height of 2.
+    struct Gun;
+
+    impl Script for Gun {
+        fn action(&mut self, game_object: &mut GameObject, context: &mut ScriptContext) {
+            if game_object.position.y >= 2 {
+                context.despawn();
+            } else if context
+                .input
+                .is_key_pressed(winit::keyboard::KeyCode::Space)
+            {
+                let mut bullet = object_at(
+                    game_object.position.x,
+                    game_object.position.y + 1,
+                    0,
+                    vec![],
+                );
+                bullet.script = Some(Box::new(Gun));
+                context.spawn(bullet);
+            }
+        }
+
+        fn new(_is_downed: bool) -> Self {
+            Gun
+        }
+    }
+
+    #[test]
+    fn test_run_scripts_spawns_and_despawns() {
+        let input = InputState::new();
+        let mut scene = Scene::new(vec![], vec![], Position::new(0, 0))
+            .unwrap()
+            .with_main_script(Box::new(Gun));
+        let dt = Duration::from_millis(10);
+
+        scene.run_scripts(dt, &input);
+        assert!(scene.manager.game_objects.is_empty());
+
+        input.set_key(winit::keyboard::KeyCode::Space, true);
+        scene.run_scripts(dt, &input);
+        assert_eq!(scene.manager.game_objects.len(), 1);
+        // The first bullet fires another one higher up, which despawns next update
+        scene.run_scripts(dt, &input);
+        let mut heights: Vec<i32> = scene
+            .manager
+            .game_objects
+            .values()
+            .map(|obj| obj.position.y)
+            .collect();
+        heights.sort_unstable();
+        assert_eq!(heights, vec![1, 1, 2]);
+        scene.main_object.script = None;
+        input.set_key(winit::keyboard::KeyCode::Space, false);
+        scene.run_scripts(dt, &input);
+        assert_eq!(scene.manager.game_objects.len(), 2);
+    }
+
+    #[test]
+    fn test_spawn_and_despawn_objects() {
+        let mut scene = Scene::new(
+            vec![object_at(1, 0, 0, vec![])],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let events = scene.events().subscribe();
+        let uid = scene
+            .spawn(vec![], object_at(2, 0, 0, vec![]).position)
+            .unwrap();
+        assert_eq!(scene.uids(), vec![1, uid]);
+        scene.object_mut(uid).unwrap().position.y = 5;
+        assert_eq!(scene.object(uid).unwrap().position.y, 5);
+
+        assert_eq!(scene.despawn(1).unwrap().position.x, 1);
+        assert!(scene.despawn(1).is_none());
+        assert!(scene.object(1).is_none());
+        assert_eq!(scene.uids(), vec![uid]);
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::ObjectSpawned(uid), GameEvent::ObjectDespawned(1)]
+        );
+    }
+
+    #[test]
+    fn test_object_limit_is_an_error() {
+        let objects = (0..=MAX_OBJECTS as i32)
+            .map(|x| object_at(x, 0, 0, vec![]))
+            .collect();
+        assert!(matches!(
+            Scene::new(objects, vec![], Position::new(0, 0)),
+            Err(GameObjectError::UIDError(_))
+        ));
+
+        let objects = (0..MAX_OBJECTS as i32)
+            .map(|x| object_at(x, 0, 0, vec![]))
+            .collect();
+        let mut scene = Scene::new(objects, vec![], Position::new(0, 0)).unwrap();
+        assert!(scene.spawn(vec![], Position::new(0, 0)).is_err());
+        assert_eq!(scene.uids().len(), MAX_OBJECTS);
+    }
+
+    #[test]
+    fn test_children_move_with_their_parent() {
+        let ship = object_at(10, 20, 0, opaque_sprite(2, 2));
+        let mut turret = object_at(1, -1, 1, opaque_sprite(2, 2));
+        turret.position.is_relative = true;
+        let mut flag = object_at(0, 3, 2, vec![]);
+        flag.position.is_relative = true;
+        let mut scene = Scene::new(vec![ship, turret, flag], vec![], Position::new(0, 0)).unwrap();
+        assert!(scene.set_parent(2, Some(1)).is_ok());
+        assert!(scene.set_parent(3, Some(2)).is_ok());
+        assert_eq!(scene.parent(3), Some(2));
+
+        let flag_position = scene.world_position(3).unwrap();
+        assert_eq!((flag_position.x, flag_position.y), (11, 22));
+        let turret_offset = scene.init().unwrap().iter().find(|r| r.0 == 2).unwrap().3;
+        assert_eq!(turret_offset, (10, 20));
+
+        scene.object_mut(1).unwrap().add_position((5, 0));
+        assert_eq!(scene.world_position(3).unwrap().x, 16);
+        // An absolute child ignores its parent
+        scene.object_mut(3).unwrap().position.is_relative = false;
+        assert_eq!(scene.world_position(3).unwrap().x, 0);
+    }
+
+    #[test]
+    fn test_set_parent_rejects_cycles_and_missing_objects() {
+        let mut objects: Vec<GameObject> = (0..2).map(|_| object_at(0, 0, 0, vec![])).collect();
+        objects[1].position.is_relative = true;
+        let mut scene = Scene::new(objects, vec![], Position::new(0, 0)).unwrap();
+        scene.main_object.position.x = 7;
+        assert!(scene.set_parent(2, Some(1)).is_ok());
+        assert!(scene.set_parent(1, Some(2)).is_err());
+        assert!(scene.set_parent(1, Some(1)).is_err());
+        assert!(scene.set_parent(9, Some(1)).is_err());
+        assert!(scene.set_parent(1, Some(9)).is_err());
+
+        assert!(scene.set_parent(1, Some(MAIN_OBJECT_UID)).is_ok());
+        // The parent of 2 is absolute, so only its own position counts
+        assert_eq!(scene.world_position(2).unwrap().x, 0);
+        scene.object_mut(1).unwrap().position.is_relative = true;
+        assert_eq!(scene.world_position(2).unwrap().x, 7);
+
+        assert!(scene.set_parent(2, None).is_ok());
+        assert_eq!(scene.parent(2), None);
+    }
+
+    #[test]
+    fn test_despawned_parent_leaves_children_in_place() {
+        let mut child = object_at(1, 1, 0, vec![]);
+        child.position.is_relative = true;
+        let mut scene = Scene::new(
+            vec![object_at(4, 4, 0, vec![]), child],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        assert!(scene.set_parent(2, Some(1)).is_ok());
+        scene.despawn(1);
+        let position = scene.world_position(2).unwrap();
+        assert_eq!((position.x, position.y), (5, 5));
+        assert_eq!(scene.parent(2), None);
+    }
+
+    #[test]
+    fn test_scene_keeps_tags_of_objects() {
+        let objects = vec![
+            object_at(0, 0, 0, vec![]).with_tag("enemy"),
+            object_at(0, 0, 0, vec![]),
+        ];
+        let mut scene = Scene::new(objects, vec![], Position::new(0, 0)).unwrap();
+        let spawned = scene
+            .spawn_object(object_at(0, 0, 0, vec![]).with_tag("enemy"))
+            .unwrap();
+        assert_eq!(scene.find_by_tag("enemy"), vec![1, spawned]);
+    }
+
+    #[test]
+    fn test_recycled_uid_is_reported_once() {
+        let mut scene = Scene::new(
+            vec![object_at(0, 0, 0, vec![])],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uid = scene
+            .spawn(sprite_components(), Position::new(0, 0))
+            .unwrap();
+        assert!(!scene.take_needs_redraw());
+
+        scene.despawn(uid);
+        assert_eq!(
+            scene
+                .spawn(sprite_components(), Position::new(0, 0))
+                .unwrap(),
+            uid
+        );
+        assert!(scene.take_needs_redraw());
+        assert!(!scene.take_needs_redraw());
+        assert_eq!(scene.init().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_load_scene_file() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_scene");
+        std::fs::create_dir_all(&dir).unwrap();
+        DynamicImage::new_rgba8(3, 2)
+            .save(dir.join("ship.png"))
+            .unwrap();
+        let path = dir.join("scene.toml");
+        std::fs::write(
+            &path,
+            "[main]\nsprite = \"ship.png\"\nx = 5\ny = 6\nscript = \"gun\"\n\n\
+             [[objects]]\nsprite = \"ship.png\"\nx = 1\ny = 2\ntags = [\"enemy\"]\n",
+        )
+        .unwrap();
+
+        assert!(Scene::load(&path).is_err());
+        let mut scripts = ScriptRegistry::new();
+        scripts.register("gun", Box::new(|| Box::new(Gun)));
+        let scene = Scene::load_with_scripts(&path, scripts).unwrap();
+        assert_eq!(scene.uids(), vec![1]);
+        assert_eq!(scene.find_by_tag("enemy"), vec![1]);
+        assert_eq!(scene.main_object.position.x, 5);
+        assert_eq!(scene.main_object.position.z, 2);
+        assert!(scene.main_object.script.is_some());
+        assert_eq!(scene.init().unwrap().len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reload_keeps_main_object_position() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_reload");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scene.toml");
+        std::fs::write(&path, "[main]\nx = 0\ny = 0\n\n[[objects]]\nx = 1\ny = 1\n").unwrap();
+        let mut scene = Scene::load(&path).unwrap();
+        scene.main_object.add_position((10, 20));
+        let events = scene.events().subscribe();
+
+        std::fs::write(&path, "[main]\nx = 0\ny = 0\ntags = [\"player\"]\n\n[[objects]]\nx = 3\ny = 3\n\n[[objects]]\nx = 4\ny = 4\n").unwrap();
+        scene.reload().unwrap();
+        assert_eq!(scene.uids(), vec![1, 2]);
+        assert_eq!(scene.object(1).map(|obj| obj.position.x), Some(3));
+        assert_eq!(
+            (scene.main_object.position.x, scene.main_object.position.y),
+            (10, 20)
+        );
+        assert!(scene.main_object.has_tag("player"));
+        assert!(scene.take_needs_redraw());
+        assert_eq!(events.drain().len(), 3);
+
+        std::fs::write(&path, "[main]\n").unwrap();
+        assert!(scene.reload().is_err());
+        assert_eq!(scene.uids(), vec![1, 2]);
+        assert!(
+            Scene::new(vec![], vec![], Position::new(0, 0))
+                .unwrap()
+                .reload()
+                .is_err()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_loaded_images_replace_placeholders() {
+        let assets = crate::engine::assets::AssetManager::new();
+        let handle = assets.load_image_async(Path::new("resources/perf_diag.png"));
+        let mut scene = Scene::new(vec![], vec![], Position::new(0, 0)).unwrap();
+        scene
+            .spawn(
+                vec![Box::new(Sprite::loading(handle.clone(), false, (0, 0)))],
+                Position::new(0, 0).with_z(1),
+            )
+            .unwrap();
+        let loaded = handle.wait().unwrap();
+        assert!(!scene.take_needs_redraw());
+
+        scene.resolve_pending_images();
+        assert!(scene.take_needs_redraw());
+        let sprites = scene.init().unwrap();
+        assert_eq!(sprites[0].2.dimensions(), loaded.dimensions());
+    }
+
+    #[test]
+    fn test_instantiate_prefab() {
+        let mut scene = Scene::new(vec![], vec![], Position::new(0, 0)).unwrap();
+        let prefab = Prefab::new()
+            .with_image(DynamicImage::new_rgba8(2, 2))
+            .with_script("gun")
+            .with_tag("turret");
+        assert!(
+            scene
+                .instantiate(&prefab, Position::new(0, 0).with_z(1))
+                .is_err()
+        );
+
+        scene.register_script("gun", Box::new(|| Box::new(Gun)));
+        let uids: Vec<usize> = (0..3)
+            .filter_map(|_| {
+                scene
+                    .instantiate(&prefab, Position::new(0, 0).with_z(1))
+                    .ok()
+            })
+            .collect();
+        assert_eq!(uids, vec![1, 2, 3]);
+        assert_eq!(scene.find_by_tag("turret"), uids);
+        assert_eq!(scene.init().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_init_skips_main_object_without_image() {
+        let main =
+            vec![Box::new(Sprite::new(None, false, (0, 0))) as Box<dyn Component + Send + Sync>];
+        let scene = Scene::new(vec![], main, Position::new(0, 0)).unwrap();
+        assert!(scene.init().unwrap().is_empty());
+    }
+
+    fn with_collider(collider: Collider, x: i32, y: i32) -> GameObject {
+        GameObject::new(
+            vec![Box::new(collider)],
+            None,
+            Position {
+                x,
+                y,
+                z: 0,
+                is_relative: false,
+            },
+        )
+    }
+
+    #[test]
+    fn test_raycast_finds_closest_collider() {
+        let scene = Scene::new(
+            vec![
+                with_collider(Collider::new((4, 4), (0, 0)), 20, 2),
+                with_collider(Collider::circle(2, (0, 0)), 10, 0),
+                with_collider(Collider::new((4, 4), (0, 0)), 10, 20),
+            ],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uids = scene.uids();
+        let hit = scene.raycast((0.0, 0.0), (3.0, 0.0), 100.0).unwrap();
+        assert_eq!(hit.uid, uids[1]);
+        assert_eq!(hit.point, (8.0, 0.0));
+        assert_eq!(hit.distance, 8.0);
+        // Too short to reach anything
+        assert_eq!(scene.raycast((0.0, 0.0), (1.0, 0.0), 7.5), None);
+        assert_eq!(scene.raycast((0.0, 0.0), (0.0, 0.0), 100.0), None);
+        let hit = scene.raycast((12.0, 30.0), (0.0, -1.0), 100.0).unwrap();
+        assert_eq!((hit.uid, hit.point), (uids[2], (12.0, 20.0)));
+    }
+
+    #[test]
+    fn test_raycast_skips_triggers_and_filtered_colliders() {
+        let main =
+            vec![Box::new(Collider::new((2, 2), (-1, 1))) as Box<dyn Component + Send + Sync>];
+        let scene = Scene::new(
+            vec![
+                with_collider(Collider::new((4, 40), (0, 0)).into_trigger(), 5, 20),
+                with_collider(Collider::new((4, 40), (0, 0)), 15, 20),
+            ],
+            main,
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let uids = scene.uids();
+        // The ray starts inside the main object
+        assert_eq!(
+            scene.raycast((0.0, 0.0), (1.0, 0.0), 100.0).unwrap().uid,
+            MAIN_OBJECT_UID
+        );
+        let hit = scene
+            .raycast_filtered((0.0, 0.0), (1.0, 0.0), 100.0, |uid, collider| {
+                uid != MAIN_OBJECT_UID && !collider.trigger
+            })
+            .unwrap();
+        assert_eq!((hit.uid, hit.distance), (uids[1], 15.0));
+        // A ray of length 0 finds the collider under a point
+        assert_eq!(
+            scene
+                .raycast_filtered((6.0, 0.0), (1.0, 0.0), 0.0, |_, _| true)
+                .unwrap()
+                .uid,
+            uids[0]
+        );
+    }
+
+    #[test]
+    fn test_preload_sounds_fills_the_cache() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_scene_sounds");
+        std::fs::create_dir_all(&dir).unwrap();
+        let wav = [
+            b"RIFF".as_slice(),
+            &38u32.to_le_bytes(),
+            b"WAVEfmt ",
+            &16u32.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &8000u32.to_le_bytes(),
+            &8000u32.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &8u16.to_le_bytes(),
+            b"data",
+            &2u32.to_le_bytes(),
+            &[128, 255],
+        ]
+        .concat();
+        std::fs::write(dir.join("step.wav"), &wav).unwrap();
+        std::fs::write(dir.join("jump.wav"), &wav).unwrap();
+
+        let assets = crate::engine::assets::AssetManager::new().with_root(Some(dir.clone()));
+        let mut scene = Scene::new(vec![], vec![], Position::new(0, 0))
+            .unwrap()
+            .with_sounds(["step.wav", "jump.wav"])
+            .with_sounds(["missing.wav"]);
+        scene.set_audio(Audio::new().with_assets(assets.clone()));
+        assert_eq!(scene.sounds().len(), 3);
+
+        let handles = scene.preload_sounds();
+        assert!(handles[0].wait().is_ok());
+        assert!(handles[1].wait().is_ok());
+        assert!(handles[2].wait().is_err());
+        assert_eq!(assets.sound_count(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1,15 +1,16 @@
 //! A factory responsible for creating and managing unique game objects within limits.
 //!
 //! `GameObjectFactory` maintains a pool of unique IDs to keep track of created objects,
-//! enforcing a maximum allowed count and reusing IDs of deleted objects.
+//! enforcing a maximum allowed count and reusing IDs of deleted objects, lowest first,
+//! so the same sequence of spawns and despawns always hands out the same IDs.
 //!
 //! This struct is used to reliably create new `GameObject` instances while managing
 //! their identity uniqueness and allocation state.
 
 use crate::engine::scene::game_object::Position;
 use crate::engine::scene::game_object::components::Component;
-use crate::engine::scene::game_object::{GameObject, Object};
-use std::collections::{HashMap, HashSet};
+use crate::engine::scene::game_object::{GameObject, GameObjectError, Object};
+use std::collections::{BTreeSet, HashMap};
 
 /// Factory struct for creating game objects with unique IDs.
 ///
@@ -17,7 +18,7 @@ use std::collections::{HashMap, HashSet};
 /// allowing reuse of IDs to prevent overflow and manage resources efficiently.
 struct GameObjectFactory {
     /// Set of reusable unique IDs from deleted or freed objects.
-    uids: HashSet<usize>,
+    uids: BTreeSet<usize>,
     /// Maximum number of game objects that can be created.
     max_objects: usize,
     /// Count of currently allocated objects.
@@ -34,16 +35,24 @@ impl GameObjectFactory {
     /// A new factory with no allocated objects and an empty UID pool.
     pub fn new(max_objects: usize) -> Self {
         GameObjectFactory {
-            uids: HashSet::new(),
+            uids: BTreeSet::new(),
             max_objects,
             allocated_objects: 0,
         }
     }
 
+    /// Makes the UID of a removed object available again.
+    ///
+    /// UIDs the factory never handed out are ignored.
+    pub fn free_uid(&mut self, uid: usize) {
+        if (1..=self.allocated_objects).contains(&uid) {
+            self.uids.insert(uid);
+        }
+    }
+
     /// Creates a new game object with provided components and position.
     ///
-    /// If there are reusable UIDs available, assigns one; otherwise, increments allocated count.
-    /// Panics if the maximum object limit has been reached without free UIDs.
+    /// If there are reusable UIDs available, assigns the lowest; otherwise, increments allocated count.
     ///
     /// # Parameters
     /// - `components`: Components composing the new game object.
@@ -51,23 +60,28 @@ impl GameObjectFactory {
     ///
     /// # Returns
     /// A tuple containing the assigned unique ID and the newly created `GameObject`.
+    ///
+    /// # Errors
+    /// Fails with [`GameObjectError::UIDError`] if the maximum object limit has been reached
+    /// without free UIDs.
     pub fn create_object(
         &mut self,
         components: Vec<Box<dyn Component + Send + Sync>>,
         position: Position,
-    ) -> (usize, GameObject) {
+    ) -> Result<(usize, GameObject), GameObjectError> {
         if self.uids.is_empty() && self.max_objects == self.allocated_objects {
-            panic!("Trying to create object above limit")
-        } else if !self.uids.is_empty() {
-            let uid = *self.uids.iter().next().unwrap();
-            self.uids.remove(&uid);
-            return (uid, GameObject::new(components, None, position));
+            return Err(GameObjectError::UIDError(format!(
+                "cannot create more than {} objects",
+                self.max_objects
+            )));
+        } else if let Some(uid) = self.uids.pop_first() {
+            return Ok((uid, GameObject::new(components, None, position)));
         }
         self.allocated_objects += 1;
-        (
+        Ok((
             self.allocated_objects,
             GameObject::new(components, None, position),
-        )
+        ))
     }
 }
 
@@ -113,7 +127,7 @@ mod factory_tests {
         let components = create_test_components();
         let position = create_test_position(0, 0, 0, false);
 
-        let (uid, _obj) = factory.create_object(components, position);
+        let (uid, _obj) = factory.create_object(components, position).unwrap();
 
         assert_eq!(uid, 1);
         assert_eq!(factory.allocated_objects, 1);
@@ -124,7 +138,9 @@ mod factory_tests {
         let mut factory = GameObjectFactory::new(10);
         let position = create_test_position(10, 20, 30, false);
 
-        let (_uid, obj) = factory.create_object(create_test_components(), position);
+        let (_uid, obj) = factory
+            .create_object(create_test_components(), position)
+            .unwrap();
 
         assert_eq!(obj.position.x, 10);
         assert_eq!(obj.position.y, 20);
@@ -139,46 +155,57 @@ mod factory_tests {
             Box::new(Sprite::new(None, false, (0, 0))) as Box<dyn Component + Send + Sync>,
         ];
 
-        let (_uid, obj) = factory.create_object(components, create_test_position(0, 0, 0, false));
+        let (_uid, obj) = factory
+            .create_object(components, create_test_position(0, 0, 0, false))
+            .unwrap();
 
         assert_eq!(obj.components.len(), 2);
     }
 
     #[test]
-    #[should_panic(expected = "Trying to create object above limit")]
-    fn test_create_object_panics_when_exceeding_limit() {
+    fn test_create_object_fails_when_exceeding_limit() {
         let mut factory = GameObjectFactory::new(2);
 
-        factory.create_object(
-            create_test_components(),
-            create_test_position(0, 0, 0, false),
-        );
-        factory.create_object(
-            create_test_components(),
-            create_test_position(1, 1, 1, false),
-        );
-
-        factory.create_object(
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(1, 1, 1, false),
+            )
+            .unwrap();
+
+        let result = factory.create_object(
             create_test_components(),
             create_test_position(2, 2, 2, false),
         );
+        assert!(matches!(result, Err(GameObjectError::UIDError(msg)) if msg.contains('2')));
+        assert_eq!(factory.allocated_objects, 2);
     }
 
     #[test]
     fn test_create_object_reuses_freed_uid() {
         let mut factory = GameObjectFactory::new(10);
 
-        factory.create_object(
-            create_test_components(),
-            create_test_position(0, 0, 0, false),
-        );
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
 
         factory.uids.insert(1);
 
-        let (uid, _) = factory.create_object(
-            create_test_components(),
-            create_test_position(1, 1, 1, false),
-        );
+        let (uid, _) = factory
+            .create_object(
+                create_test_components(),
+                create_test_position(1, 1, 1, false),
+            )
+            .unwrap();
 
         assert_eq!(uid, 1);
         assert!(factory.uids.is_empty());
@@ -189,30 +216,40 @@ mod factory_tests {
     fn test_create_object_reuses_multiple_freed_uids() {
         let mut factory = GameObjectFactory::new(10);
 
-        factory.create_object(
-            create_test_components(),
-            create_test_position(0, 0, 0, false),
-        );
-        factory.create_object(
-            create_test_components(),
-            create_test_position(1, 1, 1, false),
-        );
-        factory.create_object(
-            create_test_components(),
-            create_test_position(2, 2, 2, false),
-        );
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(1, 1, 1, false),
+            )
+            .unwrap();
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(2, 2, 2, false),
+            )
+            .unwrap();
 
         factory.uids.insert(1);
         factory.uids.insert(2);
 
-        let (uid1, _) = factory.create_object(
-            create_test_components(),
-            create_test_position(3, 3, 3, false),
-        );
-        let (uid2, _) = factory.create_object(
-            create_test_components(),
-            create_test_position(4, 4, 4, false),
-        );
+        let (uid1, _) = factory
+            .create_object(
+                create_test_components(),
+                create_test_position(3, 3, 3, false),
+            )
+            .unwrap();
+        let (uid2, _) = factory
+            .create_object(
+                create_test_components(),
+                create_test_position(4, 4, 4, false),
+            )
+            .unwrap();
 
         assert!(uid1 == 1 || uid1 == 2);
         assert!(uid2 == 1 || uid2 == 2);
@@ -224,21 +261,27 @@ mod factory_tests {
     fn test_create_object_with_freed_uids_at_limit() {
         let mut factory = GameObjectFactory::new(2);
 
-        factory.create_object(
-            create_test_components(),
-            create_test_position(0, 0, 0, false),
-        );
-        factory.create_object(
-            create_test_components(),
-            create_test_position(1, 1, 1, false),
-        );
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(1, 1, 1, false),
+            )
+            .unwrap();
 
         factory.uids.insert(1);
 
-        let (uid, _) = factory.create_object(
-            create_test_components(),
-            create_test_position(2, 2, 2, false),
-        );
+        let (uid, _) = factory
+            .create_object(
+                create_test_components(),
+                create_test_position(2, 2, 2, false),
+            )
+            .unwrap();
 
         assert_eq!(uid, 1);
         assert_eq!(factory.allocated_objects, 2);
@@ -248,30 +291,66 @@ mod factory_tests {
     fn test_create_object_with_empty_components() {
         let mut factory = GameObjectFactory::new(10);
 
-        let (_uid, obj) = factory.create_object(vec![], create_test_position(0, 0, 0, false));
+        let (_uid, obj) = factory
+            .create_object(vec![], create_test_position(0, 0, 0, false))
+            .unwrap();
 
         assert_eq!(obj.components.len(), 0);
     }
 
+    #[test]
+    fn test_freed_uids_are_reused_lowest_first() {
+        let mut factory = GameObjectFactory::new(4);
+        for i in 0..4 {
+            factory
+                .create_object(
+                    create_test_components(),
+                    create_test_position(i, i, i, false),
+                )
+                .unwrap();
+        }
+        factory.free_uid(3);
+        factory.free_uid(2);
+        factory.free_uid(7);
+        factory.free_uid(0);
+
+        let uids: Vec<usize> = (0..2)
+            .map(|_| {
+                factory
+                    .create_object(vec![], create_test_position(0, 0, 0, false))
+                    .unwrap()
+                    .0
+            })
+            .collect();
+        assert_eq!(uids, vec![2, 3]);
+        assert!(factory.uids.is_empty());
+    }
+
     #[test]
     fn test_uid_reuse_priority() {
         let mut factory = GameObjectFactory::new(10);
 
-        factory.create_object(
-            create_test_components(),
-            create_test_position(0, 0, 0, false),
-        );
-        factory.create_object(
-            create_test_components(),
-            create_test_position(1, 1, 1, false),
-        );
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
+        factory
+            .create_object(
+                create_test_components(),
+                create_test_position(1, 1, 1, false),
+            )
+            .unwrap();
 
         factory.uids.insert(5);
 
-        let (uid, _) = factory.create_object(
-            create_test_components(),
-            create_test_position(2, 2, 2, false),
-        );
+        let (uid, _) = factory
+            .create_object(
+                create_test_components(),
+                create_test_position(2, 2, 2, false),
+            )
+            .unwrap();
 
         assert_eq!(uid, 5);
         assert!(factory.uids.is_empty());
@@ -279,6 +358,8 @@ mod factory_tests {
 }
 pub struct GameObjectManager {
     pub game_objects: HashMap<usize, GameObject>,
+    /// Parent UID of every object that has a parent.
+    parents: HashMap<usize, usize>,
     factory: GameObjectFactory,
 }
 
@@ -286,17 +367,101 @@ impl GameObjectManager {
     pub fn new(max_objects: usize) -> Self {
         GameObjectManager {
             game_objects: HashMap::new(),
+            parents: HashMap::new(),
             factory: GameObjectFactory::new(max_objects),
         }
     }
 
+    /// Creates a game object and stores it under a new UID.
+    ///
+    /// # Returns
+    /// The UID of the stored object.
+    ///
+    /// # Errors
+    /// Fails if the manager already holds its maximum number of objects.
     pub fn add_game_object(
         &mut self,
         components: Vec<Box<dyn Component + Send + Sync>>,
         position: Position,
-    ) {
-        let (uid, object) = self.factory.create_object(components, position);
+    ) -> Result<usize, GameObjectError> {
+        let (uid, object) = self.factory.create_object(components, position)?;
+        self.game_objects.insert(uid, object);
+        Ok(uid)
+    }
+
+    /// Stores an already built object, with its script and tags, under a new UID.
+    ///
+    /// # Returns
+    /// The UID of the stored object.
+    ///
+    /// # Errors
+    /// Fails if the manager already holds its maximum number of objects.
+    pub fn insert_game_object(&mut self, object: GameObject) -> Result<usize, GameObjectError> {
+        // The factory hands out the UID, the object it creates is replaced right away
+        let uid = self.add_game_object(Vec::new(), object.position)?;
         self.game_objects.insert(uid, object);
+        Ok(uid)
+    }
+
+    /// Returns the object stored under the UID, if any.
+    pub fn get(&self, uid: usize) -> Option<&GameObject> {
+        self.game_objects.get(&uid)
+    }
+
+    /// Returns the object stored under the UID for changing it, if any.
+    pub fn get_mut(&mut self, uid: usize) -> Option<&mut GameObject> {
+        self.game_objects.get_mut(&uid)
+    }
+
+    /// Returns the UIDs of all objects carrying the tag, in ascending order.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<usize> {
+        let mut uids: Vec<usize> = self
+            .game_objects
+            .iter()
+            .filter(|(_, object)| object.has_tag(tag))
+            .map(|(uid, _)| *uid)
+            .collect();
+        uids.sort_unstable();
+        uids
+    }
+
+    /// Removes the object stored under the UID, which may then be reused.
+    ///
+    /// # Returns
+    /// The removed object, or None if no object has this UID.
+    pub fn remove_game_object(&mut self, uid: usize) -> Option<GameObject> {
+        let object = self.game_objects.remove(&uid)?;
+        self.factory.free_uid(uid);
+        self.parents.remove(&uid);
+        self.parents.retain(|_, parent| *parent != uid);
+        Some(object)
+    }
+
+    /// Links an object to a parent, or unlinks it with None.
+    ///
+    /// The caller makes sure both objects exist and no cycle is formed.
+    pub fn set_parent(&mut self, child: usize, parent: Option<usize>) {
+        match parent {
+            Some(parent) => self.parents.insert(child, parent),
+            None => self.parents.remove(&child),
+        };
+    }
+
+    /// Returns the UID of the object's parent, if it has one.
+    pub fn parent(&self, uid: usize) -> Option<usize> {
+        self.parents.get(&uid).copied()
+    }
+
+    /// Returns the UIDs of the object's children, in ascending order.
+    pub fn children(&self, uid: usize) -> Vec<usize> {
+        let mut children: Vec<usize> = self
+            .parents
+            .iter()
+            .filter(|(_, parent)| **parent == uid)
+            .map(|(child, _)| *child)
+            .collect();
+        children.sort_unstable();
+        children
     }
 }
 
@@ -334,10 +499,12 @@ mod manager_tests {
     fn test_add_game_object_adds_to_hashmap() {
         let mut manager = GameObjectManager::new(10);
 
-        manager.add_game_object(
-            create_test_components(),
-            create_test_position(0, 0, 0, false),
-        );
+        manager
+            .add_game_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
 
         assert_eq!(manager.game_objects.len(), 1);
         assert!(manager.game_objects.contains_key(&1));
@@ -348,7 +515,9 @@ mod manager_tests {
         let mut manager = GameObjectManager::new(10);
         let position = create_test_position(15, 25, 35, false);
 
-        manager.add_game_object(create_test_components(), position);
+        manager
+            .add_game_object(create_test_components(), position)
+            .unwrap();
 
         let obj = manager.game_objects.get(&1).unwrap();
         assert_eq!(obj.position.x, 15);
@@ -360,7 +529,9 @@ mod manager_tests {
     fn test_add_game_object_with_empty_components() {
         let mut manager = GameObjectManager::new(10);
 
-        manager.add_game_object(vec![], create_test_position(0, 0, 0, false));
+        manager
+            .add_game_object(vec![], create_test_position(0, 0, 0, false))
+            .unwrap();
 
         assert_eq!(manager.game_objects.len(), 1);
         let obj = manager.game_objects.get(&1).unwrap();
@@ -368,24 +539,27 @@ mod manager_tests {
     }
 
     #[test]
-    #[should_panic(expected = "Trying to create object above limit")]
     fn test_add_game_object_with_zero_limit() {
         let mut manager = GameObjectManager::new(0);
 
-        manager.add_game_object(
+        let result = manager.add_game_object(
             create_test_components(),
             create_test_position(0, 0, 0, false),
         );
+        assert!(matches!(result, Err(GameObjectError::UIDError(_))));
+        assert!(manager.game_objects.is_empty());
     }
 
     #[test]
     fn test_add_game_object_with_negative_positions() {
         let mut manager = GameObjectManager::new(10);
 
-        manager.add_game_object(
-            create_test_components(),
-            create_test_position(-10, -20, -30, false),
-        );
+        manager
+            .add_game_object(
+                create_test_components(),
+                create_test_position(-10, -20, -30, false),
+            )
+            .unwrap();
 
         let obj = manager.game_objects.get(&1).unwrap();
         assert_eq!(obj.position.x, -10);
@@ -397,10 +571,12 @@ mod manager_tests {
     fn test_manager_can_retrieve_objects_by_uid() {
         let mut manager = GameObjectManager::new(10);
 
-        manager.add_game_object(
-            create_test_components(),
-            create_test_position(100, 200, 300, false),
-        );
+        manager
+            .add_game_object(
+                create_test_components(),
+                create_test_position(100, 200, 300, false),
+            )
+            .unwrap();
 
         let retrieved = manager.game_objects.get(&1);
         assert!(retrieved.is_some());
@@ -411,11 +587,94 @@ mod manager_tests {
         assert_eq!(obj.position.z, 300);
     }
 
+    #[test]
+    fn test_remove_game_object_frees_uid() {
+        let mut manager = GameObjectManager::new(1);
+        let uid = manager
+            .add_game_object(
+                create_test_components(),
+                create_test_position(1, 2, 3, false),
+            )
+            .unwrap();
+        assert!(manager.remove_game_object(uid).is_some());
+        assert!(manager.remove_game_object(uid).is_none());
+
+        let reused = manager
+            .add_game_object(
+                create_test_components(),
+                create_test_position(0, 0, 0, false),
+            )
+            .unwrap();
+        assert_eq!(reused, uid);
+    }
+
+    #[test]
+    fn test_removing_parent_unlinks_children() {
+        let mut manager = GameObjectManager::new(3);
+        let ship = manager
+            .add_game_object(vec![], create_test_position(0, 0, 0, false))
+            .unwrap();
+        let turret = manager
+            .add_game_object(vec![], create_test_position(1, 1, 0, true))
+            .unwrap();
+        let flag = manager
+            .add_game_object(vec![], create_test_position(0, 2, 0, true))
+            .unwrap();
+        manager.set_parent(turret, Some(ship));
+        manager.set_parent(flag, Some(turret));
+        assert_eq!(manager.parent(flag), Some(turret));
+        assert_eq!(manager.children(ship), vec![turret]);
+
+        manager.remove_game_object(turret);
+        assert_eq!(manager.parent(flag), None);
+        assert!(manager.children(ship).is_empty());
+    }
+
+    #[test]
+    fn test_find_by_tag() {
+        let mut manager = GameObjectManager::new(4);
+        let position = create_test_position(0, 0, 0, false);
+        let enemy = manager
+            .insert_game_object(GameObject::new(vec![], None, position).with_tag("enemy"))
+            .unwrap();
+        manager
+            .add_game_object(create_test_components(), position)
+            .unwrap();
+        let boss = manager
+            .insert_game_object(
+                GameObject::new(vec![], None, position)
+                    .with_tag("enemy")
+                    .with_tag("boss"),
+            )
+            .unwrap();
+        assert_eq!(manager.find_by_tag("enemy"), vec![enemy, boss]);
+        assert_eq!(manager.find_by_tag("boss"), vec![boss]);
+        assert!(manager.find_by_tag("player").is_empty());
+    }
+
     #[test]
     fn test_manager_returns_none_for_nonexistent_uid() {
         let manager = GameObjectManager::new(10);
 
         let retrieved = manager.game_objects.get(&999);
         assert!(retrieved.is_none());
+        assert!(manager.get(999).is_none());
+    }
+
+    #[test]
+    fn test_get_mut_changes_stored_object() {
+        let mut manager = GameObjectManager::new(10);
+        let uid = manager
+            .add_game_object(vec![], create_test_position(1, 2, 3, false))
+            .unwrap();
+
+        let object = manager.get_mut(uid).unwrap();
+        object.position.x = 40;
+        object
+            .add_component(Box::new(Sprite::new(None, false, (0, 0))))
+            .ok();
+        let object = manager.get(uid).unwrap();
+        assert_eq!(object.position.x, 40);
+        assert_eq!(object.components.len(), 1);
     }
 }
@@ -0,0 +1,254 @@
+//! Reusable templates of game objects.
+//!
+//! A `Prefab` describes a sprite, its placement and the name of a script once, so objects
+//! such as bullets or enemies are built from it with [`Scene::instantiate`] wherever they
+//! spawn instead of being assembled by hand. The sprite image is loaded when the prefab is
+//! made, and all instances share it. Scripts are given by name and looked up in a
+//! `ScriptRegistry`, so prefabs can later be described in data files.
+//!
+//! [`Scene::instantiate`]: crate::engine::scene::Scene::instantiate
+
+use crate::engine::scene::game_object::components::Component;
+use crate::engine::scene::game_object::components::collider::Collider;
+use crate::engine::scene::game_object::components::script::Script;
+use crate::engine::scene::game_object::components::sprite::{Layer, Sprite};
+use crate::engine::scene::game_object::{GameObject, GameObjectError, Object, Position};
+use image::{DynamicImage, ImageReader};
+use std::collections::HashMap;
+use std::io::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Function creating a fresh script for a new object.
+pub type ScriptConstructor = Box<dyn Fn() -> Box<dyn Script + Send + Sync> + Send + Sync>;
+
+/// Scripts available to prefabs, by name.
+#[derive(Default)]
+pub struct ScriptRegistry {
+    constructors: HashMap<String, ScriptConstructor>,
+}
+
+impl ScriptRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        ScriptRegistry::default()
+    }
+
+    /// Makes a script available under a name, replacing a script registered before under it.
+    ///
+    /// # Parameters
+    /// - `name`: Name prefabs refer to the script by.
+    /// - `constructor`: Creates the script of every new instance.
+    pub fn register(&mut self, name: impl Into<String>, constructor: ScriptConstructor) {
+        self.constructors.insert(name.into(), constructor);
+    }
+
+    /// Creates the script registered under the name, if any.
+    pub fn create(&self, name: &str) -> Option<Box<dyn Script + Send + Sync>> {
+        self.constructors.get(name).map(|constructor| constructor())
+    }
+}
+
+/// Template of a game object, instantiated any number of times.
+#[derive(Debug, Clone, Default)]
+pub struct Prefab {
+    /// Image of the sprite; None for objects without a sprite.
+    image: Option<Arc<DynamicImage>>,
+    /// Offset of the sprite from the object position.
+    pub offset: (i32, i32),
+    /// Whether the sprite casts a shadow.
+    pub shadow: bool,
+    /// Render layer of the sprite.
+    pub layer: Layer,
+    /// Name of the script run by every instance, looked up in the scene's script registry.
+    pub script: Option<String>,
+    /// Tags of every instance.
+    pub tags: Vec<String>,
+    /// Collision box of every instance, if any.
+    pub collider: Option<Collider>,
+}
+
+impl Prefab {
+    /// Creates a prefab of objects without sprite, script and tags.
+    pub fn new() -> Self {
+        Prefab::default()
+    }
+
+    /// Loads the sprite image of the prefab from a file.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the image, e.g. a `.png`.
+    ///
+    /// # Returns
+    /// The same prefab showing the image.
+    ///
+    /// # Errors
+    /// Fails if the image cannot be read or decoded.
+    pub fn with_sprite(self, path: &Path) -> Result<Self, Error> {
+        let image = ImageReader::open(path)?.decode().map_err(Error::other)?;
+        Ok(self.with_image(image))
+    }
+
+    /// Uses an image already in memory as the sprite of the prefab.
+    pub fn with_image(self, image: DynamicImage) -> Self {
+        self.with_shared_image(Arc::new(image))
+    }
+
+    /// Uses an image shared with other sprites, e.g. from an asset manager, as the sprite.
+    pub fn with_shared_image(mut self, image: Arc<DynamicImage>) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Sets the offset of the sprite from the object position.
+    pub fn with_offset(mut self, offset: (i32, i32)) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets whether the sprite casts a shadow.
+    pub fn with_shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /// Moves the sprite to another render layer.
+    pub fn with_layer(mut self, layer: Layer) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Sets the name of the script every instance runs.
+    pub fn with_script(mut self, name: impl Into<String>) -> Self {
+        self.script = Some(name.into());
+        self
+    }
+
+    /// Gives every instance a collision box.
+    pub fn with_collider(mut self, collider: Collider) -> Self {
+        self.collider = Some(collider);
+        self
+    }
+
+    /// Adds a tag every instance carries.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Returns the sprite image of the prefab, if any.
+    pub fn image(&self) -> Option<&DynamicImage> {
+        self.image.as_deref()
+    }
+
+    /// Builds a new object from the prefab.
+    ///
+    /// # Parameters
+    /// - `position`: Position of the new object.
+    /// - `scripts`: Registry the script of the prefab is looked up in.
+    ///
+    /// # Errors
+    /// Fails with [`GameObjectError::ScriptError`] if no script is registered under the
+    /// prefab's script name.
+    pub fn build(
+        &self,
+        position: Position,
+        scripts: &ScriptRegistry,
+    ) -> Result<GameObject, GameObjectError> {
+        let script = match &self.script {
+            Some(name) => Some(scripts.create(name).ok_or_else(|| {
+                GameObjectError::ScriptError(format!("no script registered as {name}"))
+            })?),
+            None => None,
+        };
+        let mut components: Vec<Box<dyn Component + Send + Sync>> = vec![];
+        if let Some(image) = &self.image {
+            components.push(Box::new(
+                Sprite::shared(image.clone(), self.shadow, self.offset).with_layer(self.layer),
+            ));
+        }
+        if let Some(collider) = self.collider {
+            components.push(Box::new(collider));
+        }
+        let mut object = GameObject::new(components, script, position);
+        for tag in &self.tags {
+            object.add_tag(tag.clone());
+        }
+        Ok(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::game_object::components::ComponentType;
+    use crate::engine::scene::game_object::components::script::ScriptContext;
+
+    struct Fall;
+
+    impl Script for Fall {
+        fn action(&mut self, game_object: &mut GameObject, _context: &mut ScriptContext) {
+            game_object.add_position((0, -1));
+        }
+
+        fn new(_is_downed: bool) -> Self {
+            Fall
+        }
+    }
+
+    #[test]
+    fn test_build_creates_independent_instances() {
+        let mut scripts = ScriptRegistry::new();
+        scripts.register("fall", Box::new(|| Box::new(Fall)));
+        let prefab = Prefab::new()
+            .with_image(DynamicImage::new_rgba8(3, 2))
+            .with_offset((1, -1))
+            .with_layer(Layer::Foreground)
+            .with_script("fall")
+            .with_tag("bullet");
+
+        let first = prefab
+            .build(Position::new(4, 5).with_z(1), &scripts)
+            .ok()
+            .unwrap();
+        let second = prefab
+            .build(Position::new(4, 5).with_z(1), &scripts)
+            .ok()
+            .unwrap();
+        for object in [&first, &second] {
+            assert_eq!(object.components.len(), 1);
+            let sprite = &object.components[0];
+            assert_eq!(sprite.get_component_type(), ComponentType::Sprite);
+            assert_eq!(sprite.get_sprite_offset_unchecked(), Some((1, -1)));
+            assert_eq!(sprite.get_layer_unchecked(), Layer::Foreground);
+            assert!(object.script.is_some());
+            assert!(object.has_tag("bullet"));
+        }
+        assert!(Arc::ptr_eq(
+            first.components[0].get_sprite_unchecked().unwrap(),
+            second.components[0].get_sprite_unchecked().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_build_fails_for_unknown_script() {
+        let prefab = Prefab::new().with_script("missing");
+        assert!(matches!(
+            prefab.build(Position::new(4, 5).with_z(1), &ScriptRegistry::new()),
+            Err(GameObjectError::ScriptError(_))
+        ));
+        let plain = Prefab::new().build(Position::new(4, 5).with_z(1), &ScriptRegistry::new());
+        assert!(plain.is_ok_and(|object| object.components.is_empty()));
+    }
+
+    #[test]
+    fn test_with_sprite_loads_image() {
+        assert!(Prefab::new().with_sprite(Path::new("missing.png")).is_err());
+
+        let path = std::env::temp_dir().join("rusty_ache_test_prefab.png");
+        DynamicImage::new_rgba8(5, 4).save(&path).unwrap();
+        let prefab = Prefab::new().with_sprite(&path).unwrap();
+        assert_eq!(prefab.image().map(|image| image.width()), Some(5));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,243 @@
+//! Solid level geometry laid out on a grid.
+//!
+//! Walls and floors made of many equal tiles would need one collider object per tile. A
+//! `SolidTiles` grid marks whole tiles as solid instead; the `PhysicsSystem` stops objects
+//! moving into them. The grid only describes collision: the tiles are drawn by sprites or
+//! the background as before.
+
+use crate::engine::scene::game_object::components::collider::Aabb;
+
+/// Grid of square tiles, each either solid or empty.
+///
+/// The grid hangs down and to the right from its top-left corner, like sprites: column 0
+/// is the leftmost one and row 0 the topmost one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolidTiles {
+    /// World position of the top-left corner of the grid.
+    origin: (i32, i32),
+    /// Edge length of a tile in world units.
+    tile_size: u32,
+    /// Number of columns.
+    width: usize,
+    /// Number of rows.
+    height: usize,
+    /// Solid flags row by row.
+    solid: Vec<bool>,
+}
+
+impl SolidTiles {
+    /// Creates a grid of empty tiles.
+    ///
+    /// # Parameters
+    /// - `origin`: World position of the top-left corner of the grid.
+    /// - `tile_size`: Edge length of a tile in world units, at least 1.
+    /// - `width`: Number of columns.
+    /// - `height`: Number of rows.
+    pub fn new(origin: (i32, i32), tile_size: u32, width: usize, height: usize) -> Self {
+        SolidTiles {
+            origin,
+            tile_size: tile_size.max(1),
+            width,
+            height,
+            solid: vec![false; width * height],
+        }
+    }
+
+    /// Creates a grid from rows of text, `#` marking solid tiles and any other character
+    /// empty ones, e.g. `["#####", "#...#", "#####"]` for a closed room.
+    ///
+    /// Shorter rows are padded with empty tiles.
+    pub fn from_rows(origin: (i32, i32), tile_size: u32, rows: &[&str]) -> Self {
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut tiles = SolidTiles::new(origin, tile_size, width, rows.len());
+        for (row, line) in rows.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                tiles.set_solid(column, row, c == '#');
+            }
+        }
+        tiles
+    }
+
+    /// Returns the number of columns and rows.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Marks a tile as solid or empty; tiles outside the grid are ignored.
+    pub fn set_solid(&mut self, column: usize, row: usize, solid: bool) {
+        if column < self.width && row < self.height {
+            self.solid[row * self.width + column] = solid;
+        }
+    }
+
+    /// Checks whether a tile is solid; tiles outside the grid are empty.
+    pub fn is_solid(&self, column: usize, row: usize) -> bool {
+        column < self.width && row < self.height && self.solid[row * self.width + column]
+    }
+
+    /// Returns the column and row of the tile holding a world point, if inside the grid.
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let size = self.tile_size as i64;
+        let column = (x as i64 - self.origin.0 as i64).div_euclid(size);
+        // Rows grow downwards; the top edge belongs to row 0
+        let row = (self.origin.1 as i64 - y as i64).div_euclid(size);
+        (column >= 0 && row >= 0 && (column as usize) < self.width && (row as usize) < self.height)
+            .then_some((column as usize, row as usize))
+    }
+
+    /// Returns the world area of a tile.
+    pub fn tile_bounds(&self, column: usize, row: usize) -> Aabb {
+        let size = self.tile_size as i32;
+        let left = self.origin.0 + column as i32 * size;
+        let top = self.origin.1 - row as i32 * size;
+        Aabb {
+            left,
+            top,
+            right: left + size,
+            bottom: top - size,
+        }
+    }
+
+    /// Checks whether a rectangle shares any area with a solid tile.
+    pub fn overlaps(&self, area: &Aabb) -> bool {
+        if self.width == 0 || self.height == 0 || area.left >= area.right || area.bottom >= area.top
+        {
+            return false;
+        }
+        let size = self.tile_size as i64;
+        let column = |x: i64| (x - self.origin.0 as i64).div_euclid(size);
+        let row = |y: i64| (self.origin.1 as i64 - y).div_euclid(size);
+        // The right and bottom edges do not belong to the rectangle
+        let columns = (
+            column(area.left as i64).max(0),
+            column(area.right as i64 - 1).min(self.width as i64 - 1),
+        );
+        let rows = (
+            row(area.top as i64).max(0),
+            row(area.bottom as i64 + 1).min(self.height as i64 - 1),
+        );
+        (rows.0..=rows.1).any(|row| {
+            (columns.0..=columns.1).any(|column| self.is_solid(column as usize, row as usize))
+        })
+    }
+
+    /// Moves a rectangle as far as possible towards a target without entering solid tiles.
+    ///
+    /// The rectangle moves along x first, then along y, one world unit at a time, and stops
+    /// on an axis at the first solid tile, so it slides along walls. A rectangle already
+    /// overlapping solid tiles moves freely, letting objects caught in a wall get out.
+    ///
+    /// # Parameters
+    /// - `area`: World area of the rectangle before moving.
+    /// - `delta`: Movement wanted along x and y.
+    ///
+    /// # Returns
+    /// The movement possible and, for each axis, whether a solid tile blocked it.
+    pub fn sweep(&self, area: &Aabb, delta: (i32, i32)) -> ((i32, i32), (bool, bool)) {
+        let shifted = |dx: i32, dy: i32| Aabb {
+            left: area.left + dx,
+            top: area.top + dy,
+            right: area.right + dx,
+            bottom: area.bottom + dy,
+        };
+        if self.overlaps(area) {
+            return (delta, (false, false));
+        }
+        let mut moved = (0, 0);
+        let mut blocked = (false, false);
+        while moved.0 != delta.0 {
+            let next = moved.0 + delta.0.signum();
+            if self.overlaps(&shifted(next, 0)) {
+                blocked.0 = true;
+                break;
+            }
+            moved.0 = next;
+        }
+        while moved.1 != delta.1 {
+            let next = moved.1 + delta.1.signum();
+            if self.overlaps(&shifted(moved.0, next)) {
+                blocked.1 = true;
+                break;
+            }
+            moved.1 = next;
+        }
+        (moved, blocked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(left: i32, top: i32, right: i32, bottom: i32) -> Aabb {
+        Aabb {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    fn room() -> SolidTiles {
+        SolidTiles::from_rows((0, 0), 10, &["#####", "#...#", "#...#", "#####"])
+    }
+
+    #[test]
+    fn test_from_rows_marks_solid_tiles() {
+        let tiles = SolidTiles::from_rows((0, 0), 8, &["#.#", "#"]);
+        assert_eq!(tiles.size(), (3, 2));
+        assert!(tiles.is_solid(0, 0));
+        assert!(!tiles.is_solid(1, 0));
+        assert!(tiles.is_solid(2, 0));
+        assert!(tiles.is_solid(0, 1));
+        assert!(!tiles.is_solid(1, 1));
+        assert!(!tiles.is_solid(5, 5));
+    }
+
+    #[test]
+    fn test_tile_positions() {
+        let tiles = SolidTiles::new((-20, 10), 10, 4, 3);
+        assert_eq!(tiles.tile_at(-20, 10), Some((0, 0)));
+        assert_eq!(tiles.tile_at(-11, 1), Some((0, 0)));
+        assert_eq!(tiles.tile_at(-10, 0), Some((1, 1)));
+        assert_eq!(tiles.tile_at(19, -19), Some((3, 2)));
+        assert_eq!(tiles.tile_at(20, 0), None);
+        assert_eq!(tiles.tile_at(0, 11), None);
+        assert_eq!(tiles.tile_bounds(1, 2), area(-10, -10, 0, -20));
+    }
+
+    #[test]
+    fn test_overlaps_needs_shared_area() {
+        let tiles = room();
+        // Inside the room
+        assert!(!tiles.overlaps(&area(10, -10, 40, -30)));
+        // Reaching into the left wall
+        assert!(tiles.overlaps(&area(9, -10, 20, -20)));
+        // Outside the grid
+        assert!(!tiles.overlaps(&area(-30, 30, -20, 20)));
+        assert!(tiles.overlaps(&area(-30, 30, 5, -5)));
+    }
+
+    #[test]
+    fn test_sweep_stops_at_walls_and_slides() {
+        let tiles = room();
+        let player = area(15, -15, 20, -20);
+        assert_eq!(tiles.sweep(&player, (10, 5)), ((10, 5), (false, false)));
+        // Stops at the right wall, still moving down along it
+        assert_eq!(tiles.sweep(&player, (30, -5)), ((20, -5), (true, false)));
+        // Stops on the floor
+        assert_eq!(tiles.sweep(&player, (0, -40)), ((0, -10), (false, true)));
+        assert_eq!(tiles.sweep(&player, (-9, 9)), ((-5, 5), (true, true)));
+    }
+
+    #[test]
+    fn test_sweep_frees_objects_stuck_in_walls() {
+        let tiles = room();
+        let stuck = area(5, -15, 12, -20);
+        assert_eq!(tiles.sweep(&stuck, (10, 0)), ((10, 0), (false, false)));
+    }
+}
@@ -0,0 +1,92 @@
+//! Watching of scene files for changes.
+//!
+//! A `FileWatcher` is notified by the operating system whenever a file is written, so a
+//! scene loaded from it can be rebuilt while the game runs. The directory of the file is
+//! watched rather than the file itself, since many editors save by replacing the file.
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Watches a single file and remembers whether it changed.
+pub(crate) struct FileWatcher {
+    /// Keeps the watch alive; dropping it stops watching.
+    _watcher: RecommendedWatcher,
+    /// Whether the file changed since the last check.
+    changed: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    /// Starts watching a file.
+    ///
+    /// # Errors
+    /// Fails if the path has no file name or its directory cannot be watched.
+    pub(crate) fn new(path: &Path) -> Result<Self, Error> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::other(format!("{} is not a file", path.display())))?
+            .to_owned();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let changed = Arc::new(AtomicBool::new(false));
+        let flag = changed.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            let is_write = event.kind.is_create() || event.kind.is_modify();
+            if is_write
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == Some(&file_name))
+            {
+                flag.store(true, Ordering::Relaxed);
+            }
+        })
+        .map_err(Error::other)?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(Error::other)?;
+        Ok(FileWatcher {
+            _watcher: watcher,
+            changed,
+        })
+    }
+
+    /// Checks whether the file changed since the last call, and resets the flag.
+    pub(crate) fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_writes_to_watched_file_are_noticed() {
+        let dir = std::env::temp_dir().join("rusty_ache_test_watch");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scene.toml");
+        fs::write(&path, "a").unwrap();
+        let watcher = FileWatcher::new(&path).unwrap();
+        assert!(!watcher.take_changed());
+
+        fs::write(dir.join("other.toml"), "b").unwrap();
+        fs::write(&path, "c").unwrap();
+        let start = Instant::now();
+        while !watcher.take_changed() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -2,15 +2,41 @@
 //!
 //! The `SceneManager` struct holds the currently active scene and provides methods to access
 //! and initialize its renderable components for rendering purposes.
+//!
+//! Besides the active scene, it keeps a registry of scenes by name, e.g. the levels of a
+//! game. Switching to a registered scene takes it out of the registry and makes it active;
+//! the scene active before is handed back to be torn down.
+//!
+//! Scenes can also be stacked: a scene pushed on top of the active one, e.g. a pause menu
+//! or an inventory, becomes the active scene, which is updated and receives the input.
+//! The scenes below are frozen until the scenes above them are popped again. Switching
+//! scenes only ever replaces the scene on top.
+//!
+//! Scenes can be built on worker threads while the active scene keeps running, by a
+//! [`SceneLoader`] handed to the engine; the built scene joins the registry once ready.
+
+use crate::engine::assets::AssetManager;
+use crate::engine::scene::{RenderableSprite, Scene};
+use crate::render::error::RenderError;
+use std::collections::BTreeMap;
+use std::io::Error;
 
-use crate::engine::scene::Scene;
-use crate::engine::scene::game_object::GameObject;
-use image::DynamicImage;
+/// Function building a scene on a worker thread, given the asset cache of the engine.
+///
+/// A loader does all the slow work of getting a level ready, such as reading its file
+/// and decoding its images, e.g. `Box::new(move |_| Scene::load(&path))`.
+pub type SceneLoader = dyn FnOnce(&AssetManager) -> Result<Scene, Error> + Send;
 
 /// Manages active scene and provides scene-related operations.
 pub struct SceneManager {
     /// The scene currently active in the engine.
     pub(crate) active_scene: Scene,
+    /// Name of the active scene, if it was switched to by name.
+    active_name: Option<String>,
+    /// Scenes waiting to be switched to, by name.
+    scenes: BTreeMap<String, Scene>,
+    /// Scenes covered by the active one, the bottom one first, with their names.
+    covered: Vec<(Scene, Option<String>)>,
 }
 
 impl SceneManager {
@@ -24,9 +50,86 @@ impl SceneManager {
     pub fn new(main_scene: Scene) -> Self {
         SceneManager {
             active_scene: main_scene,
+            active_name: None,
+            scenes: BTreeMap::new(),
+            covered: Vec::new(),
         }
     }
 
+    /// Registers a scene under a name for switching to it later.
+    ///
+    /// # Returns
+    /// The scene registered under the name before, if any.
+    pub fn add_scene(&mut self, name: impl Into<String>, scene: Scene) -> Option<Scene> {
+        self.scenes.insert(name.into(), scene)
+    }
+
+    /// Takes a scene out of the registry.
+    pub fn remove_scene(&mut self, name: &str) -> Option<Scene> {
+        self.scenes.remove(name)
+    }
+
+    /// Returns a registered scene.
+    pub fn scene(&self, name: &str) -> Option<&Scene> {
+        self.scenes.get(name)
+    }
+
+    /// Returns the names of the registered scenes in alphabetical order.
+    pub fn scene_names(&self) -> Vec<&str> {
+        self.scenes.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the name of the active scene, None if it was not switched to by name.
+    pub fn active_name(&self) -> Option<&str> {
+        self.active_name.as_deref()
+    }
+
+    /// Puts a scene on top of the active one, which stays frozen below it.
+    pub fn push_scene(&mut self, scene: Scene) {
+        let below = std::mem::replace(&mut self.active_scene, scene);
+        self.covered.push((below, self.active_name.take()));
+    }
+
+    /// Takes the scene on top away, making the scene below it active again.
+    ///
+    /// # Returns
+    /// The scene taken away, or None if no scene is covered by the active one.
+    pub fn pop_scene(&mut self) -> Option<Scene> {
+        let (below, name) = self.covered.pop()?;
+        self.active_name = name;
+        Some(std::mem::replace(&mut self.active_scene, below))
+    }
+
+    /// Returns the scene right below the active one, if it covers another scene.
+    pub fn covered_scene(&self) -> Option<&Scene> {
+        self.covered.last().map(|(scene, _)| scene)
+    }
+
+    /// Returns the number of scenes covered by the active one.
+    pub fn depth(&self) -> usize {
+        self.covered.len()
+    }
+
+    /// Makes a scene active without a name.
+    ///
+    /// # Returns
+    /// The scene active before.
+    pub fn set_active_scene(&mut self, scene: Scene) -> Scene {
+        self.active_name = None;
+        std::mem::replace(&mut self.active_scene, scene)
+    }
+
+    /// Makes a registered scene active, taking it out of the registry.
+    ///
+    /// # Returns
+    /// The scene active before, or None if no scene is registered under the name.
+    pub fn switch_to(&mut self, name: &str) -> Option<Scene> {
+        let scene = self.scenes.remove(name)?;
+        let old = std::mem::replace(&mut self.active_scene, scene);
+        self.active_name = Some(name.to_string());
+        Some(old)
+    }
+
     /// Returns a reference to the currently active scene.
     ///
     /// # Returns
@@ -38,12 +141,15 @@ impl SceneManager {
     /// Initializes and retrieves all renderable objects from the active scene.
     ///
     /// This method calls the `init()` method of the current scene, which prepares
-    /// sprite components sorted by their z-position for rendering.
+    /// sprite components sorted by their layer and z-position for rendering.
     ///
     /// # Returns
-    /// A vector of tuples, each containing references to game objects,
-    /// their sprite images, positional offsets, and shadow flags.
-    pub fn init_active_scene(&self) -> Vec<(&GameObject, &DynamicImage, (i32, i32), bool)> {
+    /// A vector of tuples, each containing the object's UID, references to game objects,
+    /// their sprite images, positional offsets, shadow flags and render layers.
+    ///
+    /// # Errors
+    /// Fails if a sprite of the scene is broken, see [`Scene::init`].
+    pub fn init_active_scene(&self) -> Result<Vec<RenderableSprite<'_>>, RenderError> {
         self.active_scene.init()
     }
 }
@@ -51,7 +157,7 @@ impl SceneManager {
 #[cfg(test)]
 mod tests {
     use crate::engine::scene::game_object::{
-        Object, Position,
+        GameObject, Object, Position,
         components::{Component, sprite::Sprite},
     };
 
@@ -76,6 +182,7 @@ mod tests {
             create_test_components(),
             create_test_position(0, 0, 0, false),
         )
+        .unwrap()
     }
 
     fn _create_scene_with_sprites(sprite_count: usize) -> Scene {
@@ -90,7 +197,7 @@ mod tests {
             objects.push(obj);
         }
 
-        Scene::new(objects, vec![], create_test_position(0, 0, 0, false))
+        Scene::new(objects, vec![], create_test_position(0, 0, 0, false)).unwrap()
     }
 
     #[test]
@@ -99,7 +206,8 @@ mod tests {
             vec![],
             create_test_components(),
             create_test_position(10, 20, 30, false),
-        );
+        )
+        .unwrap();
 
         let manager = SceneManager::new(scene);
 
@@ -114,7 +222,8 @@ mod tests {
             vec![],
             create_test_components(),
             create_test_position(15, 25, 35, false),
-        );
+        )
+        .unwrap();
 
         let manager = SceneManager::new(scene);
         let active = manager.active_scene();
@@ -129,7 +238,7 @@ mod tests {
         let scene = create_simple_scene();
         let manager = SceneManager::new(scene);
 
-        let renderable = manager.init_active_scene();
+        let renderable = manager.init_active_scene().unwrap();
 
         assert_eq!(renderable.len(), 0);
     }
@@ -139,17 +248,17 @@ mod tests {
     //     let scene = create_scene_with_sprites(1);
     //     let manager = SceneManager::new(scene);
 
-    //     let renderable = manager.init_active_scene();
+    //     let renderable = manager.init_active_scene().unwrap();
 
     //     assert_eq!(renderable.len(), 1);
     // }
 
     #[test]
     fn test_scene_manager_with_empty_scene() {
-        let scene = Scene::new(vec![], vec![], create_test_position(0, 0, 0, false));
+        let scene = Scene::new(vec![], vec![], create_test_position(0, 0, 0, false)).unwrap();
         let manager = SceneManager::new(scene);
 
-        let renderable = manager.init_active_scene();
+        let renderable = manager.init_active_scene().unwrap();
 
         assert_eq!(renderable.len(), 0);
     }
@@ -169,10 +278,10 @@ mod tests {
     //         vec![obj1, obj2],
     //         vec![],
     //         create_test_position(0, 0, 0, false),
-    //     );
+    //     ).unwrap();
     //     let manager = SceneManager::new(scene);
 
-    //     let renderable = manager.init_active_scene();
+    //     let renderable = manager.init_active_scene().unwrap();
 
     //     assert_eq!(renderable.len(), 0);
     // }
@@ -194,13 +303,69 @@ mod tests {
             vec![obj1, obj2],
             vec![],
             create_test_position(7, 8, 9, false),
-        );
+        )
+        .unwrap();
 
         let manager = SceneManager::new(scene);
         let active = manager.active_scene();
         assert_eq!(active.main_object.position.x, 7);
     }
 
+    #[test]
+    fn test_switch_to_registered_scene() {
+        let mut manager = SceneManager::new(create_simple_scene());
+        assert!(
+            manager
+                .add_scene(
+                    "level2",
+                    Scene::new(vec![], vec![], create_test_position(5, 0, 0, false)).unwrap()
+                )
+                .is_none()
+        );
+        manager.add_scene("level1", create_simple_scene());
+        assert_eq!(manager.scene_names(), ["level1", "level2"]);
+        assert_eq!(manager.active_name(), None);
+
+        assert!(manager.switch_to("level3").is_none());
+        let old = manager.switch_to("level2").unwrap();
+        assert_eq!(old.main_object.position.x, 0);
+        assert_eq!(manager.active_scene().main_object.position.x, 5);
+        assert_eq!(manager.active_name(), Some("level2"));
+        assert_eq!(manager.scene_names(), ["level1"]);
+
+        manager.set_active_scene(create_simple_scene());
+        assert_eq!(manager.active_name(), None);
+        assert!(manager.scene("level1").is_some());
+        assert!(manager.remove_scene("level1").is_some());
+        assert!(manager.scene_names().is_empty());
+    }
+
+    #[test]
+    fn test_push_and_pop_scenes() {
+        let scene_at =
+            |x| Scene::new(vec![], vec![], create_test_position(x, 0, 0, false)).unwrap();
+        let mut manager = SceneManager::new(create_simple_scene());
+        manager.add_scene("level", scene_at(1));
+        manager.switch_to("level");
+        assert!(manager.pop_scene().is_none());
+
+        manager.push_scene(scene_at(2));
+        manager.push_scene(scene_at(3));
+        assert_eq!(manager.depth(), 2);
+        assert_eq!(manager.active_scene().main_object.position.x, 3);
+        assert_eq!(manager.covered_scene().unwrap().main_object.position.x, 2);
+        assert_eq!(manager.active_name(), None);
+
+        // Switching replaces the scene on top only
+        manager.set_active_scene(scene_at(4));
+        assert_eq!(manager.pop_scene().unwrap().main_object.position.x, 4);
+        assert_eq!(manager.pop_scene().unwrap().main_object.position.x, 2);
+        assert_eq!(manager.active_scene().main_object.position.x, 1);
+        assert_eq!(manager.active_name(), Some("level"));
+        assert_eq!(manager.depth(), 0);
+        assert!(manager.covered_scene().is_none());
+    }
+
     #[test]
     fn test_active_scene_is_immutable_reference() {
         let scene = create_simple_scene();
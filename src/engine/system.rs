@@ -0,0 +1,75 @@
+//! Game logic spanning the whole scene.
+//!
+//! Scripts only see the object they are attached to, so logic involving several objects,
+//! such as all enemies chasing the player, is written as a `System` instead. Systems are
+//! registered with the engine and run once per update, after the object scripts, in the
+//! order they were added.
+
+use crate::engine::input::InputState;
+use crate::engine::scene::Scene;
+use std::time::Duration;
+
+/// Logic run once per update with access to the whole active scene.
+pub trait System: Send {
+    /// Runs the system for one update.
+    ///
+    /// # Parameters
+    /// - `scene`: The active scene, whose objects can be read, changed, added and removed.
+    /// - `dt`: Game time advanced by the update.
+    /// - `input`: Keyboard and mouse state of the game window.
+    fn run(&mut self, scene: &mut Scene, dt: Duration, input: &InputState);
+}
+
+/// Runs every system once, in order.
+pub(crate) fn run_systems(
+    systems: &mut [Box<dyn System>],
+    scene: &mut Scene,
+    dt: Duration,
+    input: &InputState,
+) {
+    for system in systems {
+        system.run(scene, dt, input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::game_object::Position;
+
+    /// System appending its id to a shared log.
+    struct Log(u32, std::sync::Arc<std::sync::Mutex<Vec<u32>>>);
+
+    impl System for Log {
+        fn run(&mut self, scene: &mut Scene, _dt: Duration, _input: &InputState) {
+            self.1.lock().unwrap().push(self.0);
+            scene.main_object.position.x += 1;
+        }
+    }
+
+    #[test]
+    fn test_systems_run_in_order() {
+        let log = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let mut systems: Vec<Box<dyn System>> =
+            vec![Box::new(Log(1, log.clone())), Box::new(Log(2, log.clone()))];
+        let mut scene = Scene::new(
+            vec![],
+            vec![],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap();
+        run_systems(
+            &mut systems,
+            &mut scene,
+            Duration::from_millis(10),
+            &InputState::new(),
+        );
+        assert_eq!(*log.lock().unwrap(), vec![1, 2]);
+        assert_eq!(scene.main_object.position.x, 2);
+    }
+}
@@ -0,0 +1,640 @@
+//! Frame pacing of the engine loop, its fixed update rate and frame time metrics.
+//!
+//! Without pacing the producer thread renders frames as fast as it can, keeping a CPU core
+//! busy. The `FrameLimiter` sleeps between frames so a target frame rate is held instead.
+//! Game logic is updated at a fixed rate independent of the frame rate: a `FixedTimestep`
+//! accumulates the time that passed and tells the loop how many updates are due, so the
+//! game runs at the same speed on slow and fast machines.
+//! A `GameClock` pauses and resumes the game logic, e.g. for a pause menu; frames keep
+//! being presented meanwhile. Its time scale speeds game time up or slows it down, for
+//! slow motion, hit-stop or fast-forward effects. While paused, the clock can advance the
+//! game by single updates, to debug collisions or scripts frame by frame.
+//! The window measures the frames it shows and publishes `FrameStats` once per second
+//! through a `SharedFrameStats` handle, which the engine and game code can read.
+//! Every shown frame is also counted in a `FrameHistogram` of frame times, which can be
+//! written as CSV or JSON, e.g. at shutdown to track performance regressions of a game.
+
+use std::fmt::Write;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Frame rate the engine targets unless configured otherwise.
+pub const DEFAULT_TARGET_FPS: u32 = 60;
+
+/// Updates per second of the game logic unless configured otherwise.
+pub const DEFAULT_UPDATE_RATE: u32 = 60;
+
+/// Most updates run for a single frame at normal speed; a longer backlog is dropped.
+/// A faster clock runs proportionally more.
+const MAX_UPDATES_PER_FRAME: u32 = 8;
+
+/// Width of a bucket of the frame time histogram.
+pub const HISTOGRAM_BUCKET_WIDTH: Duration = Duration::from_millis(1);
+
+/// Number of buckets of the frame time histogram; the last one also counts all longer frames.
+pub const HISTOGRAM_BUCKETS: usize = 100;
+
+/// Largest factor game time can run faster than real time.
+pub const MAX_TIME_SCALE: f32 = 16.0;
+
+/// Sleeps between frames to hold a target frame rate.
+pub struct FrameLimiter {
+    /// Time between two frames; None when the frame rate is uncapped.
+    frame_time: Option<Duration>,
+    /// Moment the next frame is due.
+    next_frame: Instant,
+}
+
+impl FrameLimiter {
+    /// Creates a limiter for the given frame rate.
+    ///
+    /// # Parameters
+    /// - `target_fps`: Frames per second to hold; None or zero leaves the rate uncapped.
+    pub fn new(target_fps: Option<u32>) -> Self {
+        FrameLimiter {
+            frame_time: target_fps
+                .filter(|fps| *fps > 0)
+                .map(|fps| Duration::from_secs(1) / fps),
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// Returns the time between two frames, or None when the frame rate is uncapped.
+    pub fn frame_time(&self) -> Option<Duration> {
+        self.frame_time
+    }
+
+    /// Computes how long to wait at `now` for the next frame and schedules the one after.
+    ///
+    /// A frame that is late by more than a whole frame time does not wait, and the schedule
+    /// restarts from `now` instead of rushing to catch up.
+    fn delay(&mut self, now: Instant) -> Duration {
+        let Some(frame_time) = self.frame_time else {
+            return Duration::ZERO;
+        };
+        if now > self.next_frame + frame_time {
+            self.next_frame = now;
+        }
+        let delay = self.next_frame.saturating_duration_since(now);
+        self.next_frame += frame_time;
+        delay
+    }
+
+    /// Sleeps until the next frame is due; returns immediately when uncapped.
+    pub fn wait(&mut self) {
+        let delay = self.delay(Instant::now());
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+}
+
+/// Accumulator running the game logic at a fixed rate.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep {
+    /// Game time advanced by one update.
+    dt: Duration,
+    /// Time passed that no update has accounted for yet.
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// Creates an accumulator for the given update rate.
+    ///
+    /// # Parameters
+    /// - `rate`: Updates per second, at least 1.
+    pub fn new(rate: u32) -> Self {
+        FixedTimestep {
+            dt: Duration::from_secs(1) / rate.max(1),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Returns the game time advanced by one update.
+    pub fn dt(&self) -> Duration {
+        self.dt
+    }
+
+    /// Accounts for the time passed since the previous frame.
+    ///
+    /// Time left over is carried to the next frame. After a long stall, e.g. while the
+    /// window was dragged, at most a few updates are run and the rest is dropped, so the
+    /// loop does not fall further behind trying to catch up. The limit grows with the
+    /// time scale, so a fast-forwarded game does not lose time on every frame.
+    ///
+    /// # Parameters
+    /// - `elapsed`: Real time since the previous frame.
+    /// - `time_scale`: Factor game time passes faster than real time, see
+    ///   [`GameClock::time_scale`].
+    ///
+    /// # Returns
+    /// The number of updates of [`FixedTimestep::dt`] to run for this frame.
+    pub fn advance(&mut self, elapsed: Duration, time_scale: f32) -> u32 {
+        self.accumulator += elapsed.mul_f64(time_scale as f64);
+        let max_updates = (MAX_UPDATES_PER_FRAME as f32 * time_scale.max(1.0)).ceil() as u32;
+        let due = self.accumulator.as_nanos() / self.dt.as_nanos();
+        if due > max_updates as u128 {
+            self.accumulator = Duration::ZERO;
+            return max_updates;
+        }
+        let updates = due as u32;
+        self.accumulator -= self.dt * updates;
+        updates
+    }
+}
+
+/// [Handle](crate#handles) to the clock driving the game logic.
+///
+/// Pausing, stepping or scaling time through any clone, e.g. from the game window's
+/// frame step key, changes the updates run by the engine loop.
+#[derive(Clone)]
+pub struct GameClock {
+    paused: Arc<AtomicBool>,
+    /// Strength of the darkening of paused frames, 0 leaving them as they are.
+    pause_dim: Arc<AtomicU8>,
+    /// Bits of the `f32` factor game time passes faster than real time.
+    time_scale: Arc<AtomicU32>,
+    /// Updates requested to run while paused.
+    ticks: Arc<AtomicU32>,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            paused: Arc::default(),
+            pause_dim: Arc::default(),
+            time_scale: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+            ticks: Arc::default(),
+        }
+    }
+}
+
+impl GameClock {
+    /// Creates a running clock at real-time speed that does not dim paused frames.
+    pub fn new() -> Self {
+        GameClock::default()
+    }
+
+    /// Halts the updates of scripts and systems; the last frame stays on screen.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Continues the updates after [`GameClock::pause`], without catching up on the paused time.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+        self.ticks.store(0, Ordering::Relaxed);
+    }
+
+    /// Runs exactly one more update while paused, then stays paused.
+    ///
+    /// Does nothing while the clock runs. Every call adds one update, whatever the time
+    /// scale; the updates run on the next frames.
+    pub fn advance_tick(&self) {
+        if self.is_paused() {
+            self.ticks.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of updates requested with [`GameClock::advance_tick`], and resets it.
+    pub(crate) fn take_ticks(&self) -> u32 {
+        self.ticks.swap(0, Ordering::Relaxed)
+    }
+
+    /// Checks whether the game logic is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Sets how much the window darkens frames while paused, e.g. behind a pause menu.
+    ///
+    /// # Parameters
+    /// - `strength`: 0 keeps paused frames as they are, 255 turns them black.
+    pub fn set_pause_dim(&self, strength: u8) {
+        self.pause_dim.store(strength, Ordering::Relaxed);
+    }
+
+    /// Returns the strength frames are darkened with while paused, see [`GameClock::set_pause_dim`].
+    pub fn pause_dim(&self) -> u8 {
+        self.pause_dim.load(Ordering::Relaxed)
+    }
+
+    /// Sets how fast game time passes compared to real time.
+    ///
+    /// The time of every update is multiplied by the scale, e.g. 0.5 for slow motion,
+    /// 0.0 to freeze the game for a hit-stop while frames keep being presented, or 2.0
+    /// to fast-forward. The fixed update rate stays the same: a scaled clock runs fewer
+    /// or more updates per second of real time.
+    ///
+    /// # Parameters
+    /// - `scale`: Factor between 0.0 and [`MAX_TIME_SCALE`]; other values are clamped,
+    ///   and NaN is taken as 0.0.
+    pub fn set_time_scale(&self, scale: f32) {
+        let scale = scale.clamp(0.0, MAX_TIME_SCALE);
+        let scale = if scale.is_nan() { 0.0 } else { scale };
+        self.time_scale.store(scale.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the factor game time passes faster than real time.
+    pub fn time_scale(&self) -> f32 {
+        f32::from_bits(self.time_scale.load(Ordering::Relaxed))
+    }
+
+    /// Converts real time passed into game time passed.
+    pub fn scale(&self, elapsed: Duration) -> Duration {
+        elapsed.mul_f32(self.time_scale())
+    }
+}
+
+/// Frame rate and frame times measured over the last second.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// Frames shown per second.
+    pub fps: f32,
+    /// Mean time between two shown frames.
+    pub average_frame_time: Duration,
+    /// Frame time that 99 percent of the frames stayed below.
+    pub p99_frame_time: Duration,
+    /// Time between the last two shown frames.
+    pub last_frame_time: Duration,
+}
+
+impl FrameStats {
+    /// Computes the statistics of a measurement period.
+    ///
+    /// # Parameters
+    /// - `frames`: Number of frames shown during the period.
+    /// - `period`: Length of the period.
+    /// - `frame_times`: Times between consecutive frames, in the order they were shown.
+    pub fn measure(frames: u32, period: Duration, frame_times: &[Duration]) -> Self {
+        let fps = if period.is_zero() {
+            0.0
+        } else {
+            frames as f32 / period.as_secs_f32()
+        };
+        let Some(&last_frame_time) = frame_times.last() else {
+            return FrameStats {
+                fps,
+                ..FrameStats::default()
+            };
+        };
+        let average_frame_time = frame_times.iter().sum::<Duration>() / frame_times.len() as u32;
+        let mut sorted = frame_times.to_vec();
+        sorted.sort_unstable();
+        let p99_index = (sorted.len() * 99).div_ceil(100) - 1;
+        FrameStats {
+            fps,
+            average_frame_time,
+            p99_frame_time: sorted[p99_index],
+            last_frame_time,
+        }
+    }
+}
+
+/// [Handle](crate#handles) to the latest frame statistics.
+///
+/// The game window updates them every second, and the performance overlay shows them.
+#[derive(Clone, Default)]
+pub struct SharedFrameStats {
+    stats: Arc<RwLock<FrameStats>>,
+}
+
+impl SharedFrameStats {
+    /// Creates a handle holding empty statistics.
+    pub fn new() -> Self {
+        SharedFrameStats::default()
+    }
+
+    /// Returns the statistics of the last full second.
+    pub fn get(&self) -> FrameStats {
+        *self.stats.read().unwrap()
+    }
+
+    /// Publishes new statistics to all handles.
+    pub(crate) fn set(&self, stats: FrameStats) {
+        *self.stats.write().unwrap() = stats;
+    }
+}
+
+/// File format of an exported frame time histogram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistogramFormat {
+    /// One `start_ms,end_ms,count` line per bucket below a header line.
+    Csv,
+    /// An object with the total frame count and a list of buckets.
+    Json,
+}
+
+impl HistogramFormat {
+    /// Picks the format from the extension of a path: JSON for `.json`, CSV otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => HistogramFormat::Json,
+            _ => HistogramFormat::Csv,
+        }
+    }
+}
+
+/// Handle to a histogram of the times between shown frames.
+///
+/// Frame times are counted in [`HISTOGRAM_BUCKETS`] buckets of [`HISTOGRAM_BUCKET_WIDTH`];
+/// the last bucket has no upper bound. Every bucket is exported, including empty ones, so
+/// the exports of different runs line up.
+///
+/// The game window records into this [handle](crate#handles) and the engine saves it
+/// when shutting down.
+#[derive(Clone)]
+pub struct FrameHistogram {
+    counts: Arc<RwLock<Vec<u64>>>,
+}
+
+impl Default for FrameHistogram {
+    fn default() -> Self {
+        FrameHistogram {
+            counts: Arc::new(RwLock::new(vec![0; HISTOGRAM_BUCKETS])),
+        }
+    }
+}
+
+impl FrameHistogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        FrameHistogram::default()
+    }
+
+    /// Counts a frame time in its bucket.
+    pub fn record(&self, frame_time: Duration) {
+        let bucket = (frame_time.as_nanos() / HISTOGRAM_BUCKET_WIDTH.as_nanos()) as usize;
+        self.counts.write().unwrap()[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+    }
+
+    /// Returns the number of frames counted in every bucket, shortest frame times first.
+    pub fn counts(&self) -> Vec<u64> {
+        self.counts.read().unwrap().clone()
+    }
+
+    /// Returns the number of frames counted in all buckets.
+    pub fn total(&self) -> u64 {
+        self.counts.read().unwrap().iter().sum()
+    }
+
+    /// Forgets all counted frames.
+    pub fn clear(&self) {
+        self.counts.write().unwrap().fill(0);
+    }
+
+    /// Returns the bounds of a bucket in milliseconds; the last bucket has no upper bound.
+    fn bucket_bounds(index: usize) -> (u128, Option<u128>) {
+        let width = HISTOGRAM_BUCKET_WIDTH.as_millis();
+        let start = index as u128 * width;
+        (
+            start,
+            (index + 1 < HISTOGRAM_BUCKETS).then_some(start + width),
+        )
+    }
+
+    /// Formats the histogram as CSV, leaving the upper bound of the last bucket empty.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("start_ms,end_ms,count\n");
+        for (i, count) in self.counts().into_iter().enumerate() {
+            let (start, end) = Self::bucket_bounds(i);
+            let end = end.map(|end| end.to_string()).unwrap_or_default();
+            let _ = writeln!(csv, "{start},{end},{count}");
+        }
+        csv
+    }
+
+    /// Formats the histogram as JSON, with a null upper bound for the last bucket.
+    pub fn to_json(&self) -> String {
+        let counts = self.counts();
+        let buckets: Vec<String> = counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let (start, end) = Self::bucket_bounds(i);
+                let end = end.map_or("null".to_string(), |end| end.to_string());
+                format!("{{\"start_ms\":{start},\"end_ms\":{end},\"count\":{count}}}")
+            })
+            .collect();
+        format!(
+            "{{\"frames\":{},\"buckets\":[{}]}}\n",
+            counts.iter().sum::<u64>(),
+            buckets.join(",")
+        )
+    }
+
+    /// Writes the histogram to a file.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the file; its extension selects the format, see
+    ///   [`HistogramFormat::from_path`].
+    ///
+    /// # Errors
+    /// Fails if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let text = match HistogramFormat::from_path(path) {
+            HistogramFormat::Csv => self.to_csv(),
+            HistogramFormat::Json => self.to_json(),
+        };
+        fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncapped_never_waits() {
+        let mut limiter = FrameLimiter::new(None);
+        assert_eq!(limiter.frame_time(), None);
+        assert_eq!(limiter.delay(Instant::now()), Duration::ZERO);
+        assert_eq!(FrameLimiter::new(Some(0)).frame_time(), None);
+    }
+
+    #[test]
+    fn test_frames_are_paced() {
+        let mut limiter = FrameLimiter::new(Some(50));
+        let start = limiter.next_frame;
+        assert_eq!(limiter.frame_time(), Some(Duration::from_millis(20)));
+
+        assert_eq!(limiter.delay(start), Duration::ZERO);
+        assert_eq!(
+            limiter.delay(start + Duration::from_millis(5)),
+            Duration::from_millis(15)
+        );
+    }
+
+    #[test]
+    fn test_late_frame_restarts_schedule() {
+        let mut limiter = FrameLimiter::new(Some(50));
+        let late = limiter.next_frame + Duration::from_millis(100);
+        assert_eq!(limiter.delay(late), Duration::ZERO);
+        assert_eq!(
+            limiter.delay(late + Duration::from_millis(5)),
+            Duration::from_millis(15)
+        );
+    }
+
+    #[test]
+    fn test_fixed_timestep_carries_remainder() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(timestep.dt(), Duration::from_millis(10));
+
+        assert_eq!(timestep.advance(Duration::from_millis(4), 1.0), 0);
+        assert_eq!(timestep.advance(Duration::from_millis(7), 1.0), 1);
+        assert_eq!(timestep.advance(Duration::from_millis(29), 1.0), 3);
+        assert_eq!(timestep.accumulator, Duration::ZERO);
+        assert_eq!(FixedTimestep::new(0).dt(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_fixed_timestep_drops_long_backlog() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(
+            timestep.advance(Duration::from_secs(5), 1.0),
+            MAX_UPDATES_PER_FRAME
+        );
+        assert_eq!(timestep.advance(Duration::from_millis(5), 1.0), 0);
+        // A faster clock may run more updates before dropping the backlog
+        assert_eq!(
+            timestep.advance(Duration::from_secs(5), MAX_TIME_SCALE),
+            MAX_UPDATES_PER_FRAME * MAX_TIME_SCALE as u32
+        );
+        assert_eq!(timestep.advance(Duration::from_millis(50), 0.5), 2);
+    }
+
+    #[test]
+    fn test_clock_pauses_and_resumes() {
+        let clock = GameClock::new();
+        assert!(!clock.is_paused());
+        clock.clone().pause();
+        clock.set_pause_dim(96);
+        assert!(clock.is_paused());
+        assert_eq!(clock.pause_dim(), 96);
+        clock.resume();
+        assert!(!clock.is_paused());
+    }
+
+    #[test]
+    fn test_advance_tick_only_while_paused() {
+        let clock = GameClock::new();
+        clock.advance_tick();
+        assert_eq!(clock.take_ticks(), 0);
+
+        clock.pause();
+        clock.advance_tick();
+        clock.clone().advance_tick();
+        assert_eq!(clock.take_ticks(), 2);
+        assert_eq!(clock.take_ticks(), 0);
+        clock.advance_tick();
+        clock.resume();
+        assert_eq!(clock.take_ticks(), 0);
+    }
+
+    #[test]
+    fn test_time_scale_is_clamped() {
+        let clock = GameClock::new();
+        assert_eq!(clock.time_scale(), 1.0);
+        clock.clone().set_time_scale(0.5);
+        assert_eq!(
+            clock.scale(Duration::from_millis(20)),
+            Duration::from_millis(10)
+        );
+        clock.set_time_scale(-1.0);
+        assert_eq!(clock.time_scale(), 0.0);
+        clock.set_time_scale(f32::INFINITY);
+        assert_eq!(clock.time_scale(), MAX_TIME_SCALE);
+        clock.set_time_scale(f32::NAN);
+        assert_eq!(clock.scale(Duration::from_secs(1)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_measure_frame_stats() {
+        let mut frame_times = vec![Duration::from_millis(10); 99];
+        frame_times.push(Duration::from_millis(110));
+        let stats = FrameStats::measure(100, Duration::from_secs(2), &frame_times);
+        assert_eq!(stats.fps, 50.0);
+        assert_eq!(stats.average_frame_time, Duration::from_millis(11));
+        assert_eq!(stats.p99_frame_time, Duration::from_millis(10));
+        assert_eq!(stats.last_frame_time, Duration::from_millis(110));
+
+        let empty = FrameStats::measure(0, Duration::ZERO, &[]);
+        assert_eq!(empty, FrameStats::default());
+    }
+
+    #[test]
+    fn test_shared_stats_are_seen_by_clones() {
+        let stats = SharedFrameStats::new();
+        let clone = stats.clone();
+        let measured = FrameStats::measure(1, Duration::from_secs(1), &[Duration::from_millis(5)]);
+        stats.set(measured);
+        assert_eq!(clone.get(), measured);
+    }
+
+    #[test]
+    fn test_histogram_buckets_frame_times() {
+        let histogram = FrameHistogram::new();
+        histogram.clone().record(Duration::from_micros(16_700));
+        histogram.record(Duration::from_millis(16));
+        histogram.record(Duration::ZERO);
+        histogram.record(Duration::from_secs(3));
+        let counts = histogram.counts();
+        assert_eq!(counts.len(), HISTOGRAM_BUCKETS);
+        assert_eq!(
+            (counts[0], counts[16], counts[HISTOGRAM_BUCKETS - 1]),
+            (1, 2, 1)
+        );
+        assert_eq!(histogram.total(), 4);
+
+        histogram.clear();
+        assert_eq!(histogram.total(), 0);
+    }
+
+    #[test]
+    fn test_histogram_exports() {
+        let histogram = FrameHistogram::new();
+        histogram.record(Duration::from_millis(1));
+        histogram.record(Duration::from_secs(1));
+
+        let csv = histogram.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), HISTOGRAM_BUCKETS + 1);
+        assert_eq!(lines[0], "start_ms,end_ms,count");
+        assert_eq!(lines[1], "0,1,0");
+        assert_eq!(lines[2], "1,2,1");
+        assert_eq!(lines[HISTOGRAM_BUCKETS], "99,,1");
+
+        let json = histogram.to_json();
+        assert!(json.starts_with(r#"{"frames":2,"buckets":[{"start_ms":0,"end_ms":1,"count":0},"#));
+        assert!(json.contains(r#"{"start_ms":1,"end_ms":2,"count":1}"#));
+        assert!(
+            json.trim_end()
+                .ends_with(r#"{"start_ms":99,"end_ms":null,"count":1}]}"#)
+        );
+    }
+
+    #[test]
+    fn test_histogram_saved_in_format_of_extension() {
+        assert_eq!(
+            HistogramFormat::from_path(Path::new("frames.JSON")),
+            HistogramFormat::Json
+        );
+        assert_eq!(
+            HistogramFormat::from_path(Path::new("frames")),
+            HistogramFormat::Csv
+        );
+
+        let histogram = FrameHistogram::new();
+        histogram.record(Duration::from_millis(5));
+        let path = std::env::temp_dir().join("rusty_ache_test_histogram.json");
+        histogram.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), histogram.to_json());
+        let _ = fs::remove_file(&path);
+    }
+}
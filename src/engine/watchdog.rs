@@ -0,0 +1,376 @@
+//! Detection of a stalled engine loop.
+//!
+//! The producer thread reports every finished tick and the stage of the tick it is in
+//! through a `Heartbeat`. A `Watchdog` running on its own thread checks the heartbeat
+//! and, when no tick finished for the configured timeout, logs what the producer was
+//! doing: the stage it is stuck in, the locks held in that stage and whether it panicked.
+//! Without a watchdog a deadlocked or crashed producer only shows as a frozen window.
+//! The watchdog can also shut the engine down, so the game exits with an error instead.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use winit::window::Window;
+
+/// Time without a finished tick after which the loop counts as stalled, unless configured otherwise.
+pub const DEFAULT_WATCHDOG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest time the watchdog sleeps between two checks.
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Part of a tick the producer thread is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Waiting for the game window to open.
+    Waiting,
+    /// Sleeping until the next frame is due.
+    Pacing,
+    /// Running scripts and systems.
+    Updating,
+    /// Drawing the frame.
+    Rendering,
+    /// Applying post effects and the overlays to the frame.
+    Emitting,
+    /// Handing the frame to the window.
+    Presenting,
+    /// The loop has ended.
+    Stopped,
+}
+
+impl Stage {
+    /// Every stage, in the order of their discriminants.
+    const ALL: [Stage; 7] = [
+        Stage::Waiting,
+        Stage::Pacing,
+        Stage::Updating,
+        Stage::Rendering,
+        Stage::Emitting,
+        Stage::Presenting,
+        Stage::Stopped,
+    ];
+
+    /// Describes the locks the producer holds during the stage.
+    pub fn held_locks(self) -> &'static str {
+        match self {
+            Stage::Updating => "renderer (write) and systems",
+            Stage::Rendering | Stage::Emitting => "renderer (write)",
+            Stage::Waiting | Stage::Pacing | Stage::Presenting | Stage::Stopped => "none",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Waiting => "waiting for the window",
+            Stage::Pacing => "pacing",
+            Stage::Updating => "updating scripts and systems",
+            Stage::Rendering => "rendering",
+            Stage::Emitting => "emitting",
+            Stage::Presenting => "presenting",
+            Stage::Stopped => "stopped",
+        })
+    }
+}
+
+/// [Handle](crate#handles) the producer thread reports its progress through.
+///
+/// The producer beats and enters stages on its clone, which the watchdog reads.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    /// Moment the beats are measured from.
+    origin: Instant,
+    /// Nanoseconds from `origin` to the last finished tick.
+    last_beat: Arc<AtomicU64>,
+    /// Index of the current stage in [`Stage::ALL`].
+    stage: Arc<AtomicU8>,
+    /// Whether the producer thread panicked.
+    panicked: Arc<AtomicBool>,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Heartbeat {
+            origin: Instant::now(),
+            last_beat: Arc::new(AtomicU64::new(0)),
+            stage: Arc::new(AtomicU8::new(Stage::Waiting as u8)),
+            panicked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Heartbeat {
+    /// Creates a heartbeat of a producer that is waiting for the window.
+    pub fn new() -> Self {
+        Heartbeat::default()
+    }
+
+    /// Records that a tick finished.
+    pub fn beat(&self) {
+        self.beat_at(Instant::now());
+    }
+
+    /// Records that a tick finished at the given moment.
+    fn beat_at(&self, now: Instant) {
+        let nanos = now.saturating_duration_since(self.origin).as_nanos() as u64;
+        self.last_beat.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Records the stage the producer enters.
+    pub fn enter(&self, stage: Stage) {
+        self.stage.store(stage as u8, Ordering::Relaxed);
+    }
+
+    /// Returns the stage the producer is in.
+    pub fn stage(&self) -> Stage {
+        Stage::ALL[self.stage.load(Ordering::Relaxed) as usize]
+    }
+
+    /// Records that the producer thread panicked in its current stage.
+    pub(crate) fn set_panicked(&self) {
+        self.panicked.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks whether the producer thread panicked.
+    pub fn has_panicked(&self) -> bool {
+        self.panicked.load(Ordering::Relaxed)
+    }
+
+    /// Returns the time that passed since the last finished tick, or since the heartbeat
+    /// was created if no tick finished yet.
+    pub fn since_last_beat(&self, now: Instant) -> Duration {
+        let last = self.origin + Duration::from_nanos(self.last_beat.load(Ordering::Relaxed));
+        now.saturating_duration_since(last)
+    }
+}
+
+/// Marks the heartbeat of a panicking producer when dropped during unwinding.
+pub(crate) struct PanicGuard(pub(crate) Heartbeat);
+
+impl Drop for PanicGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.set_panicked();
+        }
+    }
+}
+
+/// What the watchdog does when the engine loop stalls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchdogSettings {
+    /// Time without a finished tick after which the loop counts as stalled.
+    pub timeout: Duration,
+    /// Whether to shut the engine down, making `run` fail, instead of only logging.
+    pub exit: bool,
+}
+
+impl Default for WatchdogSettings {
+    /// Logs stalls of [`DEFAULT_WATCHDOG_TIMEOUT`] without exiting.
+    fn default() -> Self {
+        WatchdogSettings {
+            timeout: DEFAULT_WATCHDOG_TIMEOUT,
+            exit: false,
+        }
+    }
+}
+
+/// Diagnostics of a stalled engine loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stall {
+    /// Stage the producer was in when it stopped finishing ticks.
+    pub stage: Stage,
+    /// Time since the last finished tick.
+    pub since: Duration,
+    /// Whether the producer thread panicked.
+    pub panicked: bool,
+}
+
+impl fmt::Display for Stall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no frame for {:.1}s, last stage: {}, locks held: {}",
+            self.since.as_secs_f32(),
+            self.stage,
+            self.stage.held_locks()
+        )?;
+        if self.panicked {
+            f.write_str(", producer panicked")?;
+        }
+        Ok(())
+    }
+}
+
+/// Watches the heartbeat of the producer thread.
+pub struct Watchdog {
+    heartbeat: Heartbeat,
+    settings: WatchdogSettings,
+}
+
+impl Watchdog {
+    /// Creates a watchdog of the given heartbeat.
+    pub fn new(heartbeat: Heartbeat, settings: WatchdogSettings) -> Self {
+        Watchdog {
+            heartbeat,
+            settings,
+        }
+    }
+
+    /// Checks whether the loop is stalled.
+    ///
+    /// A producer waiting for the window or one that stopped normally never stalls.
+    ///
+    /// # Parameters
+    /// - `now`: Moment of the check.
+    ///
+    /// # Returns
+    /// The diagnostics of the stall, or None if the loop is running.
+    pub fn check(&self, now: Instant) -> Option<Stall> {
+        let stage = self.heartbeat.stage();
+        let panicked = self.heartbeat.has_panicked();
+        if matches!(stage, Stage::Waiting | Stage::Stopped) && !panicked {
+            return None;
+        }
+        let since = self.heartbeat.since_last_beat(now);
+        (panicked || since >= self.settings.timeout).then_some(Stall {
+            stage,
+            since,
+            panicked,
+        })
+    }
+
+    /// Checks the heartbeat until the shutdown flag is set.
+    ///
+    /// A stall is logged once; if the loop recovers, that is logged too. When the settings
+    /// ask to exit, the shutdown flag is set at the first stall and the window is woken up,
+    /// so the event loop ends.
+    ///
+    /// # Parameters
+    /// - `window`: Slot of the game window, woken up when exiting.
+    /// - `shutdown`: Flag ending the engine loop.
+    ///
+    /// # Returns
+    /// The stall that made the watchdog exit, or None if the engine shut down otherwise.
+    pub(crate) fn run(
+        self,
+        window: Arc<RwLock<Option<Arc<Window>>>>,
+        shutdown: Arc<AtomicBool>,
+    ) -> Option<Stall> {
+        let poll = (self.settings.timeout / 4).clamp(Duration::from_millis(1), MAX_POLL_INTERVAL);
+        let mut reported = false;
+        while !shutdown.load(Ordering::Relaxed) {
+            thread::sleep(poll);
+            match self.check(Instant::now()) {
+                Some(stall) if !reported => {
+                    log::error!("Engine loop stalled: {stall}");
+                    reported = true;
+                    if self.settings.exit {
+                        shutdown.store(true, Ordering::Relaxed);
+                        if let Some(window) = window.read().unwrap().as_ref() {
+                            window.request_redraw();
+                        }
+                        return Some(stall);
+                    }
+                }
+                None if reported => {
+                    log::info!("Engine loop recovered");
+                    reported = false;
+                }
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(exit: bool) -> WatchdogSettings {
+        WatchdogSettings {
+            timeout: Duration::from_millis(100),
+            exit,
+        }
+    }
+
+    #[test]
+    fn test_stage_round_trips_through_heartbeat() {
+        let heartbeat = Heartbeat::new();
+        assert_eq!(heartbeat.stage(), Stage::Waiting);
+        for stage in Stage::ALL {
+            heartbeat.clone().enter(stage);
+            assert_eq!(heartbeat.stage(), stage);
+        }
+    }
+
+    #[test]
+    fn test_stall_detected_after_timeout() {
+        let heartbeat = Heartbeat::new();
+        let watchdog = Watchdog::new(heartbeat.clone(), settings(false));
+        let start = Instant::now();
+        heartbeat.beat_at(start);
+        heartbeat.enter(Stage::Rendering);
+        assert_eq!(watchdog.check(start + Duration::from_millis(50)), None);
+
+        let stall = watchdog.check(start + Duration::from_millis(150)).unwrap();
+        assert_eq!(stall.stage, Stage::Rendering);
+        assert!(!stall.panicked);
+        assert!(stall.since >= Duration::from_millis(150));
+        assert!(stall.to_string().contains("locks held: renderer (write)"));
+
+        // Waiting for the window and stopping are no stalls
+        heartbeat.enter(Stage::Waiting);
+        assert_eq!(watchdog.check(start + Duration::from_secs(10)), None);
+        heartbeat.enter(Stage::Stopped);
+        assert_eq!(watchdog.check(start + Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_panic_is_reported_immediately() {
+        let heartbeat = Heartbeat::new();
+        let guard_heartbeat = heartbeat.clone();
+        let result = thread::spawn(move || {
+            let _guard = PanicGuard(guard_heartbeat.clone());
+            guard_heartbeat.enter(Stage::Updating);
+            panic!("script failed");
+        })
+        .join();
+        assert!(result.is_err());
+
+        heartbeat.beat();
+        let stall = Watchdog::new(heartbeat, settings(false))
+            .check(Instant::now())
+            .unwrap();
+        assert!(stall.panicked);
+        assert_eq!(stall.stage, Stage::Updating);
+        assert!(stall.to_string().ends_with("producer panicked"));
+    }
+
+    #[test]
+    fn test_exiting_watchdog_sets_shutdown() {
+        let heartbeat = Heartbeat::new();
+        heartbeat.enter(Stage::Emitting);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stall = Watchdog::new(heartbeat, settings(true))
+            .run(Arc::new(RwLock::new(None)), shutdown.clone())
+            .unwrap();
+        assert_eq!(stall.stage, Stage::Emitting);
+        assert!(shutdown.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_logging_watchdog_stops_on_shutdown() {
+        let heartbeat = Heartbeat::new();
+        heartbeat.enter(Stage::Rendering);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let watchdog = Watchdog::new(heartbeat, settings(false));
+        let flag = shutdown.clone();
+        let handle = thread::spawn(move || watchdog.run(Arc::new(RwLock::new(None)), flag));
+        thread::sleep(Duration::from_millis(200));
+        shutdown.store(true, Ordering::Relaxed);
+        assert_eq!(handle.join().unwrap(), None);
+    }
+}
@@ -5,18 +5,22 @@
 //! and create configured instances of the game engine.
 //!
 //! These functions support workflow from asset loading to scene setup to engine initialization.
+//! Scenes can also be described in a scene file and loaded with [`load_scene`].
+//! Images are loaded through an `AssetManager`, so an image shared by several objects
+//! is decoded only once.
 
-use image::ImageReader;
+use image::DynamicImage;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::{
-    Resolution,
-    engine::{
-        Engine, GameEngine,
-        config::{Config, EngineConfig},
-        scene::{
-            Scene,
-            game_object::{GameObject, Object, Position, components::sprite::Sprite},
-        },
+use crate::engine::{
+    GameEngine,
+    assets::AssetManager,
+    builder::EngineBuilder,
+    error::EngineError,
+    scene::{
+        Scene,
+        game_object::{GameObject, Object, Position, components::sprite::Sprite},
     },
 };
 
@@ -46,13 +50,23 @@ pub struct ObjectWithImage<'a> {
 ///
 /// # Returns
 /// Vector of fully constructed game objects ready for scene insertion.
-pub fn create_gameobj_vec(objs: &[ObjectWithImage]) -> Vec<GameObject> {
+///
+/// # Errors
+/// Fails with [`EngineError::Asset`] if an image cannot be loaded.
+pub fn create_gameobj_vec(objs: &[ObjectWithImage]) -> Result<Vec<GameObject>, EngineError> {
+    create_gameobj_vec_with_assets(objs, &AssetManager::new())
+}
+
+/// Same as [`create_gameobj_vec`], loading the images through the given asset manager.
+pub fn create_gameobj_vec_with_assets(
+    objs: &[ObjectWithImage],
+    assets: &AssetManager,
+) -> Result<Vec<GameObject>, EngineError> {
     let mut res = Vec::new();
-    let mut z_coord = 1;
-    for obj in objs {
+    for (z_coord, obj) in (1..).zip(objs.iter()) {
         res.push(GameObject::new(
-            vec![Box::new(Sprite::new(
-                Some(ImageReader::open(obj.image_path).unwrap().decode().unwrap()),
+            vec![Box::new(Sprite::shared(
+                load_image(assets, obj.image_path)?,
                 obj.has_shadow,
                 (0, 0),
             ))],
@@ -64,9 +78,46 @@ pub fn create_gameobj_vec(objs: &[ObjectWithImage]) -> Vec<GameObject> {
                 is_relative: false,
             },
         ));
-        z_coord += 1;
     }
-    res
+    Ok(res)
+}
+
+/// Loads an image through the asset manager, reporting failures as asset errors.
+fn load_image(assets: &AssetManager, path: &str) -> Result<Arc<DynamicImage>, EngineError> {
+    assets
+        .load_image(Path::new(path))
+        .map_err(|source| EngineError::Asset {
+            path: PathBuf::from(path),
+            source,
+        })
+}
+
+/// Same as [`create_gameobj_vec_with_assets`], loading the images on worker threads.
+///
+/// Returns at once; every sprite shows a placeholder until its image is loaded.
+pub fn create_gameobj_vec_async(
+    objs: &[ObjectWithImage],
+    assets: &AssetManager,
+) -> Vec<GameObject> {
+    (1..)
+        .zip(objs.iter())
+        .map(|(z_coord, obj)| {
+            GameObject::new(
+                vec![Box::new(Sprite::loading(
+                    assets.load_image_async(Path::new(obj.image_path)),
+                    obj.has_shadow,
+                    (0, 0),
+                ))],
+                None,
+                Position {
+                    x: obj.x,
+                    y: obj.y,
+                    z: z_coord,
+                    is_relative: false,
+                },
+            )
+        })
+        .collect()
 }
 
 /// Creates an `ObjectWithImage` instance from image path and position data.
@@ -80,7 +131,12 @@ pub fn create_gameobj_vec(objs: &[ObjectWithImage]) -> Vec<GameObject> {
 ///
 /// # Returns
 /// A new `ObjectWithImage` instance.
-pub fn create_obj_with_img(image_path: &str, x: i32, y: i32, has_shadow: bool) -> ObjectWithImage {
+pub fn create_obj_with_img(
+    image_path: &str,
+    x: i32,
+    y: i32,
+    has_shadow: bool,
+) -> ObjectWithImage<'_> {
     ObjectWithImage {
         image_path,
         x,
@@ -92,7 +148,7 @@ pub fn create_obj_with_img(image_path: &str, x: i32, y: i32, has_shadow: bool) -
 /// Initializes a `Scene` from a slice of background objects and a single main object.
 ///
 /// Converts all background objects into game objects with sprites and setups the main object
-/// with its sprite and offset.
+/// with its sprite and offset. The main object is placed above all background objects.
 ///
 /// # Parameters
 /// - `objs`: Slice of background `ObjectWithImage`.
@@ -100,27 +156,44 @@ pub fn create_obj_with_img(image_path: &str, x: i32, y: i32, has_shadow: bool) -
 ///
 /// # Returns
 /// A full `Scene` instance initialized and ready for rendering.
-pub fn init_scene(objs: &[ObjectWithImage], main_obj: ObjectWithImage) -> Scene {
-    let game_objs = create_gameobj_vec(objs);
-    Scene::new(
+///
+/// # Errors
+/// Fails with [`EngineError::Asset`] if an image cannot be loaded, and with
+/// [`EngineError::Component`] if there are more objects than a scene can hold.
+pub fn init_scene(
+    objs: &[ObjectWithImage],
+    main_obj: ObjectWithImage,
+) -> Result<Scene, EngineError> {
+    let assets = AssetManager::new();
+    let game_objs = create_gameobj_vec_with_assets(objs, &assets)?;
+    Ok(Scene::new(
         game_objs,
-        vec![Box::new(Sprite::new(
-            Some(
-                ImageReader::open(main_obj.image_path)
-                    .unwrap()
-                    .decode()
-                    .unwrap(),
-            ),
+        vec![Box::new(Sprite::shared(
+            load_image(&assets, main_obj.image_path)?,
             true,
             (60, -60),
         ))],
         Position {
             x: main_obj.x,
             y: main_obj.y,
-            z: 0,
+            z: objs.len() as i32 + 1,
             is_relative: false,
         },
-    )
+    )?)
+}
+
+/// Loads a `Scene` from a scene file, see [`Scene::load`].
+///
+/// # Parameters
+/// - `path`: Path of the scene file.
+///
+/// # Returns
+/// The loaded scene.
+///
+/// # Errors
+/// Fails with [`EngineError::Scene`] if the file or one of its sprites cannot be loaded.
+pub fn load_scene(path: &str) -> Result<Scene, EngineError> {
+    Scene::load(Path::new(path)).map_err(EngineError::Scene)
 }
 
 /// Creates and initializes a `GameEngine` instance using the given scene and resolution.
@@ -134,16 +207,14 @@ pub fn init_scene(objs: &[ObjectWithImage], main_obj: ObjectWithImage) -> Scene
 /// # Returns
 /// A fully initialized `GameEngine` ready to run.
 pub fn init_engine(scene: Scene, width: u32, height: u32) -> GameEngine {
-    GameEngine::new(
-        Box::new(EngineConfig::new(Resolution::new(width, height))),
-        scene,
-    )
+    EngineBuilder::new()
+        .resolution(width, height)
+        .scene(scene)
+        .build()
 }
 
 #[cfg(test)]
 mod tests {
-    use std::char::TryFromCharError;
-
     use super::*;
 
     #[test]
@@ -163,12 +234,45 @@ mod tests {
             200,
             false,
         )];
-        let owi = create_gameobj_vec(&objs);
+        let owi = create_gameobj_vec(&objs).unwrap();
         assert_eq!(owi.len(), objs.len());
         assert_eq!(objs[0].x, owi[0].position.x);
         assert_eq!(objs[0].y, owi[0].position.y);
     }
 
+    #[test]
+    fn test_create_gameobj_vec_shares_images() {
+        let objs = [
+            create_obj_with_img("./resources/perf_diag.png", 0, 0, false),
+            create_obj_with_img("./resources/perf_diag.png", 10, 10, false),
+        ];
+        let assets = AssetManager::new();
+        let owi = create_gameobj_vec_with_assets(&objs, &assets).unwrap();
+        assert_eq!(owi.len(), 2);
+        assert_eq!(assets.len(), 1);
+    }
+
+    #[test]
+    fn test_create_gameobj_vec_async() {
+        let objs = [create_obj_with_img(
+            "./resources/perf_diag.png",
+            5,
+            6,
+            false,
+        )];
+        let owi = create_gameobj_vec_async(&objs, &AssetManager::new());
+        assert_eq!(owi.len(), 1);
+        assert_eq!((owi[0].position.x, owi[0].position.z), (5, 1));
+    }
+
+    #[test]
+    fn test_load_scene() {
+        assert!(load_scene("./resources/missing_scene.toml").is_err());
+        let scene = load_scene("./src/bin/resources/scene.toml").unwrap();
+        assert_eq!(scene.uids().len(), 7);
+        assert_eq!(scene.main_object.position.z, 8);
+    }
+
     #[test]
     fn test_init_scene() {
         let objs = [create_obj_with_img(
@@ -180,8 +284,19 @@ mod tests {
         let main_obj = create_obj_with_img("./resources/perf_diag.png", 300, 300, true);
         let main_obj_x = main_obj.x;
         let main_obj_y = main_obj.y;
-        let scene = init_scene(&objs, main_obj);
+        let scene = init_scene(&objs, main_obj).unwrap();
         assert_eq!(scene.main_object.position.x, main_obj_x);
         assert_eq!(scene.main_object.position.y, main_obj_y);
     }
+
+    #[test]
+    fn test_missing_image_is_an_asset_error() {
+        let objs = [create_obj_with_img("./resources/missing.png", 0, 0, false)];
+        assert!(matches!(
+            create_gameobj_vec(&objs),
+            Err(EngineError::Asset { path, .. }) if path.ends_with("missing.png")
+        ));
+        let main_obj = create_obj_with_img("./resources/missing.png", 0, 0, true);
+        assert!(init_scene(&[], main_obj).is_err());
+    }
 }
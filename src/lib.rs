@@ -2,8 +2,16 @@
 //!
 //! This module exposes submodules for the core engine logic, user interface,
 //! rendering subsystem, and screen management.
+//!
+//! # Handles
+//!
+//! State shared between the game window, the engine loop, scripts and systems is reached
+//! through handles, such as [`engine::input::InputState`] or [`engine::audio::Audio`].
+//! A handle wraps its state in an `Arc`: it is cheap to clone and can be sent to other
+//! threads, and all clones of a handle see and change the same state.
 pub mod engine;
 pub mod interface;
+pub mod logging;
 pub mod render;
 pub mod screen;
 
@@ -0,0 +1,152 @@
+//! Built-in logger for the engine diagnostics.
+//!
+//! The engine reports its errors and notable events through the `log` crate, so any
+//! logger can be used to show them. For games that don't set one up themselves,
+//! [`init`] installs a small logger writing to stderr, configured by the
+//! `RUSTY_ACHE_LOG` environment variable in the style of `env_logger`: a comma-separated
+//! list of `module=level` directives, with a bare level applying to all other modules,
+//! e.g. `RUSTY_ACHE_LOG=warn,rusty_ache::render=debug`.
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::Write;
+
+/// Environment variable holding the filter directives of the built-in logger.
+pub const LOG_ENV: &str = "RUSTY_ACHE_LOG";
+
+/// Level of modules no directive matches, unless the filter sets another one.
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// Logger writing records to stderr, filtered by module and level.
+pub struct Logger {
+    /// Level of modules no directive matches.
+    default: LevelFilter,
+    /// Module paths with their levels, the longest path first.
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl Logger {
+    /// Creates a logger from filter directives such as `warn,rusty_ache::render=debug`.
+    ///
+    /// Directives that cannot be parsed are skipped.
+    pub fn new(filter: &str) -> Self {
+        let mut default = DEFAULT_LEVEL;
+        let mut directives = vec![];
+        for directive in filter.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        directives.push((module.trim().to_string(), level));
+                    }
+                }
+                None => match directive.parse() {
+                    Ok(level) => default = level,
+                    // A bare module name enables all its records.
+                    Err(_) => directives.push((directive.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+        directives.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Logger {
+            default,
+            directives,
+        }
+    }
+
+    /// Creates a logger from the `RUSTY_ACHE_LOG` environment variable.
+    pub fn from_env() -> Self {
+        Logger::new(&std::env::var(LOG_ENV).unwrap_or_default())
+    }
+
+    /// Returns the level records of the module must reach to be written.
+    ///
+    /// # Parameters
+    /// - `target`: Module path of the records, e.g. `rusty_ache::render::gpu`.
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// Returns the most verbose level any module is logged at.
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(
+                std::io::stderr(),
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Installs the built-in logger, configured by the `RUSTY_ACHE_LOG` environment variable.
+///
+/// # Errors
+/// Fails if a logger has already been installed.
+pub fn init() -> Result<(), SetLoggerError> {
+    init_with(Logger::from_env())
+}
+
+/// Installs the given logger as the global one.
+///
+/// # Errors
+/// Fails if a logger has already been installed.
+pub fn init_with(logger: Logger) -> Result<(), SetLoggerError> {
+    let max_level = logger.max_level();
+    log::set_boxed_logger(Box::new(logger))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_by_module_and_level() {
+        let logger = Logger::new("warn, rusty_ache::render=debug,rusty_ache::render::gpu=off");
+        assert_eq!(logger.level("rusty_ache::screen"), LevelFilter::Warn);
+        assert_eq!(logger.level("rusty_ache::render"), LevelFilter::Debug);
+        assert_eq!(logger.level("rusty_ache::render::cpu"), LevelFilter::Debug);
+        assert_eq!(logger.level("rusty_ache::render::gpu"), LevelFilter::Off);
+        assert_eq!(logger.level("rusty_ache::renderer"), LevelFilter::Warn);
+        assert_eq!(logger.max_level(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_default_and_invalid_directives() {
+        let logger = Logger::new("");
+        assert_eq!(logger.level("rusty_ache"), DEFAULT_LEVEL);
+
+        let logger = Logger::new("rusty_ache::engine=loud,,rusty_ache::screen");
+        assert_eq!(logger.level("rusty_ache::engine"), DEFAULT_LEVEL);
+        assert_eq!(
+            logger.level("rusty_ache::screen::shaders"),
+            LevelFilter::Trace
+        );
+    }
+}
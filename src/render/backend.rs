@@ -0,0 +1,124 @@
+//! Abstraction over the part of the renderer that turns prepared sprites into frame pixels.
+//!
+//! The `Renderer` decides *what* is drawn: the active scene, the camera position and the
+//! drawing order. A `RenderBackend` decides *how* it is drawn: either in software on the CPU
+//! or with a GPU compositor built on wgpu. Both produce the same RGBA frame for the screen.
+
+use image::DynamicImage;
+
+use crate::Resolution;
+use crate::engine::scene::RenderableSprite;
+
+use super::Pixel;
+use super::cpu::CpuBackend;
+use super::gpu::WgpuBackend;
+use super::renderer::BackgroundMode;
+
+/// Identifies which render backend the engine should create.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackendKind {
+    /// Software rasterizer running on the CPU.
+    #[default]
+    Cpu,
+    /// GPU compositor built on wgpu, which uploads sprites as textures.
+    ///
+    /// Every frame is copied back from the GPU for the screen, and rendering waits until
+    /// the copy finished, so each frame pays a full readback.
+    Wgpu,
+}
+
+/// Trait defining a component able to draw frames from prepared sprites.
+///
+/// Implementations must be thread-safe, since the renderer is shared between
+/// the engine and its producer thread.
+pub trait RenderBackend: Send + Sync {
+    /// Draws a new frame.
+    ///
+    /// # Parameters
+    /// - `camera_top`: World position of the camera top-left corner.
+    /// - `sprites`: Sprites to draw, already sorted in drawing order.
+    fn render(&mut self, camera_top: (i32, i32), sprites: &[RenderableSprite]);
+
+    /// Returns the most recently rendered frame.
+    ///
+    /// # Returns
+    /// Optionally returns a vector representing the RGBA pixel data of the current frame.
+    fn emit(&mut self) -> Option<Vec<Pixel>>;
+
+    /// Changes the size of the produced frames.
+    fn resize(&mut self, resolution: Resolution);
+
+    /// Returns the size of the produced frames.
+    fn resolution(&self) -> Resolution;
+
+    /// Replaces the background image, or resets it to the default color with None.
+    fn set_background(&mut self, background: Option<DynamicImage>);
+
+    /// Changes how the background image is placed on the frame.
+    fn set_background_mode(&mut self, mode: BackgroundMode);
+
+    /// Forgets any state carried over from the previous frame, forcing a full redraw.
+    fn invalidate(&mut self);
+
+    /// Selects whether shadows and translucent pixels are blended in linear light
+    /// instead of raw sRGB bytes.
+    fn set_gamma_correct(&mut self, enabled: bool);
+
+    /// Returns how many sprite pixels were written while drawing the previous frame.
+    ///
+    /// Backends that do not count them report 0.
+    fn blitted_pixels(&self) -> usize {
+        0
+    }
+}
+
+/// Creates a render backend of the requested kind.
+///
+/// If the wgpu backend cannot be initialized (e.g. no suitable adapter is present),
+/// the error is reported and the CPU backend is used instead.
+///
+/// # Parameters
+/// - `kind`: Requested backend.
+/// - `resolution`: Size of the produced frames.
+/// - `background`: Optional background image.
+pub fn create_backend(
+    kind: RenderBackendKind,
+    resolution: Resolution,
+    background: Option<DynamicImage>,
+) -> Box<dyn RenderBackend> {
+    match kind {
+        RenderBackendKind::Cpu => Box::new(CpuBackend::new(resolution, background)),
+        RenderBackendKind::Wgpu => match WgpuBackend::new(resolution, background.clone()) {
+            Ok(backend) => Box::new(backend),
+            Err(e) => {
+                log::warn!("wgpu backend unavailable ({e}); falling back to CPU rendering");
+                Box::new(CpuBackend::new(resolution, background))
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_backend_kind_is_cpu() {
+        assert_eq!(RenderBackendKind::default(), RenderBackendKind::Cpu);
+    }
+
+    #[test]
+    fn test_create_cpu_backend_emits_frame() {
+        let mut backend = create_backend(RenderBackendKind::Cpu, Resolution::new(300, 300), None);
+        backend.render((0, 0), &[]);
+        assert!(backend.emit().is_some());
+    }
+
+    #[test]
+    fn test_create_wgpu_backend_always_emits_frame() {
+        // Either the GPU backend or the CPU fallback must be usable
+        let mut backend = create_backend(RenderBackendKind::Wgpu, Resolution::new(300, 300), None);
+        backend.render((0, 0), &[]);
+        assert!(backend.emit().is_some());
+    }
+}
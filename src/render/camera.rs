@@ -0,0 +1,54 @@
+//! Conversion between screen and world coordinates.
+//!
+//! The world is y-up while frames are y-down: the camera's top-left corner is shown at
+//! the top-left pixel of the frame, and world row `y` lands on screen row `top - y`. A
+//! `Camera` captures that mapping, e.g. to turn a mouse click into a world position.
+
+/// The view of the world shown in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Camera {
+    /// World position shown at the top-left pixel of the frame.
+    pub top_left: (i32, i32),
+}
+
+impl Camera {
+    /// Creates a camera showing the world from the given top-left corner.
+    pub fn new(top_left: (i32, i32)) -> Self {
+        Camera { top_left }
+    }
+
+    /// Converts a world position to the frame pixel showing it.
+    ///
+    /// The result may lie outside the frame if the position is not visible.
+    pub fn world_to_screen(&self, x: i32, y: i32) -> (i32, i32) {
+        (x - self.top_left.0, self.top_left.1 - y)
+    }
+
+    /// Converts a frame pixel, e.g. the mouse position, to the world position it shows.
+    pub fn screen_to_world(&self, x: i32, y: i32) -> (i32, i32) {
+        (self.top_left.0 + x, self.top_left.1 - y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_to_screen_is_y_flipped() {
+        let camera = Camera::new((10, 50));
+        assert_eq!(camera.world_to_screen(10, 50), (0, 0));
+        assert_eq!(camera.world_to_screen(15, 43), (5, 7));
+        assert_eq!(camera.world_to_screen(0, 60), (-10, -10));
+    }
+
+    #[test]
+    fn test_screen_to_world_inverts_world_to_screen() {
+        let camera = Camera::new((-3, 8));
+        for (x, y) in [(0, 0), (4, 9), (-2, 100)] {
+            let (wx, wy) = camera.screen_to_world(x, y);
+            assert_eq!(camera.world_to_screen(wx, wy), (x, y));
+        }
+        assert_eq!(camera.screen_to_world(4, 9), (1, -1));
+    }
+}
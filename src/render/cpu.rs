@@ -0,0 +1,1335 @@
+//! Software render backend rasterizing sprites on the CPU.
+//!
+//! Sprites are blitted pixel by pixel into a frame buffer, with shadows and blending
+//! of translucent black pixels. Work is limited to the parts of the frame that changed
+//! since the previous tick, and the redrawn area is split into bands processed in parallel.
+
+use image::{DynamicImage, GenericImageView};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::Resolution;
+use crate::engine::scene::RenderableSprite;
+
+use super::Pixel;
+use super::backend::RenderBackend;
+use super::gamma::{scale_color, subtract_channel};
+use super::lighting::{normal_map_fits, shade_pixel};
+use super::renderer::{BackgroundMode, Rectangle, Renderer, ShadowSettings};
+use super::utils::make_background_frame;
+use crate::engine::scene::game_object::components::sprite::Layer;
+use crate::engine::scene::game_object::effects::apply_tint;
+
+/// Axis-aligned area of the frame buffer in screen coordinates (y grows downwards).
+///
+/// The area is half-open on both axes: it covers columns `x0..x1` and rows `y0..y1`.
+/// Used to track which part of the frame has to be redrawn between ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+impl DirtyRect {
+    /// Returns the smallest rectangle covering both `self` and `other`.
+    pub fn union(&self, other: &DirtyRect) -> DirtyRect {
+        DirtyRect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    /// Checks whether two rectangles share at least one pixel.
+    pub fn intersects(&self, other: &DirtyRect) -> bool {
+        self.x0 < other.x1 && other.x0 < self.x1 && self.y0 < other.y1 && other.y0 < self.y1
+    }
+
+    /// Checks whether the pixel `(x, y)` lies inside the rectangle.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
+
+    /// Clips the rectangle to a frame of the given size.
+    ///
+    /// # Returns
+    /// None if nothing of the rectangle is left inside the frame.
+    pub fn clamp(&self, width: i32, height: i32) -> Option<DirtyRect> {
+        let clamped = DirtyRect {
+            x0: self.x0.max(0),
+            y0: self.y0.max(0),
+            x1: self.x1.min(width),
+            y1: self.y1.min(height),
+        };
+        if clamped.x0 < clamped.x1 && clamped.y0 < clamped.y1 {
+            Some(clamped)
+        } else {
+            None
+        }
+    }
+}
+
+/// Number of frame rows rasterized by one parallel task.
+pub const BAND_HEIGHT: i32 = 32;
+
+/// A sprite prepared for blitting during one frame.
+struct SpriteBlit<'a> {
+    /// Sprite image to draw.
+    image: &'a DynamicImage,
+    /// Normal map lighting the image, if any.
+    normal_map: Option<&'a DynamicImage>,
+    /// World position of the sprite top-left corner, offset included.
+    position: (i32, i32),
+    /// Shadow cast by the sprite, if any.
+    shadow: Option<ShadowSettings>,
+    /// Screen area touched by the sprite and its shadow.
+    screen_rect: DirtyRect,
+    /// Flash tint of the owning object, if any.
+    tint: Option<Pixel>,
+}
+
+/// How a sprite looked in a frame, apart from where it was drawn.
+#[derive(Clone)]
+struct SpriteLook {
+    image: Arc<DynamicImage>,
+    normal_map: Option<Arc<DynamicImage>>,
+    layer: Layer,
+    /// Resolved shadow of the sprite, if it casts one.
+    shadow: Option<ShadowSettings>,
+}
+
+impl PartialEq for SpriteLook {
+    /// Images are compared by identity, so an image swapped in place counts as a change.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.image, &other.image)
+            && self.normal_map.as_ref().map(Arc::as_ptr)
+                == other.normal_map.as_ref().map(Arc::as_ptr)
+            && self.layer == other.layer
+            && self.shadow == other.shadow
+    }
+}
+
+/// How an object looked in a frame, apart from where it was drawn.
+///
+/// An object whose look changed is redrawn even if it did not move.
+#[derive(Clone, PartialEq)]
+struct ObjectLook {
+    /// Depth of the object, ordering it among overlapping sprites of its layer.
+    z: i32,
+    /// Flash tint of the object, if any.
+    tint: Option<Pixel>,
+    /// Sprites of the object, in draw order.
+    sprites: Vec<SpriteLook>,
+}
+
+/// Software render backend.
+///
+/// Holds the rendering resolution, background image and the previously rendered frame.
+/// Between ticks the backend remembers where every object was drawn, so that a frame
+/// in which the camera stayed still only re-blits the regions that actually changed.
+pub struct CpuBackend {
+    resolution: Resolution,
+    background: Option<DynamicImage>,
+    background_mode: BackgroundMode,
+    /// Frame holding only the background, used to clear (parts of) the frame.
+    background_frame: Vec<Pixel>,
+    prev_frame: Vec<Pixel>,
+    /// Screen areas covered by each object (sprite and shadow) in the previous frame, by UID.
+    prev_rects: HashMap<usize, DirtyRect>,
+    /// Looks of the objects in the previous frame, by UID.
+    prev_looks: HashMap<usize, ObjectLook>,
+    /// Camera position used for the previous frame; None forces a full redraw.
+    prev_camera: Option<(i32, i32)>,
+    /// Whether shadows and translucent pixels are blended in linear light.
+    gamma_correct: bool,
+    /// Number of sprite pixels written while drawing the previous frame.
+    blitted_pixels: usize,
+}
+
+impl CpuBackend {
+    /// Creates a new CPU backend with given resolution and background image.
+    ///
+    /// Initializes the previous frame buffer based on the background or default color.
+    pub fn new(resolution: Resolution, background: Option<DynamicImage>) -> Self {
+        let init_frame = make_background_frame(
+            background.as_ref(),
+            BackgroundMode::default(),
+            (0, 0),
+            resolution,
+        );
+        CpuBackend {
+            resolution,
+            background,
+            background_mode: BackgroundMode::default(),
+            background_frame: init_frame.clone(),
+            prev_frame: init_frame,
+            prev_rects: HashMap::new(),
+            prev_looks: HashMap::new(),
+            prev_camera: None,
+            gamma_correct: false,
+            blitted_pixels: 0,
+        }
+    }
+
+    /// Rebuilds the background-only frame for the given camera position.
+    fn update_background(&mut self, camera_top: (i32, i32)) {
+        self.background_frame = make_background_frame(
+            self.background.as_ref(),
+            self.background_mode,
+            camera_top,
+            self.resolution,
+        );
+    }
+
+    /// Computes the screen area touched by a sprite, including its shadow when enabled.
+    ///
+    /// # Parameters
+    /// - `position`: Game world position of the sprite top-left corner.
+    /// - `size`: Sprite dimensions.
+    /// - `camera_top`: World position of the camera top-left corner.
+    /// - `shadow`: Shadow cast by the sprite, if any.
+    fn screen_rect(
+        position: (i32, i32),
+        size: (u32, u32),
+        camera_top: (i32, i32),
+        shadow: Option<ShadowSettings>,
+    ) -> DirtyRect {
+        let x0 = position.0 - camera_top.0;
+        let y0 = camera_top.1 - position.1;
+        let rect = DirtyRect {
+            x0,
+            y0,
+            x1: x0 + size.0 as i32,
+            y1: y0 + size.1 as i32,
+        };
+        match shadow {
+            Some(ShadowSettings { offset, .. }) => rect.union(&DirtyRect {
+                x0: rect.x0 + offset.0,
+                y0: rect.y0 + offset.1,
+                x1: rect.x1 + offset.0,
+                y1: rect.y1 + offset.1,
+            }),
+            None => rect,
+        }
+    }
+
+    /// Finds the screen region that has to be redrawn between two frames.
+    ///
+    /// An object contributes both its old and its new area if it moved, appeared or disappeared.
+    ///
+    /// # Returns
+    /// Union of all changed areas, or None if every object stayed in place.
+    fn find_dirty_region(
+        prev: &HashMap<usize, DirtyRect>,
+        cur: &HashMap<usize, DirtyRect>,
+    ) -> Option<DirtyRect> {
+        let mut dirty: Option<DirtyRect> = None;
+        let mut add = |rect: &DirtyRect| {
+            dirty = Some(match dirty {
+                None => *rect,
+                Some(d) => d.union(rect),
+            });
+        };
+        for (uid, rect) in cur {
+            match prev.get(uid) {
+                Some(old) if old == rect => {}
+                Some(old) => {
+                    add(old);
+                    add(rect);
+                }
+                None => add(rect),
+            }
+        }
+        for (uid, old) in prev {
+            if !cur.contains_key(uid) {
+                add(old);
+            }
+        }
+        dirty
+    }
+
+    /// Computes the world area whose pixels land inside the given screen area for the given camera.
+    ///
+    /// For sprites casting a shadow the area is extended by the shadow offset,
+    /// since a sprite pixel outside of the area can still throw a visible shadow into it.
+    ///
+    /// # Parameters
+    /// - `camera_top`: World position of the camera top-left corner.
+    /// - `area`: Screen area, e.g. the whole frame or one band of it.
+    /// - `shadow`: Shadow cast by the sprite, if any.
+    fn world_area(
+        camera_top: (i32, i32),
+        area: &DirtyRect,
+        shadow: Option<ShadowSettings>,
+    ) -> Rectangle {
+        // Screen row `sy` shows world row `camera_top.1 - sy`, hence the shift by one
+        let mut world = Rectangle {
+            top_left: (camera_top.0 + area.x0, camera_top.1 - area.y0 + 1),
+            bot_right: (camera_top.0 + area.x1, camera_top.1 - area.y1 + 1),
+        };
+        if let Some(ShadowSettings { offset, .. }) = shadow {
+            world.top_left.0 = world.top_left.0.min(world.top_left.0 - offset.0);
+            world.bot_right.0 = world.bot_right.0.max(world.bot_right.0 - offset.0);
+            world.top_left.1 = world.top_left.1.max(world.top_left.1 + offset.1);
+            world.bot_right.1 = world.bot_right.1.min(world.bot_right.1 + offset.1);
+        }
+        world
+    }
+
+    /// Blits (copies) a sprite onto a part of the frame buffer, writing only pixels inside the `clip` area.
+    ///
+    /// Handles pixel mapping, shadow rendering, clipping outside the viewport,
+    /// and color blending for semi-transparent pixels.
+    ///
+    /// # Parameters
+    /// - `frame`: Mutable frame buffer rows to draw on; must cover all rows of `clip`.
+    /// - `first_row`: Screen row stored at the beginning of `frame`.
+    /// - `sprite`: Sprite image to render.
+    /// - `normal_map`: Optional normal map lighting the sprite; ignored if its size differs.
+    /// - `visible_area`: Screen world rectangle describing visible part of sprite.
+    /// - `position`: Game world position of sprite.
+    /// - `camera_top`: World position of the camera top-left corner.
+    /// - `frame_size`: Dimensions of the frame buffer.
+    /// - `shadow`: Shadow to render, or None if the sprite casts none.
+    /// - `clip`: Screen area outside of which nothing is written.
+    /// - `linear`: Whether shadows and translucent pixels are blended in linear light.
+    /// - `tint`: Flash tint applied to the sprite pixels, if any.
+    ///
+    /// # Returns
+    /// The number of sprite pixels written to the frame, shadows not included.
+    #[allow(clippy::too_many_arguments)]
+    fn blit_sprite(
+        frame: &mut [Pixel],
+        first_row: i32,
+        sprite: &DynamicImage,
+        normal_map: Option<&DynamicImage>,
+        visible_area: &Rectangle,
+        position: (i32, i32),
+        camera_top: (i32, i32),
+        frame_size: (i32, i32),
+        shadow: Option<ShadowSettings>,
+        clip: &DirtyRect,
+        linear: bool,
+        tint: Option<Pixel>,
+    ) -> usize {
+        let (frame_w, frame_h) = frame_size;
+        let mut blitted = 0;
+
+        // loop over world coordinates of visible area
+        let (sprite_w, sprite_h) = sprite.dimensions();
+        let normal_map = normal_map.filter(|normal_map| normal_map_fits(sprite, normal_map));
+
+        // The whole shadow is drawn before the sprite, so it never covers the sprite casting it
+        let passes: &[bool] = if shadow.is_some() {
+            &[true, false]
+        } else {
+            &[false]
+        };
+        for &shadow_pass in passes {
+            // Iterate over world coordinates within visible area
+            for wy in visible_area.bot_right.1..visible_area.top_left.1 {
+                for wx in visible_area.top_left.0..visible_area.bot_right.0 {
+                    // Map world coordinates to sprite local coordinates
+                    if wx < position.0 || wy > position.1 {
+                        continue;
+                    }
+                    let sprite_x = wx - position.0;
+                    let sprite_y = position.1 - wy;
+
+                    if sprite_x >= sprite_w as i32 || sprite_y >= sprite_h as i32 {
+                        continue;
+                    }
+
+                    let px = sprite.get_pixel(sprite_x.try_into().unwrap(), sprite_y as u32);
+                    let src = px.0;
+
+                    // Skip fully transparent pixels
+                    if src[3] == 0 {
+                        continue;
+                    }
+
+                    if shadow_pass {
+                        let Some(ShadowSettings { offset, opacity }) = shadow else {
+                            continue;
+                        };
+                        let sx_i_shadow = wx + offset.0 - camera_top.0;
+                        let sy_i_shadow = camera_top.1 - wy + offset.1;
+                        if clip.contains(sx_i_shadow, sy_i_shadow)
+                            && sx_i_shadow >= 0
+                            && sy_i_shadow >= 0
+                            && sx_i_shadow < frame_w
+                            && sy_i_shadow < frame_h
+                        {
+                            let idx = ((sy_i_shadow - first_row) * frame_w + sx_i_shadow) as usize;
+                            let alpha = opacity as f32 / 255.0;
+                            let (r, g, b, _) = scale_color(frame[idx], 1.0 - alpha, linear);
+                            frame[idx] = (r, g, b, 255);
+                        }
+                        continue;
+                    }
+
+                    // Map world to screen coordinates
+                    let sx_i = wx - camera_top.0;
+                    let sy_i = camera_top.1 - wy;
+
+                    if sx_i < 0
+                        || sy_i < 0
+                        || sx_i >= frame_w
+                        || sy_i >= frame_h
+                        || !clip.contains(sx_i, sy_i)
+                    {
+                        continue;
+                    }
+
+                    // Write pixel data to frame, considering shadow and transparency
+                    let idx = ((sy_i - first_row) * frame_w + sx_i) as usize;
+                    let mut shadowed = src;
+                    if src[0] == 0 && src[1] == 0 && src[2] == 0 && src[3] != 255 {
+                        shadowed[0] = subtract_channel(frame[idx].0, src[3], linear);
+                        shadowed[1] = subtract_channel(frame[idx].1, src[3], linear);
+                        shadowed[2] = subtract_channel(frame[idx].2, src[3], linear);
+                    } else {
+                        if let Some(normal_map) = normal_map {
+                            let normal = normal_map.get_pixel(sprite_x as u32, sprite_y as u32);
+                            shadowed = shade_pixel(src, normal.0);
+                        }
+                        if let Some(tint) = tint {
+                            shadowed = apply_tint(shadowed, tint);
+                        }
+                    }
+
+                    frame[idx] = (shadowed[0], shadowed[1], shadowed[2], shadowed[3]);
+                    blitted += 1;
+                }
+            }
+        }
+        blitted
+    }
+
+    /// Clears `region` of the frame to the background and draws the sprites touching it.
+    ///
+    /// The region is split into horizontal bands of [`BAND_HEIGHT`] rows which are rasterized
+    /// in parallel. Every band blits only the part of each sprite that can land inside it,
+    /// so the order of writes to any pixel is the same as in a sequential pass.
+    ///
+    /// # Parameters
+    /// - `frame`: Frame buffer to draw on.
+    /// - `background`: Background-only frame used for clearing.
+    /// - `sprites`: Sprites in drawing order.
+    /// - `camera_top`: World position of the camera top-left corner.
+    /// - `frame_size`: Dimensions of the frame buffer.
+    /// - `region`: Screen area to redraw, already clipped to the frame.
+    /// - `linear`: Whether shadows and translucent pixels are blended in linear light.
+    ///
+    /// # Returns
+    /// The number of sprite pixels written to the frame.
+    fn draw_region(
+        frame: &mut [Pixel],
+        background: &[Pixel],
+        sprites: &[SpriteBlit],
+        camera_top: (i32, i32),
+        frame_size: (i32, i32),
+        region: &DirtyRect,
+        linear: bool,
+    ) -> usize {
+        let frame_w = frame_size.0;
+        let rows = &mut frame[(region.y0 * frame_w) as usize..(region.y1 * frame_w) as usize];
+
+        rows.par_chunks_mut((BAND_HEIGHT * frame_w) as usize)
+            .enumerate()
+            .map(|(i, band)| {
+                let band_rect = DirtyRect {
+                    x0: region.x0,
+                    y0: region.y0 + i as i32 * BAND_HEIGHT,
+                    x1: region.x1,
+                    y1: (region.y0 + (i as i32 + 1) * BAND_HEIGHT).min(region.y1),
+                };
+
+                for y in band_rect.y0..band_rect.y1 {
+                    let local = ((y - band_rect.y0) * frame_w) as usize;
+                    let global = (y * frame_w) as usize;
+                    band[local + band_rect.x0 as usize..local + band_rect.x1 as usize]
+                        .copy_from_slice(
+                            &background
+                                [global + band_rect.x0 as usize..global + band_rect.x1 as usize],
+                        );
+                }
+
+                let mut blitted = 0;
+                for sprite in sprites {
+                    if !sprite.screen_rect.intersects(&band_rect) {
+                        continue;
+                    }
+                    let im_size = sprite.image.dimensions();
+                    let im_rect = Rectangle {
+                        top_left: sprite.position,
+                        bot_right: (
+                            sprite.position.0 + im_size.0 as i32,
+                            sprite.position.1 - im_size.1 as i32,
+                        ),
+                    };
+                    let area = Self::world_area(camera_top, &band_rect, sprite.shadow);
+                    let Some(visible_area) = Renderer::find_intersection(&area, &im_rect) else {
+                        continue;
+                    };
+                    blitted += Self::blit_sprite(
+                        band,
+                        band_rect.y0,
+                        sprite.image,
+                        sprite.normal_map,
+                        &visible_area,
+                        sprite.position,
+                        camera_top,
+                        frame_size,
+                        sprite.shadow,
+                        &band_rect,
+                        linear,
+                        sprite.tint,
+                    );
+                }
+                blitted
+            })
+            .sum()
+    }
+}
+
+impl RenderBackend for CpuBackend {
+    /// Draws a frame from the given sprites.
+    ///
+    /// Calculates sprite positions and visible rectangles and blits sprites onto the frame.
+    /// Sprites outside of the camera viewport are skipped, and partially visible ones
+    /// are only iterated over their visible part.
+    ///
+    /// When the camera did not move since the previous frame, only the union of the areas
+    /// of objects that moved, appeared, disappeared or changed their look (image, depth,
+    /// layer, shadow or flash) is cleared and redrawn; otherwise
+    /// the whole frame is rebuilt from the background. Either way the redrawn area is
+    /// rasterized in parallel bands.
+    fn render(&mut self, camera_top: (i32, i32), sprites: &[RenderableSprite]) {
+        let frame_size = (self.resolution.width as i32, self.resolution.height as i32);
+        // Never touch rows the frame buffer does not have
+        let frame_rows = (self.prev_frame.len() as i32 / frame_size.0.max(1)).min(frame_size.1);
+
+        let mut blits = Vec::with_capacity(sprites.len());
+        let mut cur_rects = HashMap::<usize, DirtyRect>::new();
+        let mut cur_looks = HashMap::<usize, ObjectLook>::new();
+        for &(uid, obj, img, offset, shadow, layer, normal_map) in sprites {
+            let tint = obj.tint();
+            cur_looks
+                .entry(uid)
+                .or_insert_with(|| ObjectLook {
+                    z: obj.position.z,
+                    tint,
+                    sprites: Vec::new(),
+                })
+                .sprites
+                .push(SpriteLook {
+                    image: img.clone(),
+                    normal_map: normal_map.cloned(),
+                    layer,
+                    shadow,
+                });
+            let position = (obj.position.x + offset.0, obj.position.y + offset.1);
+            let screen_rect = Self::screen_rect(position, img.dimensions(), camera_top, shadow);
+            cur_rects
+                .entry(uid)
+                .and_modify(|r| *r = r.union(&screen_rect))
+                .or_insert(screen_rect);
+            blits.push(SpriteBlit {
+                image: img,
+                normal_map: normal_map.map(Arc::as_ref),
+                position,
+                shadow,
+                screen_rect,
+                tint,
+            });
+        }
+
+        let region = match self.prev_camera {
+            Some(prev_camera) if prev_camera == camera_top => {
+                let mut dirty = Self::find_dirty_region(&self.prev_rects, &cur_rects);
+                // Objects with a new image, depth, layer, shadow or flash change without moving
+                for (uid, rect) in cur_rects.iter() {
+                    if self.prev_looks.get(uid) != cur_looks.get(uid) {
+                        dirty = Some(dirty.map_or(*rect, |d| d.union(rect)));
+                    }
+                }
+                dirty
+            }
+            // Camera moved or nothing was drawn yet: redraw everything
+            _ => {
+                if self.background_mode.scrolls() {
+                    self.update_background(camera_top);
+                }
+                Some(DirtyRect {
+                    x0: 0,
+                    y0: 0,
+                    x1: frame_size.0,
+                    y1: frame_rows,
+                })
+            }
+        };
+
+        // Nothing visible changed, previous frame can be shown as is
+        self.blitted_pixels = 0;
+        if let Some(region) = region.and_then(|region| region.clamp(frame_size.0, frame_rows)) {
+            self.blitted_pixels = Self::draw_region(
+                &mut self.prev_frame,
+                &self.background_frame,
+                &blits,
+                camera_top,
+                frame_size,
+                &region,
+                self.gamma_correct,
+            );
+        }
+
+        self.prev_rects = cur_rects;
+        self.prev_looks = cur_looks;
+        self.prev_camera = Some(camera_top);
+    }
+
+    /// Returns a copy of the most recently rendered frame.
+    fn emit(&mut self) -> Option<Vec<Pixel>> {
+        Some(self.prev_frame.clone())
+    }
+
+    /// Stores the new resolution and rebuilds the frame buffers.
+    fn resize(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        self.update_background(self.prev_camera.unwrap_or((0, 0)));
+        self.prev_frame = self.background_frame.clone();
+        self.invalidate();
+    }
+
+    /// Returns the current resolution.
+    fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Replaces the background image and schedules a full redraw.
+    fn set_background(&mut self, background: Option<DynamicImage>) {
+        self.background = background;
+        self.update_background(self.prev_camera.unwrap_or((0, 0)));
+        self.invalidate();
+    }
+
+    /// Changes how the background is placed and schedules a full redraw.
+    fn set_background_mode(&mut self, mode: BackgroundMode) {
+        self.background_mode = mode;
+        self.update_background(self.prev_camera.unwrap_or((0, 0)));
+        self.invalidate();
+    }
+
+    /// Drops the information about the previous frame, so the next render is a full redraw.
+    fn invalidate(&mut self) {
+        self.prev_rects.clear();
+        self.prev_looks.clear();
+        self.prev_camera = None;
+    }
+
+    /// Switches between blending in sRGB bytes and in linear light, forcing a full redraw.
+    fn set_gamma_correct(&mut self, enabled: bool) {
+        self.gamma_correct = enabled;
+        self.invalidate();
+    }
+
+    /// Returns the number of sprite pixels written while drawing the previous frame.
+    fn blitted_pixels(&self) -> usize {
+        self.blitted_pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use crate::engine::scene::Scene;
+    use crate::engine::scene::game_object::components::sprite::Sprite;
+    use crate::engine::scene::game_object::{GameObject, Object, Position};
+    use crate::render::renderer::{DEFAULT_BACKGROUND_COLOR, OFFSET};
+    use crate::screen::{HEIGHT, WIDTH};
+    use std::time::Duration;
+
+    use super::*;
+
+    const DEFAULT_BACKGROUND: Pixel = (
+        DEFAULT_BACKGROUND_COLOR.0,
+        DEFAULT_BACKGROUND_COLOR.1,
+        DEFAULT_BACKGROUND_COLOR.2,
+        DEFAULT_BACKGROUND_COLOR.3,
+    );
+
+    fn create_sprite_with_color(width: u32, height: u32, color: [u8; 4]) -> DynamicImage {
+        let mut img = RgbaImage::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                img.put_pixel(x, y, Rgba(color));
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn test_new_cpu_backend() {
+        let backend = CpuBackend::new(Resolution::new(200, 200), None);
+        assert_eq!(backend.resolution.width, 200);
+        assert_eq!(backend.resolution.height, 200);
+        assert_eq!(backend.background, None);
+        let mut vector = backend.prev_frame;
+        for _ in 0..200 {
+            for _ in 0..200 {
+                assert_eq!(vector.pop(), Some(DEFAULT_BACKGROUND));
+            }
+        }
+        assert!(vector.is_empty());
+    }
+
+    #[test]
+    fn test_fully_opaque_no_shadow() {
+        let mut frame = vec![(0u8, 0u8, 0u8, 0u8); 10 * 10];
+        let sprite = create_sprite_with_color(3, 3, [255, 0, 0, 255]); // red opaque
+        let visible_area = Rectangle {
+            top_left: (0, 5),
+            bot_right: (5, 0),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (1, 3),
+            (0, 5),
+            (10, 10),
+            None,
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+        let idx = (2 * 10 + 1) as usize;
+        assert_eq!(frame[idx].0, 255);
+        assert_eq!(frame[idx].3, 255);
+    }
+
+    #[test]
+    fn test_transparent_pixels_skipped() {
+        let mut frame = vec![(100, 100, 100, 100); 10 * 10];
+        let mut sprite_img = RgbaImage::new(2, 2);
+        sprite_img.put_pixel(0, 0, Rgba([0, 0, 0, 0])); // transparent
+        sprite_img.put_pixel(1, 0, Rgba([50, 50, 50, 255])); // opaque
+        sprite_img.put_pixel(0, 1, Rgba([10, 10, 10, 0])); // transparent
+        sprite_img.put_pixel(1, 1, Rgba([20, 20, 20, 255])); // opaque
+        let sprite = DynamicImage::ImageRgba8(sprite_img);
+        let visible_area = Rectangle {
+            top_left: (1, 2),
+            bot_right: (3, 0),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (1, 1),
+            (0, 2),
+            (10, 10),
+            None,
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+
+        assert_eq!(frame[10], (100, 100, 100, 100));
+    }
+
+    #[test]
+    fn test_shadow_pixels_with_partial_alpha_subtract() {
+        let mut frame = vec![(100, 100, 100, 255); 10 * 10];
+        let mut sprite_img = RgbaImage::new(1, 1);
+        sprite_img.put_pixel(0, 0, Rgba([0, 0, 0, 10])); // partially transparent black pixel
+        let sprite = DynamicImage::ImageRgba8(sprite_img);
+        let visible_area = Rectangle {
+            top_left: (0, 1),
+            bot_right: (1, 0),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (0, 0),
+            (0, 1),
+            (10, 10),
+            None,
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+
+        let idx = 10;
+        assert_eq!(frame[idx].0, 90);
+        assert_eq!(frame[idx].1, 90);
+        assert_eq!(frame[idx].2, 90);
+    }
+
+    #[test]
+    fn test_pixels_outside_visible_area_not_drawn() {
+        let mut frame = vec![(50, 50, 50, 255); 10 * 10];
+        let sprite = create_sprite_with_color(2, 2, [255, 255, 255, 255]);
+        let visible_area = Rectangle {
+            top_left: (0, 2),
+            bot_right: (2, 1),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (3, 3),
+            (0, 2),
+            (10, 10),
+            None,
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+
+        for color in frame.iter() {
+            assert_eq!(*color, (50, 50, 50, 255));
+        }
+    }
+
+    #[test]
+    fn test_dirty_rect_union_and_intersects() {
+        let fst = DirtyRect {
+            x0: 0,
+            y0: 0,
+            x1: 10,
+            y1: 10,
+        };
+        let snd = DirtyRect {
+            x0: 5,
+            y0: 20,
+            x1: 15,
+            y1: 30,
+        };
+        assert!(!fst.intersects(&snd));
+        assert_eq!(
+            fst.union(&snd),
+            DirtyRect {
+                x0: 0,
+                y0: 0,
+                x1: 15,
+                y1: 30,
+            }
+        );
+        assert!(fst.union(&snd).intersects(&snd));
+        assert!(fst.contains(0, 0));
+        assert!(!fst.contains(10, 10));
+    }
+
+    #[test]
+    fn test_dirty_rect_clamp() {
+        let rect = DirtyRect {
+            x0: -5,
+            y0: -5,
+            x1: 5,
+            y1: 5,
+        };
+        assert_eq!(
+            rect.clamp(10, 10),
+            Some(DirtyRect {
+                x0: 0,
+                y0: 0,
+                x1: 5,
+                y1: 5,
+            })
+        );
+        let off_screen = DirtyRect {
+            x0: 20,
+            y0: 0,
+            x1: 30,
+            y1: 5,
+        };
+        assert_eq!(off_screen.clamp(10, 10), None);
+    }
+
+    #[test]
+    fn test_find_dirty_region_nothing_moved() {
+        let rect = DirtyRect {
+            x0: 0,
+            y0: 0,
+            x1: 10,
+            y1: 10,
+        };
+        let prev = HashMap::from([(1, rect)]);
+        let cur = HashMap::from([(1, rect)]);
+        assert_eq!(CpuBackend::find_dirty_region(&prev, &cur), None);
+    }
+
+    #[test]
+    fn test_find_dirty_region_moved_and_removed() {
+        let old = DirtyRect {
+            x0: 0,
+            y0: 0,
+            x1: 10,
+            y1: 10,
+        };
+        let new = DirtyRect {
+            x0: 2,
+            y0: 0,
+            x1: 12,
+            y1: 10,
+        };
+        let removed = DirtyRect {
+            x0: 50,
+            y0: 50,
+            x1: 60,
+            y1: 60,
+        };
+        let prev = HashMap::from([(1, old), (2, removed)]);
+        let cur = HashMap::from([(1, new)]);
+        assert_eq!(
+            CpuBackend::find_dirty_region(&prev, &cur),
+            Some(DirtyRect {
+                x0: 0,
+                y0: 0,
+                x1: 60,
+                y1: 60,
+            })
+        );
+    }
+
+    #[test]
+    fn test_screen_rect_includes_shadow() {
+        let rect =
+            CpuBackend::screen_rect((10, -10), (5, 5), (0, 0), Some(ShadowSettings::default()));
+        assert_eq!(
+            rect,
+            DirtyRect {
+                x0: 10,
+                y0: 10 + OFFSET.1,
+                x1: 15 + OFFSET.0,
+                y1: 15,
+            }
+        );
+    }
+
+    fn whole_frame(width: i32, height: i32) -> DirtyRect {
+        DirtyRect {
+            x0: 0,
+            y0: 0,
+            x1: width,
+            y1: height,
+        }
+    }
+
+    #[test]
+    fn test_viewport_without_shadow() {
+        let viewport = CpuBackend::world_area((5, 5), &whole_frame(10, 10), None);
+        assert_eq!(viewport.top_left, (5, 6));
+        assert_eq!(viewport.bot_right, (15, -4));
+    }
+
+    #[test]
+    fn test_viewport_extended_by_shadow() {
+        let viewport = CpuBackend::world_area(
+            (0, 0),
+            &whole_frame(10, 10),
+            Some(ShadowSettings::default()),
+        );
+        assert_eq!(viewport.top_left, (-OFFSET.0, 1));
+        assert_eq!(viewport.bot_right, (10, -9 + OFFSET.1));
+    }
+
+    #[test]
+    fn test_draw_region_matches_sequential_blit() {
+        let (w, h) = (64, 3 * BAND_HEIGHT + 5);
+        let background = vec![DEFAULT_BACKGROUND; (w * h) as usize];
+        let images = [
+            create_sprite_with_color(40, 50, [200, 10, 10, 255]),
+            create_sprite_with_color(30, 70, [10, 200, 10, 255]),
+        ];
+        let positions = [(5, -20), (20, -45)];
+        let camera_top = (0, 0);
+
+        let sprites: Vec<SpriteBlit> = images
+            .iter()
+            .zip(positions)
+            .map(|(image, position)| SpriteBlit {
+                image,
+                normal_map: None,
+                position,
+                shadow: Some(ShadowSettings::default()),
+                screen_rect: CpuBackend::screen_rect(
+                    position,
+                    image.dimensions(),
+                    camera_top,
+                    Some(ShadowSettings::default()),
+                ),
+                tint: None,
+            })
+            .collect();
+
+        let mut parallel = background.clone();
+        CpuBackend::draw_region(
+            &mut parallel,
+            &background,
+            &sprites,
+            camera_top,
+            (w, h),
+            &whole_frame(w, h),
+            false,
+        );
+
+        let mut sequential = background.clone();
+        for (image, position) in images.iter().zip(positions) {
+            let size = image.dimensions();
+            let im_rect = Rectangle {
+                top_left: position,
+                bot_right: (position.0 + size.0 as i32, position.1 - size.1 as i32),
+            };
+            CpuBackend::blit_sprite(
+                &mut sequential,
+                0,
+                image,
+                None,
+                &im_rect,
+                position,
+                camera_top,
+                (w, h),
+                Some(ShadowSettings::default()),
+                &whole_frame(w, h),
+                false,
+                None,
+            );
+        }
+
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_sprite_pixel_drawn_when_shadow_off_screen() {
+        let mut frame = vec![(50, 50, 50, 255); 10 * 10];
+        let sprite = create_sprite_with_color(3, 3, [255, 255, 255, 255]);
+        let visible_area = Rectangle {
+            top_left: (0, 1),
+            bot_right: (3, -3),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (0, 1),
+            (0, 0),
+            (10, 10),
+            Some(ShadowSettings::default()),
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+
+        // Shadow is shifted above the frame, the sprite itself must still be visible
+        assert_eq!(frame[10], (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn test_custom_shadow_offset_and_opacity() {
+        let mut frame = vec![(100, 100, 100, 255); 10 * 10];
+        let sprite = create_sprite_with_color(1, 2, [255, 255, 255, 255]);
+        let visible_area = Rectangle {
+            top_left: (0, 1),
+            bot_right: (1, -1),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (0, 1),
+            (0, 0),
+            (10, 10),
+            Some(ShadowSettings::new((3, 2), 255)),
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+
+        assert_eq!(frame[0], (255, 255, 255, 255));
+        assert_eq!(frame[2 * 10 + 3], (0, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_shadow_does_not_cover_own_sprite() {
+        let mut frame = vec![(100, 100, 100, 255); 10 * 10];
+        let sprite = create_sprite_with_color(1, 3, [255, 255, 255, 255]);
+        let visible_area = Rectangle {
+            top_left: (0, 1),
+            bot_right: (1, -2),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            None,
+            &visible_area,
+            (0, 1),
+            (0, 0),
+            (10, 10),
+            Some(ShadowSettings::new((0, 1), 255)),
+            &whole_frame(10, 10),
+            false,
+            None,
+        );
+
+        // The shadow points down the screen; where it overlaps the sprite, the sprite stays on top
+        assert_eq!(frame[0], (255, 255, 255, 255));
+        assert_eq!(frame[10], (255, 255, 255, 255));
+        assert_eq!(frame[20], (0, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_normal_map_shades_sprite() {
+        let mut frame = vec![(0, 0, 0, 255); 4 * 4];
+        let sprite = create_sprite_with_color(2, 2, [100, 100, 100, 255]);
+        let mut normal_map = RgbaImage::from_pixel(2, 2, Rgba([128, 128, 255, 255]));
+        // Facing away from the light
+        normal_map.put_pixel(1, 1, Rgba([230, 30, 180, 255]));
+        let normal_map = DynamicImage::ImageRgba8(normal_map);
+        let visible_area = Rectangle {
+            top_left: (0, 1),
+            bot_right: (2, -2),
+        };
+        CpuBackend::blit_sprite(
+            &mut frame,
+            0,
+            &sprite,
+            Some(&normal_map),
+            &visible_area,
+            (0, 1),
+            (0, 0),
+            (4, 4),
+            None,
+            &whole_frame(4, 4),
+            false,
+            None,
+        );
+
+        // Sprite row 1 lands on screen row 0
+        assert!((frame[0].0 as i32 - 100).abs() <= 1);
+        assert!(frame[1].0 < 100);
+    }
+
+    #[test]
+    fn test_invalidate_forgets_previous_frame() {
+        let obj = GameObject::new(
+            vec![Box::new(Sprite::new(
+                Some(create_sprite_with_color(20, 20, [255, 0, 0, 255])),
+                true,
+                (0, 0),
+            ))],
+            None,
+            Position {
+                x: 30,
+                y: -30,
+                z: 1,
+                is_relative: false,
+            },
+        );
+        let scene = Scene::new(
+            vec![obj],
+            vec![],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap();
+        let mut backend = CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None);
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(backend.prev_camera, Some((0, 0)));
+        assert_eq!(backend.prev_rects.len(), 1);
+
+        backend.invalidate();
+        assert!(backend.prev_camera.is_none());
+        assert!(backend.prev_rects.is_empty());
+    }
+
+    #[test]
+    fn test_blitted_pixels_count_redrawn_sprite_pixels() {
+        let obj = GameObject::new(
+            vec![Box::new(Sprite::new(
+                Some(create_sprite_with_color(20, 10, [255, 0, 0, 255])),
+                true,
+                (0, 0),
+            ))],
+            None,
+            Position {
+                x: 30,
+                y: -30,
+                z: 1,
+                is_relative: false,
+            },
+        );
+        let scene = Scene::new(
+            vec![obj],
+            vec![],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap();
+        let mut backend = CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None);
+        assert_eq!(backend.blitted_pixels(), 0);
+        backend.render((0, 0), &scene.init().unwrap());
+        let red = backend
+            .prev_frame
+            .iter()
+            .filter(|&&px| px == (255, 0, 0, 255))
+            .count();
+        assert!(red > 0);
+        assert_eq!(backend.blitted_pixels(), red);
+
+        // Nothing moved, so nothing is redrawn
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(backend.blitted_pixels(), 0);
+    }
+
+    #[test]
+    fn test_flash_tints_sprite_and_redraws_when_it_ends() {
+        let mut scene = Scene::new(
+            vec![],
+            vec![Box::new(Sprite::new(
+                Some(create_sprite_with_color(10, 10, [0, 0, 255, 255])),
+                false,
+                (0, 0),
+            ))],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap();
+        let mut backend = CpuBackend::new(Resolution::new(20, 20), None);
+        let idx = 5 * 20 + 5;
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(backend.emit().unwrap()[idx], (0, 0, 255, 255));
+
+        scene
+            .main_object
+            .flash((255, 255, 255, 255), Duration::from_millis(50));
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(backend.emit().unwrap()[idx], (255, 255, 255, 255));
+
+        scene.update_effects(Duration::from_millis(50));
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(backend.emit().unwrap()[idx], (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_still_camera_redraws_objects_whose_look_changed() {
+        fn sprite(scene: &mut Scene, uid: usize) -> &mut Sprite {
+            scene.object_mut(uid).unwrap().components[0]
+                .as_any_mut()
+                .downcast_mut::<Sprite>()
+                .unwrap()
+        }
+        let obj = |color, shadow, position: Position| {
+            GameObject::new(
+                vec![Box::new(
+                    Sprite::new(Some(create_sprite_with_color(6, 6, color)), shadow, (0, 0))
+                        .with_shadow_settings(ShadowSettings::new((10, 0), 100)),
+                )],
+                None,
+                position,
+            )
+        };
+        let mut scene = Scene::new(
+            vec![
+                obj([255, 0, 0, 255], true, Position::new(2, -2).with_z(1)),
+                obj([0, 255, 0, 255], false, Position::new(5, -5).with_z(2)),
+            ],
+            vec![],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        let mut backend = CpuBackend::new(Resolution::new(20, 20), None);
+        // (3, 3) shows only the first object, (6, 6) both, (14, 3) the shadow of the first
+        let px = |backend: &mut CpuBackend, x: usize, y: usize| backend.emit().unwrap()[y * 20 + x];
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(px(&mut backend, 6, 6), (0, 255, 0, 255));
+        let shadow = px(&mut backend, 14, 3);
+
+        // Image swapped in place, at the same size
+        sprite(&mut scene, 1).image =
+            Some(Arc::new(create_sprite_with_color(6, 6, [0, 0, 255, 255])));
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(px(&mut backend, 3, 3), (0, 0, 255, 255));
+
+        // Raised above the overlapping object
+        scene.object_mut(1).unwrap().position.z = 3;
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(px(&mut backend, 6, 6), (0, 0, 255, 255));
+
+        // Moved to a layer drawn above
+        sprite(&mut scene, 2).layer = Layer::Foreground;
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(px(&mut backend, 6, 6), (0, 255, 0, 255));
+
+        // Darker shadow at the same offset
+        sprite(&mut scene, 1).shadow_settings = Some(ShadowSettings::new((10, 0), 200));
+        backend.render((0, 0), &scene.init().unwrap());
+        assert!(px(&mut backend, 14, 3).0 < shadow.0);
+    }
+
+    #[test]
+    fn test_scrolling_background_moves_with_camera() {
+        let mut tile = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 0, 255]));
+        tile.put_pixel(1, 0, Rgba([255, 255, 255, 255]));
+        let mut backend = CpuBackend::new(
+            Resolution::new(WIDTH, HEIGHT),
+            Some(DynamicImage::ImageRgba8(tile)),
+        );
+        backend.set_background_mode(BackgroundMode::Tiled { scroll: true });
+
+        backend.render((0, 0), &[]);
+        let first = backend.emit().unwrap();
+        assert_eq!(first[0], (0, 0, 0, 255));
+        backend.render((1, 0), &[]);
+        let moved = backend.emit().unwrap();
+        assert_eq!(moved[0], (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn test_resize_resets_frame() {
+        let mut backend = CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None);
+        backend.render((0, 0), &[]);
+        backend.resize(Resolution::new(WIDTH / 2, HEIGHT / 2));
+        assert_eq!(backend.resolution.width, WIDTH / 2);
+        assert!(backend.prev_camera.is_none());
+        assert!(
+            backend
+                .emit()
+                .unwrap()
+                .iter()
+                .all(|px| *px == DEFAULT_BACKGROUND)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Debug drawing of primitives on top of rendered frames.
+//!
+//! The drawing functions write lines, rectangles, circles and points straight into a frame
+//! buffer in screen coordinates. For use from game code, [`DebugDraw`] queues shapes in
+//! world coordinates; the renderer draws them on the next emitted frame only, so shapes
+//! that should stay visible are queued again every frame. This makes it easy to show camera
+//! rects, collision boxes or paths while developing.
+
+use super::Pixel;
+use super::camera::Camera;
+use std::sync::{Arc, RwLock};
+
+/// Sets a single pixel, ignoring positions outside of the frame.
+///
+/// # Parameters
+/// - `frame`: RGBA pixels of the frame, row by row.
+/// - `width`: Frame width in pixels.
+/// - `height`: Frame height in pixels.
+/// - `point`: Screen position of the pixel.
+/// - `color`: New color of the pixel.
+pub fn draw_point(frame: &mut [Pixel], width: u32, height: u32, point: (i32, i32), color: Pixel) {
+    let (x, y) = point;
+    if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+        frame[(y as u32 * width + x as u32) as usize] = color;
+    }
+}
+
+/// Draws a one pixel wide line between two screen positions, both ends included.
+pub fn draw_line(
+    frame: &mut [Pixel],
+    width: u32,
+    height: u32,
+    from: (i32, i32),
+    to: (i32, i32),
+    color: Pixel,
+) {
+    // Bresenham's algorithm, working for all octants
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut err = dx + dy;
+    loop {
+        draw_point(frame, width, height, (x, y), color);
+        if (x, y) == to {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Draws the outline of a rectangle given by two opposite screen corners.
+pub fn draw_rect(
+    frame: &mut [Pixel],
+    width: u32,
+    height: u32,
+    corner: (i32, i32),
+    opposite: (i32, i32),
+    color: Pixel,
+) {
+    let (x0, y0, x1, y1) = (corner.0, corner.1, opposite.0, opposite.1);
+    draw_line(frame, width, height, (x0, y0), (x1, y0), color);
+    draw_line(frame, width, height, (x1, y0), (x1, y1), color);
+    draw_line(frame, width, height, (x1, y1), (x0, y1), color);
+    draw_line(frame, width, height, (x0, y1), (x0, y0), color);
+}
+
+/// Draws the outline of a circle around a screen position.
+pub fn draw_circle(
+    frame: &mut [Pixel],
+    width: u32,
+    height: u32,
+    center: (i32, i32),
+    radius: u32,
+    color: Pixel,
+) {
+    // Midpoint circle algorithm, mirroring one octant
+    let (cx, cy) = center;
+    let (mut x, mut y) = (radius as i32, 0);
+    let mut err = 1 - x;
+    while x >= y {
+        for (px, py) in [
+            (x, y),
+            (y, x),
+            (-y, x),
+            (-x, y),
+            (-x, -y),
+            (-y, -x),
+            (y, -x),
+            (x, -y),
+        ] {
+            draw_point(frame, width, height, (cx + px, cy + py), color);
+        }
+        y += 1;
+        if err < 0 {
+            err += 2 * y + 1;
+        } else {
+            x -= 1;
+            err += 2 * (y - x) + 1;
+        }
+    }
+}
+
+/// A primitive queued for debug drawing, in world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DebugShape {
+    Point((i32, i32)),
+    Line((i32, i32), (i32, i32)),
+    Rect((i32, i32), (i32, i32)),
+    Circle((i32, i32), u32),
+}
+
+/// [Handle](crate#handles) to the queue of debug shapes drawn on the next frame.
+///
+/// Shapes queued from scripts and systems are drawn and dequeued by the renderer.
+/// Positions are world coordinates, like object positions.
+#[derive(Clone, Default)]
+pub struct DebugDraw {
+    shapes: Arc<RwLock<Vec<(DebugShape, Pixel)>>>,
+}
+
+impl DebugDraw {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        DebugDraw::default()
+    }
+
+    fn push(&self, shape: DebugShape, color: Pixel) {
+        self.shapes.write().unwrap().push((shape, color));
+    }
+
+    /// Queues a single point.
+    pub fn point(&self, point: (i32, i32), color: Pixel) {
+        self.push(DebugShape::Point(point), color);
+    }
+
+    /// Queues a line between two points.
+    pub fn line(&self, from: (i32, i32), to: (i32, i32), color: Pixel) {
+        self.push(DebugShape::Line(from, to), color);
+    }
+
+    /// Queues the outline of a rectangle given by its top-left and bottom-right corners.
+    pub fn rect(&self, top_left: (i32, i32), bot_right: (i32, i32), color: Pixel) {
+        self.push(DebugShape::Rect(top_left, bot_right), color);
+    }
+
+    /// Queues the outline of a circle.
+    pub fn circle(&self, center: (i32, i32), radius: u32, color: Pixel) {
+        self.push(DebugShape::Circle(center, radius), color);
+    }
+
+    /// Queues a path connecting the points in order.
+    pub fn path(&self, points: &[(i32, i32)], color: Pixel) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], color);
+        }
+    }
+
+    /// Returns the number of queued shapes.
+    pub fn len(&self) -> usize {
+        self.shapes.read().unwrap().len()
+    }
+
+    /// Checks whether no shapes are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Draws all queued shapes onto the frame and empties the queue.
+    ///
+    /// # Parameters
+    /// - `frame`: RGBA pixels of the frame, row by row.
+    /// - `width`: Frame width in pixels.
+    /// - `height`: Frame height in pixels.
+    /// - `camera_top`: World position of the camera top-left corner for this frame.
+    pub(crate) fn flush(
+        &self,
+        frame: &mut [Pixel],
+        width: u32,
+        height: u32,
+        camera_top: (i32, i32),
+    ) {
+        let camera = Camera::new(camera_top);
+        let to_screen = |p: (i32, i32)| camera.world_to_screen(p.0, p.1);
+        for (shape, color) in self.shapes.write().unwrap().drain(..) {
+            match shape {
+                DebugShape::Point(p) => draw_point(frame, width, height, to_screen(p), color),
+                DebugShape::Line(a, b) => {
+                    draw_line(frame, width, height, to_screen(a), to_screen(b), color)
+                }
+                DebugShape::Rect(a, b) => {
+                    draw_rect(frame, width, height, to_screen(a), to_screen(b), color)
+                }
+                DebugShape::Circle(c, r) => {
+                    draw_circle(frame, width, height, to_screen(c), r, color)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Pixel = (0, 0, 0, 255);
+    const RED: Pixel = (255, 0, 0, 255);
+
+    fn count(frame: &[Pixel]) -> usize {
+        frame.iter().filter(|px| **px == RED).count()
+    }
+
+    #[test]
+    fn test_line_covers_both_ends() {
+        let mut frame = vec![BLACK; 5 * 5];
+        draw_line(&mut frame, 5, 5, (4, 0), (0, 2), RED);
+        assert_eq!(frame[4], RED);
+        assert_eq!(frame[2 * 5], RED);
+        assert_eq!(count(&frame), 5);
+    }
+
+    #[test]
+    fn test_shapes_are_clipped_to_frame() {
+        let mut frame = vec![BLACK; 4 * 4];
+        draw_rect(&mut frame, 4, 4, (-2, -2), (1, 1), RED);
+        assert_eq!(count(&frame), 3);
+        draw_circle(&mut frame, 4, 4, (100, 100), 3, RED);
+        assert_eq!(count(&frame), 3);
+    }
+
+    #[test]
+    fn test_circle_outline() {
+        let mut frame = vec![BLACK; 9 * 9];
+        draw_circle(&mut frame, 9, 9, (4, 4), 3, RED);
+        assert_eq!(frame[4 * 9 + 7], RED);
+        assert_eq!(frame[9 + 4], RED);
+        assert_eq!(frame[4 * 9 + 4], BLACK);
+    }
+
+    #[test]
+    fn test_queue_uses_world_coordinates_for_one_frame() {
+        let debug = DebugDraw::new();
+        debug.point((12, 8), RED);
+        debug.path(&[(10, 10), (11, 10), (11, 9)], RED);
+        assert_eq!(debug.len(), 3);
+
+        let mut frame = vec![BLACK; 4 * 4];
+        debug.flush(&mut frame, 4, 4, (10, 10));
+        assert_eq!(frame[2 * 4 + 2], RED);
+        assert_eq!(frame[0], RED);
+        assert_eq!(frame[4 + 1], RED);
+        assert!(debug.is_empty());
+    }
+}
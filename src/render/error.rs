@@ -0,0 +1,75 @@
+//! Errors reported by the rendering pipeline.
+//!
+//! Broken assets are reported as a `RenderError` instead of panicking, so a single bad
+//! sprite does not bring down the engine's producer thread.
+
+use std::fmt;
+
+/// Describes why a frame could not be rendered or emitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderError {
+    /// A sprite component of the object with the given UID has no image.
+    MissingSprite { uid: usize },
+    /// A sprite component of the object with the given UID has no offset, or its offset
+    /// moves the sprite outside of the representable world coordinates.
+    BadOffset { uid: usize },
+    /// The backend produced a frame with a different number of pixels than its resolution needs.
+    BufferSizeMismatch { expected: usize, actual: usize },
+    /// No frame has been rendered yet.
+    NoFrame,
+    /// A color lookup table image of the given size does not have the strip layout.
+    InvalidLut { width: u32, height: u32 },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::MissingSprite { uid } => {
+                write!(f, "sprite of object {uid} has no image")
+            }
+            RenderError::BadOffset { uid } => {
+                write!(f, "sprite of object {uid} has an invalid offset")
+            }
+            RenderError::BufferSizeMismatch { expected, actual } => {
+                write!(f, "frame has {actual} pixels, expected {expected}")
+            }
+            RenderError::NoFrame => write!(f, "no frame has been rendered"),
+            RenderError::InvalidLut { width, height } => {
+                write!(f, "{width}x{height} image is not a color lookup table")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<RenderError> for std::io::Error {
+    fn from(e: RenderError) -> Self {
+        std::io::Error::other(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_mentions_details() {
+        let e = RenderError::BufferSizeMismatch {
+            expected: 4,
+            actual: 2,
+        };
+        assert_eq!(e.to_string(), "frame has 2 pixels, expected 4");
+        assert!(
+            RenderError::MissingSprite { uid: 7 }
+                .to_string()
+                .contains('7')
+        );
+    }
+
+    #[test]
+    fn test_converts_to_io_error() {
+        let e: std::io::Error = RenderError::NoFrame.into();
+        assert_eq!(e.kind(), std::io::ErrorKind::Other);
+    }
+}
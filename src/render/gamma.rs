@@ -0,0 +1,123 @@
+//! Conversions between sRGB bytes and linear light for gamma-correct blending.
+//!
+//! Frames and images store sRGB encoded bytes. Blending those bytes directly makes shadows
+//! and translucent pixels look muddy, so with gamma-correct blending enabled colors are
+//! converted to linear light, blended, and converted back. Both conversions use lookup
+//! tables built on first use.
+
+use std::sync::OnceLock;
+
+use super::Pixel;
+
+/// Number of entries of the linear to sRGB table.
+const LINEAR_STEPS: usize = 4096;
+
+/// Lookup tables for both conversion directions.
+struct Tables {
+    to_linear: [f32; 256],
+    to_srgb: Vec<u8>,
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        let mut to_linear = [0.0; 256];
+        for (c, lin) in to_linear.iter_mut().enumerate() {
+            let c = c as f32 / 255.0;
+            *lin = if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+        let to_srgb = (0..LINEAR_STEPS)
+            .map(|i| {
+                let lin = i as f32 / (LINEAR_STEPS - 1) as f32;
+                let c = if lin <= 0.0031308 {
+                    lin * 12.92
+                } else {
+                    1.055 * lin.powf(1.0 / 2.4) - 0.055
+                };
+                (c * 255.0).round() as u8
+            })
+            .collect();
+        Tables { to_linear, to_srgb }
+    })
+}
+
+/// Converts an sRGB encoded channel to linear light in 0.0..=1.0.
+pub fn srgb_to_linear(c: u8) -> f32 {
+    tables().to_linear[c as usize]
+}
+
+/// Converts linear light to an sRGB encoded channel; values out of 0.0..=1.0 are clamped.
+pub fn linear_to_srgb(c: f32) -> u8 {
+    let idx = (c.clamp(0.0, 1.0) * (LINEAR_STEPS - 1) as f32).round() as usize;
+    tables().to_srgb[idx]
+}
+
+/// Scales the color channels of a pixel by `factor`, in linear light if `linear` is set.
+///
+/// Used for shadows, which let `factor` of the light below them through.
+pub fn scale_color(px: Pixel, factor: f32, linear: bool) -> Pixel {
+    let scale = |c: u8| {
+        if linear {
+            linear_to_srgb(srgb_to_linear(c) * factor)
+        } else {
+            (c as f32 * factor) as u8
+        }
+    };
+    (scale(px.0), scale(px.1), scale(px.2), px.3)
+}
+
+/// Blends a channel of a source color over a destination channel with the given alpha.
+pub fn mix_channel(src: u8, dst: u8, alpha: f32, linear: bool) -> u8 {
+    if linear {
+        linear_to_srgb(srgb_to_linear(src) * alpha + srgb_to_linear(dst) * (1.0 - alpha))
+    } else {
+        (src as f32 * alpha + dst as f32 * (1.0 - alpha)) as u8
+    }
+}
+
+/// Subtracts `amount` (0..=255) from a channel, in linear light if `linear` is set.
+///
+/// Used for translucent black pixels, which darken what lies below them.
+pub fn subtract_channel(c: u8, amount: u8, linear: bool) -> u8 {
+    if linear {
+        linear_to_srgb(srgb_to_linear(c) - amount as f32 / 255.0)
+    } else {
+        c.saturating_sub(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_keeps_bytes() {
+        for c in 0..=255u8 {
+            assert!((linear_to_srgb(srgb_to_linear(c)) as i32 - c as i32).abs() <= 1);
+        }
+        assert_eq!(linear_to_srgb(-1.0), 0);
+        assert_eq!(linear_to_srgb(2.0), 255);
+    }
+
+    #[test]
+    fn test_linear_blend_is_brighter_than_raw() {
+        // Half white over black is mid gray in light, which is 188 in sRGB, not 127
+        assert_eq!(mix_channel(255, 0, 0.5, false), 127);
+        assert!((mix_channel(255, 0, 0.5, true) as i32 - 188).abs() <= 1);
+    }
+
+    #[test]
+    fn test_scale_and_subtract() {
+        assert_eq!(scale_color((200, 100, 0, 7), 0.5, false), (100, 50, 0, 7));
+        let lin = scale_color((200, 100, 0, 7), 0.5, true);
+        assert!(lin.0 > 100 && lin.1 > 50 && lin.3 == 7);
+        assert_eq!(subtract_channel(10, 40, false), 0);
+        assert_eq!(subtract_channel(10, 40, true), 0);
+        assert!(subtract_channel(200, 40, true) < 200);
+    }
+}
@@ -0,0 +1,875 @@
+//! GPU render backend compositing sprites with wgpu.
+//!
+//! Every sprite image is uploaded once as a texture and then drawn as a textured quad
+//! into an offscreen target. Shadows and translucent black pixels are expressed with
+//! blend states, so the composed frame matches the software renderer. After drawing,
+//! the target is copied back to memory and handed to the screen like any other frame.
+//!
+//! With gamma-correct blending enabled, the target and textures use an sRGB format, so the
+//! GPU blends in linear light and stores sRGB bytes again.
+//!
+//! The tests of this backend need a GPU adapter and are ignored by default; run them with
+//! `cargo test -- --ignored` on a machine that has one.
+
+use image::{DynamicImage, GenericImageView};
+use pixels::wgpu;
+use pixels::wgpu::util::DeviceExt;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Error;
+use std::sync::{Arc, mpsc};
+
+use crate::Resolution;
+use crate::engine::scene::RenderableSprite;
+
+use super::Pixel;
+use super::backend::RenderBackend;
+use super::gamma::srgb_to_linear;
+use super::lighting::light_image;
+use super::renderer::BackgroundMode;
+use super::utils::make_background_frame;
+
+/// Format of the offscreen target and of uploaded sprite textures.
+const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Format used instead of [`TEXTURE_FORMAT`] for gamma-correct blending.
+const SRGB_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Number of floats describing one quad: screen x, screen y, width and height, followed by
+/// the RGBA flash tint of the sprite.
+const INSTANCE_FLOATS: usize = 8;
+
+/// Shader drawing sprite quads in screen pixel coordinates.
+///
+/// Quads are addressed in texels, so sprites are copied pixel by pixel without filtering.
+/// Like the software renderer, which treats the top edge of a sprite as exclusive,
+/// the quad leaves out the first texel row.
+/// `fs_shadow` outputs the shadow mask, `fs_sprite` the opaque pixels, mixed with the flash
+/// tint by its alpha, and `fs_shade` the translucent black pixels that darken whatever lies
+/// below them.
+const SHADER: &str = r#"
+struct Target {
+    size: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> target_info: Target;
+@group(1) @binding(0) var sprite: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) origin: vec2<f32>,
+    @location(1) @interpolate(flat) tint: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    @location(0) rect: vec4<f32>,
+    @location(1) tint: vec4<f32>,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let pixel = rect.xy + vec2<f32>(0.0, 1.0) + corners[index] * (rect.zw - vec2<f32>(0.0, 1.0));
+    var out: VertexOutput;
+    out.position = vec4<f32>(
+        pixel.x / target_info.size.x * 2.0 - 1.0,
+        1.0 - pixel.y / target_info.size.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.origin = rect.xy;
+    out.tint = tint;
+    return out;
+}
+
+fn load_texel(in: VertexOutput) -> vec4<f32> {
+    return textureLoad(sprite, vec2<i32>(floor(in.position.xy - in.origin)), 0);
+}
+
+fn is_shade(color: vec4<f32>) -> bool {
+    return all(color.rgb == vec3<f32>(0.0)) && color.a < 1.0;
+}
+
+@fragment
+fn fs_shadow(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = load_texel(in);
+    if color.a == 0.0 {
+        discard;
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_sprite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = load_texel(in);
+    if color.a == 0.0 || is_shade(color) {
+        discard;
+    }
+    return vec4<f32>(mix(color.rgb, in.tint.rgb, in.tint.a), color.a);
+}
+
+@fragment
+fn fs_shade(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = load_texel(in);
+    if color.a == 0.0 || !is_shade(color) {
+        discard;
+    }
+    return vec4<f32>(color.a);
+}
+"#;
+
+/// Sprite image uploaded to the GPU.
+struct CachedTexture {
+    /// Uploaded image, kept so its address is not reused while cached.
+    image: Arc<DynamicImage>,
+    /// Normal map the texture was lit with, if any.
+    normal_map: Option<Arc<DynamicImage>>,
+    /// Bind group exposing the texture to the fragment shaders.
+    bind_group: wgpu::BindGroup,
+    /// Whether the texture was used by the current frame.
+    used: bool,
+}
+
+/// GPU render backend.
+///
+/// Owns a headless wgpu device and an offscreen target of the frame size.
+/// Sprite textures are cached between frames and dropped once their image
+/// is no longer part of the rendered sprites.
+pub struct WgpuBackend {
+    resolution: Resolution,
+    background: Option<DynamicImage>,
+    background_mode: BackgroundMode,
+    /// Background pixels uploaded to the target before the sprites are drawn.
+    background_frame: Vec<Pixel>,
+    /// Camera position the background frame was built for.
+    background_camera: (i32, i32),
+    prev_frame: Vec<Pixel>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    target: wgpu::Texture,
+    target_view: wgpu::TextureView,
+    target_uniform: wgpu::Buffer,
+    target_bind_group: wgpu::BindGroup,
+    texture_layout: wgpu::BindGroupLayout,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    /// Format of the target and sprite textures, sRGB for gamma-correct blending.
+    format: wgpu::TextureFormat,
+    shadow_pipeline: wgpu::RenderPipeline,
+    sprite_pipeline: wgpu::RenderPipeline,
+    shade_pipeline: wgpu::RenderPipeline,
+    /// Uploaded sprite textures, keyed by the address of their source image. The images
+    /// are kept so their addresses are not reused while cached.
+    textures: HashMap<usize, CachedTexture>,
+}
+
+impl WgpuBackend {
+    /// Creates a new wgpu backend with given resolution and background image.
+    ///
+    /// # Returns
+    /// An error if no GPU adapter is available or the device cannot be created.
+    pub fn new(resolution: Resolution, background: Option<DynamicImage>) -> Result<Self, Error> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .ok_or_else(|| Error::other("no suitable GPU adapter found"))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("rusty_ache device"),
+                required_features: wgpu::Features::empty(),
+                required_limits:
+                    wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            },
+            None,
+        ))
+        .map_err(Error::other)?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("sprite shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+        });
+
+        let target_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("target layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sprite texture layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sprite pipeline layout"),
+            bind_group_layouts: &[&target_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let (shadow_pipeline, sprite_pipeline, shade_pipeline) =
+            Self::create_pipelines(&device, &shader, &pipeline_layout, TEXTURE_FORMAT);
+
+        let target_uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("target uniform"),
+            size: (4 * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let target_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("target bind group"),
+            layout: &target_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: target_uniform.as_entire_binding(),
+            }],
+        });
+
+        let (target, target_view) = Self::create_target(&device, resolution, TEXTURE_FORMAT);
+        let background_frame = make_background_frame(
+            background.as_ref(),
+            BackgroundMode::default(),
+            (0, 0),
+            resolution,
+        );
+        let mut backend = WgpuBackend {
+            resolution,
+            background,
+            background_mode: BackgroundMode::default(),
+            background_camera: (0, 0),
+            prev_frame: background_frame.clone(),
+            background_frame,
+            device,
+            queue,
+            target,
+            target_view,
+            target_uniform,
+            target_bind_group,
+            texture_layout,
+            shader,
+            pipeline_layout,
+            format: TEXTURE_FORMAT,
+            shadow_pipeline,
+            sprite_pipeline,
+            shade_pipeline,
+            textures: HashMap::new(),
+        };
+        backend.write_target_size();
+        Ok(backend)
+    }
+
+    /// Creates the shadow, sprite and shade pipelines drawing into a target of the given format.
+    fn create_pipelines(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        pipeline_layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+    ) -> (
+        wgpu::RenderPipeline,
+        wgpu::RenderPipeline,
+        wgpu::RenderPipeline,
+    ) {
+        // Shadow darkens the frame by a constant factor: dst * (1 - opaqueness)
+        let shadow_blend = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::Constant,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+        // Translucent black pixels subtract their alpha from the frame color
+        let shade_blend = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::ReverseSubtract,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+        let create_pipeline = |entry_point: &str, blend: wgpu::BlendState| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: (INSTANCE_FLOATS * std::mem::size_of::<f32>()) as u64,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4],
+                    }],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(blend),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+        (
+            create_pipeline("fs_shadow", shadow_blend),
+            create_pipeline("fs_sprite", wgpu::BlendState::REPLACE),
+            create_pipeline("fs_shade", shade_blend),
+        )
+    }
+
+    /// Creates the offscreen texture frames are drawn into.
+    fn create_target(
+        device: &wgpu::Device,
+        resolution: Resolution,
+        format: wgpu::TextureFormat,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("frame target"),
+            size: wgpu::Extent3d {
+                width: resolution.width.max(1),
+                height: resolution.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        (target, view)
+    }
+
+    /// Rebuilds the background pixels for the given camera position.
+    fn update_background(&mut self, camera_top: (i32, i32)) {
+        self.background_frame = make_background_frame(
+            self.background.as_ref(),
+            self.background_mode,
+            camera_top,
+            self.resolution,
+        );
+        self.background_camera = camera_top;
+    }
+
+    /// Uploads the frame size used by the vertex shader to map pixels to clip space.
+    fn write_target_size(&mut self) {
+        let size = [
+            self.resolution.width as f32,
+            self.resolution.height as f32,
+            0.0,
+            0.0,
+        ];
+        self.queue
+            .write_buffer(&self.target_uniform, 0, &floats_to_bytes(&size));
+    }
+
+    /// Returns the texture of a sprite image, uploading it when it is not cached yet.
+    ///
+    /// Since the light does not move, sprites with a normal map are lit once on upload.
+    ///
+    /// # Returns
+    /// The cache key of the texture.
+    fn texture_for(
+        &mut self,
+        image: &Arc<DynamicImage>,
+        normal_map: Option<&Arc<DynamicImage>>,
+    ) -> usize {
+        let key = Arc::as_ptr(image) as usize;
+        let size = image.dimensions();
+        if let Some(cached) = self.textures.get_mut(&key)
+            && Arc::ptr_eq(&cached.image, image)
+            && cached.normal_map.as_ref().map(Arc::as_ptr) == normal_map.map(Arc::as_ptr)
+        {
+            cached.used = true;
+            return key;
+        }
+
+        let extent = wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture_with_data(
+            &self.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("sprite texture"),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &normal_map
+                .and_then(|normal_map| light_image(image, normal_map))
+                .unwrap_or_else(|| image.to_rgba8()),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sprite bind group"),
+            layout: &self.texture_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        self.textures.insert(
+            key,
+            CachedTexture {
+                image: image.clone(),
+                normal_map: normal_map.cloned(),
+                bind_group,
+                used: true,
+            },
+        );
+        key
+    }
+
+    /// Copies the offscreen target back into memory.
+    ///
+    /// # Returns
+    /// RGBA pixels of the target, row by row, or None if the copy failed.
+    fn read_target(&self) -> Option<Vec<Pixel>> {
+        let (width, height) = (self.resolution.width.max(1), self.resolution.height.max(1));
+        // Rows of a texture to buffer copy must be aligned
+        let unpadded_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = unpadded_row.div_ceil(align) * align;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame readback"),
+            size: (padded_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("frame readback"),
+            });
+        encoder.copy_texture_to_buffer(
+            self.target.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        if let Err(e) = receiver.recv().ok()? {
+            log::error!("Failed to read the rendered frame back ({e})");
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_row as usize) {
+                for px in row[..unpadded_row as usize].chunks_exact(4) {
+                    pixels.push((px[0], px[1], px[2], px[3]));
+                }
+            }
+        }
+        buffer.unmap();
+        Some(pixels)
+    }
+}
+
+/// Converts floats to the bytes of a GPU buffer.
+fn floats_to_bytes(floats: &[f32]) -> Vec<u8> {
+    floats.iter().flat_map(|f| f.to_ne_bytes()).collect()
+}
+
+impl RenderBackend for WgpuBackend {
+    /// Draws a frame from the given sprites.
+    ///
+    /// The background is uploaded to the target, then every visible sprite is drawn as
+    /// one quad for its shadow and one for each of its opaque and translucent black pixels.
+    /// The finished frame is read back, so it is available through [`RenderBackend::emit`].
+    fn render(&mut self, camera_top: (i32, i32), sprites: &[RenderableSprite]) {
+        let (frame_w, frame_h) = (self.resolution.width as i32, self.resolution.height as i32);
+        if self.background_mode.scrolls() && self.background_camera != camera_top {
+            self.update_background(camera_top);
+        }
+        for cached in self.textures.values_mut() {
+            cached.used = false;
+        }
+
+        // Quads are collected first, the render pass needs all bind groups to outlive it
+        let mut draws = Vec::with_capacity(sprites.len());
+        let mut instances = Vec::with_capacity(sprites.len() * 2 * INSTANCE_FLOATS);
+        for &(_, obj, img, offset, shadow, _, normal_map) in sprites {
+            let (w, h) = img.dimensions();
+            if w == 0 || h == 0 {
+                continue;
+            }
+            let sx = obj.position.x + offset.0 - camera_top.0;
+            let sy = camera_top.1 - (obj.position.y + offset.1);
+            let visible =
+                |x: i32, y: i32| x < frame_w && y < frame_h && x + w as i32 > 0 && y + h as i32 > 0;
+            let shadow =
+                shadow.filter(|shadow| visible(sx + shadow.offset.0, sy + shadow.offset.1));
+            if !visible(sx, sy) && shadow.is_none() {
+                continue;
+            }
+            let shadow_offset = shadow.map_or((0, 0), |shadow| shadow.offset);
+
+            let tint = obj.tint().map_or([0.0; 4], |(r, g, b, a)| {
+                // The sRGB target blends in linear light, so the tint color is converted too
+                let channel = |c: u8| {
+                    if self.format == SRGB_TEXTURE_FORMAT {
+                        srgb_to_linear(c)
+                    } else {
+                        c as f32 / 255.0
+                    }
+                };
+                [channel(r), channel(g), channel(b), a as f32 / 255.0]
+            });
+
+            let key = self.texture_for(img, normal_map);
+            let first = (instances.len() / INSTANCE_FLOATS) as u32;
+            instances.extend_from_slice(&[sx as f32, sy as f32, w as f32, h as f32]);
+            instances.extend_from_slice(&tint);
+            instances.extend_from_slice(&[
+                (sx + shadow_offset.0) as f32,
+                (sy + shadow_offset.1) as f32,
+                w as f32,
+                h as f32,
+            ]);
+            instances.extend_from_slice(&[0.0; 4]);
+            draws.push((key, first, shadow.map(|shadow| shadow.opacity)));
+        }
+        self.textures.retain(|_, cached| cached.used);
+
+        self.queue.write_texture(
+            self.target.as_image_copy(),
+            &self
+                .background_frame
+                .iter()
+                .flat_map(|px| [px.0, px.1, px.2, px.3])
+                .collect::<Vec<u8>>(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * self.resolution.width),
+                rows_per_image: Some(self.resolution.height),
+            },
+            self.target.size(),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("frame"),
+            });
+        if !draws.is_empty() {
+            let instance_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("sprite quads"),
+                        contents: &floats_to_bytes(&instances),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    });
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("sprites"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.target_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_bind_group(0, &self.target_bind_group, &[]);
+            pass.set_vertex_buffer(0, instance_buffer.slice(..));
+            for &(key, first, shadow_opacity) in draws.iter() {
+                pass.set_bind_group(1, &self.textures[&key].bind_group, &[]);
+                if let Some(opacity) = shadow_opacity {
+                    let shadow_factor = 1.0 - opacity as f64 / 255.0;
+                    pass.set_blend_constant(wgpu::Color {
+                        r: shadow_factor,
+                        g: shadow_factor,
+                        b: shadow_factor,
+                        a: 1.0,
+                    });
+                    pass.set_pipeline(&self.shadow_pipeline);
+                    pass.draw(0..6, first + 1..first + 2);
+                }
+                pass.set_pipeline(&self.sprite_pipeline);
+                pass.draw(0..6, first..first + 1);
+                pass.set_pipeline(&self.shade_pipeline);
+                pass.draw(0..6, first..first + 1);
+            }
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        if let Some(frame) = self.read_target() {
+            self.prev_frame = frame;
+        }
+    }
+
+    /// Returns a copy of the most recently rendered frame.
+    fn emit(&mut self) -> Option<Vec<Pixel>> {
+        Some(self.prev_frame.clone())
+    }
+
+    /// Recreates the offscreen target for the new resolution.
+    fn resize(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        (self.target, self.target_view) =
+            Self::create_target(&self.device, resolution, self.format);
+        self.update_background(self.background_camera);
+        self.prev_frame = self.background_frame.clone();
+        self.write_target_size();
+    }
+
+    /// Returns the current resolution.
+    fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Replaces the background image used by the following frames.
+    fn set_background(&mut self, background: Option<DynamicImage>) {
+        self.background = background;
+        self.update_background(self.background_camera);
+    }
+
+    /// Changes how the background is placed on the following frames.
+    fn set_background_mode(&mut self, mode: BackgroundMode) {
+        self.background_mode = mode;
+        self.update_background(self.background_camera);
+    }
+
+    /// Drops all cached sprite textures.
+    ///
+    /// Textures are keyed by image address, which may be reused by a new scene.
+    fn invalidate(&mut self) {
+        self.textures.clear();
+    }
+
+    /// Switches the target, textures and pipelines between a plain and an sRGB format.
+    fn set_gamma_correct(&mut self, enabled: bool) {
+        let format = if enabled {
+            SRGB_TEXTURE_FORMAT
+        } else {
+            TEXTURE_FORMAT
+        };
+        if format == self.format {
+            return;
+        }
+        self.format = format;
+        (
+            self.shadow_pipeline,
+            self.sprite_pipeline,
+            self.shade_pipeline,
+        ) = Self::create_pipelines(&self.device, &self.shader, &self.pipeline_layout, format);
+        (self.target, self.target_view) =
+            Self::create_target(&self.device, self.resolution, format);
+        self.textures.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+    use crate::engine::scene::Scene;
+    use crate::engine::scene::game_object::components::sprite::Sprite;
+    use crate::engine::scene::game_object::{GameObject, Object, Position};
+    use crate::render::cpu::CpuBackend;
+    use crate::render::renderer::{DEFAULT_BACKGROUND_COLOR, ShadowSettings};
+    use crate::screen::{HEIGHT, WIDTH};
+
+    fn test_scene() -> Scene {
+        let mut img = RgbaImage::from_pixel(20, 20, Rgba([200, 30, 30, 255]));
+        img.put_pixel(3, 3, Rgba([0, 0, 0, 40]));
+        img.put_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        let mut normal_map = RgbaImage::from_pixel(20, 20, Rgba([128, 128, 255, 255]));
+        normal_map.put_pixel(5, 5, Rgba([230, 30, 180, 255]));
+        let obj = GameObject::new(
+            vec![Box::new(
+                Sprite::new(Some(DynamicImage::ImageRgba8(img)), true, (0, 0))
+                    .with_normal_map(DynamicImage::ImageRgba8(normal_map))
+                    .with_shadow_settings(ShadowSettings::new((-7, 12), 120)),
+            )],
+            None,
+            Position::new(30, -30).with_z(1),
+        );
+        Scene::new(vec![obj], vec![], Position::new(0, 0)).unwrap()
+    }
+
+    /// Returns the backend, panicking when the machine has no GPU adapter.
+    fn backend() -> WgpuBackend {
+        WgpuBackend::new(Resolution::new(WIDTH, HEIGHT), None).expect("No wgpu adapter")
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_empty_frame_is_background() {
+        let mut backend = backend();
+        backend.render((0, 0), &[]);
+        let frame = backend.emit().unwrap();
+        assert_eq!(frame.len(), (WIDTH * HEIGHT) as usize);
+        assert!(frame.iter().all(|px| *px == DEFAULT_BACKGROUND_COLOR));
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_frame_matches_cpu_backend() {
+        let mut backend = backend();
+        let scene = test_scene();
+        backend.render((0, 0), &scene.init().unwrap());
+        let gpu_frame = backend.emit().unwrap();
+
+        let mut cpu = CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None);
+        cpu.render((0, 0), &scene.init().unwrap());
+        let cpu_frame = cpu.emit().unwrap();
+
+        // Blending on the GPU may round differently than the integer CPU path
+        for (gpu_px, cpu_px) in gpu_frame.iter().zip(cpu_frame.iter()) {
+            assert!((gpu_px.0 as i32 - cpu_px.0 as i32).abs() <= 1);
+            assert!((gpu_px.1 as i32 - cpu_px.1 as i32).abs() <= 1);
+            assert!((gpu_px.2 as i32 - cpu_px.2 as i32).abs() <= 1);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_gamma_correct_frame_matches_cpu_backend() {
+        let mut backend = backend();
+        backend.set_gamma_correct(true);
+        let scene = test_scene();
+        backend.render((0, 0), &scene.init().unwrap());
+        let gpu_frame = backend.emit().unwrap();
+
+        let mut cpu = CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None);
+        cpu.set_gamma_correct(true);
+        cpu.render((0, 0), &scene.init().unwrap());
+        let cpu_frame = cpu.emit().unwrap();
+
+        // The CPU converts through lookup tables, which are slightly coarser
+        for (gpu_px, cpu_px) in gpu_frame.iter().zip(cpu_frame.iter()) {
+            assert!((gpu_px.0 as i32 - cpu_px.0 as i32).abs() <= 2);
+            assert!((gpu_px.1 as i32 - cpu_px.1 as i32).abs() <= 2);
+            assert!((gpu_px.2 as i32 - cpu_px.2 as i32).abs() <= 2);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_flash_tint_matches_cpu_backend() {
+        let mut backend = backend();
+        let img = RgbaImage::from_pixel(20, 20, Rgba([30, 60, 200, 255]));
+        let mut scene = Scene::new(
+            vec![],
+            vec![Box::new(Sprite::new(
+                Some(DynamicImage::ImageRgba8(img)),
+                false,
+                (0, 0),
+            ))],
+            Position::new(0, 0),
+        )
+        .unwrap();
+        scene
+            .main_object
+            .flash((255, 255, 255, 128), std::time::Duration::from_secs(1));
+        backend.render((0, 0), &scene.init().unwrap());
+        let gpu_frame = backend.emit().unwrap();
+
+        let mut cpu = CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None);
+        cpu.render((0, 0), &scene.init().unwrap());
+        let cpu_frame = cpu.emit().unwrap();
+
+        assert_ne!(cpu_frame[5 * WIDTH as usize + 5], (30, 60, 200, 255));
+        for (gpu_px, cpu_px) in gpu_frame.iter().zip(cpu_frame.iter()) {
+            assert!((gpu_px.0 as i32 - cpu_px.0 as i32).abs() <= 1);
+            assert!((gpu_px.1 as i32 - cpu_px.1 as i32).abs() <= 1);
+            assert!((gpu_px.2 as i32 - cpu_px.2 as i32).abs() <= 1);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_unused_textures_are_dropped() {
+        let mut backend = backend();
+        let scene = test_scene();
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(backend.textures.len(), 1);
+        backend.render((0, 0), &[]);
+        assert!(backend.textures.is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_cached_textures_keep_their_image() {
+        let mut backend = backend();
+        let sprite_scene = |color| {
+            let img = RgbaImage::from_pixel(20, 20, Rgba(color));
+            let sprite = Sprite::new(Some(DynamicImage::ImageRgba8(img)), false, (0, 0));
+            Scene::new(vec![], vec![Box::new(sprite)], Position::new(0, 0)).unwrap()
+        };
+        let scene = sprite_scene([200, 30, 30, 255]);
+        backend.render((0, 0), &scene.init().unwrap());
+        drop(scene);
+        // The image of the dropped scene stays alive, so a new image can't take its address
+        let scene = sprite_scene([30, 30, 200, 255]);
+        backend.render((0, 0), &scene.init().unwrap());
+        assert_eq!(
+            backend.emit().unwrap()[5 * WIDTH as usize + 5],
+            (30, 30, 200, 255)
+        );
+        assert_eq!(backend.textures.len(), 1);
+    }
+}
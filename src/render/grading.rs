@@ -0,0 +1,181 @@
+//! Color grading of emitted frames with a 3D lookup table (LUT).
+//!
+//! A LUT maps every input color to an output color, which changes the overall mood of a
+//! scene (sepia, cold night, warm sunset) without touching its assets. LUTs are loaded from
+//! images in the common strip layout: an image of `N * N` x `N` pixels made of `N` square
+//! slices placed side by side. Within a slice red grows to the right and green downwards;
+//! blue selects the slice. Colors between the table entries are interpolated trilinearly.
+
+use image::{DynamicImage, GenericImageView};
+use std::path::Path;
+
+use super::Pixel;
+use super::error::RenderError;
+use super::post::PostEffect;
+
+/// A 3D color lookup table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLut {
+    /// Number of entries along each color axis.
+    size: u32,
+    /// Output colors, indexed by `(b * size + g) * size + r`.
+    table: Vec<[f32; 3]>,
+}
+
+impl ColorLut {
+    /// Creates a LUT leaving every color unchanged.
+    ///
+    /// # Parameters
+    /// - `size`: Number of entries along each color axis, at least 2.
+    pub fn identity(size: u32) -> Self {
+        let size = size.max(2);
+        let step = 255.0 / (size - 1) as f32;
+        let mut table = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    table.push([r as f32 * step, g as f32 * step, b as f32 * step]);
+                }
+            }
+        }
+        ColorLut { size, table }
+    }
+
+    /// Reads a LUT from an image in the strip layout.
+    ///
+    /// # Errors
+    /// Fails with [`RenderError::InvalidLut`] if the image is not `N * N` x `N` pixels with N >= 2.
+    pub fn from_image(image: &DynamicImage) -> Result<Self, RenderError> {
+        let (width, height) = image.dimensions();
+        if height < 2 || width != height * height {
+            return Err(RenderError::InvalidLut { width, height });
+        }
+        let size = height;
+        let mut table = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let px = image.get_pixel(b * size + r, g);
+                    table.push([px[0] as f32, px[1] as f32, px[2] as f32]);
+                }
+            }
+        }
+        Ok(ColorLut { size, table })
+    }
+
+    /// Loads a LUT image from a file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be decoded or does not have the strip layout.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let image = image::open(path).map_err(std::io::Error::other)?;
+        Ok(Self::from_image(&image)?)
+    }
+
+    /// Returns the number of entries along each color axis.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Looks up the graded color of a pixel; alpha is left unchanged.
+    pub fn grade(&self, px: Pixel) -> Pixel {
+        let n = self.size as usize;
+        let scale = (n - 1) as f32 / 255.0;
+        // Lower table index and interpolation weight along every axis
+        let split = |c: u8| {
+            let pos = c as f32 * scale;
+            let lo = (pos as usize).min(n - 2);
+            (lo, pos - lo as f32)
+        };
+        let (r0, fr) = split(px.0);
+        let (g0, fg) = split(px.1);
+        let (b0, fb) = split(px.2);
+        let at = |r: usize, g: usize, b: usize| self.table[(b * n + g) * n + r];
+
+        let mut out = [0.0f32; 3];
+        for (db, wb) in [(0, 1.0 - fb), (1, fb)] {
+            for (dg, wg) in [(0, 1.0 - fg), (1, fg)] {
+                for (dr, wr) in [(0, 1.0 - fr), (1, fr)] {
+                    let weight = wr * wg * wb;
+                    let entry = at(r0 + dr, g0 + dg, b0 + db);
+                    for (o, e) in out.iter_mut().zip(entry) {
+                        *o += e * weight;
+                    }
+                }
+            }
+        }
+        let to_u8 = |c: f32| c.round().clamp(0.0, 255.0) as u8;
+        (to_u8(out[0]), to_u8(out[1]), to_u8(out[2]), px.3)
+    }
+}
+
+impl PostEffect for ColorLut {
+    /// Grades every pixel of the frame.
+    fn apply(&self, frame: &mut [Pixel], _width: u32, _height: u32) {
+        for px in frame.iter_mut() {
+            *px = self.grade(*px);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    /// Builds a strip LUT image, computing every entry with `f` from its (r, g, b) indices.
+    fn lut_image(size: u32, f: impl Fn(u32, u32, u32) -> [u8; 3]) -> DynamicImage {
+        let mut img = RgbaImage::new(size * size, size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let [cr, cg, cb] = f(r, g, b);
+                    img.put_pixel(b * size + r, g, Rgba([cr, cg, cb, 255]));
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn test_identity_keeps_colors() {
+        let lut = ColorLut::identity(16);
+        for px in [(0, 0, 0, 255), (255, 255, 255, 10), (12, 200, 99, 255)] {
+            assert_eq!(lut.grade(px), px);
+        }
+    }
+
+    #[test]
+    fn test_identity_image_matches_identity() {
+        let step = |c: u32| (c * 255 / 3) as u8;
+        let lut =
+            ColorLut::from_image(&lut_image(4, |r, g, b| [step(r), step(g), step(b)])).unwrap();
+        assert_eq!(lut, ColorLut::identity(4));
+    }
+
+    #[test]
+    fn test_grade_interpolates_between_entries() {
+        // Inverting LUT with only two entries per axis
+        let lut = ColorLut::from_image(&lut_image(2, |r, g, b| {
+            [
+                255 - 255 * r as u8,
+                255 - 255 * g as u8,
+                255 - 255 * b as u8,
+            ]
+        }))
+        .unwrap();
+        assert_eq!(lut.grade((0, 255, 100, 255)), (255, 0, 155, 255));
+    }
+
+    #[test]
+    fn test_rejects_wrong_layout() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(16, 8));
+        assert_eq!(
+            ColorLut::from_image(&image),
+            Err(RenderError::InvalidLut {
+                width: 16,
+                height: 8
+            })
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Lighting of sprites that carry a normal map.
+//!
+//! A normal map stores one surface normal per sprite pixel, encoded in its RGB channels
+//! (x to the right, y up, z towards the viewer). Pixels are shaded with a single
+//! directional light, so artwork without depth gets a lit, embossed look. The shading is
+//! normalized so that flat areas keep their original color.
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+/// Direction towards the light: from the top left corner, towards the viewer.
+pub const LIGHT_DIRECTION: (f32, f32, f32) = (-0.5, 0.5, std::f32::consts::FRAC_1_SQRT_2);
+
+/// Share of light reaching pixels facing away from the light.
+pub const AMBIENT_LIGHT: f32 = 0.35;
+
+/// Computes the light intensity for a pixel with the given encoded normal.
+///
+/// # Returns
+/// 1.0 for a normal facing the viewer, more for normals facing the light, less otherwise.
+fn intensity(normal: [u8; 4]) -> f32 {
+    let decode = |c: u8| c as f32 / 255.0 * 2.0 - 1.0;
+    let (nx, ny, nz) = (decode(normal[0]), decode(normal[1]), decode(normal[2]));
+    let len = (nx * nx + ny * ny + nz * nz).sqrt();
+    if len == 0.0 {
+        return 1.0;
+    }
+    let (lx, ly, lz) = LIGHT_DIRECTION;
+    let diffuse = ((nx * lx + ny * ly + nz * lz) / len).max(0.0);
+    let flat = AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * lz;
+    (AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * diffuse) / flat
+}
+
+/// Shades a sprite pixel using the matching normal map pixel.
+///
+/// # Parameters
+/// - `color`: RGBA color of the sprite pixel.
+/// - `normal`: Pixel of the normal map at the same position.
+///
+/// # Returns
+/// The lit color; alpha is left unchanged.
+pub fn shade_pixel(color: [u8; 4], normal: [u8; 4]) -> [u8; 4] {
+    let light = intensity(normal);
+    let scale = |c: u8| (c as f32 * light).min(255.0) as u8;
+    [scale(color[0]), scale(color[1]), scale(color[2]), color[3]]
+}
+
+/// Checks whether a normal map can be used to light the image.
+pub fn normal_map_fits(image: &DynamicImage, normal_map: &DynamicImage) -> bool {
+    image.dimensions() == normal_map.dimensions()
+}
+
+/// Produces a lit copy of a sprite image.
+///
+/// Translucent black pixels darken what lies below them instead of having a color,
+/// so they are kept as is.
+///
+/// # Returns
+/// None if the normal map has different dimensions than the image.
+pub fn light_image(image: &DynamicImage, normal_map: &DynamicImage) -> Option<RgbaImage> {
+    if !normal_map_fits(image, normal_map) {
+        return None;
+    }
+    let mut lit = image.to_rgba8();
+    for (x, y, px) in lit.enumerate_pixels_mut() {
+        let src = px.0;
+        if src[0] == 0 && src[1] == 0 && src[2] == 0 && src[3] != 255 {
+            continue;
+        }
+        px.0 = shade_pixel(src, normal_map.get_pixel(x, y).0);
+    }
+    Some(lit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const FLAT: [u8; 4] = [128, 128, 255, 255];
+
+    #[test]
+    fn test_flat_normal_keeps_color() {
+        let shaded = shade_pixel([100, 150, 200, 255], FLAT);
+        for (c, orig) in shaded.iter().zip([100, 150, 200]) {
+            assert!((*c as i32 - orig).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn test_normal_facing_light_is_brighter() {
+        // Normal tilted to the top left, towards the light
+        let shaded = shade_pixel([100, 100, 100, 255], [64, 192, 220, 255]);
+        assert!(shaded[0] > 100);
+    }
+
+    #[test]
+    fn test_normal_facing_away_is_darker_and_keeps_alpha() {
+        let shaded = shade_pixel([100, 100, 100, 77], [230, 30, 180, 255]);
+        assert!(shaded[0] < 100);
+        assert_eq!(shaded[3], 77);
+    }
+
+    #[test]
+    fn test_light_image_requires_matching_size() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(2, 2));
+        let normal_map = DynamicImage::ImageRgba8(RgbaImage::new(3, 2));
+        assert!(light_image(&image, &normal_map).is_none());
+    }
+
+    #[test]
+    fn test_light_image_skips_translucent_black() {
+        let mut img = RgbaImage::from_pixel(2, 1, Rgba([100, 100, 100, 255]));
+        img.put_pixel(1, 0, Rgba([0, 0, 0, 40]));
+        let normal_map =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 1, Rgba([230, 30, 180, 255])));
+        let lit = light_image(&DynamicImage::ImageRgba8(img), &normal_map).unwrap();
+        assert!(lit.get_pixel(0, 0).0[0] < 100);
+        assert_eq!(lit.get_pixel(1, 0).0, [0, 0, 0, 40]);
+    }
+}
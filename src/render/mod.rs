@@ -1,2 +1,19 @@
+pub mod backend;
+pub mod camera;
+pub mod cpu;
+pub mod debug;
+pub mod error;
+pub mod gamma;
+pub mod gpu;
+pub mod grading;
+pub mod lighting;
+pub mod overlay;
+pub mod post;
+pub mod recording;
 pub mod renderer;
+pub mod transition;
+pub mod ui;
 mod utils;
+
+/// Color as red, green, blue and alpha, the pixel type of frame buffers.
+pub(crate) type Pixel = (u8, u8, u8, u8);
@@ -0,0 +1,155 @@
+//! Performance overlay drawn above everything else.
+//!
+//! The overlay shows the frame rate, the frame time, the number of objects in the active
+//! scene and how many sprite pixels the backend blitted for the last frame. It is hidden
+//! by default and toggled with F3 in the game window, or from code through its handle.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::Pixel;
+use super::ui::{blend_image, render_text};
+use crate::engine::timing::FrameStats;
+
+/// Color of the overlay text.
+const TEXT_COLOR: Pixel = (255, 255, 255, 255);
+
+/// Color of the box behind the text; translucent so the game stays visible.
+const BOX_COLOR: Pixel = (0, 0, 0, 160);
+
+/// Distance of the box from the top-left frame corner and of the text from the box edges.
+const MARGIN: u32 = 2;
+
+/// Vertical distance between the tops of two text lines.
+const LINE_HEIGHT: u32 = 9;
+
+/// [Handle](crate#handles) to the visibility of the performance overlay.
+///
+/// The game window toggles it on F3 and the renderer checks it for every frame.
+#[derive(Debug, Clone, Default)]
+pub struct PerfOverlay {
+    visible: Arc<AtomicBool>,
+}
+
+impl PerfOverlay {
+    /// Creates a hidden overlay.
+    pub fn new() -> Self {
+        PerfOverlay::default()
+    }
+
+    /// Checks whether the overlay is shown.
+    pub fn is_visible(&self) -> bool {
+        self.visible.load(Ordering::Relaxed)
+    }
+
+    /// Shows or hides the overlay.
+    pub fn set_visible(&self, visible: bool) {
+        self.visible.store(visible, Ordering::Relaxed);
+    }
+
+    /// Shows the overlay if it is hidden and hides it otherwise.
+    pub fn toggle(&self) {
+        self.visible.fetch_xor(true, Ordering::Relaxed);
+    }
+}
+
+/// Formats the lines of text shown by the overlay.
+///
+/// # Parameters
+/// - `stats`: Frame statistics of the last full second.
+/// - `objects`: Number of objects in the active scene.
+/// - `blitted`: Number of sprite pixels blitted for the last frame.
+pub fn overlay_lines(stats: &FrameStats, objects: usize, blitted: usize) -> Vec<String> {
+    vec![
+        format!("FPS {:.1}", stats.fps),
+        format!(
+            "FRAME {:.1} MS",
+            stats.average_frame_time.as_secs_f32() * 1000.0
+        ),
+        format!("OBJECTS {objects}"),
+        format!("BLITTED {blitted}"),
+    ]
+}
+
+/// Draws the overlay lines in a translucent box at the top-left corner of a frame.
+///
+/// # Parameters
+/// - `frame`: RGBA pixels of the frame, row by row.
+/// - `width`: Frame width in pixels.
+/// - `height`: Frame height in pixels.
+/// - `lines`: Text lines to show, top to bottom.
+/// - `linear`: Whether to blend in linear light instead of raw sRGB bytes.
+pub(crate) fn draw(frame: &mut [Pixel], width: u32, height: u32, lines: &[String], linear: bool) {
+    let images: Vec<DynamicImage> = lines
+        .iter()
+        .map(|line| render_text(line, TEXT_COLOR))
+        .collect();
+    let text_width = images.iter().map(|image| image.width()).max().unwrap_or(0);
+    let box_width = text_width + 2 * MARGIN;
+    let box_height = images.len() as u32 * LINE_HEIGHT + MARGIN;
+    let background = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        box_width,
+        box_height,
+        Rgba([BOX_COLOR.0, BOX_COLOR.1, BOX_COLOR.2, BOX_COLOR.3]),
+    ));
+    let corner = MARGIN as i32;
+    blend_image(frame, width, height, &background, (corner, corner), linear);
+    for (i, image) in images.iter().enumerate() {
+        let position = (
+            corner + MARGIN as i32,
+            corner + (MARGIN + i as u32 * LINE_HEIGHT) as i32,
+        );
+        blend_image(frame, width, height, image, position, linear);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_overlay_toggles_and_clones_share_state() {
+        let overlay = PerfOverlay::new();
+        assert!(!overlay.is_visible());
+        overlay.clone().toggle();
+        assert!(overlay.is_visible());
+        overlay.toggle();
+        assert!(!overlay.is_visible());
+        overlay.set_visible(true);
+        assert!(overlay.clone().is_visible());
+    }
+
+    #[test]
+    fn test_overlay_lines() {
+        let stats = FrameStats {
+            fps: 59.96,
+            average_frame_time: Duration::from_micros(16_680),
+            p99_frame_time: Duration::from_millis(20),
+            last_frame_time: Duration::from_millis(17),
+        };
+        assert_eq!(
+            overlay_lines(&stats, 8, 1234),
+            vec!["FPS 60.0", "FRAME 16.7 MS", "OBJECTS 8", "BLITTED 1234"]
+        );
+    }
+
+    #[test]
+    fn test_draw_darkens_corner_and_writes_text() {
+        let (width, height) = (80, 50);
+        let mut frame = vec![(200, 200, 200, 255); (width * height) as usize];
+        draw(&mut frame, width, height, &["FPS 1".to_string()], false);
+        // Outside of the box the frame is untouched
+        assert_eq!(frame[0], (200, 200, 200, 255));
+        assert_eq!(
+            frame[(height - 1) as usize * width as usize],
+            (200, 200, 200, 255)
+        );
+        // Inside the box but between glyphs the frame is darkened
+        let inside = frame[3 * width as usize + 3];
+        assert!(inside.0 < 200);
+        // Some pixels of the text are drawn in the text color
+        assert!(frame.contains(&TEXT_COLOR));
+    }
+}
@@ -0,0 +1,358 @@
+//! Full-frame effects applied to rendered frames before they are shown.
+//!
+//! The renderer keeps a chain of `PostEffect`s and runs them in insertion order on every
+//! emitted frame. Effects work on a copy of the frame, so the backend's own frame, which
+//! later ticks redraw incrementally, is never modified.
+//!
+//! For one-off effects a [`PixelShader`] closure can be set instead, which maps every pixel
+//! of the frame to a new color.
+
+use rayon::prelude::*;
+
+use super::Pixel;
+
+/// Function computing the new color of a frame pixel from its screen position and color.
+pub type PixelShader = dyn Fn(u32, u32, Pixel) -> Pixel + Send + Sync;
+
+/// Runs a pixel shader on every pixel of the frame, rows in parallel.
+///
+/// # Parameters
+/// - `shader`: Function mapping `(x, y, color)` to the new color.
+/// - `frame`: RGBA pixels of the frame, row by row.
+/// - `width`: Frame width in pixels.
+pub fn apply_pixel_shader(shader: &PixelShader, frame: &mut [Pixel], width: u32) {
+    if width == 0 {
+        return;
+    }
+    frame
+        .par_chunks_mut(width as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, px) in row.iter_mut().enumerate() {
+                *px = shader(x as u32, y as u32, *px);
+            }
+        });
+}
+
+/// Trait defining an effect applied to a whole rendered frame.
+///
+/// Implementations must be thread-safe, since the renderer is shared between
+/// the engine and its producer thread.
+pub trait PostEffect: Send + Sync {
+    /// Applies the effect to the frame in place.
+    ///
+    /// # Parameters
+    /// - `frame`: RGBA pixels of the frame, row by row.
+    /// - `width`: Frame width in pixels.
+    /// - `height`: Frame height in pixels.
+    fn apply(&self, frame: &mut [Pixel], width: u32, height: u32);
+}
+
+/// Converts the frame to shades of gray.
+pub struct Grayscale;
+
+impl PostEffect for Grayscale {
+    /// Replaces every color with its luma.
+    fn apply(&self, frame: &mut [Pixel], _width: u32, _height: u32) {
+        for px in frame.iter_mut() {
+            let luma = (0.299 * px.0 as f32 + 0.587 * px.1 as f32 + 0.114 * px.2 as f32) as u8;
+            *px = (luma, luma, luma, px.3);
+        }
+    }
+}
+
+/// Darkens the frame towards its corners.
+pub struct Vignette {
+    /// How much the corners are darkened, from 0.0 (no effect) to 1.0 (black corners).
+    pub strength: f32,
+}
+
+impl PostEffect for Vignette {
+    /// Scales every color by a factor falling off quadratically with the distance from the center.
+    fn apply(&self, frame: &mut [Pixel], width: u32, height: u32) {
+        let strength = self.strength.clamp(0.0, 1.0);
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        let max_dist_sq = cx * cx + cy * cy;
+        if max_dist_sq == 0.0 {
+            return;
+        }
+        for (idx, px) in frame.iter_mut().enumerate() {
+            let x = (idx as u32 % width) as f32 + 0.5 - cx;
+            let y = (idx as u32 / width) as f32 + 0.5 - cy;
+            let factor = 1.0 - strength * (x * x + y * y) / max_dist_sq;
+            *px = (
+                (px.0 as f32 * factor) as u8,
+                (px.1 as f32 * factor) as u8,
+                (px.2 as f32 * factor) as u8,
+                px.3,
+            );
+        }
+    }
+}
+
+/// Blurs the frame with a box filter.
+pub struct Blur {
+    /// Number of neighbouring pixels averaged on every side; 0 leaves the frame unchanged.
+    pub radius: u32,
+}
+
+impl Blur {
+    /// Averages `len` pixels read through `at`, each over a window of `radius` pixels to both sides.
+    ///
+    /// Pixels outside of the line are replaced with the nearest edge pixel.
+    fn blur_line(radius: i32, len: i32, at: impl Fn(i32) -> Pixel) -> Vec<Pixel> {
+        let window = (2 * radius + 1) as u32;
+        let mut sum = [0u32; 4];
+        let add = |sum: &mut [u32; 4], px: Pixel, sign: bool| {
+            for (s, c) in sum.iter_mut().zip([px.0, px.1, px.2, px.3]) {
+                if sign {
+                    *s += c as u32;
+                } else {
+                    *s -= c as u32;
+                }
+            }
+        };
+        for i in -radius..=radius {
+            add(&mut sum, at(i.clamp(0, len - 1)), true);
+        }
+
+        let mut line = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            line.push((
+                (sum[0] / window) as u8,
+                (sum[1] / window) as u8,
+                (sum[2] / window) as u8,
+                (sum[3] / window) as u8,
+            ));
+            add(&mut sum, at((i - radius).clamp(0, len - 1)), false);
+            add(&mut sum, at((i + radius + 1).clamp(0, len - 1)), true);
+        }
+        line
+    }
+}
+
+impl PostEffect for Blur {
+    /// Blurs rows first and columns second, which equals a square box filter.
+    fn apply(&self, frame: &mut [Pixel], width: u32, height: u32) {
+        if self.radius == 0 || width == 0 || height == 0 {
+            return;
+        }
+        let (w, h, r) = (width as usize, height as usize, self.radius as i32);
+        for y in 0..h {
+            let row = Self::blur_line(r, w as i32, |x| frame[y * w + x as usize]);
+            frame[y * w..(y + 1) * w].copy_from_slice(&row);
+        }
+        for x in 0..w {
+            let column = Self::blur_line(r, h as i32, |y| frame[y as usize * w + x]);
+            for (y, px) in column.into_iter().enumerate() {
+                frame[y * w + x] = px;
+            }
+        }
+    }
+}
+
+/// Renders the frame in big square blocks of a single color.
+pub struct Pixelate {
+    /// Side of a block in pixels; 0 and 1 leave the frame unchanged.
+    pub block_size: u32,
+}
+
+impl PostEffect for Pixelate {
+    /// Fills every block with the average color of its pixels.
+    fn apply(&self, frame: &mut [Pixel], width: u32, height: u32) {
+        if self.block_size <= 1 {
+            return;
+        }
+        let (w, h, size) = (width as usize, height as usize, self.block_size as usize);
+        for block_y in (0..h).step_by(size) {
+            for block_x in (0..w).step_by(size) {
+                let (x1, y1) = ((block_x + size).min(w), (block_y + size).min(h));
+                let mut sum = [0u32; 4];
+                for y in block_y..y1 {
+                    for px in frame[y * w + block_x..y * w + x1].iter() {
+                        sum[0] += px.0 as u32;
+                        sum[1] += px.1 as u32;
+                        sum[2] += px.2 as u32;
+                        sum[3] += px.3 as u32;
+                    }
+                }
+                let count = ((x1 - block_x) * (y1 - block_y)) as u32;
+                let average = (
+                    (sum[0] / count) as u8,
+                    (sum[1] / count) as u8,
+                    (sum[2] / count) as u8,
+                    (sum[3] / count) as u8,
+                );
+                for y in block_y..y1 {
+                    frame[y * w + block_x..y * w + x1].fill(average);
+                }
+            }
+        }
+    }
+}
+
+/// Imitates an old CRT screen with scanlines, a curved picture and an RGB phosphor mask.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crt {
+    /// How much every other row is darkened, from 0.0 (no scanlines) to 1.0 (black rows).
+    pub scanline_intensity: f32,
+    /// Strength of the barrel distortion; 0.0 keeps the picture flat.
+    pub curvature: f32,
+    /// How much the two other channels are dimmed in every column of the RGB mask,
+    /// from 0.0 (no mask) to 1.0.
+    pub mask_strength: f32,
+}
+
+impl Default for Crt {
+    /// Returns a subtle CRT look.
+    fn default() -> Self {
+        Crt {
+            scanline_intensity: 0.35,
+            curvature: 0.06,
+            mask_strength: 0.2,
+        }
+    }
+}
+
+impl PostEffect for Crt {
+    /// Bends the picture first, then darkens the scanlines and applies the mask.
+    ///
+    /// Pixels bent in from outside of the frame are black.
+    fn apply(&self, frame: &mut [Pixel], width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (w, h) = (width as usize, height as usize);
+        if self.curvature != 0.0 {
+            let source = frame.to_vec();
+            let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+            for (idx, px) in frame.iter_mut().enumerate() {
+                let nx = ((idx % w) as f32 + 0.5 - cx) / cx;
+                let ny = ((idx / w) as f32 + 0.5 - cy) / cy;
+                let bend = 1.0 + self.curvature * (nx * nx + ny * ny);
+                let sx = (nx * bend * cx + cx).floor();
+                let sy = (ny * bend * cy + cy).floor();
+                *px = if sx < 0.0 || sy < 0.0 || sx >= width as f32 || sy >= height as f32 {
+                    (0, 0, 0, px.3)
+                } else {
+                    source[sy as usize * w + sx as usize]
+                };
+            }
+        }
+
+        let scanline = 1.0 - self.scanline_intensity.clamp(0.0, 1.0);
+        let mask = 1.0 - self.mask_strength.clamp(0.0, 1.0);
+        for y in 0..h {
+            let row_factor = if y % 2 == 1 { scanline } else { 1.0 };
+            for (x, px) in frame[y * w..(y + 1) * w].iter_mut().enumerate() {
+                let mut factors = [mask; 3];
+                factors[x % 3] = 1.0;
+                let scale = |c: u8, f: f32| (c as f32 * f * row_factor) as u8;
+                *px = (
+                    scale(px.0, factors[0]),
+                    scale(px.1, factors[1]),
+                    scale(px.2, factors[2]),
+                    px.3,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_shader_gets_positions() {
+        let mut frame = vec![(0, 0, 0, 255); 3 * 2];
+        apply_pixel_shader(
+            &|x, y, px: Pixel| (x as u8, y as u8, px.2, px.3),
+            &mut frame,
+            3,
+        );
+        assert_eq!(frame[0], (0, 0, 0, 255));
+        assert_eq!(frame[5], (2, 1, 0, 255));
+    }
+
+    #[test]
+    fn test_grayscale_keeps_alpha() {
+        let mut frame = vec![(255, 0, 0, 128), (10, 10, 10, 255)];
+        Grayscale.apply(&mut frame, 2, 1);
+        assert_eq!(frame[0], (76, 76, 76, 128));
+        assert_eq!(frame[1], (10, 10, 10, 255));
+    }
+
+    #[test]
+    fn test_vignette_darkens_corners_more_than_center() {
+        let mut frame = vec![(200, 200, 200, 255); 9 * 9];
+        Vignette { strength: 0.8 }.apply(&mut frame, 9, 9);
+        let center = frame[4 * 9 + 4];
+        let corner = frame[0];
+        assert!(corner.0 < center.0);
+        assert!(center.0 >= 199);
+        assert_eq!(corner.3, 255);
+    }
+
+    #[test]
+    fn test_blur_spreads_single_pixel() {
+        let mut frame = vec![(0, 0, 0, 255); 5 * 5];
+        frame[2 * 5 + 2] = (90, 90, 90, 255);
+        Blur { radius: 1 }.apply(&mut frame, 5, 5);
+        assert_eq!(frame[2 * 5 + 2], (10, 10, 10, 255));
+        assert_eq!(frame[5 + 1], (10, 10, 10, 255));
+        assert_eq!(frame[0], (0, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_blur_keeps_uniform_frame() {
+        let mut frame = vec![(40, 50, 60, 255); 7 * 3];
+        Blur { radius: 2 }.apply(&mut frame, 7, 3);
+        assert!(frame.iter().all(|px| *px == (40, 50, 60, 255)));
+    }
+
+    #[test]
+    fn test_pixelate_averages_blocks() {
+        let mut frame = vec![
+            (0, 0, 0, 255),
+            (100, 100, 100, 255),
+            (7, 7, 7, 255),
+            (0, 0, 0, 255),
+            (100, 100, 100, 255),
+            (7, 7, 7, 255),
+        ];
+        Pixelate { block_size: 2 }.apply(&mut frame, 3, 2);
+        assert_eq!(frame[0], (50, 50, 50, 255));
+        assert_eq!(frame[4], (50, 50, 50, 255));
+        // Blocks cut off by the frame edge only average the pixels they cover
+        assert_eq!(frame[2], (7, 7, 7, 255));
+        assert_eq!(frame[5], (7, 7, 7, 255));
+    }
+
+    #[test]
+    fn test_crt_scanlines_and_mask() {
+        let mut frame = vec![(200, 200, 200, 255); 3 * 2];
+        Crt {
+            scanline_intensity: 0.5,
+            curvature: 0.0,
+            mask_strength: 0.5,
+        }
+        .apply(&mut frame, 3, 2);
+        assert_eq!(frame[0], (200, 100, 100, 255));
+        assert_eq!(frame[1], (100, 200, 100, 255));
+        assert_eq!(frame[3 + 2], (50, 50, 100, 255));
+    }
+
+    #[test]
+    fn test_crt_curvature_blackens_corners_only() {
+        let mut frame = vec![(200, 200, 200, 255); 20 * 20];
+        Crt {
+            scanline_intensity: 0.0,
+            curvature: 0.3,
+            mask_strength: 0.0,
+        }
+        .apply(&mut frame, 20, 20);
+        assert_eq!(frame[0], (0, 0, 0, 255));
+        assert_eq!(frame[10 * 20 + 10], (200, 200, 200, 255));
+    }
+}
@@ -0,0 +1,167 @@
+//! Recording of the most recently emitted frames.
+//!
+//! A `FrameRecorder` keeps a ring buffer of the last N frames, so the moments before
+//! a bug or a nice move can be saved after the fact, as an animated GIF or as a
+//! sequence of numbered PNG images.
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Error};
+use std::path::Path;
+use std::time::Duration;
+
+use super::Pixel;
+
+/// Ring buffer of the last emitted frames.
+#[derive(Debug, Clone)]
+pub struct FrameRecorder {
+    /// Maximum number of frames kept; older frames are dropped.
+    capacity: usize,
+    /// Width and height shared by all recorded frames.
+    size: (u32, u32),
+    /// Recorded frames, oldest first.
+    frames: VecDeque<RgbaImage>,
+}
+
+impl FrameRecorder {
+    /// Creates an empty recorder.
+    ///
+    /// # Parameters
+    /// - `capacity`: Number of most recent frames kept, at least 1.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        FrameRecorder {
+            capacity,
+            size: (0, 0),
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a frame, dropping the oldest one when the recorder is full.
+    ///
+    /// A frame of another size than the recorded ones starts the recording over.
+    ///
+    /// # Parameters
+    /// - `frame`: RGBA pixels of the frame, row by row.
+    /// - `width`: Frame width in pixels.
+    /// - `height`: Frame height in pixels.
+    pub fn push(&mut self, frame: &[Pixel], width: u32, height: u32) {
+        if (width, height) != self.size {
+            self.frames.clear();
+            self.size = (width, height);
+        }
+        let bytes = frame
+            .iter()
+            .flat_map(|px| [px.0, px.1, px.2, px.3])
+            .collect();
+        let Some(image) = RgbaImage::from_raw(width, height, bytes) else {
+            return;
+        };
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(image);
+    }
+
+    /// Returns the number of recorded frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Checks whether no frames are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Drops all recorded frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Encodes the recorded frames as a looping animated GIF.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the GIF file.
+    /// - `frame_time`: How long every frame is shown.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be written.
+    pub fn save_gif(&self, path: &Path, frame_time: Duration) -> Result<(), Error> {
+        let mut encoder = GifEncoder::new(BufWriter::new(File::create(path)?));
+        encoder.set_repeat(Repeat::Infinite).map_err(Error::other)?;
+        let delay = Delay::from_saturating_duration(frame_time);
+        encoder
+            .encode_frames(
+                self.frames
+                    .iter()
+                    .map(|image| Frame::from_parts(image.clone(), 0, 0, delay)),
+            )
+            .map_err(Error::other)
+    }
+
+    /// Writes the recorded frames as numbered PNG images, `frame_0000.png` being the oldest.
+    ///
+    /// # Parameters
+    /// - `dir`: Directory the images are written to; it is created if missing.
+    ///
+    /// # Errors
+    /// Fails if the directory or an image cannot be written.
+    pub fn save_png_sequence(&self, dir: &Path) -> Result<(), Error> {
+        fs::create_dir_all(dir)?;
+        for (i, image) in self.frames.iter().enumerate() {
+            image
+                .save(dir.join(format!("frame_{i:04}.png")))
+                .map_err(Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::AnimationDecoder;
+    use image::codecs::gif::GifDecoder;
+    use std::io::BufReader;
+
+    fn frame(value: u8) -> Vec<Pixel> {
+        vec![(value, value, value, 255); 4]
+    }
+
+    #[test]
+    fn test_keeps_last_frames() {
+        let mut recorder = FrameRecorder::new(2);
+        for value in 1..=3 {
+            recorder.push(&frame(value), 2, 2);
+        }
+        assert_eq!(recorder.len(), 2);
+        assert_eq!(recorder.frames[0].get_pixel(0, 0).0, [2, 2, 2, 255]);
+
+        recorder.push(&[(0, 0, 0, 255); 9], 3, 3);
+        assert_eq!(recorder.len(), 1);
+        recorder.clear();
+        assert!(recorder.is_empty());
+    }
+
+    #[test]
+    fn test_save_gif_and_png_sequence() {
+        let mut recorder = FrameRecorder::new(8);
+        recorder.push(&frame(0), 2, 2);
+        recorder.push(&frame(255), 2, 2);
+
+        let path = std::env::temp_dir().join("rusty_ache_test_recording.gif");
+        recorder.save_gif(&path, Duration::from_millis(20)).unwrap();
+        let decoder = GifDecoder::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].buffer().get_pixel(0, 0).0, [255, 255, 255, 255]);
+        let _ = fs::remove_file(&path);
+
+        let dir = std::env::temp_dir().join("rusty_ache_test_recording");
+        recorder.save_png_sequence(&dir).unwrap();
+        assert!(dir.join("frame_0001.png").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
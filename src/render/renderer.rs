@@ -4,26 +4,125 @@
 //! shadow effects, and frame management within the game engine.
 //!
 //! This module bridges the game's logical scene state and the graphical output visible on the screen.
+//! The actual drawing is delegated to a [`RenderBackend`], and emitted frames pass
+//! through a chain of [`PostEffect`]s.
 
-use image::{DynamicImage, GenericImageView};
-use std::collections::HashMap;
+use image::{DynamicImage, RgbaImage};
+use std::path::Path;
+use std::time::Duration;
 
 use crate::Resolution;
+use crate::engine::scene::Scene;
 use crate::engine::scene::game_object::Position;
 use crate::engine::scene_manager::SceneManager;
-use crate::screen::{HEIGHT, WIDTH};
+use crate::engine::timing::SharedFrameStats;
 
-use super::utils::make_init_frame;
+use super::Pixel;
+use super::backend::RenderBackend;
+use super::camera::Camera;
+use super::debug::DebugDraw;
+use super::error::RenderError;
+use super::overlay::{self, PerfOverlay};
+use super::post::{PixelShader, PostEffect, apply_pixel_shader};
+use super::recording::FrameRecorder;
+use super::transition::{ActiveTransition, Transition};
+use super::ui::{Cursor, Ui};
 
 /// Default RGBA color for the background fill.
-pub const DEFAULT_BACKGROUND_COLOR: (u8, u8, u8, u8) = (98, 96, 96, 255);
+pub const DEFAULT_BACKGROUND_COLOR: Pixel = (98, 96, 96, 255);
 
-/// Offset used when rendering shadows relative to the sprite.
+/// Default offset used when rendering shadows relative to the sprite.
 pub const OFFSET: (i32, i32) = (10, -10);
 
-/// Opacity (alpha) value for shadows.
+/// Default opacity (alpha) value for shadows.
 pub const SHADOW_OPAQUENESS: u8 = 80;
 
+/// Describes how the background image is placed on the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundMode {
+    /// The image is shown once.
+    Single {
+        /// Whether the image is anchored in the world, with its top-left corner at the
+        /// world origin, and scrolls as the camera moves. Parts of the frame outside of
+        /// the image show the default background color.
+        ///
+        /// Without scrolling the top-left part of the image covers the screen and smaller
+        /// images are replaced by the default background color.
+        scroll: bool,
+    },
+    /// The image is repeated across the whole frame.
+    Tiled {
+        /// Whether the pattern moves together with the world when the camera moves.
+        scroll: bool,
+    },
+}
+
+impl BackgroundMode {
+    /// Checks whether the background depends on the camera position.
+    pub fn scrolls(&self) -> bool {
+        match self {
+            BackgroundMode::Single { scroll } | BackgroundMode::Tiled { scroll } => *scroll,
+        }
+    }
+}
+
+impl Default for BackgroundMode {
+    /// Returns a single image scrolling with the camera.
+    fn default() -> Self {
+        BackgroundMode::Single { scroll: true }
+    }
+}
+
+/// Describes how sprites cast shadows.
+///
+/// Can be set for the whole engine in the config, overridden for a scene,
+/// and overridden once more for a single sprite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShadowSettings {
+    /// Screen space offset of the shadow relative to the sprite (y grows downwards).
+    pub offset: (i32, i32),
+    /// Opacity (alpha) value of the shadow.
+    pub opacity: u8,
+}
+
+impl ShadowSettings {
+    /// Creates shadow settings from an offset and opacity.
+    ///
+    /// # Parameters
+    /// - `offset`: Screen space offset of the shadow relative to the sprite.
+    /// - `opacity`: Opacity of the shadow, 0 is invisible and 255 black.
+    pub fn new(offset: (i32, i32), opacity: u8) -> Self {
+        ShadowSettings { offset, opacity }
+    }
+
+    /// Creates shadow settings for shadows falling in the given direction.
+    ///
+    /// # Parameters
+    /// - `angle`: Direction of the shadow in degrees, counter-clockwise from the positive x axis.
+    /// - `length`: Distance between a sprite pixel and its shadow, in pixels.
+    /// - `opacity`: Opacity of the shadow, 0 is invisible and 255 black.
+    pub fn from_direction(angle: f32, length: f32, opacity: u8) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        ShadowSettings {
+            offset: (
+                (cos * length).round() as i32,
+                -(sin * length).round() as i32,
+            ),
+            opacity,
+        }
+    }
+}
+
+impl Default for ShadowSettings {
+    /// Returns the built-in shadow: [`OFFSET`] with [`SHADOW_OPAQUENESS`].
+    fn default() -> Self {
+        ShadowSettings {
+            offset: OFFSET,
+            opacity: SHADOW_OPAQUENESS,
+        }
+    }
+}
+
 /// Represents a rectangular area defined by top-left and bottom-right coordinates.
 pub struct Rectangle {
     /// Coordinates of the top-left corner.
@@ -49,213 +148,482 @@ pub struct Renderable {
 
 /// Manages the main rendering process.
 ///
-/// Holds the rendering resolution, background image, previously rendered frame,
-/// and the scene manager to access active scene data.
+/// Holds the render backend and the scene manager to access active scene data.
+/// Every tick the renderer collects the sprites of the active scene, positions the camera
+/// on the main object and lets the backend draw the frame. Post effects are applied to
+/// the emitted frame in the order they were added, followed by the color grading of the
+/// active scene and the pixel shader, if set.
+/// The screen-space overlay and queued debug shapes are drawn last, followed by the
+/// performance overlay, if shown, and the software cursor.
+/// When the active scene changes, the last frame of the old scene is blended into the
+/// frames of the new one as set with [`Renderer::set_transition`].
 pub struct Renderer {
-    resolution: Resolution,
+    backend: Box<dyn RenderBackend>,
+    post_effects: Vec<Box<dyn PostEffect>>,
+    /// User function applied to every pixel of the emitted frame.
+    pixel_shader: Option<Box<PixelShader>>,
+    /// Overlay drawn above the world, independent of the camera.
+    ui: Ui,
+    /// Debug shapes drawn on the next emitted frame.
+    debug: DebugDraw,
+    /// Visibility of the performance overlay.
+    perf_overlay: PerfOverlay,
+    /// Frame statistics shown by the performance overlay.
+    frame_stats: SharedFrameStats,
+    /// Software cursor drawn above everything else.
+    cursor: Option<Cursor>,
+    /// Screen position of the mouse, None while it is outside the frame.
+    cursor_position: Option<(i32, i32)>,
+    /// The most recently emitted frame, kept for screenshots.
+    last_frame: Option<Vec<Pixel>>,
+    /// Recorder of the emitted frames, while recording.
+    recorder: Option<FrameRecorder>,
+    /// Camera position used for the most recent frame.
+    camera_top: (i32, i32),
+    /// Whether blending happens in linear light instead of raw sRGB bytes.
+    gamma_correct: bool,
+    /// Shadow used by scenes and sprites without their own settings.
+    default_shadow: ShadowSettings,
+    /// Background image set by the game, shown while no scene is covered.
     background: Option<DynamicImage>,
-    prev_frame: Vec<(u8, u8, u8, u8)>,
+    /// Placement of the background image set by the game.
+    background_mode: BackgroundMode,
+    /// Last frames of the covered scenes, the bottom one first; the top one is shown as the
+    /// background of the active scene.
+    backdrops: Vec<DynamicImage>,
+    /// Transition shown when the active scene changes.
+    transition: Transition,
+    /// Transition from the old scene in progress, if any.
+    active_transition: Option<ActiveTransition>,
     pub scene_manager: SceneManager,
 }
 
 impl Renderer {
-    /// Creates a new renderer instance with given resolution, background image, and scene manager.
-    ///
-    /// Initializes the previous frame buffer based on the background or default color.
-    pub(crate) fn new(
-        resolution: Resolution,
-        background: Option<DynamicImage>,
-        scene_manager: SceneManager,
-    ) -> Self {
-        let background_clone = background.clone();
-        let init_frame = make_init_frame(background_clone);
+    /// Creates a new renderer instance drawing with the given backend.
+    pub(crate) fn new(backend: Box<dyn RenderBackend>, scene_manager: SceneManager) -> Self {
         Renderer {
-            resolution,
-            background,
-            prev_frame: init_frame.clone(),
+            backend,
+            post_effects: Vec::new(),
+            pixel_shader: None,
+            ui: Ui::new(),
+            debug: DebugDraw::new(),
+            perf_overlay: PerfOverlay::new(),
+            frame_stats: SharedFrameStats::new(),
+            cursor: None,
+            cursor_position: None,
+            last_frame: None,
+            recorder: None,
+            camera_top: (0, 0),
+            gamma_correct: false,
+            default_shadow: ShadowSettings::default(),
+            background: None,
+            background_mode: BackgroundMode::default(),
+            backdrops: Vec::new(),
+            transition: Transition::default(),
+            active_transition: None,
             scene_manager,
         }
     }
 
-    /// Finds the intersection of two rectangles.
+    /// Sets the shadow used by scenes and sprites without their own shadow settings.
     ///
-    /// Used during rendering to determine what part of an object should be drawn based on
-    /// the current camera's viewport.
+    /// # Parameters
+    /// - `shadow`: Engine-wide shadow settings.
+    pub fn set_default_shadow(&mut self, shadow: ShadowSettings) {
+        self.default_shadow = shadow;
+        self.invalidate();
+    }
+
+    /// Selects whether shadows, translucent pixels and the overlay are blended in linear
+    /// light, which avoids muddy dark blends, and schedules a full redraw.
     ///
     /// # Parameters
-    /// - `fst`: First rectangle.
-    /// - `snd`: Second rectangle.
+    /// - `enabled`: True for gamma-correct blending, false for blending raw sRGB bytes.
+    pub fn set_gamma_correct(&mut self, enabled: bool) {
+        self.gamma_correct = enabled;
+        self.backend.set_gamma_correct(enabled);
+        self.invalidate();
+    }
+
+    /// Appends an effect to the post-processing chain.
     ///
-    /// # Returns
-    /// Optionally returns a rectangle representing the intersecting area, or None if no intersection.
-    fn _find_intersection(fst: &Rectangle, snd: &Rectangle) -> Option<Rectangle> {
-        let left = fst.top_left.0.max(snd.top_left.0);
-        let right = fst.bot_right.0.min(snd.bot_right.0);
-        let top = fst.top_left.1.min(snd.top_left.1);
-        let bot = fst.bot_right.1.max(snd.bot_right.1);
-        if left < right && top > bot {
-            return Some(Rectangle {
-                top_left: (left, top),
-                bot_right: (right, bot),
-            });
-        };
-        None
+    /// # Parameters
+    /// - `effect`: Effect applied to every emitted frame after the previously added ones.
+    pub fn add_post_effect(&mut self, effect: Box<dyn PostEffect>) {
+        self.post_effects.push(effect);
     }
 
-    /// Blits (copies) a sprite onto the frame buffer considering visible area, position, camera offset, and shadow.
+    /// Sets a function computing the final color of every pixel of the emitted frames.
     ///
-    /// Handles pixel mapping, shadow rendering, clipping outside the viewport,
-    /// and color blending for semi-transparent pixels.
+    /// The shader runs after the post effects and color grading and before the overlay is drawn; it can be used
+    /// for custom effects such as waves or scanlines. Setting a shader replaces the previous one.
     ///
     /// # Parameters
-    /// - `frame`: Mutable frame buffer to draw on.
-    /// - `sprite`: Sprite image to render.
-    /// - `visible_area`: Screen world rectangle describing visible part of sprite.
-    /// - `position`: Game world position of sprite.
-    /// - `camera_top`: World position of the camera top-left corner.
-    /// - `frame_size`: Dimensions of the frame buffer.
-    /// - `has_shadow`: Indicates if shadow rendering should be applied.
-    fn blit_sprite(
-        frame: &mut [(u8, u8, u8, u8)],
-        sprite: &DynamicImage,
-        visible_area: &Rectangle,
-        position: (i32, i32),
-        camera_top: (i32, i32),
-        frame_size: (i32, i32),
-        has_shadow: bool,
+    /// - `shader`: Function mapping the screen position `(x, y)` and color of a pixel to its new color.
+    pub fn set_pixel_shader(
+        &mut self,
+        shader: impl Fn(u32, u32, Pixel) -> Pixel + Send + Sync + 'static,
     ) {
-        let (frame_w, frame_h) = frame_size;
+        self.pixel_shader = Some(Box::new(shader));
+    }
 
-        // loop over world coordinates of visible area
-        let (sprite_w, sprite_h) = sprite.dimensions();
+    /// Removes the pixel shader set with [`Renderer::set_pixel_shader`].
+    pub fn clear_pixel_shader(&mut self) {
+        self.pixel_shader = None;
+    }
 
-        // Iterate over world coordinates within visible area
-        for wy in visible_area.bot_right.1..visible_area.top_left.1 {
-            for wx in visible_area.top_left.0..visible_area.bot_right.0 {
-                // Map world coordinates to sprite local coordinates
-                if wx < position.0 || wy > position.1 {
-                    continue;
-                }
-                let sprite_x = wx - position.0;
-                let sprite_y = position.1 - wy;
+    /// Returns a handle to the screen-space overlay drawn above every frame.
+    pub fn ui(&self) -> Ui {
+        self.ui.clone()
+    }
 
-                if sprite_x >= sprite_w as i32 || sprite_y >= sprite_h as i32 {
-                    continue;
-                }
+    /// Returns a handle to the queue of debug shapes drawn on the next emitted frame.
+    pub fn debug(&self) -> DebugDraw {
+        self.debug.clone()
+    }
 
-                let px = sprite.get_pixel(sprite_x.try_into().unwrap(), sprite_y as u32);
-                let src = px.0;
+    /// Returns a handle to the visibility of the performance overlay.
+    pub fn perf_overlay(&self) -> PerfOverlay {
+        self.perf_overlay.clone()
+    }
 
-                // Skip fully transparent pixels
-                if src[3] == 0 {
-                    continue;
-                }
+    /// Sets the frame statistics shown by the performance overlay.
+    pub(crate) fn set_frame_stats(&mut self, frame_stats: SharedFrameStats) {
+        self.frame_stats = frame_stats;
+    }
 
-                if has_shadow {
-                    let sx_i_shadow = wx + OFFSET.0 - camera_top.0;
-                    let sy_i_shadow = camera_top.1 - wy + OFFSET.1;
-                    if sx_i_shadow < 0 || sy_i_shadow < 0 {
-                        continue;
-                    }
-                    let sx_shadow = sx_i_shadow as u32;
-                    let sy_shadow = sy_i_shadow as u32;
-                    if sx_shadow >= frame_w as u32 || sy_shadow >= frame_h as u32 {
-                        continue;
-                    }
-                    let idx = (sy_shadow * frame_w as u32 + sx_shadow) as usize;
-                    let existing = frame[idx];
-                    let alpha = SHADOW_OPAQUENESS as f32 / 255.0;
-                    let blended = (
-                        (existing.0 as f32 * (1.0 - alpha)) as u8,
-                        (existing.1 as f32 * (1.0 - alpha)) as u8,
-                        (existing.2 as f32 * (1.0 - alpha)) as u8,
-                        255,
-                    );
-                    frame[idx] = blended;
-                }
+    /// Sets the software cursor drawn at the mouse position, or removes it with None.
+    ///
+    /// # Parameters
+    /// - `cursor`: Cursor sprite and its hotspot.
+    pub fn set_cursor(&mut self, cursor: Option<Cursor>) {
+        self.cursor = cursor;
+    }
 
-                // Map world to screen coordinates
-                let sx_i = wx - camera_top.0;
-                let sy_i = camera_top.1 - wy;
+    /// Checks whether a software cursor is set.
+    pub fn has_cursor(&self) -> bool {
+        self.cursor.is_some()
+    }
 
-                if sx_i < 0 || sy_i < 0 {
-                    continue;
-                }
-                let sx = sx_i as u32;
-                let sy = sy_i as u32;
+    /// Moves the software cursor.
+    ///
+    /// # Parameters
+    /// - `position`: Screen position of the mouse, or None to hide the cursor.
+    pub fn set_cursor_position(&mut self, position: Option<(i32, i32)>) {
+        self.cursor_position = position;
+    }
 
-                if sx >= frame_w as u32 || sy >= frame_h as u32 {
-                    continue;
-                }
+    /// Returns a copy of the most recently emitted frame as an image.
+    ///
+    /// # Errors
+    /// Fails with [`RenderError::NoFrame`] if no frame was emitted yet.
+    pub fn screenshot(&self) -> Result<RgbaImage, RenderError> {
+        let frame = self.last_frame.as_ref().ok_or(RenderError::NoFrame)?;
+        let resolution = self.backend.resolution();
+        let bytes = frame
+            .iter()
+            .flat_map(|px| [px.0, px.1, px.2, px.3])
+            .collect();
+        RgbaImage::from_raw(resolution.width, resolution.height, bytes).ok_or(
+            RenderError::BufferSizeMismatch {
+                expected: (resolution.width * resolution.height) as usize,
+                actual: frame.len(),
+            },
+        )
+    }
 
-                // Write pixel data to frame, considering shadow and transparency
-                let idx = (sy * frame_w as u32 + sx) as usize;
-                let mut shadowed = src;
-                if src[0] == 0 && src[1] == 0 && src[2] == 0 && src[3] != 255 {
-                    shadowed[0] = frame[idx].0.saturating_sub(src[3]);
-                    shadowed[1] = frame[idx].1.saturating_sub(src[3]);
-                    shadowed[2] = frame[idx].2.saturating_sub(src[3]);
-                }
+    /// Writes the most recently emitted frame to an image file.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the file; its extension selects the format, e.g. `.png`.
+    ///
+    /// # Errors
+    /// Fails if no frame was emitted yet or the file cannot be written.
+    pub fn save_screenshot(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.screenshot()?.save(path).map_err(std::io::Error::other)
+    }
 
-                frame[idx] = (shadowed[0], shadowed[1], shadowed[2], shadowed[3]);
-            }
+    /// Starts recording the emitted frames, keeping the most recent ones.
+    ///
+    /// A running recording is discarded.
+    ///
+    /// # Parameters
+    /// - `max_frames`: Number of most recent frames kept.
+    pub fn start_recording(&mut self, max_frames: usize) {
+        self.recorder = Some(FrameRecorder::new(max_frames));
+    }
+
+    /// Stops recording and hands out the recorded frames.
+    ///
+    /// # Returns
+    /// The recorder holding the last frames, or None if no recording was running.
+    pub fn stop_recording(&mut self) -> Option<FrameRecorder> {
+        self.recorder.take()
+    }
+
+    /// Returns the running recording, if any.
+    pub fn recording(&self) -> Option<&FrameRecorder> {
+        self.recorder.as_ref()
+    }
+
+    /// Replaces the background image and schedules a full redraw.
+    ///
+    /// # Parameters
+    /// - `background`: New background image, or None for the default background color.
+    ///
+    /// While a scene is covered by a pushed one, the new background shows once the covered
+    /// scene is active again.
+    pub fn set_background(&mut self, background: Option<DynamicImage>) {
+        self.background = background;
+        if self.backdrops.is_empty() {
+            self.backend.set_background(self.background.clone());
         }
     }
 
-    /// Core rendering function that creates a new frame based on the current scene and background.
+    /// Changes how the background image is placed on the frame and schedules a full redraw.
     ///
-    /// Initializes the frame, collects renderable objects from the active scene,
-    /// calculates their positions and visible rectangles, and blits sprites onto the frame.
-    pub(crate) fn render(&mut self) {
-        // Camera rectangle based on main object's position and screen size
-        let main_object = &self.scene_manager.active_scene.main_object;
-        let mut frame: Vec<(u8, u8, u8, u8)> = make_init_frame(self.background.clone());
+    /// # Parameters
+    /// - `mode`: Single or tiled, scrolling or screen-fixed background.
+    pub fn set_background_mode(&mut self, mode: BackgroundMode) {
+        self.background_mode = mode;
+        if self.backdrops.is_empty() {
+            self.backend.set_background_mode(mode);
+        }
+    }
 
-        let renderable = self.scene_manager.init_active_scene();
+    /// Puts a scene on top of the active one, which stays visible below it.
+    ///
+    /// The active scene is rendered once more and its frame becomes the screen-fixed
+    /// background of the pushed scene, so the covered scene costs nothing to show while it
+    /// is frozen.
+    ///
+    /// # Errors
+    /// Fails without pushing the scene if a sprite of the active scene is broken.
+    pub(crate) fn push_scene(&mut self, scene: Scene) -> Result<(), RenderError> {
+        self.render()?;
+        let frame = self.backend.emit().ok_or(RenderError::NoFrame)?;
+        let resolution = self.backend.resolution();
+        let backdrop = RgbaImage::from_fn(resolution.width, resolution.height, |x, y| {
+            let (r, g, b, a) = frame[(y * resolution.width + x) as usize];
+            image::Rgba([r, g, b, a])
+        });
+        self.backdrops.push(DynamicImage::ImageRgba8(backdrop));
+        self.show_backdrop();
+        self.scene_manager.push_scene(scene);
+        self.invalidate();
+        Ok(())
+    }
 
-        let _camera_rect = Rectangle {
-            top_left: (main_object.position.x, main_object.position.y),
-            bot_right: (
-                main_object.position.x + WIDTH as i32,
-                main_object.position.y - HEIGHT as i32,
-            ),
+    /// Takes the scene on top away and shows the scene below it again.
+    ///
+    /// # Returns
+    /// The scene taken away, or None if no scene is covered by the active one.
+    pub(crate) fn pop_scene(&mut self) -> Option<Scene> {
+        let popped = self.scene_manager.pop_scene()?;
+        self.backdrops.pop();
+        self.show_backdrop();
+        self.invalidate();
+        Some(popped)
+    }
+
+    /// Shows the frame of the covered scene behind the active one, or the background of
+    /// the game if no scene is covered.
+    fn show_backdrop(&mut self) {
+        match self.backdrops.last() {
+            Some(backdrop) => {
+                self.backend.set_background(Some(backdrop.clone()));
+                self.backend
+                    .set_background_mode(BackgroundMode::Single { scroll: false });
+            }
+            None => {
+                self.backend.set_background(self.background.clone());
+                self.backend.set_background_mode(self.background_mode);
+            }
+        }
+    }
+
+    /// Sets the transition shown when the active scene changes, a cut by default.
+    pub fn set_transition(&mut self, transition: Transition) {
+        self.transition = transition;
+    }
+
+    /// Returns the transition shown when the active scene changes.
+    pub fn transition(&self) -> Transition {
+        self.transition
+    }
+
+    /// Checks whether a transition from the old scene is still shown.
+    pub fn is_transitioning(&self) -> bool {
+        self.active_transition.is_some()
+    }
+
+    /// Starts the transition away from the most recently rendered frame, called right
+    /// before the active scene is replaced. Does nothing for a cut or before the first frame.
+    pub(crate) fn start_transition(&mut self) {
+        if self.transition.duration().is_zero() {
+            return;
+        }
+        if let Some(frame) = self.backend.emit() {
+            self.active_transition = Some(ActiveTransition::new(self.transition, frame));
+        }
+    }
+
+    /// Lets the transition in progress, if any, run for the given time.
+    pub(crate) fn advance_transition(&mut self, elapsed: Duration) {
+        if let Some(active) = self.active_transition.as_mut()
+            && !active.advance(elapsed)
+        {
+            self.active_transition = None;
+        }
+    }
+
+    /// Returns the size of the rendered frames.
+    pub fn resolution(&self) -> Resolution {
+        self.backend.resolution()
+    }
+
+    /// Changes the size of the rendered frames and schedules a full redraw.
+    pub fn resize(&mut self, resolution: Resolution) {
+        self.backend.resize(resolution);
+    }
+
+    /// Drops the information about the previous frame, so the next render is a full redraw.
+    ///
+    /// Must be called whenever the drawn content changes in a way not visible through
+    /// object positions, e.g. when the active scene is replaced.
+    pub(crate) fn invalidate(&mut self) {
+        self.backend.invalidate();
+    }
+
+    /// Finds the intersection of two rectangles.
+    ///
+    /// Used during rendering to determine what part of an object should be drawn based on
+    /// the current camera's viewport.
+    ///
+    /// # Parameters
+    /// - `fst`: First rectangle.
+    /// - `snd`: Second rectangle.
+    ///
+    /// # Returns
+    /// Optionally returns a rectangle representing the intersecting area, or None if no intersection.
+    pub(crate) fn find_intersection(fst: &Rectangle, snd: &Rectangle) -> Option<Rectangle> {
+        let left = fst.top_left.0.max(snd.top_left.0);
+        let right = fst.bot_right.0.min(snd.bot_right.0);
+        let top = fst.top_left.1.min(snd.top_left.1);
+        let bot = fst.bot_right.1.max(snd.bot_right.1);
+        if left < right && top > bot {
+            return Some(Rectangle {
+                top_left: (left, top),
+                bot_right: (right, bot),
+            });
         };
+        None
+    }
 
-        let _uids_by_z = HashMap::<u32, usize>::new();
-        for (obj, img, offset, has_shadow) in renderable {
-            let pos = Position {
-                x: obj.position.x + offset.0,
-                y: obj.position.y + offset.1,
-                z: obj.position.z,
-                is_relative: obj.position.is_relative,
-            };
-
-            let im_size = img.dimensions();
-            let im_bot_right = (pos.x + im_size.0 as i32, pos.y - im_size.1 as i32);
-            let im_rect = Rectangle {
-                top_left: (pos.x, pos.y),
-                bot_right: im_bot_right,
-            };
-
-            Self::blit_sprite(
-                &mut frame,
-                img,
-                &im_rect,
-                (pos.x, pos.y),
-                (main_object.position.x, main_object.position.y),
-                (self.resolution.width as i32, self.resolution.height as i32),
-                has_shadow,
-            );
-            self.prev_frame = frame.clone();
+    /// Returns the camera of the most recently rendered frame.
+    ///
+    /// It converts between positions in that frame, such as the mouse position, and world
+    /// positions.
+    pub fn camera(&self) -> Camera {
+        Camera::new(self.camera_top)
+    }
+
+    /// Renders the current frame of the active scene.
+    ///
+    /// The camera follows the main object; sprites are passed to the backend in drawing order.
+    ///
+    /// # Errors
+    /// Fails without drawing anything if a sprite of the scene is broken.
+    pub(crate) fn render(&mut self) -> Result<(), RenderError> {
+        // Camera rectangle based on main object's position and screen size
+        // Sprites of a recycled UID cannot be told apart from the ones of the old object,
+        // and loaded images may have replaced placeholders of the same size
+        if self.scene_manager.active_scene.take_needs_redraw() {
+            self.invalidate();
         }
+        let main_object = &self.scene_manager.active_scene.main_object;
+        let camera_top = (main_object.position.x, main_object.position.y);
+        self.camera_top = camera_top;
+        let sprites = self
+            .scene_manager
+            .active_scene
+            .init_with_shadow(self.default_shadow)?;
+        self.backend.render(camera_top, &sprites);
+        Ok(())
     }
 
-    /// Returns the most recently rendered frame for display.
+    /// Returns the most recently rendered frame for display, with post effects applied
+    /// and the overlay drawn on top. Queued debug shapes are drawn last and dequeued.
     ///
     /// # Returns
-    /// Optionally returns a vector representing the RGBA pixel data of the current frame.
-    pub fn emit(&mut self) -> Option<Vec<(u8, u8, u8, u8)>> {
-        Some(self.prev_frame.clone())
+    /// A vector representing the RGBA pixel data of the current frame.
+    ///
+    /// # Errors
+    /// Fails if nothing was rendered yet or the frame does not match the backend resolution.
+    pub fn emit(&mut self) -> Result<Vec<Pixel>, RenderError> {
+        let mut frame = self.backend.emit().ok_or(RenderError::NoFrame)?;
+        let resolution = self.backend.resolution();
+        let covered = (resolution.width * resolution.height) as usize;
+        if frame.len() != covered {
+            return Err(RenderError::BufferSizeMismatch {
+                expected: covered,
+                actual: frame.len(),
+            });
+        }
+        if let Some(active) = &self.active_transition {
+            active.apply(&mut frame, resolution.width);
+        }
+        for effect in self.post_effects.iter() {
+            effect.apply(&mut frame, resolution.width, resolution.height);
+        }
+        if let Some(lut) = self.scene_manager.active_scene.color_grading() {
+            lut.apply(&mut frame, resolution.width, resolution.height);
+        }
+        if let Some(shader) = &self.pixel_shader {
+            apply_pixel_shader(shader.as_ref(), &mut frame, resolution.width);
+        }
+        self.ui.draw(
+            &mut frame,
+            resolution.width,
+            resolution.height,
+            self.gamma_correct,
+        );
+        self.debug.flush(
+            &mut frame,
+            resolution.width,
+            resolution.height,
+            self.camera_top,
+        );
+        if self.perf_overlay.is_visible() {
+            let lines = overlay::overlay_lines(
+                &self.frame_stats.get(),
+                self.scene_manager.active_scene.uids().len(),
+                self.backend.blitted_pixels(),
+            );
+            overlay::draw(
+                &mut frame,
+                resolution.width,
+                resolution.height,
+                &lines,
+                self.gamma_correct,
+            );
+        }
+        if let Some(cursor) = &self.cursor
+            && let Some(position) = self.cursor_position
+        {
+            cursor.draw(
+                &mut frame,
+                resolution.width,
+                resolution.height,
+                position,
+                self.gamma_correct,
+            );
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.push(&frame, resolution.width, resolution.height);
+        }
+        self.last_frame = Some(frame.clone());
+        Ok(frame)
     }
 }
 
@@ -263,11 +631,18 @@ impl Renderer {
 mod tests {
     use image::{Rgba, RgbaImage};
 
+    use crate::engine::scene::game_object::components::sprite::Sprite;
+    use crate::engine::scene::game_object::{GameObject, Object};
+    use crate::engine::scene::{RenderableSprite, Scene};
     use crate::interface::{create_obj_with_img, init_scene};
+    use crate::render::cpu::CpuBackend;
+    use crate::render::grading::ColorLut;
+    use crate::render::post::{Grayscale, Pixelate};
+    use crate::screen::{HEIGHT, WIDTH};
 
     use super::*;
 
-    const DEFAULT_BACKGROUND: (u8, u8, u8, u8) = (
+    const DEFAULT_BACKGROUND: Pixel = (
         DEFAULT_BACKGROUND_COLOR.0,
         DEFAULT_BACKGROUND_COLOR.1,
         DEFAULT_BACKGROUND_COLOR.2,
@@ -276,7 +651,6 @@ mod tests {
 
     fn test_init_renderer() -> Renderer {
         let resolution = Resolution::new(200, 200);
-        let background = None;
         let objs = [create_obj_with_img(
             "./resources/perf_diag.png",
             200,
@@ -284,10 +658,10 @@ mod tests {
             false,
         )];
         let main_obj = create_obj_with_img("./resources/perf_diag.png", 300, 300, true);
-        let main_scene = init_scene(&objs, main_obj);
+        let main_scene = init_scene(&objs, main_obj).unwrap();
         let scene_manager = SceneManager::new(main_scene);
-        let renderer = Renderer::new(resolution, background, scene_manager);
-        return renderer;
+
+        Renderer::new(Box::new(CpuBackend::new(resolution, None)), scene_manager)
     }
 
     fn create_sprite_with_color(width: u32, height: u32, color: [u8; 4]) -> DynamicImage {
@@ -300,20 +674,6 @@ mod tests {
         DynamicImage::ImageRgba8(img)
     }
 
-    #[test]
-    fn test_renderer() {
-        let renderer = test_init_renderer();
-        assert_eq!(renderer.resolution.height, 200);
-        assert_eq!(renderer.resolution.height, 200);
-        assert_eq!(renderer.background, None);
-        let mut vector = renderer.prev_frame;
-        for _ in 0..HEIGHT {
-            for _ in 0..WIDTH {
-                assert_eq!(vector.pop(), Some(DEFAULT_BACKGROUND));
-            }
-        }
-    }
-
     #[test]
     fn test_find_intersection_symmetric_rectangles() {
         let fst = Rectangle {
@@ -324,9 +684,9 @@ mod tests {
             top_left: (0, 200),
             bot_right: (200, 0),
         };
-        let result = Renderer::_find_intersection(&fst, &snd);
+        let result = Renderer::find_intersection(&fst, &snd);
         match result {
-            None => assert!(false),
+            None => panic!("Expected an intersection"),
             Some(res) => {
                 assert_eq!(res.top_left, (0, 200));
                 assert_eq!(res.bot_right, (200, 0));
@@ -344,9 +704,9 @@ mod tests {
             top_left: (0, 200),
             bot_right: (150, 150),
         };
-        let result = Renderer::_find_intersection(&fst, &snd);
+        let result = Renderer::find_intersection(&fst, &snd);
         match result {
-            None => assert!(false),
+            None => panic!("Expected an intersection"),
             Some(res) => {
                 assert_eq!(res.top_left, (0, 200));
                 assert_eq!(res.bot_right, (150, 150));
@@ -364,123 +724,640 @@ mod tests {
             top_left: (-200, 200),
             bot_right: (0, 0),
         };
-        let result = Renderer::_find_intersection(&fst, &snd);
+        let result = Renderer::find_intersection(&fst, &snd);
         match result {
-            None => assert!(true),
-            Some(_) => assert!(false),
+            None => {}
+            Some(_) => panic!("Expected no intersection"),
         }
     }
 
     #[test]
-    fn test_fully_opaque_no_shadow() {
-        let mut frame = vec![(0u8, 0u8, 0u8, 0u8); 10 * 10];
-        let sprite = create_sprite_with_color(3, 3, [255, 0, 0, 255]); // red opaque
-        let visible_area = Rectangle {
-            top_left: (0, 5),
-            bot_right: (5, 0),
-        };
-        Renderer::blit_sprite(
-            &mut frame,
-            &sprite,
-            &visible_area,
-            (1, 3),
-            (0, 5),
-            (10, 10),
-            false,
+    fn test_emit() {
+        let mut renderer = test_init_renderer();
+        let result = renderer.emit();
+        match result {
+            Err(e) => panic!("Expected a frame, got {e}"),
+            Ok(mut res) => {
+                for _ in 0..200 {
+                    for _ in 0..200 {
+                        assert_eq!(res.pop(), Some(DEFAULT_BACKGROUND));
+                    }
+                }
+                assert!(res.is_empty());
+            }
+        }
+    }
+
+    fn scene_with_sprite(color: [u8; 4]) -> Scene {
+        let obj = GameObject::new(
+            vec![Box::new(Sprite::new(
+                Some(create_sprite_with_color(20, 20, color)),
+                true,
+                (0, 0),
+            ))],
+            None,
+            Position {
+                x: 30,
+                y: -30,
+                z: 1,
+                is_relative: false,
+            },
         );
-        let idx = (2 * 10 + 1) as usize;
-        assert_eq!(frame[idx].0, 255);
-        assert_eq!(frame[idx].3, 255);
+        Scene::new(
+            vec![obj],
+            vec![],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap()
     }
 
     #[test]
-    fn test_transparent_pixels_skipped() {
-        let mut frame = vec![(100, 100, 100, 100); 10 * 10];
-        let mut sprite_img = RgbaImage::new(2, 2);
-        sprite_img.put_pixel(0, 0, Rgba([0, 0, 0, 0])); // transparent
-        sprite_img.put_pixel(1, 0, Rgba([50, 50, 50, 255])); // opaque
-        sprite_img.put_pixel(0, 1, Rgba([10, 10, 10, 0])); // transparent
-        sprite_img.put_pixel(1, 1, Rgba([20, 20, 20, 255])); // opaque
-        let sprite = DynamicImage::ImageRgba8(sprite_img);
-        let visible_area = Rectangle {
-            top_left: (1, 2),
-            bot_right: (3, 0),
-        };
-        Renderer::blit_sprite(
-            &mut frame,
-            &sprite,
-            &visible_area,
-            (1, 1),
-            (0, 2),
-            (10, 10),
-            false,
+    fn test_render_without_changes_keeps_frame() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.render().unwrap();
+        let first = renderer.emit().unwrap();
+        renderer.render().unwrap();
+        let second = renderer.emit().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first[(40 * WIDTH + 40) as usize], (255, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_invalidate_forces_full_redraw() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
         );
+        renderer.render().unwrap();
+        renderer.invalidate();
+
+        renderer.scene_manager = SceneManager::new(scene_with_sprite([0, 255, 0, 255]));
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(40 * WIDTH + 40) as usize], (0, 255, 0, 255));
+    }
 
-        assert_eq!(frame[1 * 10 + 0], (100, 100, 100, 100));
+    fn scene_with_sprite_at(x: i32, y: i32, has_shadow: bool) -> Scene {
+        let obj = GameObject::new(
+            vec![Box::new(Sprite::new(
+                Some(create_sprite_with_color(10, 10, [255, 255, 255, 255])),
+                has_shadow,
+                (0, 0),
+            ))],
+            None,
+            Position {
+                x,
+                y,
+                z: 1,
+                is_relative: false,
+            },
+        );
+        Scene::new(
+            vec![obj],
+            vec![],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap()
     }
 
     #[test]
-    fn test_shadow_pixels_with_partial_alpha_subtract() {
-        let mut frame = vec![(100, 100, 100, 255); 10 * 10];
-        let mut sprite_img = RgbaImage::new(1, 1);
-        sprite_img.put_pixel(0, 0, Rgba([0, 0, 0, 10])); // partially transparent black pixel
-        let sprite = DynamicImage::ImageRgba8(sprite_img);
-        let visible_area = Rectangle {
-            top_left: (0, 1),
-            bot_right: (1, 0),
-        };
-        Renderer::blit_sprite(
-            &mut frame,
-            &sprite,
-            &visible_area,
-            (0, 0),
-            (0, 1),
-            (10, 10),
-            false,
+    fn test_render_skips_off_screen_sprite() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite_at(1000, 1000, true)),
         );
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert!(frame.iter().all(|px| *px == DEFAULT_BACKGROUND));
+    }
 
-        let idx = (1 * 10 + 0) as usize;
-        assert_eq!(frame[idx].0, 90);
-        assert_eq!(frame[idx].1, 90);
-        assert_eq!(frame[idx].2, 90);
+    #[test]
+    fn test_render_keeps_shadow_of_off_screen_sprite() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite_at(-10, -20, true)),
+        );
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_ne!(frame[(15 * WIDTH + 5) as usize], DEFAULT_BACKGROUND);
     }
 
     #[test]
-    fn test_pixels_outside_visible_area_not_drawn() {
-        let mut frame = vec![(50, 50, 50, 255); 10 * 10];
-        let sprite = create_sprite_with_color(2, 2, [255, 255, 255, 255]);
-        let visible_area = Rectangle {
-            top_left: (0, 2),
-            bot_right: (2, 1),
-        };
-        Renderer::blit_sprite(
-            &mut frame,
-            &sprite,
-            &visible_area,
-            (3, 3),
-            (0, 2),
-            (10, 10),
-            false,
+    fn test_render_empty_scene_clears_previous_frame() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
         );
+        renderer.render().unwrap();
 
-        for color in frame.iter() {
-            assert_eq!(*color, (50, 50, 50, 255));
-        }
+        renderer.scene_manager = SceneManager::new(
+            Scene::new(
+                vec![],
+                vec![],
+                Position {
+                    x: 0,
+                    y: 0,
+                    z: 0,
+                    is_relative: false,
+                },
+            )
+            .unwrap(),
+        );
+        renderer.invalidate();
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert!(frame.iter().all(|px| *px == DEFAULT_BACKGROUND));
     }
 
     #[test]
-    fn test_emit() {
-        let mut renderer = test_init_renderer();
-        let result = renderer.emit();
-        match result {
-            None => assert!(false),
-            Some(mut res) => {
-                for _ in 0..HEIGHT {
-                    for _ in 0..WIDTH {
-                        assert_eq!(res.pop(), Some(DEFAULT_BACKGROUND));
-                    }
+    fn test_covered_scene_stays_visible_behind_pushed_scene() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.render().unwrap();
+        let covered = renderer.emit().unwrap();
+
+        let menu = Scene::new(
+            vec![],
+            vec![],
+            renderer.scene_manager.active_scene.main_object.position,
+        )
+        .unwrap();
+        renderer.push_scene(menu).unwrap();
+        assert_eq!(renderer.scene_manager.depth(), 1);
+        renderer.render().unwrap();
+        assert_eq!(renderer.emit().unwrap(), covered);
+
+        // The background set meanwhile shows once the covered scene is back
+        let blue = create_sprite_with_color(WIDTH, HEIGHT, [0, 0, 255, 255]);
+        renderer.set_background(Some(blue));
+        renderer.render().unwrap();
+        assert_eq!(renderer.emit().unwrap(), covered);
+
+        assert!(renderer.pop_scene().is_some());
+        assert!(renderer.pop_scene().is_none());
+        renderer.set_background(None);
+        renderer.render().unwrap();
+        assert_eq!(renderer.emit().unwrap(), covered);
+    }
+
+    #[test]
+    fn test_fade_blends_old_scene_into_new_scene() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.set_transition(Transition::Fade(Duration::from_millis(100)));
+        renderer.render().unwrap();
+        let old = renderer.emit().unwrap();
+
+        renderer.start_transition();
+        let position = renderer.scene_manager.active_scene.main_object.position;
+        renderer.scene_manager = SceneManager::new(Scene::new(vec![], vec![], position).unwrap());
+        renderer.invalidate();
+        renderer.render().unwrap();
+        assert_eq!(renderer.emit().unwrap(), old);
+
+        renderer.advance_transition(Duration::from_millis(50));
+        let red = old.iter().position(|px| *px == (255, 0, 0, 255)).unwrap();
+        let half = renderer.emit().unwrap()[red];
+        assert!(half.0 < old[red].0 && half.0 > DEFAULT_BACKGROUND.0);
+        assert!(renderer.is_transitioning());
+
+        renderer.advance_transition(Duration::from_millis(50));
+        assert!(!renderer.is_transitioning());
+        let frame = renderer.emit().unwrap();
+        assert!(frame.iter().all(|px| *px == DEFAULT_BACKGROUND));
+    }
+
+    #[test]
+    fn test_post_effects_applied_in_order_on_emit() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.add_post_effect(Box::new(Grayscale));
+        renderer.add_post_effect(Box::new(Pixelate { block_size: 4 }));
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert!(frame.iter().all(|px| px.0 == px.1 && px.1 == px.2));
+        assert_eq!(frame[0], frame[(3 * WIDTH + 3) as usize]);
+    }
+
+    #[test]
+    fn test_post_effects_do_not_change_backend_frame() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.add_post_effect(Box::new(Grayscale));
+        renderer.render().unwrap();
+        renderer.emit().unwrap();
+        // A second frame redraws nothing, so it must not get the effect applied twice
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(40 * WIDTH + 40) as usize], (76, 76, 76, 255));
+    }
+
+    #[test]
+    fn test_shadow_from_direction() {
+        assert_eq!(
+            ShadowSettings::from_direction(45.0, 200f32.sqrt(), 80),
+            ShadowSettings::default()
+        );
+        assert_eq!(
+            ShadowSettings::from_direction(180.0, 5.0, 10).offset,
+            (-5, 0)
+        );
+    }
+
+    #[test]
+    fn test_default_shadow_used_for_scene() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.set_default_shadow(ShadowSettings::new((0, 25), 255));
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        // Sprite covers rows 31..50, the shadow is moved 25 rows down
+        assert_eq!(frame[(60 * WIDTH + 40) as usize], (0, 0, 0, 255));
+        assert_eq!(frame[(20 * WIDTH + 45) as usize], DEFAULT_BACKGROUND);
+    }
+
+    #[test]
+    fn test_pixel_shader_runs_after_post_effects() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.add_post_effect(Box::new(Grayscale));
+        // Scanlines: every other row is black, the others keep their color
+        renderer.set_pixel_shader(|_, y, px| if y % 2 == 0 { px } else { (0, 0, 0, px.3) });
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(40 * WIDTH + 40) as usize], (76, 76, 76, 255));
+        assert_eq!(frame[(41 * WIDTH + 40) as usize], (0, 0, 0, 255));
+
+        renderer.clear_pixel_shader();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(41 * WIDTH + 40) as usize], (76, 76, 76, 255));
+    }
+
+    #[test]
+    fn test_scene_color_grading_applied_on_emit() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        // Strip LUT swapping the red and blue channels
+        let mut lut = RgbaImage::new(4, 2);
+        for b in 0..2 {
+            for g in 0..2 {
+                for r in 0..2 {
+                    lut.put_pixel(
+                        b * 2 + r,
+                        g,
+                        Rgba([255 * b as u8, 255 * g as u8, 255 * r as u8, 255]),
+                    );
                 }
             }
         }
+        let lut = ColorLut::from_image(&DynamicImage::ImageRgba8(lut)).unwrap();
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255]).with_color_grading(lut)),
+        );
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(40 * WIDTH + 40) as usize], (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_debug_shapes_follow_camera_for_one_frame() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.scene_manager.active_scene.main_object.position.x = 100;
+        let debug = renderer.debug();
+        debug.rect((110, -10), (120, -20), (0, 255, 0, 255));
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(10 * WIDTH + 10) as usize], (0, 255, 0, 255));
+        assert_eq!(frame[(20 * WIDTH + 20) as usize], (0, 255, 0, 255));
+
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(10 * WIDTH + 10) as usize], DEFAULT_BACKGROUND);
+    }
+
+    #[test]
+    fn test_object_respawned_under_same_uid_is_redrawn() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 0, 0, 255])),
+        );
+        renderer.render().unwrap();
+        assert_eq!(
+            renderer.emit().unwrap()[(40 * WIDTH + 40) as usize],
+            (255, 0, 0, 255)
+        );
+
+        let scene = &mut renderer.scene_manager.active_scene;
+        let red = scene.despawn(1).unwrap();
+        let uid = scene
+            .spawn(
+                vec![Box::new(Sprite::new(
+                    Some(create_sprite_with_color(20, 20, [0, 255, 0, 255])),
+                    true,
+                    (0, 0),
+                ))],
+                red.position,
+            )
+            .unwrap();
+        assert_eq!(uid, 1);
+        renderer.render().unwrap();
+        assert_eq!(
+            renderer.emit().unwrap()[(40 * WIDTH + 40) as usize],
+            (0, 255, 0, 255)
+        );
+    }
+
+    #[test]
+    fn test_camera_of_last_frame_converts_clicks() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.scene_manager.active_scene.main_object.position.x = 100;
+        renderer.render().unwrap();
+        renderer.scene_manager.active_scene.main_object.position.x = 200;
+
+        let camera = renderer.camera();
+        assert_eq!(camera.top_left, (100, 0));
+        assert_eq!(camera.screen_to_world(10, 10), (110, -10));
+        assert_eq!(camera.world_to_screen(120, -20), (20, 20));
+    }
+
+    #[test]
+    fn test_gamma_correct_shadow_is_lighter() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.set_default_shadow(ShadowSettings::new((0, 25), 128));
+        renderer.render().unwrap();
+        let raw = renderer.emit().unwrap()[(60 * WIDTH + 40) as usize];
+
+        renderer.set_gamma_correct(true);
+        renderer.render().unwrap();
+        let linear = renderer.emit().unwrap()[(60 * WIDTH + 40) as usize];
+        assert!(linear.0 > raw.0);
+        assert_eq!(linear.3, 255);
+    }
+
+    #[test]
+    fn test_ui_ignores_camera_and_post_effects() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.add_post_effect(Box::new(Grayscale));
+        let ui = renderer.ui();
+        ui.push_sprite(
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]))),
+            (2, 2),
+        );
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(3 * WIDTH + 3) as usize], (255, 0, 0, 255));
+
+        renderer.scene_manager.active_scene.main_object.position.x += 50;
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[(3 * WIDTH + 3) as usize], (255, 0, 0, 255));
+
+        ui.clear();
+        let frame = renderer.emit().unwrap();
+        assert_ne!(frame[(3 * WIDTH + 3) as usize], (255, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_perf_overlay_drawn_only_while_visible() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite_at(100, -100, false)),
+        );
+        renderer.render().unwrap();
+        let plain = renderer.emit().unwrap();
+
+        let overlay = renderer.perf_overlay();
+        overlay.toggle();
+        let frame = renderer.emit().unwrap();
+        assert_ne!(
+            frame[(3 * WIDTH + 3) as usize],
+            plain[(3 * WIDTH + 3) as usize]
+        );
+        assert!(frame.contains(&(255, 255, 255, 255)));
+
+        overlay.toggle();
+        assert_eq!(renderer.emit().unwrap(), plain);
+    }
+
+    #[test]
+    fn test_cursor_drawn_at_mouse_position_above_overlay() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.ui().push_sprite(
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]))),
+            (0, 0),
+        );
+        renderer.set_cursor(Some(Cursor::new(
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([0, 255, 0, 255]))),
+            (0, 0),
+        )));
+        assert!(renderer.has_cursor());
+        renderer.render().unwrap();
+
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[WIDTH as usize + 1], (255, 0, 0, 255));
+
+        renderer.set_cursor_position(Some((1, 1)));
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame[WIDTH as usize + 1], (0, 255, 0, 255));
+    }
+
+    #[test]
+    fn test_screenshot_of_last_emitted_frame() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        assert_eq!(renderer.screenshot(), Err(RenderError::NoFrame));
+
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        let shot = renderer.screenshot().unwrap();
+        assert_eq!(shot.dimensions(), (WIDTH, HEIGHT));
+        let px = shot.get_pixel(7, 3).0;
+        assert_eq!(
+            (px[0], px[1], px[2], px[3]),
+            frame[(3 * WIDTH + 7) as usize]
+        );
+
+        let path = std::env::temp_dir().join("rusty_ache_test_screenshot.png");
+        renderer.save_screenshot(&path).unwrap();
+        assert_eq!(image::open(&path).unwrap().to_rgba8(), shot);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_recording_keeps_last_emitted_frames() {
+        let resolution = Resolution::new(WIDTH, HEIGHT);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite([255, 255, 255, 255])),
+        );
+        renderer.render().unwrap();
+        renderer.emit().unwrap();
+        assert!(renderer.recording().is_none());
+
+        renderer.start_recording(2);
+        for _ in 0..3 {
+            renderer.emit().unwrap();
+        }
+        assert_eq!(renderer.recording().map(|r| r.len()), Some(2));
+        assert_eq!(renderer.stop_recording().map(|r| r.len()), Some(2));
+        assert!(renderer.stop_recording().is_none());
+    }
+
+    /// Backend returning a fixed frame, regardless of its resolution.
+    struct FixedFrameBackend {
+        frame: Option<Vec<Pixel>>,
+        resolution: Resolution,
+    }
+
+    impl RenderBackend for FixedFrameBackend {
+        fn render(&mut self, _camera_top: (i32, i32), _sprites: &[RenderableSprite]) {}
+        fn emit(&mut self) -> Option<Vec<Pixel>> {
+            self.frame.clone()
+        }
+        fn resize(&mut self, resolution: Resolution) {
+            self.resolution = resolution;
+        }
+        fn resolution(&self) -> Resolution {
+            self.resolution
+        }
+        fn set_background(&mut self, _background: Option<DynamicImage>) {}
+        fn set_background_mode(&mut self, _mode: BackgroundMode) {}
+        fn invalidate(&mut self) {}
+        fn set_gamma_correct(&mut self, _enabled: bool) {}
+    }
+
+    #[test]
+    fn test_frame_matches_configured_resolution() {
+        let resolution = Resolution::new(128, 72);
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(resolution, None)),
+            SceneManager::new(scene_with_sprite_at(5, -5, false)),
+        );
+        renderer.render().unwrap();
+        let frame = renderer.emit().unwrap();
+        assert_eq!(frame.len(), 128 * 72);
+        // Sprite at world (5, -5) shows up 5 rows down on a 128 pixels wide frame
+        assert_eq!(frame[6 * 128 + 5], (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn test_emit_reports_missing_and_short_frames() {
+        let scene = || SceneManager::new(scene_with_sprite_at(0, 0, false));
+        let mut renderer = Renderer::new(
+            Box::new(FixedFrameBackend {
+                frame: None,
+                resolution: Resolution::new(2, 2),
+            }),
+            scene(),
+        );
+        assert_eq!(renderer.emit(), Err(RenderError::NoFrame));
+
+        let mut renderer = Renderer::new(
+            Box::new(FixedFrameBackend {
+                frame: Some(vec![DEFAULT_BACKGROUND; 3]),
+                resolution: Resolution::new(2, 2),
+            }),
+            scene(),
+        );
+        assert_eq!(
+            renderer.emit(),
+            Err(RenderError::BufferSizeMismatch {
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_reports_broken_sprite() {
+        let broken = GameObject::new(
+            vec![Box::new(Sprite::new(None, false, (0, 0)))],
+            None,
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        );
+        let scene = Scene::new(
+            vec![broken],
+            vec![],
+            Position {
+                x: 0,
+                y: 0,
+                z: 0,
+                is_relative: false,
+            },
+        )
+        .unwrap();
+        let mut renderer = Renderer::new(
+            Box::new(CpuBackend::new(Resolution::new(WIDTH, HEIGHT), None)),
+            SceneManager::new(scene),
+        );
+        assert_eq!(
+            renderer.render(),
+            Err(RenderError::MissingSprite { uid: 1 })
+        );
     }
 }
@@ -0,0 +1,204 @@
+//! Transitions shown when the active scene changes.
+//!
+//! When a scene replaces another one, the renderer keeps the last frame of the old scene
+//! and blends it with the frames of the new scene, starting with its first one, until the
+//! duration of the [`Transition`] has passed. Blending happens on the frames of the
+//! backend, before post effects, so the overlay and the cursor are never blended.
+
+use std::time::Duration;
+
+use super::Pixel;
+
+/// Side a wipe starts from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WipeDirection {
+    /// The new scene comes in from the left edge.
+    LeftToRight,
+    /// The new scene comes in from the right edge.
+    RightToLeft,
+    /// The new scene comes in from the top edge.
+    TopToBottom,
+    /// The new scene comes in from the bottom edge.
+    BottomToTop,
+}
+
+/// How the old scene gives way to the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transition {
+    /// The new scene shows at once.
+    #[default]
+    Cut,
+    /// The old scene fades into the new one.
+    Fade(Duration),
+    /// The new scene moves in over the old one, revealed by an edge crossing the screen.
+    Wipe {
+        /// How long the edge takes to cross the screen.
+        duration: Duration,
+        /// Side the edge starts from.
+        direction: WipeDirection,
+    },
+}
+
+impl Transition {
+    /// Returns how long the transition runs; zero for a cut.
+    pub fn duration(&self) -> Duration {
+        match self {
+            Transition::Cut => Duration::ZERO,
+            Transition::Fade(duration) | Transition::Wipe { duration, .. } => *duration,
+        }
+    }
+
+    /// Blends the frame of the old scene into the frame of the new one.
+    ///
+    /// # Parameters
+    /// - `from`: Last frame of the old scene.
+    /// - `to`: Frame of the new scene, receiving the blended frame.
+    /// - `width`: Frame width in pixels.
+    /// - `progress`: Part of the transition done, from 0 showing the old scene only to 1
+    ///   showing the new scene only.
+    pub fn blend(&self, from: &[Pixel], to: &mut [Pixel], width: u32, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        let width = width.max(1) as usize;
+        let height = to.len() / width;
+        match *self {
+            Transition::Cut => {}
+            Transition::Fade(_) => {
+                let mix = |old: u8, new: u8| {
+                    (old as f32 + (new as f32 - old as f32) * progress).round() as u8
+                };
+                for (new, old) in to.iter_mut().zip(from) {
+                    *new = (
+                        mix(old.0, new.0),
+                        mix(old.1, new.1),
+                        mix(old.2, new.2),
+                        mix(old.3, new.3),
+                    );
+                }
+            }
+            Transition::Wipe { direction, .. } => {
+                let covered = |extent: usize| (extent as f32 * progress).round() as usize;
+                for (i, (new, old)) in to.iter_mut().zip(from).enumerate() {
+                    let (x, y) = (i % width, i / width);
+                    let shows_new = match direction {
+                        WipeDirection::LeftToRight => x < covered(width),
+                        WipeDirection::RightToLeft => x >= width - covered(width),
+                        WipeDirection::TopToBottom => y < covered(height),
+                        WipeDirection::BottomToTop => y >= height - covered(height),
+                    };
+                    if !shows_new {
+                        *new = *old;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A transition in progress.
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveTransition {
+    transition: Transition,
+    /// Last frame of the old scene.
+    from: Vec<Pixel>,
+    elapsed: Duration,
+}
+
+impl ActiveTransition {
+    /// Starts a transition away from the given frame.
+    pub(crate) fn new(transition: Transition, from: Vec<Pixel>) -> Self {
+        ActiveTransition {
+            transition,
+            from,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Lets time pass.
+    ///
+    /// # Returns
+    /// False once the transition is over.
+    pub(crate) fn advance(&mut self, elapsed: Duration) -> bool {
+        self.elapsed += elapsed;
+        self.elapsed < self.transition.duration()
+    }
+
+    /// Blends the frame of the old scene into a frame of the new one; frames of another
+    /// size than the old one, e.g. after a resize, are left as they are.
+    pub(crate) fn apply(&self, frame: &mut [Pixel], width: u32) {
+        if frame.len() != self.from.len() {
+            return;
+        }
+        let duration = self.transition.duration();
+        let progress = if duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / duration.as_secs_f32()
+        };
+        self.transition.blend(&self.from, frame, width, progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: Pixel = (0, 0, 0, 255);
+    const NEW: Pixel = (200, 100, 50, 255);
+
+    #[test]
+    fn test_fade_mixes_frames() {
+        let fade = Transition::Fade(Duration::from_secs(1));
+        let mut frame = vec![NEW; 4];
+        fade.blend(&[OLD; 4], &mut frame, 2, 0.25);
+        assert_eq!(frame, vec![(50, 25, 13, 255); 4]);
+        let mut frame = vec![NEW; 4];
+        fade.blend(&[OLD; 4], &mut frame, 2, 1.0);
+        assert_eq!(frame, vec![NEW; 4]);
+    }
+
+    #[test]
+    fn test_wipe_reveals_new_frame_from_edge() {
+        let wipe = |direction| Transition::Wipe {
+            duration: Duration::from_secs(1),
+            direction,
+        };
+        // 4x2 frame, half way through
+        let blended = |direction| {
+            let mut frame = vec![NEW; 8];
+            wipe(direction).blend(&[OLD; 8], &mut frame, 4, 0.5);
+            frame
+        };
+        assert_eq!(
+            blended(WipeDirection::LeftToRight),
+            [NEW, NEW, OLD, OLD, NEW, NEW, OLD, OLD]
+        );
+        assert_eq!(
+            blended(WipeDirection::RightToLeft),
+            [OLD, OLD, NEW, NEW, OLD, OLD, NEW, NEW]
+        );
+        assert_eq!(
+            blended(WipeDirection::TopToBottom),
+            [NEW, NEW, NEW, NEW, OLD, OLD, OLD, OLD]
+        );
+        assert_eq!(
+            blended(WipeDirection::BottomToTop),
+            [OLD, OLD, OLD, OLD, NEW, NEW, NEW, NEW]
+        );
+    }
+
+    #[test]
+    fn test_active_transition_ends_after_duration() {
+        let mut active =
+            ActiveTransition::new(Transition::Fade(Duration::from_millis(100)), vec![OLD; 2]);
+        let mut frame = vec![NEW; 2];
+        active.apply(&mut frame, 2);
+        assert_eq!(frame, vec![OLD; 2]);
+        assert!(active.advance(Duration::from_millis(50)));
+        assert!(!active.advance(Duration::from_millis(50)));
+        // Frames of another size are not blended
+        let mut frame = vec![NEW; 3];
+        active.apply(&mut frame, 3);
+        assert_eq!(frame, vec![NEW; 3]);
+        assert_eq!(Transition::default().duration(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,430 @@
+//! Screen-space overlay drawn above the rendered world.
+//!
+//! The overlay holds HUD elements such as health bars, icons or score text. Its elements
+//! are positioned in screen pixels and ignore the camera, so they stay in place while the
+//! world scrolls. The overlay is drawn after post effects, which keeps the HUD sharp.
+//!
+//! A software [`Cursor`] can be drawn on top of the overlay at the mouse position, replacing
+//! the hidden OS cursor.
+//!
+//! Text is drawn with a small built-in 5x7 pixel font covering ASCII letters, digits
+//! and common punctuation; lowercase letters are shown as uppercase.
+
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use std::sync::{Arc, RwLock};
+
+use super::Pixel;
+use super::gamma::mix_channel;
+
+/// Width of a font glyph in pixels.
+const GLYPH_WIDTH: u32 = 5;
+
+/// Height of a font glyph in pixels.
+const GLYPH_HEIGHT: u32 = 7;
+
+/// Horizontal space between two glyphs.
+const GLYPH_SPACING: u32 = 1;
+
+/// Glyph used for characters missing from the font.
+const UNKNOWN_GLYPH: [u8; 7] = [
+    0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+];
+
+/// Returns the rows of a font glyph, the highest bit of 5 being the leftmost pixel.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'B' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+        'C' => [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+        'D' => [
+            0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110,
+        ],
+        'E' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+        'F' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'G' => [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+        'H' => [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'I' => [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        'J' => [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+        'K' => [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+        'L' => [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+        'M' => [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+        'N' => [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+        'O' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'P' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'Q' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+        'R' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+        'S' => [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+        'T' => [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'U' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'V' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+        'W' => [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+        'X' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+        'Y' => [
+            0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+        ],
+        'Z' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+        '0' => [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+        '1' => [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        '2' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+        '3' => [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+        '4' => [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+        '5' => [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+        '6' => [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+        '7' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+        '8' => [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+        '9' => [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+        ' ' => [0; 7],
+        '.' => [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+        ],
+        ',' => [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+        ],
+        ':' => [
+            0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+        ],
+        '-' => [
+            0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+        ],
+        '+' => [
+            0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000,
+        ],
+        '!' => [
+            0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+        ],
+        '/' => [
+            0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+        ],
+        '%' => [
+            0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+        ],
+        _ => UNKNOWN_GLYPH,
+    }
+}
+
+/// Rasterizes a line of text into an image with a transparent background.
+///
+/// # Parameters
+/// - `text`: Text to draw; line breaks are not supported.
+/// - `color`: RGBA color of the glyphs.
+pub fn render_text(text: &str, color: Pixel) -> DynamicImage {
+    let count = text.chars().count() as u32;
+    let width = (count * (GLYPH_WIDTH + GLYPH_SPACING)).saturating_sub(GLYPH_SPACING);
+    let mut img = RgbaImage::new(width, GLYPH_HEIGHT);
+    for (i, c) in text.chars().enumerate() {
+        let x0 = i as u32 * (GLYPH_WIDTH + GLYPH_SPACING);
+        for (y, row) in glyph(c).iter().enumerate() {
+            for x in 0..GLYPH_WIDTH {
+                if row & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                    img.put_pixel(x0 + x, y as u32, Rgba([color.0, color.1, color.2, color.3]));
+                }
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(img)
+}
+
+/// An element of the overlay: an image placed at a screen position.
+struct UiElement {
+    /// Image drawn for the element; text is rasterized when it is pushed.
+    image: DynamicImage,
+    /// Screen position of the image top-left corner (y grows downwards).
+    position: (i32, i32),
+}
+
+/// [Handle](crate#handles) to the screen-space overlay, whose elements are drawn by the
+/// renderer on every frame.
+///
+/// Elements stay on screen until [`Ui::clear`] is called, so a HUD
+/// that changes every frame clears the overlay and pushes its elements again.
+#[derive(Clone, Default)]
+pub struct Ui {
+    elements: Arc<RwLock<Vec<UiElement>>>,
+}
+
+impl Ui {
+    /// Creates an empty overlay.
+    pub fn new() -> Self {
+        Ui::default()
+    }
+
+    /// Adds an image to the overlay, drawn above the elements pushed before.
+    ///
+    /// # Parameters
+    /// - `image`: Image to draw.
+    /// - `position`: Screen position of the image top-left corner.
+    pub fn push_sprite(&self, image: DynamicImage, position: (i32, i32)) {
+        self.elements
+            .write()
+            .unwrap()
+            .push(UiElement { image, position });
+    }
+
+    /// Adds a line of text to the overlay, drawn above the elements pushed before.
+    ///
+    /// # Parameters
+    /// - `text`: Text to draw.
+    /// - `position`: Screen position of the top-left corner of the first glyph.
+    /// - `color`: RGBA color of the text.
+    pub fn push_text(&self, text: &str, position: (i32, i32), color: Pixel) {
+        self.push_sprite(render_text(text, color), position);
+    }
+
+    /// Removes all elements from the overlay.
+    pub fn clear(&self) {
+        self.elements.write().unwrap().clear();
+    }
+
+    /// Returns the number of elements in the overlay.
+    pub fn len(&self) -> usize {
+        self.elements.read().unwrap().len()
+    }
+
+    /// Checks whether the overlay has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Draws all overlay elements onto a frame, alpha blending them with its content.
+    ///
+    /// # Parameters
+    /// - `frame`: RGBA pixels of the frame, row by row.
+    /// - `width`: Frame width in pixels.
+    /// - `height`: Frame height in pixels.
+    /// - `linear`: Whether to blend in linear light instead of raw sRGB bytes.
+    pub(crate) fn draw(&self, frame: &mut [Pixel], width: u32, height: u32, linear: bool) {
+        for element in self.elements.read().unwrap().iter() {
+            blend_image(
+                frame,
+                width,
+                height,
+                &element.image,
+                element.position,
+                linear,
+            );
+        }
+    }
+}
+
+/// A cursor sprite drawn at the mouse position above the overlay.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    /// Image of the cursor.
+    image: DynamicImage,
+    /// Pixel of the image placed at the mouse position, e.g. the tip of an arrow.
+    hotspot: (i32, i32),
+}
+
+impl Cursor {
+    /// Creates a cursor from an image.
+    ///
+    /// # Parameters
+    /// - `image`: Image of the cursor.
+    /// - `hotspot`: Pixel of the image placed at the mouse position.
+    pub fn new(image: DynamicImage, hotspot: (i32, i32)) -> Self {
+        Cursor { image, hotspot }
+    }
+
+    /// Draws the cursor onto a frame with its hotspot at the mouse position.
+    ///
+    /// # Parameters
+    /// - `frame`: RGBA pixels of the frame, row by row.
+    /// - `width`: Frame width in pixels.
+    /// - `height`: Frame height in pixels.
+    /// - `position`: Screen position of the mouse.
+    /// - `linear`: Whether to blend in linear light instead of raw sRGB bytes.
+    pub(crate) fn draw(
+        &self,
+        frame: &mut [Pixel],
+        width: u32,
+        height: u32,
+        position: (i32, i32),
+        linear: bool,
+    ) {
+        let top_left = (position.0 - self.hotspot.0, position.1 - self.hotspot.1);
+        blend_image(frame, width, height, &self.image, top_left, linear);
+    }
+}
+
+/// Alpha blends an image onto a frame, clipping it to the frame.
+///
+/// # Parameters
+/// - `frame`: RGBA pixels of the frame, row by row.
+/// - `width`: Frame width in pixels.
+/// - `height`: Frame height in pixels.
+/// - `image`: Image to draw.
+/// - `position`: Screen position of the image top-left corner.
+/// - `linear`: Whether to blend in linear light instead of raw sRGB bytes.
+pub(super) fn blend_image(
+    frame: &mut [Pixel],
+    width: u32,
+    height: u32,
+    image: &DynamicImage,
+    position: (i32, i32),
+    linear: bool,
+) {
+    let (w, h) = (width as i32, height as i32);
+    let (img_w, img_h) = image.dimensions();
+    let (x0, y0) = position;
+    for y in y0.max(0)..(y0 + img_h as i32).min(h) {
+        for x in x0.max(0)..(x0 + img_w as i32).min(w) {
+            let src = image.get_pixel((x - x0) as u32, (y - y0) as u32).0;
+            if src[3] == 0 {
+                continue;
+            }
+            let dst = &mut frame[(y * w + x) as usize];
+            let alpha = src[3] as u32;
+            let blend = |s: u8, d: u8| {
+                if linear {
+                    mix_channel(s, d, alpha as f32 / 255.0, true)
+                } else {
+                    ((s as u32 * alpha + d as u32 * (255 - alpha)) / 255) as u8
+                }
+            };
+            *dst = (
+                blend(src[0], dst.0),
+                blend(src[1], dst.1),
+                blend(src[2], dst.2),
+                dst.3.max(src[3]),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text_size_and_pixels() {
+        let img = render_text("HI", (255, 255, 255, 255));
+        assert_eq!(img.dimensions(), (11, 7));
+        // Left column of 'H'
+        assert_eq!(img.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        // Gap between the glyphs
+        assert_eq!(img.get_pixel(5, 0).0[3], 0);
+        // Middle column of 'I'
+        assert_eq!(img.get_pixel(8, 3).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_lowercase_uses_uppercase_glyphs() {
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph('~'), UNKNOWN_GLYPH);
+    }
+
+    #[test]
+    fn test_handles_share_elements() {
+        let ui = Ui::new();
+        let handle = ui.clone();
+        handle.push_text("1", (0, 0), (0, 0, 0, 255));
+        assert_eq!(ui.len(), 1);
+        ui.clear();
+        assert!(handle.is_empty());
+    }
+
+    #[test]
+    fn test_draw_blends_and_clips() {
+        let ui = Ui::new();
+        let mut img = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        img.put_pixel(1, 0, Rgba([0, 0, 255, 0]));
+        img.put_pixel(0, 1, Rgba([255, 255, 255, 51]));
+        ui.push_sprite(DynamicImage::ImageRgba8(img), (3, 3));
+
+        let mut frame = vec![(0, 0, 0, 255); 4 * 4];
+        ui.draw(&mut frame, 4, 4, false);
+        assert_eq!(frame[3 * 4 + 3], (255, 0, 0, 255));
+        assert!(frame[..3 * 4 + 3].iter().all(|px| *px == (0, 0, 0, 255)));
+
+        let mut frame = vec![(0, 0, 0, 255); 4 * 4];
+        ui.push_sprite(DynamicImage::ImageRgba8(RgbaImage::new(1, 1)), (0, 0));
+        ui.clear();
+        ui.push_sprite(
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 2, Rgba([255, 255, 255, 51]))),
+            (0, 0),
+        );
+        ui.draw(&mut frame, 4, 4, false);
+        assert_eq!(frame[0], (51, 51, 51, 255));
+        assert_eq!(frame[4], (51, 51, 51, 255));
+    }
+
+    #[test]
+    fn test_cursor_hotspot_at_mouse_position() {
+        let mut img = RgbaImage::new(3, 3);
+        img.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        let cursor = Cursor::new(DynamicImage::ImageRgba8(img), (1, 1));
+
+        let mut frame = vec![(0, 0, 0, 255); 4 * 4];
+        cursor.draw(&mut frame, 4, 4, (0, 2), false);
+        assert_eq!(frame[2 * 4], (255, 0, 0, 255));
+        assert_eq!(frame.iter().filter(|px| **px != (0, 0, 0, 255)).count(), 1);
+    }
+}
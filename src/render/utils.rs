@@ -2,23 +2,23 @@
 //!
 //! Provides functions to create an initial framebuffer for the screen,
 //! either filling it with a default background color or extracting pixel data
-//! from a provided background image.
+//! from a provided background image, optionally repeated across the whole frame.
 
-use super::renderer::DEFAULT_BACKGROUND_COLOR;
-use crate::screen::{HEIGHT, WIDTH};
+use super::Pixel;
+use super::renderer::{BackgroundMode, DEFAULT_BACKGROUND_COLOR};
+use crate::Resolution;
 use image::{DynamicImage, GenericImageView};
 
-/// Creates a vector filled with the default background color,
-/// sized to the screen resolution (`WIDTH` x `HEIGHT`).
+/// Creates a vector filled with the default background color, sized to the given resolution.
 ///
 /// This represents the initial pixel buffer when no background image is available.
 ///
 /// # Returns
 /// A vector of RGBA tuples representing screen pixels all set to the default background color.
-fn make_init_default_background() -> Vec<(u8, u8, u8, u8)> {
-    let mut pixels = Vec::with_capacity((WIDTH * HEIGHT) as usize);
-    for _ in 0..HEIGHT {
-        for _ in 0..WIDTH {
+fn make_init_default_background(resolution: Resolution) -> Vec<Pixel> {
+    let mut pixels = Vec::with_capacity((resolution.width * resolution.height) as usize);
+    for _ in 0..resolution.height {
+        for _ in 0..resolution.width {
             pixels.push((
                 DEFAULT_BACKGROUND_COLOR.0,
                 DEFAULT_BACKGROUND_COLOR.1,
@@ -39,24 +39,26 @@ fn make_init_default_background() -> Vec<(u8, u8, u8, u8)> {
 ///
 /// # Parameters
 /// - `image`: Optional dynamic image providing the background.
+/// - `resolution`: Size of the frame.
 ///
 /// # Returns
-/// A vector of RGBA pixel tuples sized for the screen resolution suitable for initial rendering.
+/// A vector of RGBA pixel tuples sized for the resolution suitable for initial rendering.
 // TODO: maybe not top left corner
-pub fn make_init_frame(image: Option<DynamicImage>) -> Vec<(u8, u8, u8, u8)> {
+pub fn make_init_frame(image: Option<DynamicImage>, resolution: Resolution) -> Vec<Pixel> {
     match image {
-        None => make_init_default_background(),
+        None => make_init_default_background(resolution),
         Some(image) => {
             let (width, height) = image.dimensions();
-            if width < WIDTH || height < HEIGHT {
-                eprintln!(
-                    "Error: background image is smaller than screen size; Initialized with default background"
+            if width < resolution.width || height < resolution.height {
+                log::warn!(
+                    "Background image is smaller than screen size; initialized with default background"
                 );
-                make_init_default_background()
+                make_init_default_background(resolution)
             } else {
-                let mut pixels = Vec::with_capacity((WIDTH * HEIGHT) as usize);
-                for y in 0..HEIGHT {
-                    for x in 0..WIDTH {
+                let mut pixels =
+                    Vec::with_capacity((resolution.width * resolution.height) as usize);
+                for y in 0..resolution.height {
+                    for x in 0..resolution.width {
                         let pixel = image.get_pixel(x, y);
                         pixels.push((pixel[0], pixel[1], pixel[2], pixel[3]));
                     }
@@ -67,19 +69,117 @@ pub fn make_init_frame(image: Option<DynamicImage>) -> Vec<(u8, u8, u8, u8)> {
     }
 }
 
+/// Creates a framebuffer by repeating an image across the screen.
+///
+/// # Parameters
+/// - `image`: Tile repeated in both directions.
+/// - `shift`: Screen position of the top-left corner of one of the tiles.
+/// - `resolution`: Size of the frame.
+///
+/// # Returns
+/// A vector of RGBA pixel tuples sized for the resolution.
+fn make_tiled_frame(image: &DynamicImage, shift: (i32, i32), resolution: Resolution) -> Vec<Pixel> {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return make_init_default_background(resolution);
+    }
+    let tile = image.to_rgba8();
+    let mut pixels = Vec::with_capacity((resolution.width * resolution.height) as usize);
+    for y in 0..resolution.height as i32 {
+        let ty = (y - shift.1).rem_euclid(height as i32) as u32;
+        for x in 0..resolution.width as i32 {
+            let tx = (x - shift.0).rem_euclid(width as i32) as u32;
+            let pixel = tile.get_pixel(tx, ty);
+            pixels.push((pixel[0], pixel[1], pixel[2], pixel[3]));
+        }
+    }
+    pixels
+}
+
+/// Creates a framebuffer showing the part of a world-anchored image seen by the camera.
+///
+/// The image's top-left corner lies at the world origin; screen pixels outside of
+/// the image get the default background color.
+///
+/// # Parameters
+/// - `image`: Background image.
+/// - `camera_top`: World position of the camera top-left corner.
+/// - `resolution`: Size of the frame.
+///
+/// # Returns
+/// A vector of RGBA pixel tuples sized for the resolution.
+fn make_world_frame(
+    image: &DynamicImage,
+    camera_top: (i32, i32),
+    resolution: Resolution,
+) -> Vec<Pixel> {
+    let (width, height) = image.dimensions();
+    let mut pixels = Vec::with_capacity((resolution.width * resolution.height) as usize);
+    for y in 0..resolution.height as i32 {
+        // Screen row `y` shows world row `camera_top.1 - y`, image rows grow downwards
+        let iy = y - camera_top.1;
+        for x in 0..resolution.width as i32 {
+            let ix = x + camera_top.0;
+            if ix < 0 || iy < 0 || ix >= width as i32 || iy >= height as i32 {
+                pixels.push(DEFAULT_BACKGROUND_COLOR);
+            } else {
+                let pixel = image.get_pixel(ix as u32, iy as u32);
+                pixels.push((pixel[0], pixel[1], pixel[2], pixel[3]));
+            }
+        }
+    }
+    pixels
+}
+
+/// Creates the background part of a frame for the given background mode.
+///
+/// # Parameters
+/// - `image`: Optional background image.
+/// - `mode`: How the image is placed on the frame.
+/// - `camera_top`: World position of the camera top-left corner, used by scrolling backgrounds.
+/// - `resolution`: Size of the frame.
+///
+/// # Returns
+/// A vector of RGBA pixel tuples sized for the resolution.
+pub fn make_background_frame(
+    image: Option<&DynamicImage>,
+    mode: BackgroundMode,
+    camera_top: (i32, i32),
+    resolution: Resolution,
+) -> Vec<Pixel> {
+    match (image, mode) {
+        (Some(image), BackgroundMode::Tiled { scroll: true }) => {
+            // World point (0, 0) keeps the same place on the tile pattern
+            make_tiled_frame(image, (-camera_top.0, camera_top.1), resolution)
+        }
+        (Some(image), BackgroundMode::Tiled { scroll: false }) => {
+            make_tiled_frame(image, (0, 0), resolution)
+        }
+        (Some(image), BackgroundMode::Single { scroll: true }) => {
+            make_world_frame(image, camera_top, resolution)
+        }
+        (image, _) => make_init_frame(image.cloned(), resolution),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    const DEFAULT_BACKGROUND: (u8, u8, u8, u8) = (
+    use crate::screen::{HEIGHT, WIDTH};
+    const DEFAULT_BACKGROUND: Pixel = (
         DEFAULT_BACKGROUND_COLOR.0,
         DEFAULT_BACKGROUND_COLOR.1,
         DEFAULT_BACKGROUND_COLOR.2,
         DEFAULT_BACKGROUND_COLOR.3,
     );
 
+    fn screen() -> Resolution {
+        Resolution::new(WIDTH, HEIGHT)
+    }
+
     #[test]
     fn test_make_init_default_background() {
-        let mut vector = make_init_default_background();
+        let mut vector = make_init_default_background(screen());
         for _ in 0..HEIGHT {
             for _ in 0..WIDTH {
                 assert_eq!(vector.pop(), Some(DEFAULT_BACKGROUND));
@@ -90,7 +190,7 @@ mod tests {
     #[test]
     fn test_make_init_frame_none() {
         let image = None;
-        let mut vector = make_init_frame(image);
+        let mut vector = make_init_frame(image, screen());
         for _ in 0..HEIGHT {
             for _ in 0..WIDTH {
                 assert_eq!(vector.pop(), Some(DEFAULT_BACKGROUND));
@@ -101,11 +201,101 @@ mod tests {
     #[test]
     fn test_make_init_frame_some_image() {
         let image = DynamicImage::new_rgb8(WIDTH, HEIGHT);
-        let mut vector = make_init_frame(Some(image));
+        let mut vector = make_init_frame(Some(image), screen());
         for _ in 0..HEIGHT {
             for _ in 0..WIDTH {
                 assert_eq!(vector.pop(), Some((0, 0, 0, 255)));
             }
         }
     }
+
+    fn checkerboard() -> DynamicImage {
+        let mut tile = image::RgbaImage::new(2, 2);
+        tile.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        tile.put_pixel(1, 0, image::Rgba([0, 255, 0, 255]));
+        tile.put_pixel(0, 1, image::Rgba([0, 0, 255, 255]));
+        tile.put_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
+        DynamicImage::ImageRgba8(tile)
+    }
+
+    #[test]
+    fn test_tiled_background_repeats_small_image() {
+        let tile = checkerboard();
+        let frame = make_background_frame(
+            Some(&tile),
+            BackgroundMode::Tiled { scroll: false },
+            (100, 100),
+            screen(),
+        );
+        assert_eq!(frame.len(), (WIDTH * HEIGHT) as usize);
+        assert_eq!(frame[0], (255, 0, 0, 255));
+        assert_eq!(frame[2], (255, 0, 0, 255));
+        assert_eq!(frame[(WIDTH + 1) as usize], (255, 255, 255, 255));
+        assert_eq!(frame[(3 * WIDTH + 4) as usize], (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_scrolling_tiled_background_follows_camera() {
+        let tile = checkerboard();
+        let mode = BackgroundMode::Tiled { scroll: true };
+        let still = make_background_frame(Some(&tile), mode, (0, 0), screen());
+        let moved = make_background_frame(Some(&tile), mode, (1, 0), screen());
+        assert_eq!(moved[0], still[1]);
+        let moved_up = make_background_frame(Some(&tile), mode, (0, 1), screen());
+        assert_eq!(moved_up[WIDTH as usize], still[0]);
+    }
+
+    #[test]
+    fn test_fixed_background_rejects_small_image() {
+        let tile = checkerboard();
+        let frame = make_background_frame(
+            Some(&tile),
+            BackgroundMode::Single { scroll: false },
+            (0, 0),
+            screen(),
+        );
+        assert!(frame.iter().all(|px| *px == DEFAULT_BACKGROUND));
+    }
+
+    #[test]
+    fn test_single_background_scrolls_with_camera() {
+        let mut image =
+            image::RgbaImage::from_pixel(WIDTH + 10, HEIGHT + 10, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(5, 7, image::Rgba([1, 2, 3, 255]));
+        let image = DynamicImage::ImageRgba8(image);
+        let mode = BackgroundMode::Single { scroll: true };
+
+        let still = make_background_frame(Some(&image), mode, (0, 0), screen());
+        assert_eq!(still[(7 * WIDTH + 5) as usize], (1, 2, 3, 255));
+        // Camera moved right by 5 and down by 7 world units
+        let moved = make_background_frame(Some(&image), mode, (5, -7), screen());
+        assert_eq!(moved[0], (1, 2, 3, 255));
+        // The world left of the image shows the default color
+        let left = make_background_frame(Some(&image), mode, (-1, 0), screen());
+        assert_eq!(left[0], DEFAULT_BACKGROUND);
+        assert_eq!(left[1], (0, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_frames_match_requested_resolution() {
+        let resolution = Resolution::new(7, 3);
+        assert_eq!(make_init_frame(None, resolution).len(), 21);
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            8,
+            4,
+            image::Rgba([9, 9, 9, 255]),
+        ));
+        let frame = make_init_frame(Some(image.clone()), resolution);
+        assert_eq!(frame.len(), 21);
+        assert!(frame.iter().all(|px| *px == (9, 9, 9, 255)));
+        for mode in [
+            BackgroundMode::Single { scroll: true },
+            BackgroundMode::Tiled { scroll: false },
+        ] {
+            assert_eq!(
+                make_background_frame(Some(&image), mode, (0, 0), resolution).len(),
+                21
+            );
+        }
+    }
 }
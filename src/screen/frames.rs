@@ -0,0 +1,124 @@
+//! Handoff of finished frames from the producer to the window.
+//!
+//! Frames are passed through a triple buffer: the producer draws into its back buffer,
+//! the window shows its front buffer, and the newest finished frame waits in the middle
+//! buffer between them. Publishing a frame or taking the newest one only swaps buffers,
+//! so the producer never waits for the window to finish presenting, the window never sees
+//! a partially written frame, and it always gets the latest complete one.
+
+use super::PixelData;
+use crate::render::Pixel;
+use std::sync::{Arc, Mutex};
+
+/// Buffer holding the newest finished frame.
+struct Middle {
+    frame: PixelData,
+    /// Whether the frame was published since the reader last took one.
+    fresh: bool,
+}
+
+/// Writing side of a triple buffer, owned by the producer.
+pub struct FrameWriter {
+    back: PixelData,
+    middle: Arc<Mutex<Middle>>,
+}
+
+/// Reading side of a triple buffer, owned by the app showing the frames.
+pub struct FrameReader {
+    front: PixelData,
+    middle: Arc<Mutex<Middle>>,
+}
+
+/// Creates a triple buffer passing frames from a writer to a reader; all buffers start empty.
+pub fn triple_buffer() -> (FrameWriter, FrameReader) {
+    let middle = Arc::new(Mutex::new(Middle {
+        frame: PixelData::new(),
+        fresh: false,
+    }));
+    (
+        FrameWriter {
+            back: PixelData::new(),
+            middle: middle.clone(),
+        },
+        FrameReader {
+            front: PixelData::new(),
+            middle,
+        },
+    )
+}
+
+impl FrameWriter {
+    /// Draws a frame into the back buffer and publishes it as the newest frame.
+    ///
+    /// The back buffer's memory is reused, so steady frame sizes cause no allocations.
+    /// A frame the reader has not taken yet is replaced.
+    ///
+    /// # Parameters
+    /// - `pixels`: RGBA pixels of the frame, row by row.
+    pub fn write(&mut self, pixels: impl IntoIterator<Item = Pixel>) {
+        self.back.clear();
+        self.back.extend(pixels);
+        let mut middle = self.middle.lock().unwrap();
+        std::mem::swap(&mut self.back, &mut middle.frame);
+        middle.fresh = true;
+    }
+}
+
+impl FrameReader {
+    /// Takes the newest published frame as the front buffer, if one was published since
+    /// the last call.
+    ///
+    /// # Returns
+    /// True if the front buffer changed.
+    pub fn update(&mut self) -> bool {
+        let mut middle = self.middle.lock().unwrap();
+        if !middle.fresh {
+            return false;
+        }
+        std::mem::swap(&mut self.front, &mut middle.frame);
+        middle.fresh = false;
+        true
+    }
+
+    /// Returns the front buffer, the frame taken by the last successful [`FrameReader::update`].
+    pub fn frame(&self) -> &PixelData {
+        &self.front
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_gets_latest_frame() {
+        let (mut writer, mut reader) = triple_buffer();
+        assert!(!reader.update());
+        assert!(reader.frame().is_empty());
+
+        writer.write([(1, 1, 1, 255)]);
+        writer.write([(2, 2, 2, 255)]);
+        assert!(reader.update());
+        assert_eq!(reader.frame(), &vec![(2, 2, 2, 255)]);
+        assert!(!reader.update());
+        assert_eq!(reader.frame(), &vec![(2, 2, 2, 255)]);
+    }
+
+    #[test]
+    fn test_buffers_are_reused() {
+        let (mut writer, mut reader) = triple_buffer();
+        for value in 0..3 {
+            writer.write(vec![(value, 0, 0, 255); 16]);
+            assert!(reader.update());
+        }
+        // All three buffers have been filled, so the next frames need no allocation
+        let capacities = |writer: &FrameWriter, reader: &FrameReader| {
+            let middle = writer.middle.lock().unwrap().frame.capacity();
+            [writer.back.capacity(), middle, reader.front.capacity()]
+        };
+        assert!(capacities(&writer, &reader).iter().all(|c| *c >= 16));
+        writer.write(vec![(9, 0, 0, 255); 16]);
+        assert!(reader.update());
+        assert_eq!(reader.frame()[0], (9, 0, 0, 255));
+    }
+}
@@ -2,116 +2,865 @@
 //!
 //! This module implements the `App` struct, which manages the window, screen buffer, pixel data,
 //! and keyboard input state. It integrates with the winit event loop to handle window events,
-//! update pixel frames, and process user keyboard input.
+//! update pixel frames, and process user keyboard input. Besides the game window, the app can
+//! open further windows, e.g. for debug views, each showing the buffer of a `WindowHandle`.
 //!
-//! The example function demonstrates initializing shared pixel data and window, spawning a producer thread
-//! to modify pixel data dynamically, and running the event loop to render changes to the screen.
+//! The example function demonstrates initializing a frame channel and window, spawning a producer thread
+//! sending frames through it, and running the event loop to render changes to the screen.
+//! The game window receives its frames through the triple buffer of the `frames` module.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+pub mod frames;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::Resolution;
-use pixels::{Pixels, SurfaceTexture};
+use crate::engine::config::{DEFAULT_QUIT_KEY, DEFAULT_WINDOW_TITLE};
+use crate::engine::events::{EngineEvent, EventQueue};
+use crate::engine::input::InputState;
+use crate::engine::timing::{FrameHistogram, FrameStats, GameClock, SharedFrameStats};
+use crate::render::Pixel;
+use crate::render::overlay::PerfOverlay;
+use frames::FrameReader;
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture, wgpu};
+use serde::Deserialize;
+use std::path::Path;
 use winit::application::ApplicationHandler;
-use winit::dpi::LogicalSize;
-use winit::event::KeyEvent;
-use winit::event::WindowEvent;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{Ime, KeyEvent, Touch, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::keyboard::{KeyCode, PhysicalKey};
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::monitor::MonitorHandle;
+use winit::window::{Fullscreen, Icon, Window, WindowAttributes, WindowId};
 
 /// Screen dimensions constants.
 pub const WIDTH: u32 = 300;
 pub const HEIGHT: u32 = 300;
 
+/// Controls how finished frames are presented to the window.
+///
+/// Waiting for the display refresh avoids tearing at the cost of latency.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PresentMode {
+    /// Frames are shown on display refresh, without tearing.
+    #[default]
+    Vsync,
+    /// Frames are shown immediately; lowest latency, but may tear.
+    NoVsync,
+    /// Frames are shown on display refresh, replacing queued frames with newer ones.
+    /// Low latency without tearing, but not supported on every platform.
+    Mailbox,
+}
+
+impl PresentMode {
+    /// Returns the matching wgpu present mode.
+    fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Vsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::NoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+}
+
+/// Controls how frames are mapped onto the window surface.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ScalingMode {
+    /// Frames are scaled by the largest whole factor that fits and letterboxed, keeping
+    /// every frame pixel the same size.
+    #[default]
+    IntegerScale,
+    /// Frames are scaled as large as they fit while keeping their aspect ratio and
+    /// letterboxed; frame pixels may differ in size by one physical pixel.
+    Fit,
+    /// Frames cover the whole surface, ignoring their aspect ratio.
+    Stretch,
+}
+
+/// Controls where a window opens on its monitor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WindowPosition {
+    /// The platform picks the position.
+    #[default]
+    Default,
+    /// The window is centered on the monitor.
+    Centered,
+    /// The top-left corner of the window is placed at an offset from the monitor's
+    /// top-left corner, in physical pixels.
+    At(i32, i32),
+}
+
+/// Computes the top-left corner of a window on a monitor, in desktop coordinates.
+///
+/// # Parameters
+/// - `position`: Requested placement of the window.
+/// - `monitor`: Top-left corner and size of the monitor.
+/// - `window`: Size of the window.
+///
+/// # Returns
+/// None for [`WindowPosition::Default`], leaving the placement to the platform.
+fn window_origin(
+    position: WindowPosition,
+    monitor: (PhysicalPosition<i32>, PhysicalSize<u32>),
+    window: PhysicalSize<u32>,
+) -> Option<PhysicalPosition<i32>> {
+    let (origin, size) = monitor;
+    let (x, y) = match position {
+        WindowPosition::Default => return None,
+        WindowPosition::Centered => (
+            (size.width as i32 - window.width as i32) / 2,
+            (size.height as i32 - window.height as i32) / 2,
+        ),
+        WindowPosition::At(x, y) => (x, y),
+    };
+    Some(PhysicalPosition::new(origin.x + x, origin.y + y))
+}
+
+/// Moves a window to a position on a monitor; does nothing for [`WindowPosition::Default`].
+fn place_window(window: &Window, monitor: &MonitorHandle, position: WindowPosition) {
+    if let Some(origin) = window_origin(
+        position,
+        (monitor.position(), monitor.size()),
+        window.outer_size(),
+    ) {
+        window.set_outer_position(origin);
+    }
+}
+
+/// Computes where a frame lands on a surface, as `(x, y, width, height)` in surface pixels.
+///
+/// # Parameters
+/// - `mode`: How the frame is scaled.
+/// - `frame`: Width and height of the frame.
+/// - `surface`: Width and height of the surface.
+fn scaled_rect(mode: ScalingMode, frame: (u32, u32), surface: (u32, u32)) -> (u32, u32, u32, u32) {
+    let scale_x = surface.0 as f64 / frame.0 as f64;
+    let scale_y = surface.1 as f64 / frame.1 as f64;
+    let scale = match mode {
+        ScalingMode::Stretch => return (0, 0, surface.0, surface.1),
+        ScalingMode::Fit => scale_x.min(scale_y),
+        ScalingMode::IntegerScale => scale_x.min(scale_y).floor().max(1.0),
+    };
+    let width = ((frame.0 as f64 * scale).round() as u32).min(surface.0);
+    let height = ((frame.1 as f64 * scale).round() as u32).min(surface.1);
+    (
+        (surface.0 - width) / 2,
+        (surface.1 - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Copies a frame into a buffer with nearest-neighbour scaling, filling the border black.
+///
+/// # Parameters
+/// - `frame`: RGBA pixels of the frame, row by row.
+/// - `frame_size`: Width and height of the frame.
+/// - `buffer`: RGBA bytes of the buffer, row by row.
+/// - `buffer_width`: Width of the buffer in pixels.
+/// - `rect`: Area of the buffer covered by the frame, as returned by [`scaled_rect`].
+fn blit_scaled(
+    frame: &[Pixel],
+    frame_size: (u32, u32),
+    buffer: &mut [u8],
+    buffer_width: u32,
+    rect: (u32, u32, u32, u32),
+) {
+    let (rx, ry, rw, rh) = rect;
+    let columns: Vec<usize> = (0..rw)
+        .map(|x| (x as u64 * frame_size.0 as u64 / rw as u64) as usize)
+        .collect();
+    for (y, row) in buffer
+        .chunks_exact_mut(buffer_width as usize * 4)
+        .enumerate()
+    {
+        let y = y as u32;
+        if y < ry || y >= ry + rh {
+            for px in row.chunks_exact_mut(4) {
+                px.copy_from_slice(&[0, 0, 0, 255]);
+            }
+            continue;
+        }
+        let src_y = ((y - ry) as u64 * frame_size.1 as u64 / rh as u64) as usize;
+        let src_row = &frame[src_y * frame_size.0 as usize..];
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            let x = x as u32;
+            if x < rx || x >= rx + rw {
+                px.copy_from_slice(&[0, 0, 0, 255]);
+                continue;
+            }
+            let (r, g, b, a) = src_row[columns[(x - rx) as usize]];
+            px.copy_from_slice(&[r, g, b, a]);
+        }
+    }
+}
+
 /// Represents the screen on which game frames are drawn.
 ///
 /// Wraps the `Pixels` buffer and provides methods for pixel frame updates.
+/// With [`ScalingMode::IntegerScale`] the buffer has the frame size and pixels scales it;
+/// the other modes scale frames on the CPU into a buffer of the surface size.
 pub struct Screen<'a> {
     pixels: Pixels<'a>,
+    /// Size of the shown frames.
+    resolution: Resolution,
+    /// How frames are mapped onto the surface.
+    scaling: ScalingMode,
+    /// Width of the surface in physical pixels.
+    surface_width: u32,
+    /// Area of the buffer covered by the frame, unless using integer scaling.
+    rect: (u32, u32, u32, u32),
 }
 
 impl Screen<'_> {
     /// Creates a new `Screen` attached to the specified window and resolution.
     ///
+    /// # Parameters
+    /// - `window`: Window the frames are shown in.
+    /// - `resolution`: Size of the frames.
+    /// - `present_mode`: How frames are presented to the window.
+    /// - `scaling`: How frames are mapped onto the window surface.
+    ///
     /// # Errors
     /// Returns a `pixels::Error` if pixel buffer initialization fails.
-    pub fn new(window: Arc<Window>, resolution: Resolution) -> Result<Self, pixels::Error> {
+    pub fn new(
+        window: Arc<Window>,
+        resolution: Resolution,
+        present_mode: PresentMode,
+        scaling: ScalingMode,
+    ) -> Result<Self, pixels::Error> {
+        // The surface covers the window in physical pixels, whatever the frame size is
+        let surface_size = window.inner_size();
         let surface_texture =
-            SurfaceTexture::new(resolution.width, resolution.height, window.clone());
-        let pixels = Pixels::new(resolution.width, resolution.height, surface_texture)?;
-        Ok(Self { pixels })
+            SurfaceTexture::new(surface_size.width, surface_size.height, window.clone());
+        let pixels = PixelsBuilder::new(resolution.width, resolution.height, surface_texture)
+            .present_mode(present_mode.to_wgpu())
+            .build()?;
+        let mut screen = Self {
+            pixels,
+            resolution,
+            scaling,
+            surface_width: resolution.width,
+            rect: (0, 0, resolution.width, resolution.height),
+        };
+        screen.resize(surface_size.width, surface_size.height)?;
+        Ok(screen)
     }
 
     /// Updates the pixel frame with new RGBA color data and renders it.
     ///
     /// # Parameters
     /// - `pixel_colors`: Slice of RGBA tuples representing new frame pixel data.
-    pub fn update(&mut self, pixel_colors: &[(u8, u8, u8, u8)]) {
-        let cur_frame = self.pixels.frame_mut();
-        for (i, &(r, g, b, a)) in pixel_colors.iter().enumerate() {
-            let base = i * 4;
-            cur_frame[base] = r;
-            cur_frame[base + 1] = g;
-            cur_frame[base + 2] = b;
-            cur_frame[base + 3] = a;
+    pub fn update(&mut self, pixel_colors: &[Pixel]) {
+        let frame_size = (self.resolution.width, self.resolution.height);
+        if self.scaling == ScalingMode::IntegerScale {
+            let cur_frame = self.pixels.frame_mut();
+            for (i, &(r, g, b, a)) in pixel_colors.iter().enumerate() {
+                let base = i * 4;
+                cur_frame[base] = r;
+                cur_frame[base + 1] = g;
+                cur_frame[base + 2] = b;
+                cur_frame[base + 3] = a;
+            }
+        } else if pixel_colors.len() >= (frame_size.0 * frame_size.1) as usize {
+            blit_scaled(
+                pixel_colors,
+                frame_size,
+                self.pixels.frame_mut(),
+                self.surface_width,
+                self.rect,
+            );
         }
         let _ = self.pixels.render();
     }
+
+    /// Resizes the window surface and maps the frame onto it according to the scaling mode.
+    ///
+    /// With integer scaling, pixels scales the frame by the largest whole factor that fits
+    /// and fills the remaining border with the clear color. The other modes resize the
+    /// buffer to the surface and compute the area the frame is scaled into.
+    /// A zero sized surface, e.g. of a minimized window, is ignored.
+    ///
+    /// # Errors
+    /// Returns a `pixels::TextureError` if the surface cannot be resized.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), pixels::TextureError> {
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        self.pixels.resize_surface(width, height)?;
+        if self.scaling != ScalingMode::IntegerScale {
+            self.pixels.resize_buffer(width, height)?;
+            self.surface_width = width;
+            self.rect = scaled_rect(
+                self.scaling,
+                (self.resolution.width, self.resolution.height),
+                (width, height),
+            );
+        }
+        Ok(())
+    }
+
+    /// Converts a physical window position to a pixel of the frame.
+    ///
+    /// # Returns
+    /// The frame position, or None if the window position lies outside the frame.
+    pub fn window_to_frame(&self, position: (f64, f64)) -> Option<(i32, i32)> {
+        if self.scaling == ScalingMode::IntegerScale {
+            return self
+                .pixels
+                .window_pos_to_pixel((position.0 as f32, position.1 as f32))
+                .ok()
+                .map(|(x, y)| (x as i32, y as i32));
+        }
+        // The buffer matches the surface, so window positions are buffer positions
+        let (rx, ry, rw, rh) = self.rect;
+        let (x, y) = (position.0 - rx as f64, position.1 - ry as f64);
+        if x < 0.0 || y < 0.0 || x >= rw as f64 || y >= rh as f64 {
+            return None;
+        }
+        Some((
+            (x * self.resolution.width as f64 / rw as f64) as i32,
+            (y * self.resolution.height as f64 / rh as f64) as i32,
+        ))
+    }
+}
+
+/// Loads an image file as a window icon.
+///
+/// # Errors
+/// Fails if the file cannot be decoded or is not a valid icon.
+pub fn load_icon(path: &Path) -> Result<Icon, std::io::Error> {
+    let image = image::open(path)
+        .map_err(std::io::Error::other)?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height).map_err(std::io::Error::other)
 }
 
 /// Type alias for pixel color data vectors.
-type PixelData = Vec<(u8, u8, u8, u8)>;
-
-/// Holds the pressed state of movement keys (WASD) via atomic booleans for thread-safe access.
-pub struct Keys {
-    pub w: AtomicBool,
-    pub a: AtomicBool,
-    pub s: AtomicBool,
-    pub d: AtomicBool,
+pub(crate) type PixelData = Vec<Pixel>;
+
+/// [Handle](crate#handles) to an additional window and the pixel buffer it shows.
+///
+/// Frames written before the window opens are shown once it does.
+#[derive(Clone)]
+pub struct WindowHandle {
+    /// Title of the window.
+    title: String,
+    /// Size of the frames shown in the window.
+    resolution: Resolution,
+    /// Pixels of the frame shown in the window.
+    pixel_data: Arc<RwLock<PixelData>>,
+    /// The window, once it is open.
+    window: Arc<RwLock<Option<Arc<Window>>>>,
+}
+
+impl WindowHandle {
+    /// Creates a handle for a window showing frames of the given size, initially black.
+    ///
+    /// # Parameters
+    /// - `title`: Text shown in the window's title bar.
+    /// - `resolution`: Size of the frames written to the window.
+    pub fn new(title: impl Into<String>, resolution: Resolution) -> Self {
+        WindowHandle {
+            title: title.into(),
+            resolution,
+            pixel_data: Arc::new(RwLock::new(vec![
+                (0, 0, 0, 255);
+                (resolution.width * resolution.height)
+                    as usize
+            ])),
+            window: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Returns the size of the frames shown in the window.
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Checks whether the window is currently open.
+    pub fn is_open(&self) -> bool {
+        self.window.read().unwrap().is_some()
+    }
+
+    /// Replaces the shown frame and requests a redraw of the window.
+    ///
+    /// # Parameters
+    /// - `pixels`: RGBA pixels of the frame, row by row; extra pixels are ignored.
+    pub fn present(&self, pixels: &[Pixel]) {
+        for (dst, src) in self.pixel_data.write().unwrap().iter_mut().zip(pixels) {
+            *dst = *src;
+        }
+        if let Some(window) = self.window.read().unwrap().as_ref() {
+            window.request_redraw();
+        }
+    }
+}
+
+/// [Handle](crate#handles) to the game window for changing its placement while the
+/// engine runs.
+///
+/// The game window is stored in it once it opens; changes requested before fail.
+#[derive(Clone, Default)]
+pub struct GameWindow {
+    /// The window, once it is open.
+    window: Arc<RwLock<Option<Arc<Window>>>>,
+}
+
+impl GameWindow {
+    /// Creates a handle to a window that is not open yet.
+    pub fn new() -> Self {
+        GameWindow::default()
+    }
+
+    /// Returns the shared slot the app stores the window in when it opens.
+    pub(crate) fn slot(&self) -> Arc<RwLock<Option<Arc<Window>>>> {
+        self.window.clone()
+    }
+
+    /// Checks whether the window is currently open.
+    pub fn is_open(&self) -> bool {
+        self.window.read().unwrap().is_some()
+    }
+
+    /// Returns the number of connected monitors, or 0 while the window is not open.
+    pub fn monitor_count(&self) -> usize {
+        self.window
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |window| window.available_monitors().count())
+    }
+
+    /// Allows or forbids input methods on the window, e.g. while a text field has focus.
+    ///
+    /// Input methods compose text in languages such as Japanese; characters typed
+    /// directly are collected either way.
+    ///
+    /// # Errors
+    /// Fails if the window is not open.
+    pub fn set_ime_allowed(&self, allowed: bool) -> Result<(), std::io::Error> {
+        self.open_window()?.set_ime_allowed(allowed);
+        Ok(())
+    }
+
+    /// Moves the window on the monitor it is currently shown on.
+    ///
+    /// # Errors
+    /// Fails if the window is not open or its monitor is unknown.
+    pub fn set_position(&self, position: WindowPosition) -> Result<(), std::io::Error> {
+        let window = self.open_window()?;
+        let monitor = window
+            .current_monitor()
+            .ok_or_else(|| std::io::Error::other("the window's monitor is unknown"))?;
+        place_window(&window, &monitor, position);
+        Ok(())
+    }
+
+    /// Moves the window to another monitor.
+    ///
+    /// # Parameters
+    /// - `index`: Index of the monitor, between 0 and [`GameWindow::monitor_count`].
+    /// - `position`: Placement on that monitor; [`WindowPosition::Default`] centers the window.
+    ///
+    /// # Errors
+    /// Fails if the window is not open or there is no monitor with the given index.
+    pub fn move_to_monitor(
+        &self,
+        index: usize,
+        position: WindowPosition,
+    ) -> Result<(), std::io::Error> {
+        let window = self.open_window()?;
+        let monitor = window
+            .available_monitors()
+            .nth(index)
+            .ok_or_else(|| std::io::Error::other(format!("there is no monitor {index}")))?;
+        let position = match position {
+            WindowPosition::Default => WindowPosition::Centered,
+            position => position,
+        };
+        place_window(&window, &monitor, position);
+        Ok(())
+    }
+
+    /// Returns the window, failing if it is not open.
+    fn open_window(&self) -> Result<Arc<Window>, std::io::Error> {
+        self.window
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| std::io::Error::other("the game window is not open"))
+    }
+}
+
+/// Returns the initial window size in physical pixels for frames of the given resolution.
+///
+/// Every frame pixel covers a whole number of physical pixels, the display scale factor
+/// rounded, so the window has about the same size on screens of any pixel density.
+fn window_size(resolution: Resolution, scale_factor: f64) -> PhysicalSize<u32> {
+    let scale = scale_factor.round().max(1.0) as u32;
+    PhysicalSize::new(resolution.width * scale, resolution.height * scale)
+}
+
+/// [Handle](crate#handles) to the scale factor of the display showing the game window.
+///
+/// The game window updates it when it moves to another display. The scale factor is 1.0
+/// until the window opens.
+#[derive(Clone)]
+pub struct ScaleFactor {
+    bits: Arc<AtomicU64>,
+}
+
+impl Default for ScaleFactor {
+    fn default() -> Self {
+        ScaleFactor {
+            bits: Arc::new(AtomicU64::new(1.0f64.to_bits())),
+        }
+    }
+}
+
+impl ScaleFactor {
+    /// Creates a handle with a scale factor of 1.0.
+    pub fn new() -> Self {
+        ScaleFactor::default()
+    }
+
+    /// Returns the number of physical pixels per logical pixel of the display.
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.bits.load(Ordering::Relaxed))
+    }
+
+    /// Stores a new scale factor.
+    pub(crate) fn set(&self, scale_factor: f64) {
+        self.bits.store(scale_factor.to_bits(), Ordering::Relaxed);
+    }
 }
 
 /// Main GUI application struct.
 ///
-/// Holds references to the window, screen, pixel buffer, and keyboard input state.
-/// Tracks frame count and timing for optional FPS measurements.
+/// Holds references to the window, screen, frame channel, and keyboard, mouse and touch input state.
+/// Measures the shown frames and publishes their statistics once per second.
 pub struct App {
     /// Reference to the main window, inside a read-write lock.
     window: Arc<RwLock<Option<Arc<Window>>>>,
     /// The `Screen` object rendering pixel frames.
     screen: Option<Screen<'static>>,
-    /// Shared pixel data provided by the renderer.
-    pixel_data: Arc<RwLock<PixelData>>,
-    /// Atomic flags indicating pressed state for WASD keys.
-    pub(crate) keys_pressed: Arc<Keys>,
+    /// Triple buffer delivering the frames finished by the producer.
+    frames: FrameReader,
+    /// Keys, mouse buttons, mouse position and wheel of the game window.
+    pub(crate) input: InputState,
+    /// Set when the screenshot key (F12) is pressed, until the screenshot is taken.
+    pub(crate) screenshot_requested: Arc<AtomicBool>,
+    /// Set once the app exits, telling the threads producing its frames to stop.
+    pub(crate) shutdown: Arc<AtomicBool>,
+    /// Whether the OS cursor is hidden over the game window.
+    cursor_hidden: bool,
+    /// Scale factor of the display showing the game window.
+    scale_factor: ScaleFactor,
+    /// Queue receiving focus, minimize and file drop events of the game window.
+    events: EventQueue,
+    /// Size of the frames shown on the screen.
+    resolution: Resolution,
+    /// Title of the window.
+    title: String,
+    /// Icon of the window, None for the platform default.
+    icon: Option<Icon>,
+    /// How frames are presented to the window.
+    present_mode: PresentMode,
+    /// How frames are mapped onto the window surface.
+    scaling: ScalingMode,
+    /// Key exiting the app when pressed, if any.
+    quit_key: Option<KeyCode>,
+    /// Key pausing the game clock and stepping it by single updates, if any.
+    frame_step_key: Option<KeyCode>,
+    /// Clock of the game shown in the window.
+    clock: GameClock,
+    /// Where the game window opens.
+    window_position: WindowPosition,
+    /// Index of the monitor the game window opens on; the primary monitor if None.
+    monitor: Option<usize>,
+    /// Whether the game window covers its whole monitor.
+    fullscreen: bool,
 
     /// Frame count for FPS calculation.
     frame_count: u32,
     /// Timestamp of last FPS measurement.
     last_fps_report_time: Instant,
+    /// Timestamp of the last shown frame.
+    last_frame_time: Option<Instant>,
+    /// Times between the frames shown since the last FPS measurement.
+    frame_times: Vec<Duration>,
+    /// Statistics published at every FPS measurement.
+    frame_stats: SharedFrameStats,
+    /// Histogram counting the time between every two shown frames.
+    frame_histogram: FrameHistogram,
+    /// Performance overlay toggled with F3.
+    perf_overlay: PerfOverlay,
+    /// Additional windows opened next to the game window.
+    extra_windows: Vec<WindowHandle>,
+    /// Screens of the open additional windows, with the index of their handle.
+    extra_screens: HashMap<WindowId, (usize, Screen<'static>)>,
 }
 
 impl App {
-    /// Constructs a new App with shared pixel data and window references.
-    pub fn new(
-        pixel_data: Arc<RwLock<PixelData>>,
-        window: Arc<RwLock<Option<Arc<Window>>>>,
-    ) -> Self {
+    /// Constructs a new App showing the frames of the triple buffer, with a shared window reference.
+    pub fn new(frames: FrameReader, window: Arc<RwLock<Option<Arc<Window>>>>) -> Self {
         App {
             screen: None,
-            pixel_data,
+            frames,
             window,
             //key_pressed: Arc::new(RwLock::new(None)),
-            keys_pressed: Arc::new(Keys {
-                w: AtomicBool::new(false),
-                a: AtomicBool::new(false),
-                s: AtomicBool::new(false),
-                d: AtomicBool::new(false),
-            }),
+            input: InputState::new(),
+            screenshot_requested: Arc::new(AtomicBool::new(false)),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            cursor_hidden: false,
+            scale_factor: ScaleFactor::new(),
+            events: EventQueue::new(),
+            resolution: Resolution {
+                width: WIDTH,
+                height: HEIGHT,
+            },
+            title: DEFAULT_WINDOW_TITLE.to_string(),
+            icon: None,
+            present_mode: PresentMode::default(),
+            scaling: ScalingMode::default(),
+            quit_key: Some(DEFAULT_QUIT_KEY),
+            frame_step_key: None,
+            clock: GameClock::new(),
+            window_position: WindowPosition::default(),
+            monitor: None,
+            fullscreen: false,
             frame_count: 0,
             last_fps_report_time: Instant::now(),
+            last_frame_time: None,
+            frame_times: Vec::new(),
+            frame_stats: SharedFrameStats::new(),
+            frame_histogram: FrameHistogram::new(),
+            perf_overlay: PerfOverlay::new(),
+            extra_windows: Vec::new(),
+            extra_screens: HashMap::new(),
+        }
+    }
+
+    /// Sets the size of the shown frames; defaults to `WIDTH` x `HEIGHT`.
+    ///
+    /// # Parameters
+    /// - `resolution`: Size of the pixel frames written to the shared pixel data.
+    pub fn with_resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Sets the window title; defaults to [`DEFAULT_WINDOW_TITLE`].
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Sets the window icon; without one the platform default is used.
+    pub fn with_icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Sets how frames are presented; defaults to [`PresentMode::Vsync`].
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    /// Sets how frames are mapped onto the window; defaults to [`ScalingMode::IntegerScale`].
+    pub fn with_scaling_mode(mut self, scaling: ScalingMode) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    /// Sets the key exiting the app; defaults to [`DEFAULT_QUIT_KEY`], None disables it.
+    pub fn with_quit_key(mut self, key: Option<KeyCode>) -> Self {
+        self.quit_key = key;
+        self
+    }
+
+    /// Sets the key pausing the game and, while paused, running single updates; off by default.
+    pub fn with_frame_step_key(mut self, key: Option<KeyCode>) -> Self {
+        self.frame_step_key = key;
+        self
+    }
+
+    /// Shares the clock the frame step key pauses and steps.
+    pub fn with_clock(mut self, clock: GameClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Pauses the clock, or runs a single update if it is paused already.
+    fn step_frame(&self) {
+        if self.clock.is_paused() {
+            self.clock.advance_tick();
+        } else {
+            self.clock.pause();
+        }
+    }
+
+    /// Sets where the game window opens; defaults to [`WindowPosition::Default`].
+    pub fn with_window_position(mut self, position: WindowPosition) -> Self {
+        self.window_position = position;
+        self
+    }
+
+    /// Opens the game window on the monitor with the given index instead of the primary one.
+    ///
+    /// On another monitor, [`WindowPosition::Default`] centers the window.
+    pub fn with_monitor(mut self, monitor: Option<usize>) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Opens the game window as a borderless window covering its monitor; windowed by default.
+    pub fn with_fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// Sets the handle the frame statistics are published to.
+    pub fn with_frame_stats(mut self, frame_stats: SharedFrameStats) -> Self {
+        self.frame_stats = frame_stats;
+        self
+    }
+
+    /// Sets the histogram the times between shown frames are counted in.
+    pub fn with_frame_histogram(mut self, frame_histogram: FrameHistogram) -> Self {
+        self.frame_histogram = frame_histogram;
+        self
+    }
+
+    /// Sets the performance overlay shown and hidden with F3.
+    pub fn with_perf_overlay(mut self, perf_overlay: PerfOverlay) -> Self {
+        self.perf_overlay = perf_overlay;
+        self
+    }
+
+    /// Hides the OS cursor over the game window, e.g. when the game draws its own cursor.
+    pub fn with_cursor_hidden(mut self, hidden: bool) -> Self {
+        self.cursor_hidden = hidden;
+        self
+    }
+
+    /// Sets the handle the display scale factor is published to.
+    pub fn with_scale_factor(mut self, scale_factor: ScaleFactor) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Sets the state the game window records keyboard and mouse input in.
+    pub fn with_input(mut self, input: InputState) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Sets the queue the game window reports its events to.
+    pub fn with_events(mut self, events: EventQueue) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Adds a window opened next to the game window when the app starts.
+    ///
+    /// Closing it only closes that window; closing the game window exits the app.
+    pub fn with_window(mut self, window: WindowHandle) -> Self {
+        self.extra_windows.push(window);
+        self
+    }
+
+    /// Opens the additional windows and creates their screens.
+    fn open_extra_windows(&mut self, event_loop: &ActiveEventLoop) {
+        let scale_factor = self.scale_factor.get();
+        for (index, handle) in self.extra_windows.iter().enumerate() {
+            let window_attributes = WindowAttributes::default()
+                .with_title(handle.title.clone())
+                .with_inner_size(window_size(handle.resolution, scale_factor))
+                .with_min_inner_size(window_size(handle.resolution, 1.0));
+            let window = match event_loop.create_window(window_attributes) {
+                Ok(window) => Arc::new(window),
+                Err(e) => {
+                    log::error!("Couldn't open window {:?}: {e}", handle.title);
+                    continue;
+                }
+            };
+            match Screen::new(
+                window.clone(),
+                handle.resolution,
+                self.present_mode,
+                self.scaling,
+            ) {
+                Ok(screen) => {
+                    self.extra_screens.insert(window.id(), (index, screen));
+                    *handle.window.write().unwrap() = Some(window);
+                }
+                Err(e) => log::error!("Screen object initialization error: {e:?}"),
+            }
+        }
+    }
+
+    /// Signals the shutdown to the frame producers and exits the event loop.
+    fn exit(&self, event_loop: &ActiveEventLoop) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        event_loop.exit();
+    }
+
+    /// Handles an event of an additional window.
+    ///
+    /// # Returns
+    /// False if `window_id` is not one of the additional windows.
+    fn extra_window_event(&mut self, window_id: WindowId, event: &WindowEvent) -> bool {
+        let Some((index, screen)) = self.extra_screens.get_mut(&window_id) else {
+            return false;
+        };
+        let handle = &self.extra_windows[*index];
+        match event {
+            WindowEvent::CloseRequested => {
+                *handle.window.write().unwrap() = None;
+                self.extra_screens.remove(&window_id);
+            }
+            WindowEvent::Resized(size) => {
+                if let Err(e) = screen.resize(size.width, size.height) {
+                    log::warn!("Couldn't resize screen: {e}");
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                screen.update(&handle.pixel_data.read().unwrap());
+            }
+            _ => (),
+        }
+        true
+    }
+
+    /// Records a shown frame and publishes the statistics once a second has passed.
+    ///
+    /// # Parameters
+    /// - `now`: Moment the frame was shown.
+    fn record_frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame_time {
+            self.frame_times.push(now - last);
+            self.frame_histogram.record(now - last);
+        }
+        self.last_frame_time = Some(now);
+        self.frame_count += 1;
+
+        let elapsed = now - self.last_fps_report_time;
+        if elapsed >= Duration::from_secs(1) {
+            self.frame_stats.set(FrameStats::measure(
+                self.frame_count,
+                elapsed,
+                &self.frame_times,
+            ));
+            self.frame_count = 0;
+            self.frame_times.clear();
+            self.last_fps_report_time = now;
         }
     }
 
@@ -122,15 +871,44 @@ impl App {
 impl ApplicationHandler for App {
     /// Called when the application is resumed or started.
     ///
-    /// Creates the window and initializes the `Screen`.
+    /// Creates the window with the configured title and icon, sized for the display's scale
+    /// factor, initializes the `Screen` and then opens the additional windows.
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_size = LogicalSize::new(WIDTH / 2, HEIGHT / 2);
-        let window_attributes = WindowAttributes::default()
-            /*.with_title("rusty_ache")*/
-            .with_inner_size(window_size)
-            .with_min_inner_size(window_size)
-            .with_max_inner_size(window_size);
+        let mut position = self.window_position;
+        let monitor = match self.monitor {
+            Some(index) => {
+                let monitor = event_loop.available_monitors().nth(index);
+                if monitor.is_none() {
+                    log::warn!("There is no monitor {index}, opening on the primary one");
+                } else if position == WindowPosition::Default {
+                    position = WindowPosition::Centered;
+                }
+                monitor.or_else(|| event_loop.primary_monitor())
+            }
+            None => event_loop.primary_monitor(),
+        };
+        let scale_factor = monitor
+            .as_ref()
+            .map_or(1.0, |monitor| monitor.scale_factor());
+        let size = window_size(self.resolution, scale_factor);
+        let mut window_attributes = WindowAttributes::default()
+            .with_title(self.title.clone())
+            .with_window_icon(self.icon.clone())
+            .with_inner_size(size)
+            .with_min_inner_size(window_size(self.resolution, 1.0));
+        if let Some(origin) = monitor
+            .as_ref()
+            .and_then(|monitor| window_origin(position, (monitor.position(), monitor.size()), size))
+        {
+            window_attributes = window_attributes.with_position(origin);
+        }
+        if self.fullscreen {
+            window_attributes =
+                window_attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor.clone())));
+        }
         let window = event_loop.create_window(window_attributes).unwrap();
+        self.scale_factor.set(window.scale_factor());
+        window.set_cursor_visible(!self.cursor_hidden);
 
         let arc = Arc::new(window);
         {
@@ -138,72 +916,147 @@ impl ApplicationHandler for App {
             *shared_window_lock = Some(arc.clone());
         }
 
-        let resolution = Resolution {
-            width: WIDTH,
-            height: HEIGHT,
-        };
-        match Screen::new(arc, resolution) {
+        match Screen::new(arc, self.resolution, self.present_mode, self.scaling) {
             Ok(screen) => {
                 self.screen = Some(screen);
             }
-            Err(e) => eprintln!("Screen object initialization error: {:?}", e),
+            Err(e) => log::error!("Screen object initialization error: {e:?}"),
         }
+
+        self.open_extra_windows(event_loop);
     }
 
     /// Handles window events such as close requests, redraw requests, and keyboard input.
     ///
     /// - CloseRequested: exits event loop.
-    /// - RedrawRequested: updates the screen with new pixels and optionally calculates FPS.
-    /// - KeyboardInput: updates atomic key states for WASD keys.
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+    /// - Resized: resizes the surface, scaling the frame according to the scaling mode, and
+    ///   reports a zero size as the window being minimized.
+    /// - Focused: reports the focus change; losing focus releases all keys.
+    /// - DroppedFile: reports the path of the file dropped onto the window.
+    /// - ScaleFactorChanged: publishes the new scale factor and resizes the window to match it.
+    /// - RedrawRequested: updates the screen with new pixels and measures the frame.
+    /// - KeyboardInput: exits on the quit key, records the key state and typed text and
+    ///   requests a screenshot on F12.
+    /// - Ime: records the composed and committed text of the input method.
+    /// - CursorMoved / CursorLeft: updates the mouse position over the frame.
+    /// - MouseInput / MouseWheel: records the button state and the scrolled wheel distance.
+    /// - Touch: records fingers touching the frame as pointers.
+    ///
+    /// Events of additional windows only resize, redraw or close that window.
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if self.extra_window_event(window_id, &event) {
+            return;
+        }
         let screen = match self.screen.as_mut() {
             Some(s) => s,
             None => return,
         };
         match event {
             WindowEvent::CloseRequested => {
-                //dbg!("Bye bye");
-                event_loop.exit();
+                log::debug!("Window close requested");
+                self.exit(event_loop);
             }
 
-            WindowEvent::RedrawRequested => {
-                let pixel_data = match self.pixel_data.read() {
-                    Ok(data) => data,
-                    Err(_) => {
-                        eprintln!("Couldn't get data from provider");
-                        return;
-                    }
-                };
+            WindowEvent::Resized(size) => {
+                self.events
+                    .set_minimized(size.width == 0 || size.height == 0);
+                if let Err(e) = screen.resize(size.width, size.height) {
+                    log::warn!("Couldn't resize screen: {e}");
+                }
+            }
 
-                screen.update(&pixel_data);
+            WindowEvent::Focused(focused) => {
+                if !focused {
+                    self.input.release_all();
+                }
+                self.events.set_focused(focused);
+            }
+
+            WindowEvent::DroppedFile(path) => self.events.push(EngineEvent::FileDropped(path)),
 
-                // FPS computation
-                self.frame_count += 1;
-                let elapsed = self.last_fps_report_time.elapsed();
-                if elapsed >= Duration::from_secs(1) {
-                    self.frame_count = 0;
-                    self.last_fps_report_time = Instant::now();
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                mut inner_size_writer,
+            } => {
+                self.scale_factor.set(scale_factor);
+                let _ = inner_size_writer
+                    .request_inner_size(window_size(self.resolution, scale_factor));
+            }
+
+            WindowEvent::RedrawRequested => {
+                // The watchdog may shut the engine down and wake the window to exit
+                if self.shutdown.load(Ordering::Relaxed) {
+                    event_loop.exit();
+                    return;
                 }
+                self.frames.update();
+                screen.update(self.frames.frame());
+
+                self.record_frame(Instant::now());
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.input
+                    .set_mouse_position(screen.window_to_frame((position.x, position.y)));
+            }
+            WindowEvent::CursorLeft { .. } => self.input.set_mouse_position(None),
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.input.set_button(button, state.is_pressed())
+            }
+            WindowEvent::MouseWheel { delta, .. } => self.input.add_wheel_delta(delta),
+            WindowEvent::Touch(Touch {
+                id,
+                phase,
+                location,
+                ..
+            }) => {
+                let position = screen.window_to_frame((location.x, location.y));
+                self.input.touch(id, phase, position);
             }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
-                        physical_key: PhysicalKey::Code(key_code),
+                        physical_key,
                         state,
+                        text,
+                        repeat,
                         ..
                     },
                 ..
             } => {
                 let pressed = state.is_pressed();
+                if pressed && let Some(text) = text {
+                    self.input.add_text(&text);
+                }
+                let PhysicalKey::Code(key_code) = physical_key else {
+                    return;
+                };
+                if pressed && self.quit_key == Some(key_code) && !self.input.is_rebinding() {
+                    self.exit(event_loop);
+                    return;
+                }
 
-                match key_code {
-                    KeyCode::KeyW => self.keys_pressed.w.store(pressed, Ordering::Relaxed),
-                    KeyCode::KeyA => self.keys_pressed.a.store(pressed, Ordering::Relaxed),
-                    KeyCode::KeyS => self.keys_pressed.s.store(pressed, Ordering::Relaxed),
-                    KeyCode::KeyD => self.keys_pressed.d.store(pressed, Ordering::Relaxed),
-                    _ => {}
+                self.input.set_key(key_code, pressed);
+                if key_code == KeyCode::F12 && pressed {
+                    self.screenshot_requested.store(true, Ordering::Relaxed);
                 }
+                if key_code == KeyCode::F3 && pressed && !repeat {
+                    self.perf_overlay.toggle();
+                }
+                if pressed && self.frame_step_key == Some(key_code) && !self.input.is_rebinding() {
+                    self.step_frame();
+                }
+            }
+            WindowEvent::Ime(Ime::Preedit(text, _)) => self.input.set_preedit(text),
+            WindowEvent::Ime(Ime::Commit(text)) => {
+                self.input.set_preedit(String::new());
+                self.input.add_text(&text);
             }
+            WindowEvent::Ime(Ime::Disabled) => self.input.set_preedit(String::new()),
             _ => (),
         }
     }
@@ -211,52 +1064,40 @@ impl ApplicationHandler for App {
 
 /// Example function demonstrating app initialization and running.
 ///
-/// Sets up shared pixel buffers and windows, spawns a producer thread that
+/// Sets up a triple buffer and window, spawns a producer thread that
 /// dynamically updates pixel colors in a loop, and runs the event loop.
 pub fn example() {
-    let initial_resolution = Resolution {
-        width: WIDTH,
-        height: HEIGHT,
-    };
-    let initial_pixels = vec![
-        (0x00, 0x00, 0x00, 0xFF);
-        (initial_resolution.width * initial_resolution.height) as usize
-    ];
-
-    let shared_pixel_data = Arc::new(RwLock::new(initial_pixels));
+    let (mut writer, reader) = frames::triple_buffer();
     let shared_window = Arc::new(RwLock::new(None));
 
-    let shared_pixel_data_clone = shared_pixel_data.clone();
     let shared_window_clone = shared_window.clone();
+    let mut app = App::new(reader, shared_window);
+    let shutdown = app.shutdown.clone();
 
     // Producer thread
-    thread::spawn(move || {
+    let producer = thread::spawn(move || {
         let window_arc: Arc<Window> = loop {
+            if shutdown.load(Ordering::Relaxed) {
+                return;
+            }
             if let Some(arc) = shared_window_clone.read().unwrap().clone() {
                 break arc;
             }
             thread::sleep(Duration::from_millis(50));
         };
 
-        //dbg!("Producer has started");
+        log::debug!("Producer has started");
 
         let mut frame_count: u32 = 0; // for generating new color
         let screen_size = (WIDTH * HEIGHT) as usize;
-        loop {
+        while !shutdown.load(Ordering::Relaxed) {
             let new_color = (frame_count * 0x10001 + 0x010000) % 0xFFFFFF; // gradient for testing
             let r = ((new_color >> 16) & 0xFF) as u8;
             let g = ((new_color >> 8) & 0xFF) as u8;
             let b = (new_color & 0xFF) as u8;
             let a = 0xFF_u8;
-            {
-                let mut pixels = shared_pixel_data_clone
-                    .write()
-                    .expect("Producer couldn't get lock to write new pixel data into App");
-
-                for i in 0..screen_size {
-                    pixels[i] = (r, g, b, a); // recolor all pixels into new color
-                }
-            }
+            // recolor all pixels into new color
+            writer.write(std::iter::repeat_n((r, g, b, a), screen_size));
             window_arc.request_redraw();
             frame_count = frame_count.wrapping_add(1);
         }
@@ -264,164 +1105,294 @@ pub fn example() {
 
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Wait);
-    let mut app = App::new(shared_pixel_data, shared_window);
     let _ = event_loop.run_app(&mut app);
+    app.shutdown.store(true, Ordering::Relaxed);
+    let _ = producer.join();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::atomic::Ordering;
     use std::sync::{Arc, RwLock};
-    use std::thread;
 
     #[test]
-    fn test_keys_new_all_false() {
-        let keys = Keys {
-            w: AtomicBool::new(false),
-            a: AtomicBool::new(false),
-            s: AtomicBool::new(false),
-            d: AtomicBool::new(false),
-        };
+    fn test_width_constant() {
+        assert_eq!(WIDTH, 300);
+        const { assert!(WIDTH > 0) };
+    }
 
-        assert_eq!(keys.w.load(Ordering::Relaxed), false);
-        assert_eq!(keys.a.load(Ordering::Relaxed), false);
-        assert_eq!(keys.s.load(Ordering::Relaxed), false);
-        assert_eq!(keys.d.load(Ordering::Relaxed), false);
+    #[test]
+    fn test_height_constant() {
+        assert_eq!(HEIGHT, 300);
+        const { assert!(HEIGHT > 0) };
     }
 
     #[test]
-    fn test_keys_store_and_load() {
-        let keys = Keys {
-            w: AtomicBool::new(false),
-            a: AtomicBool::new(false),
-            s: AtomicBool::new(false),
-            d: AtomicBool::new(false),
-        };
+    fn test_app_new_initialization() {
+        let (_writer, reader) = frames::triple_buffer();
+        let window = Arc::new(RwLock::new(None));
 
-        keys.w.store(true, Ordering::Relaxed);
-        keys.a.store(true, Ordering::SeqCst);
+        let app = App::new(reader, window.clone());
 
-        assert_eq!(keys.w.load(Ordering::Relaxed), true);
-        assert_eq!(keys.a.load(Ordering::SeqCst), true);
-        assert_eq!(keys.s.load(Ordering::Relaxed), false);
-        assert_eq!(keys.d.load(Ordering::Relaxed), false);
+        assert!(app.input.pressed_keys().is_empty());
+
+        assert_eq!(app.frame_count, 0);
+        assert!(!app.shutdown.load(Ordering::Relaxed));
     }
 
     #[test]
-    fn test_keys_toggle_operations() {
-        let keys = Keys {
-            w: AtomicBool::new(false),
-            a: AtomicBool::new(false),
-            s: AtomicBool::new(false),
-            d: AtomicBool::new(false),
-        };
+    fn test_app_run_method() {
+        let (_writer, reader) = frames::triple_buffer();
+        let window = Arc::new(RwLock::new(None));
 
-        keys.w.store(true, Ordering::Relaxed);
-        assert_eq!(keys.w.load(Ordering::Relaxed), true);
-        keys.w.store(false, Ordering::Relaxed);
-        assert_eq!(keys.w.load(Ordering::Relaxed), false);
+        let mut app = App::new(reader, window);
+        app.run();
     }
 
     #[test]
-    fn test_keys_all_true() {
-        let keys = Keys {
-            w: AtomicBool::new(true),
-            a: AtomicBool::new(true),
-            s: AtomicBool::new(true),
-            d: AtomicBool::new(true),
-        };
+    fn test_app_keys_simulation() {
+        let (_writer, reader) = frames::triple_buffer();
+        let window = Arc::new(RwLock::new(None));
+
+        let app = App::new(reader, window);
 
-        assert_eq!(keys.w.load(Ordering::Relaxed), true);
-        assert_eq!(keys.a.load(Ordering::Relaxed), true);
-        assert_eq!(keys.s.load(Ordering::Relaxed), true);
-        assert_eq!(keys.d.load(Ordering::Relaxed), true);
+        app.input.set_key(KeyCode::KeyW, true);
+        app.input.set_key(KeyCode::KeyD, true);
+
+        assert!(app.input.is_key_pressed(KeyCode::KeyW));
+        assert!(!app.input.is_key_pressed(KeyCode::KeyA));
+        assert!(!app.input.is_key_pressed(KeyCode::KeyS));
+        assert!(app.input.is_key_pressed(KeyCode::KeyD));
+
+        app.input.set_key(KeyCode::KeyW, false);
+        app.input.set_key(KeyCode::KeyD, false);
+
+        assert!(!app.input.is_key_pressed(KeyCode::KeyW));
+        assert!(!app.input.is_key_pressed(KeyCode::KeyD));
     }
 
     #[test]
-    fn test_keys_compare_and_swap() {
-        let keys = Keys {
-            w: AtomicBool::new(false),
-            a: AtomicBool::new(false),
-            s: AtomicBool::new(false),
-            d: AtomicBool::new(false),
-        };
+    fn test_app_frame_counting_simulation() {
+        let (_writer, reader) = frames::triple_buffer();
+        let window = Arc::new(RwLock::new(None));
+
+        let mut app = App::new(reader, window);
 
-        let old = keys.w.swap(true, Ordering::Relaxed);
-        assert_eq!(old, false);
-        assert_eq!(keys.w.load(Ordering::Relaxed), true);
+        for _ in 0..10 {
+            app.frame_count += 1;
+        }
+
+        assert_eq!(app.frame_count, 10);
     }
 
     #[test]
-    fn test_width_constant() {
-        assert_eq!(WIDTH, 300);
-        assert!(WIDTH > 0);
+    fn test_app_title_and_icon() {
+        let (_writer, reader) = frames::triple_buffer();
+        let window = Arc::new(RwLock::new(None));
+
+        let app = App::new(reader, window);
+        assert_eq!(app.title, DEFAULT_WINDOW_TITLE);
+        assert!(app.icon.is_none());
+
+        let icon = Icon::from_rgba(vec![255; 4 * 4 * 4], 4, 4).unwrap();
+        let app = app.with_title("Game".to_string()).with_icon(icon);
+        assert_eq!(app.title, "Game");
+        assert!(app.icon.is_some());
     }
 
     #[test]
-    fn test_height_constant() {
-        assert_eq!(HEIGHT, 300);
-        assert!(HEIGHT > 0);
+    fn test_present_mode() {
+        assert_eq!(PresentMode::default(), PresentMode::Vsync);
+        assert_eq!(PresentMode::Vsync.to_wgpu(), wgpu::PresentMode::AutoVsync);
+        assert_eq!(
+            PresentMode::NoVsync.to_wgpu(),
+            wgpu::PresentMode::AutoNoVsync
+        );
+        assert_eq!(PresentMode::Mailbox.to_wgpu(), wgpu::PresentMode::Mailbox);
+
+        let (_writer, reader) = frames::triple_buffer();
+        let app =
+            App::new(reader, Arc::new(RwLock::new(None))).with_present_mode(PresentMode::Mailbox);
+        assert_eq!(app.present_mode, PresentMode::Mailbox);
     }
 
     #[test]
-    fn test_app_new_initialization() {
-        let pixel_data = Arc::new(RwLock::new(vec![(0, 0, 0, 0); 100]));
-        let window = Arc::new(RwLock::new(None));
+    fn test_load_icon() {
+        let path = std::env::temp_dir().join("rusty_ache_test_icon.png");
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([1, 2, 3, 255]))
+            .save(&path)
+            .unwrap();
+        assert!(load_icon(&path).is_ok());
+        let _ = std::fs::remove_file(&path);
 
-        let app = App::new(pixel_data.clone(), window.clone());
+        assert!(load_icon(Path::new("missing_icon.png")).is_err());
+    }
 
-        assert_eq!(app.keys_pressed.w.load(Ordering::Relaxed), false);
-        assert_eq!(app.keys_pressed.a.load(Ordering::Relaxed), false);
-        assert_eq!(app.keys_pressed.s.load(Ordering::Relaxed), false);
-        assert_eq!(app.keys_pressed.d.load(Ordering::Relaxed), false);
+    #[test]
+    fn test_app_publishes_frame_stats_every_second() {
+        let (_writer, reader) = frames::triple_buffer();
+        let stats = SharedFrameStats::new();
+        let mut app = App::new(reader, Arc::new(RwLock::new(None))).with_frame_stats(stats.clone());
 
+        let start = app.last_fps_report_time;
+        for i in 1..=40 {
+            app.record_frame(start + Duration::from_millis(25 * i));
+        }
+        let published = stats.get();
+        assert_eq!(published.fps, 40.0);
+        assert_eq!(published.last_frame_time, Duration::from_millis(25));
         assert_eq!(app.frame_count, 0);
+
+        app.record_frame(start + Duration::from_millis(1025));
+        assert_eq!(stats.get(), published);
     }
 
     #[test]
-    fn test_app_run_method() {
-        let pixel_data = Arc::new(RwLock::new(vec![(0, 0, 0, 0); 100]));
-        let window = Arc::new(RwLock::new(None));
+    fn test_app_counts_frame_times_in_histogram() {
+        let (_writer, reader) = frames::triple_buffer();
+        let histogram = FrameHistogram::new();
+        let mut app =
+            App::new(reader, Arc::new(RwLock::new(None))).with_frame_histogram(histogram.clone());
 
-        let mut app = App::new(pixel_data, window);
-        app.run();
+        let start = Instant::now();
+        for i in 0..=3 {
+            app.record_frame(start + Duration::from_millis(16 * i));
+        }
+        assert_eq!(histogram.total(), 3);
+        assert_eq!(histogram.counts()[16], 3);
     }
 
     #[test]
-    fn test_app_keys_simulation() {
-        let pixel_data = Arc::new(RwLock::new(vec![(0, 0, 0, 0); 100]));
-        let window = Arc::new(RwLock::new(None));
+    fn test_window_handle_keeps_frame_until_open() {
+        let handle = WindowHandle::new("Inspector", Resolution::new(2, 2));
+        assert!(!handle.is_open());
+        assert_eq!(handle.resolution().width, 2);
+        assert_eq!(
+            &*handle.pixel_data.read().unwrap(),
+            &vec![(0, 0, 0, 255); 4]
+        );
+
+        let clone = handle.clone();
+        clone.present(&[(1, 2, 3, 4); 5]);
+        assert_eq!(&*handle.pixel_data.read().unwrap(), &vec![(1, 2, 3, 4); 4]);
+    }
+
+    #[test]
+    fn test_app_with_extra_windows() {
+        let (_writer, reader) = frames::triple_buffer();
+        let app = App::new(reader, Arc::new(RwLock::new(None)))
+            .with_window(WindowHandle::new("Inspector", Resolution::new(10, 10)))
+            .with_window(WindowHandle::new("Map", Resolution::new(20, 20)));
+        assert_eq!(app.extra_windows.len(), 2);
+        assert_eq!(app.extra_windows[1].title, "Map");
+        assert!(app.extra_screens.is_empty());
+    }
+
+    #[test]
+    fn test_window_size_follows_scale_factor() {
+        let resolution = Resolution::new(300, 200);
+        assert_eq!(window_size(resolution, 1.0), PhysicalSize::new(300, 200));
+        assert_eq!(window_size(resolution, 2.0), PhysicalSize::new(600, 400));
+        assert_eq!(window_size(resolution, 1.25), PhysicalSize::new(300, 200));
+        assert_eq!(window_size(resolution, 0.5), PhysicalSize::new(300, 200));
+    }
 
-        let app = App::new(pixel_data, window);
+    #[test]
+    fn test_window_origin_on_monitor() {
+        let monitor = (
+            PhysicalPosition::new(1920, 0),
+            PhysicalSize::new(1280, 1024),
+        );
+        let window = PhysicalSize::new(600, 400);
+        assert_eq!(
+            window_origin(WindowPosition::Default, monitor, window),
+            None
+        );
+        assert_eq!(
+            window_origin(WindowPosition::Centered, monitor, window),
+            Some(PhysicalPosition::new(2260, 312))
+        );
+        assert_eq!(
+            window_origin(WindowPosition::At(10, -5), monitor, window),
+            Some(PhysicalPosition::new(1930, -5))
+        );
+    }
 
-        app.keys_pressed.w.store(true, Ordering::Relaxed);
-        app.keys_pressed.d.store(true, Ordering::Relaxed);
+    #[test]
+    fn test_game_window_not_open() {
+        let window = GameWindow::new();
+        assert!(!window.is_open());
+        assert_eq!(window.monitor_count(), 0);
+        assert!(window.set_position(WindowPosition::Centered).is_err());
+        assert!(window.set_ime_allowed(true).is_err());
+        assert!(window.move_to_monitor(0, WindowPosition::Default).is_err());
+    }
 
-        assert_eq!(app.keys_pressed.w.load(Ordering::Relaxed), true);
-        assert_eq!(app.keys_pressed.a.load(Ordering::Relaxed), false);
-        assert_eq!(app.keys_pressed.s.load(Ordering::Relaxed), false);
-        assert_eq!(app.keys_pressed.d.load(Ordering::Relaxed), true);
+    #[test]
+    fn test_scale_factor_shared_between_clones() {
+        let scale_factor = ScaleFactor::new();
+        assert_eq!(scale_factor.get(), 1.0);
+        scale_factor.clone().set(2.5);
+        assert_eq!(scale_factor.get(), 2.5);
+    }
 
-        app.keys_pressed.w.store(false, Ordering::Relaxed);
-        app.keys_pressed.d.store(false, Ordering::Relaxed);
+    #[test]
+    fn test_scaled_rect_modes() {
+        let frame = (100, 50);
+        assert_eq!(
+            scaled_rect(ScalingMode::Stretch, frame, (300, 300)),
+            (0, 0, 300, 300)
+        );
+        assert_eq!(
+            scaled_rect(ScalingMode::Fit, frame, (250, 300)),
+            (0, 87, 250, 125)
+        );
+        assert_eq!(
+            scaled_rect(ScalingMode::IntegerScale, frame, (250, 300)),
+            (25, 100, 200, 100)
+        );
+        assert_eq!(
+            scaled_rect(ScalingMode::IntegerScale, frame, (50, 50)),
+            (0, 0, 50, 50)
+        );
+    }
 
-        assert_eq!(app.keys_pressed.w.load(Ordering::Relaxed), false);
-        assert_eq!(app.keys_pressed.d.load(Ordering::Relaxed), false);
+    #[test]
+    fn test_blit_scaled_letterboxes() {
+        let frame = [(255, 0, 0, 255), (0, 255, 0, 255)];
+        let mut buffer = vec![7u8; 4 * 4 * 4];
+        blit_scaled(&frame, (2, 1), &mut buffer, 4, (0, 1, 4, 2));
+        let px = |x: usize, y: usize| &buffer[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4];
+        assert_eq!(px(0, 0), &[0, 0, 0, 255]);
+        assert_eq!(px(1, 1), &[255, 0, 0, 255]);
+        assert_eq!(px(2, 2), &[0, 255, 0, 255]);
+        assert_eq!(px(3, 3), &[0, 0, 0, 255]);
     }
 
     #[test]
-    fn test_app_frame_counting_simulation() {
-        let pixel_data = Arc::new(RwLock::new(vec![(0, 0, 0, 0); 100]));
-        let window = Arc::new(RwLock::new(None));
+    fn test_frame_step_key_pauses_then_steps() {
+        let clock = GameClock::new();
+        let app = App::new(frames::triple_buffer().1, Arc::new(RwLock::new(None)))
+            .with_frame_step_key(Some(KeyCode::F10))
+            .with_clock(clock.clone());
+        assert_eq!(app.frame_step_key, Some(KeyCode::F10));
 
-        let mut app = App::new(pixel_data, window);
+        app.step_frame();
+        assert!(clock.is_paused());
+        assert_eq!(clock.take_ticks(), 0);
+        app.step_frame();
+        app.step_frame();
+        assert_eq!(clock.take_ticks(), 2);
+    }
 
-        for _ in 0..10 {
-            app.frame_count += 1;
-        }
+    #[test]
+    fn test_app_with_quit_key() {
+        let (_writer, reader) = frames::triple_buffer();
+        let app = App::new(reader, Arc::new(RwLock::new(None)));
+        assert_eq!(app.quit_key, Some(KeyCode::Escape));
 
-        assert_eq!(app.frame_count, 10);
+        let app = app.with_quit_key(None);
+        assert_eq!(app.quit_key, None);
     }
 
     #[test]